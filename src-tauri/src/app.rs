@@ -0,0 +1,2300 @@
+// Tauri application shell: commands, hotkeys and app setup
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Local, Utc};
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::fs;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{Manager, State};
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+use tauri_plugin_store::StoreExt;
+use tracing::{debug, error, info, warn};
+
+use crate::browser::{self, BrowserPlugin};
+use crate::confirm::{self, ConfirmKind};
+use crate::control_server::{self, ControlServer};
+use crate::events::{self, BusEvent, BusEventType, EventBus};
+use crate::hotkeys::{self, HotkeyManager};
+use crate::macrox_core::acceleration::{self, MouseCalibration};
+use crate::macrox_core::accessibility;
+use crate::macrox_core::archive;
+use crate::macrox_core::capabilities::{self, PlatformCapabilities};
+use crate::macrox_core::devices;
+use crate::macrox_core::diff::{self, MacroDiff};
+use crate::macrox_core::editing;
+use crate::macrox_core::elevation;
+use crate::macrox_core::encoding;
+use crate::macrox_core::history::{RunHistory, MAX_RUNS};
+use crate::macrox_core::input::{self, EnigoSink, InputSink, RdevSource};
+use crate::macrox_core::keymap::{self, KeymapReport};
+use crate::macrox_core::lint::{self, LintContext};
+use crate::macrox_core::onboarding::{OnboardingState, OnboardingStatus, OnboardingStep};
+use crate::macrox_core::permissions;
+use crate::macrox_core::player::{self, AskUser, IterationCap, PlaybackControl, Player};
+use crate::macrox_core::plugin::PluginRegistry;
+use crate::macrox_core::pool::{PlaybackPool, RunningMacro};
+#[cfg(target_os = "linux")]
+use crate::macrox_core::portal;
+use crate::macrox_core::practice::PracticeSink;
+use crate::macrox_core::privilege;
+use crate::macrox_core::recorder::Recorder;
+use crate::macrox_core::release::UpdateInfo;
+use crate::macrox_core::retro::RetroBuffer;
+use crate::macrox_core::scene;
+use crate::macrox_core::screen;
+use crate::macrox_core::script;
+use crate::macrox_core::shortcut;
+use crate::macrox_core::storage::{self, LastRuns, MacroPersister};
+use crate::macrox_core::storyboard;
+use crate::macrox_core::transcript;
+use crate::macrox_core::triggers::{self, TriggerCleanup};
+use crate::macrox_core::types::*;
+use crate::macrox_core::window;
+#[cfg(target_os = "windows")]
+use crate::macrox_core::window_target::WindowSink;
+use crate::mqtt::{self, MqttBridge};
+use crate::{diagnostics, display, health, launcher, logging, updates, watcher, window_state};
+
+pub(crate) const SETTINGS_FILENAME: &str = "settings.json";
+const MACROS_FILENAME: &str = "macros.json";
+/// Holds a recording that was still running when the app exited
+const RECOVERY_FILENAME: &str = "recovery.json";
+
+/// App settings fields with their own update commands, ignored by `update_app_settings`
+const SEPARATELY_UPDATED_SETTINGS: &[&str] = &[
+    "mqtt",
+    "controlServer",
+    "emitter",
+    "logging",
+    "privilegeGrants",
+    "leader",
+    "launcher",
+    "browser",
+    "inputBackend",
+    "confirmDestructive",
+    "macroHotkeys",
+    "windowGeometry",
+    "onboarding",
+    "scenes",
+    "activeScene",
+    "retro",
+    "mouseAccelerationFactor",
+];
+
+/// Longest wait on exit for the player to stop and release held inputs
+const SHUTDOWN_WAIT: Duration = Duration::from_secs(1);
+
+/// How far around a click `calibrate_image_target` searches by default
+const CALIBRATION_RADIUS: u32 = 200;
+
+pub(crate) fn load_hotkeys_from_store(app: &tauri::AppHandle) -> HotkeySettings {
+    let store = app.store(SETTINGS_FILENAME).expect("failed to get store");
+
+    // Attempt to load settings
+    let _ = store.reload();
+
+    if let Some(value) = store.get("hotkeys") {
+        if let Ok(settings) = serde_json::from_value(value) {
+            return settings;
+        }
+    }
+
+    // Default if not found or invalid
+    let default_settings = HotkeySettings::default();
+    // Save defaults
+    let _ = store.set(
+        "hotkeys".to_string(),
+        serde_json::to_value(&default_settings).unwrap(),
+    );
+    let _ = store.save();
+
+    default_settings
+}
+
+pub(crate) fn load_app_settings_from_store(app: &tauri::AppHandle) -> AppSettings {
+    let store = app.store(SETTINGS_FILENAME).expect("failed to get store");
+    let _ = store.reload();
+
+    if let Some(value) = store.get("app_settings") {
+        match serde_json::from_value(value) {
+            Ok(settings) => return settings,
+            // Leave the stored value alone rather than overwrite it with defaults
+            Err(e) => {
+                warn!("Stored app settings are invalid, using defaults: {}", e);
+                return AppSettings::default();
+            }
+        }
+    }
+
+    let default_settings = AppSettings::default();
+    let _ = store.set(
+        "app_settings".to_string(),
+        serde_json::to_value(&default_settings).unwrap(),
+    );
+    let _ = store.save();
+
+    default_settings
+}
+
+/// Write the app settings and tell the frontend they changed
+pub(crate) fn save_app_settings(
+    app: &tauri::AppHandle,
+    settings: &AppSettings,
+) -> Result<(), String> {
+    let store = app.store(SETTINGS_FILENAME).map_err(|e| e.to_string())?;
+    let _ = store.set(
+        "app_settings".to_string(),
+        serde_json::to_value(settings).map_err(|e| e.to_string())?,
+    );
+    let _ = store.save();
+
+    events::emit(app, BusEventType::SettingsChanged, settings);
+    Ok(())
+}
+
+fn load_macros_from_store(app: &tauri::AppHandle) -> (Vec<Arc<Macro>>, LastRuns) {
+    let store = app.store(MACROS_FILENAME).expect("failed to get store");
+    let _ = store.reload();
+
+    let library = serde_json::json!({
+        "macros": store.get("macros"),
+        "lastRuns": store.get("lastRuns"),
+    });
+    let macros = serde_json::from_value(library["macros"].clone()).unwrap_or_default();
+    (macros, storage::read_last_runs(&library))
+}
+
+/// Queue the macro library for a debounced, atomic write
+fn save_macros_to_store(state: &AppState, macros: &[Arc<Macro>]) {
+    state.persister.save(macros.to_vec());
+}
+
+/// Application state for managing macros and recording
+pub struct AppState {
+    /// Shared so reads don't copy events; edit through `Arc::make_mut`
+    pub(crate) macros: Arc<Mutex<Vec<Arc<Macro>>>>,
+    /// Written with the library by the persister
+    last_runs: Arc<Mutex<LastRuns>>,
+    pub(crate) recorder: Arc<Mutex<Option<Recorder>>>,
+    /// Warnings of the last recording that was stopped
+    last_recording_warnings: Mutex<RecordingWarningSummary>,
+    pub(crate) playback: Arc<PlaybackControl>,
+    /// Macros playing right now and the input channels they hold
+    pub(crate) pool: Arc<PlaybackPool>,
+    app_handle: tauri::AppHandle,
+    persister: MacroPersister,
+    plugins: Arc<PluginRegistry>,
+    pub(crate) history: Arc<RunHistory>,
+    _macros_watcher: Option<notify::RecommendedWatcher>,
+}
+
+/// Run a command's work on the blocking thread pool, so input hooks, playback
+/// and file work never hold up the IPC thread
+async fn run_blocking<T: Send + 'static>(
+    app: tauri::AppHandle,
+    work: impl FnOnce(&tauri::AppHandle) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(move || work(&app))
+        .await
+        .map_err(|e| format!("Command stopped unexpectedly: {}", e))?
+}
+
+// Errors the UI matches on to offer stopping or overriding, keep them stable
+const PLAYBACK_ACTIVE_ERROR: &str = "Can't record while a macro is playing";
+const RECORDING_ACTIVE_ERROR: &str = "Can't play a macro while recording";
+
+/// Whether any macro is playing or paused
+fn playback_active(state: &AppState) -> bool {
+    !state.pool.is_empty() || !matches!(state.playback.status(), PlaybackStatus::Idle)
+}
+
+/// Refuse to record over a playing macro unless the caller allows it
+fn check_can_record(state: &AppState, allow_during_playback: bool) -> Result<(), String> {
+    if state.recorder.lock().is_some() {
+        return Err("Recording already in progress".to_string());
+    }
+    if playback_active(state) && !allow_during_playback {
+        return Err(format!(
+            "{}; stop playback first or record anyway",
+            PLAYBACK_ACTIVE_ERROR
+        ));
+    }
+    Ok(())
+}
+
+/// Refuse to play while recording, since the recorder would capture the playback
+fn check_can_play(state: &AppState) -> Result<(), String> {
+    if state.recorder.lock().is_some() {
+        return Err(format!(
+            "{}; stop the recording first",
+            RECORDING_ACTIVE_ERROR
+        ));
+    }
+    Ok(())
+}
+
+/// Start recording macro events
+///
+/// Fails while a macro plays unless `allow_during_playback` is set.
+#[tauri::command]
+async fn start_recording(
+    app: tauri::AppHandle,
+    settings: RecordingSettings,
+    allow_during_playback: Option<bool>,
+) -> Result<(), String> {
+    run_blocking(app, move |app| {
+        let state = app.state::<AppState>();
+        check_can_record(&state, allow_during_playback.unwrap_or(false))?;
+        let mut recorder_lock = state.recorder.lock();
+
+        // Another call may have started recording in the meantime
+        if recorder_lock.is_some() {
+            return Err("Recording already in progress".to_string());
+        }
+
+        *recorder_lock = Some(begin_recording(&state.app_handle, settings, Vec::new())?);
+
+        info!("Recording started");
+        Ok(())
+    })
+    .await
+}
+
+/// Create and start a recorder, appending after `prefix` when continuing a macro
+fn begin_recording(
+    app: &tauri::AppHandle,
+    settings: RecordingSettings,
+    prefix: Vec<MacroEvent>,
+) -> Result<Recorder, String> {
+    if !refresh_onboarding(app).is_done(OnboardingStep::Permissions) {
+        return Err("MacroX needs the Accessibility permission before it can record".to_string());
+    }
+
+    // Forward recorder warnings to the frontend
+    let handle = app.clone();
+    let mut recorder = Recorder::new(
+        settings,
+        Some(Arc::new(move |warning: RecordingWarning| {
+            events::emit(&handle, BusEventType::RecordingWarning, warning);
+        })),
+    );
+    recorder.set_prefix(prefix);
+    let handle = app.clone();
+    recorder.set_block_observer(Arc::new(move |blocked: bool| {
+        events::emit(
+            &handle,
+            BusEventType::RecordingBlocked,
+            serde_json::json!({ "blocked": blocked, "reason": "secureInput" }),
+        );
+    }));
+    recorder.set_plugins(Arc::clone(&app.state::<AppState>().plugins));
+
+    // Stream captured events to the UI in batches
+    let handle = app.clone();
+    recorder.set_event_observer(Arc::new(move |event: &MacroEvent| {
+        events::emit_batched(&handle, BusEventType::RecordedEvents, event);
+    }));
+
+    // Load hotkeys to pass to recorder for filtering
+    let hotkeys = load_hotkeys_from_store(app);
+
+    // Start with hotkeys
+    recorder.start(hotkeys)?;
+
+    Ok(recorder)
+}
+
+/// Play a macro up to `step`, then switch to recording from that point.
+///
+/// `stop_recording` returns the kept events followed by the newly recorded ones.
+#[tauri::command]
+async fn record_from_step(app: tauri::AppHandle, id: String, step: usize) -> Result<(), String> {
+    run_blocking(app, move |app| {
+        let state = app.state::<AppState>();
+        check_can_record(&state, false)?;
+
+        let mut macro_data = state
+            .macros
+            .lock()
+            .iter()
+            .find(|m| m.id == id)
+            .map(|m| Macro::clone(m))
+            .ok_or_else(|| format!("Macro not found: {}", id))?;
+        if step > macro_data.events.len() {
+            return Err(format!(
+                "Step {} is past the end of the macro ({} events)",
+                step,
+                macro_data.events.len()
+            ));
+        }
+
+        // Replay the part being kept exactly once
+        macro_data.events.truncate(step);
+        macro_data.playback_settings.repeat_mode = "once".to_string();
+
+        let library = state.macros.lock().clone();
+        let (control, lease) = state.pool.claim(&macro_data, &library, &state.playback)?;
+        let current_display = display::current_display_info(&state.app_handle);
+        let mut player = new_player(
+            &control,
+            &state.plugins,
+            &macro_data,
+            current_display.as_ref(),
+            RunOptions::load(&state.app_handle, &control, &macro_data),
+        )?;
+        player.play_macro(&macro_data)?;
+        drop(lease);
+
+        let mut recorder_lock = state.recorder.lock();
+        if recorder_lock.is_some() {
+            return Err("Recording already in progress".to_string());
+        }
+        *recorder_lock = Some(begin_recording(
+            &state.app_handle,
+            macro_data.recording_settings.clone(),
+            macro_data.events,
+        )?);
+
+        info!(
+            "Recording resumed from step {} of {}",
+            step, macro_data.name
+        );
+        Ok(())
+    })
+    .await
+}
+
+/// Stop recording and return the captured events with session statistics
+#[tauri::command]
+async fn stop_recording(app: tauri::AppHandle) -> Result<RecordingSession, String> {
+    run_blocking(app, move |app| {
+        let state = app.state::<AppState>();
+        let mut recorder_lock = state.recorder.lock();
+
+        if let Some(mut recorder) = recorder_lock.take() {
+            let events = recorder.stop();
+            let session = recorder.session(events);
+            *state.last_recording_warnings.lock() = recorder.warning_summary();
+            info!(
+                "Recording stopped. Captured {} events, dropped {}",
+                session.events.len(),
+                session.dropped_events
+            );
+            if !session.events.is_empty() {
+                mark_onboarding_step(&state.app_handle, OnboardingStep::FirstRecording);
+            }
+            Ok(session)
+        } else {
+            Err("No active recording".to_string())
+        }
+    })
+    .await
+}
+
+/// Warnings of the running recording, or of the last one once it stopped
+#[tauri::command]
+fn get_recording_warnings(state: State<'_, AppState>) -> RecordingWarningSummary {
+    match state.recorder.lock().as_ref() {
+        Some(recorder) => recorder.warning_summary(),
+        None => state.last_recording_warnings.lock().clone(),
+    }
+}
+
+/// Check if recording is currently in progress
+#[tauri::command]
+fn is_recording(state: State<'_, AppState>) -> bool {
+    state.recorder.lock().is_some()
+}
+
+/// What a run takes from the app rather than the macro: permissions, settings
+/// and the prompts the player may show
+struct RunOptions {
+    grants: Vec<PrivilegeGrant>,
+    backend: InputBackend,
+    /// Pointer acceleration compensation for relative moves
+    relative_scale: f64,
+    confirm: Option<AskUser>,
+    cap: IterationCap,
+    /// Set in practice mode, replaces the desktop as the target
+    practice: Option<PracticeSink>,
+    /// The library, for RunMacro events
+    macros: Arc<Mutex<Vec<Arc<Macro>>>>,
+}
+
+impl RunOptions {
+    fn load(app: &tauri::AppHandle, control: &Arc<PlaybackControl>, macro_data: &Macro) -> Self {
+        let app_settings = load_app_settings_from_store(app);
+        Self {
+            grants: app_settings.privilege_grants,
+            backend: app_settings.input_backend,
+            relative_scale: app_settings.mouse_acceleration_factor,
+            confirm: destructive_confirm(app, control, macro_data),
+            cap: iteration_cap(app, control, macro_data),
+            practice: app_settings.practice_mode.then(|| practice::sink(app)),
+            macros: Arc::clone(&app.state::<AppState>().macros),
+        }
+    }
+}
+
+/// Player for `macro_data`, corrected for the current display scaling
+fn new_player(
+    control: &Arc<PlaybackControl>,
+    plugins: &Arc<PluginRegistry>,
+    macro_data: &Macro,
+    current_display: Option<&DisplayInfo>,
+    options: RunOptions,
+) -> Result<Player<Box<dyn InputSink>>, String> {
+    let practicing = options.practice.is_some();
+    let sink: Box<dyn InputSink> = match (
+        options.practice,
+        &macro_data.playback_settings.target_window,
+    ) {
+        (Some(practice), _) => Box::new(practice),
+        #[cfg(target_os = "windows")]
+        (None, Some(title)) => Box::new(WindowSink::find(title)?),
+        #[cfg(not(target_os = "windows"))]
+        (None, Some(_)) => {
+            return Err("Window-targeted playback is only supported on Windows".to_string())
+        }
+        (None, None) => input::new_sink(options.backend)?,
+    };
+    let mut player = Player::with_sink(sink, Arc::clone(control));
+    player.set_coordinate_scale(display::coordinate_scale(macro_data, current_display));
+    player.set_relative_move_scale(options.relative_scale);
+    player.set_plugins(Arc::clone(plugins));
+    player.set_screen_grabber(screen::capture);
+    player.set_element_locator(accessibility::locate);
+    // Window-targeted and practice playback don't go to whatever is in front
+    if macro_data.playback_settings.target_window.is_none() && !practicing {
+        player.set_elevation_check(elevation::blocked_foreground_window);
+        player.set_window_locator(window::find_app_window);
+    }
+    player.set_privilege_grants(options.grants);
+    if let Some(confirm) = options.confirm {
+        player.set_destructive_confirm(confirm);
+    }
+    player.set_iteration_cap(options.cap);
+    let macros = options.macros;
+    player.set_macro_resolver(Arc::new(move |id: &str| {
+        macros.lock().iter().find(|m| m.id == id).cloned()
+    }));
+
+    // The user can't get in the way of a practice run
+    if macro_data.playback_settings.human_override != HumanOverride::Off && !practicing {
+        player::watch_user_input(control);
+    }
+
+    Ok(player)
+}
+
+/// Play `macro_data` to the end, add the run to the history and publish how
+/// its timing compared to the recording
+fn play_and_record(
+    app: &tauri::AppHandle,
+    control: &Arc<PlaybackControl>,
+    macro_data: &Macro,
+    current_display: Option<&DisplayInfo>,
+    options: RunOptions,
+) -> Result<RunOutputs, String> {
+    let state = app.state::<AppState>();
+    let started_at = Utc::now();
+    let started = Instant::now();
+    let mut timing = None;
+    let result = new_player(
+        control,
+        &state.plugins,
+        macro_data,
+        current_display,
+        options,
+    )
+    .and_then(|mut player| {
+        let outputs = player.play_macro(macro_data);
+        timing = Some(player.timing_report());
+        outputs
+    });
+
+    let outcome = match &result {
+        Err(_) => RunOutcome::Failed,
+        Ok(_) if control.is_stopped() => RunOutcome::Stopped,
+        Ok(_) => RunOutcome::Completed,
+    };
+    if let Some(report) = &timing {
+        events::emit(
+            app,
+            BusEventType::TimingReport,
+            serde_json::json!({ "macroId": macro_data.id, "report": report }),
+        );
+    }
+    state.history.push(RunRecord {
+        macro_id: macro_data.id.clone(),
+        macro_name: macro_data.name.clone(),
+        started_at,
+        duration_ms: started.elapsed().as_millis() as u64,
+        outcome,
+        error: result.as_ref().err().cloned(),
+        outputs: result.clone().unwrap_or_default(),
+        timing,
+    });
+
+    result
+}
+
+/// A hook asking the user before destructive key combinations, if the macro
+/// or else the app settings want one
+fn destructive_confirm(
+    app: &tauri::AppHandle,
+    control: &Arc<PlaybackControl>,
+    macro_data: &Macro,
+) -> Option<AskUser> {
+    let enabled = macro_data
+        .playback_settings
+        .confirm_destructive
+        .unwrap_or_else(|| load_app_settings_from_store(app).confirm_destructive);
+    enabled.then(|| confirm::asker(app, control, &macro_data.id, ConfirmKind::Destructive))
+}
+
+/// The app's limit on "infinite" repeats, asking the user before going past it
+fn iteration_cap(
+    app: &tauri::AppHandle,
+    control: &Arc<PlaybackControl>,
+    macro_data: &Macro,
+) -> IterationCap {
+    IterationCap {
+        max_iterations: load_app_settings_from_store(app).max_infinite_iterations,
+        confirm: Some(confirm::asker(
+            app,
+            control,
+            &macro_data.id,
+            ConfirmKind::RepeatLimit,
+        )),
+    }
+}
+
+/// Answer a destructive-action confirmation the player is waiting on
+#[tauri::command]
+fn answer_confirmation(app: tauri::AppHandle, id: u64, approved: bool) -> Result<(), String> {
+    confirm::answer(&app, id, approved)
+}
+
+/// Privileged event types the user has allowed this macro to play
+fn granted_privileges(app: &tauri::AppHandle, macro_id: &str) -> Vec<String> {
+    load_app_settings_from_store(app)
+        .privilege_grants
+        .into_iter()
+        .find(|g| g.macro_id == macro_id)
+        .map(|g| g.event_types)
+        .unwrap_or_default()
+}
+
+/// Privileged events in a stored macro that it isn't allowed to play yet
+#[tauri::command]
+fn get_missing_privilege_grants(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let macros = state.macros.lock();
+    let macro_data = macros
+        .iter()
+        .find(|m| m.id == id)
+        .ok_or_else(|| format!("Macro not found: {}", id))?;
+    Ok(privilege::missing_grants(
+        &macro_data.events,
+        &state.plugins,
+        &granted_privileges(&state.app_handle, &id),
+    ))
+}
+
+/// Allow a stored macro to play the privileged events it currently contains
+#[tauri::command]
+fn grant_privileged_events(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let event_types = {
+        let macros = state.macros.lock();
+        let macro_data = macros
+            .iter()
+            .find(|m| m.id == id)
+            .ok_or_else(|| format!("Macro not found: {}", id))?;
+        privilege::privileged_event_types(&macro_data.events, &state.plugins)
+    };
+
+    let mut app_settings = load_app_settings_from_store(&state.app_handle);
+    app_settings.privilege_grants.retain(|g| g.macro_id != id);
+    info!(
+        "Granted '{}' privileged events: {}",
+        id,
+        event_types.join(", ")
+    );
+    app_settings.privilege_grants.push(PrivilegeGrant {
+        macro_id: id,
+        event_types,
+    });
+    save_app_settings(&state.app_handle, &app_settings)?;
+
+    Ok(())
+}
+
+/// Take back a macro's permission to play privileged events
+#[tauri::command]
+fn revoke_privileged_events(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&state.app_handle);
+    app_settings.privilege_grants.retain(|g| g.macro_id != id);
+    save_app_settings(&state.app_handle, &app_settings)?;
+
+    Ok(())
+}
+
+/// Play a macro, returning the values its events read
+#[tauri::command]
+async fn play_macro(app: tauri::AppHandle, macro_data: Macro) -> Result<RunOutputs, String> {
+    run_blocking(app, move |app| {
+        let state = app.state::<AppState>();
+        info!(
+            "Playing macro: {} with {} events",
+            macro_data.name,
+            macro_data.events.len()
+        );
+
+        check_can_play(&state)?;
+        let library = state.macros.lock().clone();
+        let (control, _lease) = state.pool.claim(&macro_data, &library, &state.playback)?;
+
+        // The playback hotkey is what has to stay held down
+        if macro_data.playback_settings.repeat_mode == "held" {
+            let hotkeys = load_hotkeys_from_store(&state.app_handle);
+            player::watch_trigger_release(&control, Box::new(RdevSource), &hotkeys.playback_start)?;
+        }
+
+        let current_display = display::current_display_info(&state.app_handle);
+        let outputs = play_and_record(
+            app,
+            &control,
+            &macro_data,
+            current_display.as_ref(),
+            RunOptions::load(&state.app_handle, &control, &macro_data),
+        )?;
+        note_run(&state, &macro_data.id);
+        publish_outputs(&state.app_handle, &macro_data.id, &outputs);
+
+        info!("Playback completed");
+        Ok(outputs)
+    })
+    .await
+}
+
+/// Let the frontend show what a finished run read, also for runs it didn't start
+fn publish_outputs(app: &tauri::AppHandle, macro_id: &str, outputs: &RunOutputs) {
+    events::emit(
+        app,
+        BusEventType::RunOutputs,
+        serde_json::json!({ "macroId": macro_id, "outputs": outputs }),
+    );
+}
+
+/// Remember when a stored macro last ran, for archiving unused macros
+fn note_run(state: &AppState, macro_id: &str) {
+    let macros = state.macros.lock();
+    if macros.iter().any(|m| m.id == macro_id) {
+        state
+            .last_runs
+            .lock()
+            .insert(macro_id.to_string(), Utc::now());
+        save_macros_to_store(state, &macros);
+    }
+}
+
+/// Recent playback runs, newest first
+#[tauri::command]
+fn get_run_history(limit: Option<usize>, state: State<'_, AppState>) -> Vec<RunRecord> {
+    state.history.recent(limit.unwrap_or(MAX_RUNS))
+}
+
+/// Where the mouse cursor is right now, in physical pixels
+#[tauri::command]
+fn get_cursor_position() -> Result<(i32, i32), String> {
+    EnigoSink::new()?.cursor_position()
+}
+
+/// Whether this is a Wayland session and which remote input portals it offers
+#[tauri::command]
+fn get_wayland_capabilities() -> WaylandCapabilities {
+    #[cfg(target_os = "linux")]
+    return portal::capabilities();
+    #[cfg(not(target_os = "linux"))]
+    WaylandCapabilities::default()
+}
+
+/// What recording, playback and the other features can do on this OS with the
+/// selected input backend, with the reason for anything unavailable
+#[tauri::command]
+fn get_platform_capabilities(app: tauri::AppHandle) -> PlatformCapabilities {
+    capabilities::current(load_app_settings_from_store(&app).input_backend)
+}
+
+/// Whether MacroX runs as administrator, so it can automate elevated windows
+#[tauri::command]
+fn is_elevated() -> bool {
+    elevation::is_elevated()
+}
+
+/// Restart MacroX as administrator after a UAC prompt
+#[tauri::command]
+fn relaunch_elevated(app: tauri::AppHandle) -> Result<(), String> {
+    elevation::relaunch_elevated()?;
+    info!("Restarting as administrator");
+    app.exit(0);
+    Ok(())
+}
+
+/// Color of the screen pixel at (x, y)
+#[tauri::command]
+fn get_pixel_color(x: i32, y: i32) -> Result<PixelColor, String> {
+    screen::pixel_color(screen::capture, x, y)
+}
+
+/// Scan the screen around a click for its recorded screenshot and report the
+/// match scores, so its matchTolerance can be tuned before relying on it
+#[tauri::command]
+async fn calibrate_image_target(
+    app: tauri::AppHandle,
+    id: String,
+    event_index: usize,
+    radius: Option<u32>,
+) -> Result<ImageCalibration, String> {
+    run_blocking(app, move |app| {
+        let state = app.state::<AppState>();
+        let (reference, (x, y)) = {
+            let macros = state.macros.lock();
+            let macro_data = macros
+                .iter()
+                .find(|m| m.id == id)
+                .ok_or_else(|| format!("Macro not found: {}", id))?;
+            let encoded = macro_data
+                .events
+                .get(event_index)
+                .and_then(|e| e.data.get("screenshot"))
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| format!("Event {} has no click screenshot", event_index))?;
+            let position = player::click_position(&macro_data.events, event_index)
+                .ok_or_else(|| format!("Event {} has no recorded position", event_index))?;
+            (screen::decode_png(encoded)?, position)
+        };
+
+        screen::calibrate(
+            screen::capture,
+            &reference,
+            (x as i32, y as i32),
+            radius.unwrap_or(CALIBRATION_RADIUS),
+        )
+    })
+    .await
+}
+
+/// Measure pointer acceleration with a few relative moves in the middle of the
+/// primary screen and save the factor relative moves are played with
+#[tauri::command]
+async fn calibrate_mouse(app: tauri::AppHandle) -> Result<MouseCalibration, String> {
+    run_blocking(app, |app| {
+        let mut app_settings = load_app_settings_from_store(app);
+        let mut sink = input::new_sink(app_settings.input_backend)?;
+        let origin = match display::monitor_bounds(app).first() {
+            Some(screen) => (
+                screen.x + screen.width as i32 / 2,
+                screen.y + screen.height as i32 / 2,
+            ),
+            None => sink.cursor_position()?,
+        };
+
+        let calibration = acceleration::calibrate(&mut sink, origin)?;
+        info!("Mouse acceleration factor: {:.3}", calibration.factor);
+        app_settings.mouse_acceleration_factor = calibration.factor;
+        save_app_settings(app, &app_settings)?;
+        Ok(calibration)
+    })
+    .await
+}
+
+/// Global hotkeys of enabled features, which the key map test leaves out
+fn bound_hotkeys(app: &tauri::AppHandle) -> Vec<String> {
+    let hotkeys = load_hotkeys_from_store(app);
+    let settings = load_app_settings_from_store(app);
+    let mut bound = vec![
+        hotkeys.record_start,
+        hotkeys.record_stop,
+        hotkeys.playback_start,
+        hotkeys.playback_stop,
+        hotkeys.mark,
+    ];
+    let features = [
+        (settings.leader.enabled, settings.leader.hotkey),
+        (settings.launcher.enabled, settings.launcher.hotkey),
+        (settings.retro.enabled, settings.retro.hotkey),
+    ];
+    bound.extend(features.into_iter().filter(|f| f.0).map(|f| f.1));
+    bound.extend(settings.macro_hotkeys.into_iter().map(|b| b.hotkey));
+    bound
+}
+
+/// Developer self-test: a macro pressing every key and button the player maps
+#[tauri::command]
+fn generate_keymap_test_macro(app: tauri::AppHandle) -> Macro {
+    let bound = bound_hotkeys(&app);
+    keymap::test_macro(&bound.iter().map(String::as_str).collect::<Vec<_>>())
+}
+
+/// Play the key map test macro while recording it and diff the two streams.
+/// It types into the focused window, so focus a scratch text field first.
+#[tauri::command]
+async fn verify_keymap(app: tauri::AppHandle) -> Result<KeymapReport, String> {
+    run_blocking(app, |app| {
+        let state = app.state::<AppState>();
+        check_can_record(&state, false)?;
+        check_can_play(&state)?;
+
+        let sink = input::new_sink(load_app_settings_from_store(app).input_backend)?;
+        let bound = bound_hotkeys(app);
+        let bound: Vec<&str> = bound.iter().map(String::as_str).collect();
+        let hotkeys = load_hotkeys_from_store(app);
+        let report = keymap::verify(sink, Box::new(RdevSource), hotkeys, &bound)?;
+        info!(
+            "Key map test: {} sent, {} recorded, {} differences",
+            report.sent_events,
+            report.recorded_events,
+            report.changes.len()
+        );
+        Ok(report)
+    })
+    .await
+}
+
+/// Look for a newer release on GitHub, with its release notes
+#[tauri::command]
+async fn check_for_updates(app: tauri::AppHandle) -> Result<UpdateInfo, String> {
+    updates::check(&app).await
+}
+
+/// Keyboards and mice that recordings can be limited to (Windows only, empty elsewhere)
+#[tauri::command]
+fn list_input_devices() -> Vec<InputDevice> {
+    devices::list_devices()
+}
+
+/// Get the primary display's current scaling, stored with new recordings
+#[tauri::command]
+fn get_display_info(state: State<'_, AppState>) -> Option<DisplayInfo> {
+    display::current_display_info(&state.app_handle)
+}
+
+/// Get the current playback state
+#[tauri::command]
+fn get_playback_status(state: State<'_, AppState>) -> PlaybackStatus {
+    state.playback.status()
+}
+
+/// Expected runtime of a stored macro, optionally with different playback settings
+#[tauri::command]
+fn estimate_macro_duration(
+    id: String,
+    settings: Option<PlaybackSettings>,
+    state: State<'_, AppState>,
+) -> Result<DurationEstimate, String> {
+    let macros = state.macros.lock();
+    let macro_data = macros
+        .iter()
+        .find(|m| m.id == id)
+        .ok_or_else(|| format!("Macro not found: {}", id))?;
+
+    let settings = settings.as_ref().unwrap_or(&macro_data.playback_settings);
+    Ok(player::estimate_duration(&macro_data.events, settings))
+}
+
+/// Pause the running macros before their next event
+#[tauri::command]
+fn pause_playback(state: State<'_, AppState>) {
+    state.playback.set_paused(true);
+    state.pool.set_paused_all(true);
+}
+
+/// Stop the running macros before their next event
+#[tauri::command]
+fn stop_playback(state: State<'_, AppState>) {
+    state.playback.stop();
+    state.pool.stop_all();
+}
+
+/// Resume paused macros
+#[tauri::command]
+fn resume_playback(state: State<'_, AppState>) {
+    state.playback.set_paused(false);
+    state.pool.set_paused_all(false);
+}
+
+/// Macros playing right now and the input channels each one drives
+#[tauri::command]
+fn get_running_macros(state: State<'_, AppState>) -> Vec<RunningMacro> {
+    state.pool.running()
+}
+
+/// Save a macro to the in-memory store, reporting a stored macro with the same steps
+#[tauri::command]
+fn save_macro(macro_data: Macro, state: State<'_, AppState>) -> Result<SaveMacroResult, String> {
+    let mut macros = state.macros.lock();
+
+    let duplicate_of = diff::find_duplicate(&macros, &macro_data).map(|m| MacroRef {
+        id: m.id.clone(),
+        name: m.name.clone(),
+    });
+
+    // Check if macro exists and update it, or add new
+    let mut parts = Vec::new();
+    if let Some(pos) = macros.iter().position(|m| m.id == macro_data.id) {
+        macros[pos] = Arc::new(macro_data.clone());
+    } else {
+        // New recordings over the size limit become a chain of smaller macros
+        let limit = macro_data.recording_settings.split_after_events as usize;
+        let (chained, split) = editing::split_macro(macro_data.clone(), limit);
+        for part in split {
+            parts.push(MacroRef {
+                id: part.id.clone(),
+                name: part.name.clone(),
+            });
+            macros.push(Arc::new(part));
+        }
+        macros.push(Arc::new(chained));
+    }
+
+    info!("Saved macro: {}", macro_data.name);
+    if !parts.is_empty() {
+        info!("Split {} into {} parts", macro_data.name, parts.len());
+        let visible: Vec<Arc<Macro>> = macros.iter().filter(|m| !m.archived).cloned().collect();
+        events::emit(&state.app_handle, BusEventType::MacrosChanged, visible);
+    }
+    if let Some(duplicate) = &duplicate_of {
+        info!(
+            "Macro {} has the same steps as {}",
+            macro_data.name, duplicate.name
+        );
+    }
+
+    // Persist changes
+    save_macros_to_store(&state, &macros);
+
+    Ok(SaveMacroResult {
+        duplicate_of,
+        parts,
+    })
+}
+
+/// Permanently speed up (factor below 1) or slow down a stored macro, or
+/// only the events in `range`
+#[tauri::command]
+fn retime_macro(
+    id: String,
+    factor: f64,
+    range: Option<EventRange>,
+    state: State<'_, AppState>,
+) -> Result<Macro, String> {
+    let mut macros = state.macros.lock();
+    let macro_data = macros
+        .iter_mut()
+        .find(|m| m.id == id)
+        .map(Arc::make_mut)
+        .ok_or_else(|| format!("Macro not found: {}", id))?;
+
+    editing::retime(&mut macro_data.events, factor, range)?;
+    macro_data.updated_at = Utc::now();
+    let retimed = macro_data.clone();
+    info!("Retimed macro {} by {}", retimed.name, factor);
+
+    save_macros_to_store(&state, &macros);
+    Ok(retimed)
+}
+
+/// Replace a stored macro's description, author, source URL and license
+#[tauri::command]
+fn update_macro_metadata(
+    id: String,
+    metadata: MacroMetadata,
+    state: State<'_, AppState>,
+) -> Result<Macro, String> {
+    // Blank fields are cleared rather than kept as empty strings
+    let optional = |value: Option<String>| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let source_url = optional(metadata.source_url);
+    if let Some(url) = &source_url {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(format!(
+                "Source URL must start with http:// or https://: {}",
+                url
+            ));
+        }
+    }
+
+    let mut macros = state.macros.lock();
+    let macro_data = macros
+        .iter_mut()
+        .find(|m| m.id == id)
+        .map(Arc::make_mut)
+        .ok_or_else(|| format!("Macro not found: {}", id))?;
+
+    macro_data.description = metadata.description;
+    macro_data.author = optional(metadata.author);
+    macro_data.source_url = source_url;
+    macro_data.license = optional(metadata.license);
+    macro_data.updated_at = Utc::now();
+    let updated = macro_data.clone();
+    info!("Updated metadata of macro {}", updated.name);
+
+    save_macros_to_store(&state, &macros);
+    Ok(updated)
+}
+
+/// Snap a stored macro's mouse positions to a `grid_px` grid, dropping moves
+/// that no longer go anywhere
+#[tauri::command]
+fn align_mouse_events(
+    id: String,
+    grid_px: u32,
+    state: State<'_, AppState>,
+) -> Result<Macro, String> {
+    let mut macros = state.macros.lock();
+    let macro_data = macros
+        .iter_mut()
+        .find(|m| m.id == id)
+        .map(Arc::make_mut)
+        .ok_or_else(|| format!("Macro not found: {}", id))?;
+
+    let merged = editing::align_to_grid(&mut macro_data.events, grid_px)?;
+    macro_data.updated_at = Utc::now();
+    let aligned = macro_data.clone();
+    info!(
+        "Aligned macro {} to a {}px grid, merged {} moves",
+        aligned.name, grid_px, merged
+    );
+
+    save_macros_to_store(&state, &macros);
+    Ok(aligned)
+}
+
+/// Load all macros that aren't archived
+#[tauri::command]
+fn load_all_macros(state: State<'_, AppState>) -> Result<Vec<Arc<Macro>>, String> {
+    archive_stale_macros(&state);
+    let macros = state.macros.lock();
+    Ok(macros.iter().filter(|m| !m.archived).cloned().collect())
+}
+
+/// Archive expired and long-unused macros and free their hotkeys
+fn archive_stale_macros(state: &AppState) {
+    let unused_days = load_app_settings_from_store(&state.app_handle).archive_unused_days;
+    let mut macros = state.macros.lock();
+    let archived = archive::archive_stale(
+        &mut macros,
+        &state.last_runs.lock(),
+        unused_days,
+        Utc::now(),
+    );
+    if archived.is_empty() {
+        return;
+    }
+    info!("Archived {} stale macros", archived.len());
+    save_macros_to_store(state, &macros);
+    drop(macros);
+    if let Err(e) = rebind_macro_hotkeys(&state.app_handle) {
+        warn!("{}", e);
+    }
+}
+
+/// Macros that were archived, for the archive view
+#[tauri::command]
+fn list_archived_macros(state: State<'_, AppState>) -> Vec<Arc<Macro>> {
+    let macros = state.macros.lock();
+    macros.iter().filter(|m| m.archived).cloned().collect()
+}
+
+/// Move an archived macro back into the library, with its hotkey
+#[tauri::command]
+fn restore_macro(id: String, state: State<'_, AppState>) -> Result<Macro, String> {
+    let mut macros = state.macros.lock();
+    let macro_data = macros
+        .iter_mut()
+        .find(|m| m.id == id)
+        .map(Arc::make_mut)
+        .ok_or_else(|| format!("Macro not found: {}", id))?;
+    archive::restore(macro_data, Utc::now());
+    let restored = macro_data.clone();
+    save_macros_to_store(&state, &macros);
+    drop(macros);
+
+    rebind_macro_hotkeys(&state.app_handle)?;
+    info!("Restored macro: {}", restored.name);
+    Ok(restored)
+}
+
+/// Delete a macro by ID
+#[tauri::command]
+fn delete_macro(macro_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let mut macros = state.macros.lock();
+    // Parts made when a long recording was split go with it
+    let removed = editing::delete_macro(&mut macros, &macro_id)?;
+
+    info!("Deleted macros: {}", removed.join(", "));
+
+    // Persist changes
+    save_macros_to_store(&state, &macros);
+    drop(macros);
+
+    // Its hotkey, leader key and scene entries would otherwise point at nothing
+    reconcile_triggers(state.app_handle.clone())?;
+    Ok(())
+}
+
+/// Event types added by plugins, for the macro editor
+#[tauri::command]
+fn get_plugin_event_types(state: State<'_, AppState>) -> Vec<String> {
+    state.plugins.event_types()
+}
+
+/// Compare two stored macros, e.g. a re-recording against the original
+#[tauri::command]
+fn diff_macros(
+    id_a: String,
+    id_b: String,
+    state: State<'_, AppState>,
+) -> Result<MacroDiff, String> {
+    let macros = state.macros.lock();
+    let find = |id: &str| {
+        macros
+            .iter()
+            .find(|m| m.id == id)
+            .ok_or_else(|| format!("Macro not found: {}", id))
+    };
+
+    Ok(diff::diff_macros(find(&id_a)?, find(&id_b)?))
+}
+
+/// Text a macro would type, for auditing imported macros before running them
+#[tauri::command]
+fn transcribe_macro(id: String, state: State<'_, AppState>) -> Result<String, String> {
+    let macros = state.macros.lock();
+    let macro_data = macros
+        .iter()
+        .find(|m| m.id == id)
+        .ok_or_else(|| format!("Macro not found: {}", id))?;
+
+    Ok(transcript::transcribe(&macro_data.events))
+}
+
+/// Warnings about likely problems in a stored macro
+#[tauri::command]
+fn lint_macro(
+    id: String,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<LintWarning>, String> {
+    let hotkeys = load_hotkeys_from_store(&app);
+    let macros = state.macros.lock();
+    let macro_data = macros
+        .iter()
+        .find(|m| m.id == id)
+        .ok_or_else(|| format!("Macro not found: {}", id))?;
+
+    let context = LintContext {
+        displays: display::monitor_bounds(&app),
+        hotkey_keys: [
+            &hotkeys.record_start,
+            &hotkeys.record_stop,
+            &hotkeys.playback_start,
+            &hotkeys.playback_stop,
+        ]
+        .iter()
+        .map(|hotkey| lint::hotkey_key(hotkey))
+        .collect(),
+        macro_ids: macros.iter().map(|m| m.id.clone()).collect(),
+    };
+
+    Ok(lint::lint_events(&macro_data.events, &context))
+}
+
+/// A macro's events as editable script text
+#[tauri::command]
+fn export_macro_as_text(id: String, state: State<'_, AppState>) -> Result<String, String> {
+    let macros = state.macros.lock();
+    let macro_data = macros
+        .iter()
+        .find(|m| m.id == id)
+        .ok_or_else(|| format!("Macro not found: {}", id))?;
+
+    Ok(script::to_script(&macro_data.events))
+}
+
+/// Draw a stored macro's mouse path and clicks into the downloads folder, as
+/// an animated GIF or, with `frames`, a folder of PNGs. Returns the path.
+#[tauri::command]
+async fn export_macro_storyboard(
+    app: tauri::AppHandle,
+    id: String,
+    frames: Option<bool>,
+) -> Result<String, String> {
+    run_blocking(app, move |app| {
+        let state = app.state::<AppState>();
+        let events = state
+            .macros
+            .lock()
+            .iter()
+            .find(|m| m.id == id)
+            .map(|m| m.events.clone())
+            .ok_or_else(|| format!("Macro not found: {}", id))?;
+        let images = storyboard::render(&events)?;
+
+        let paths = state.app_handle.path();
+        let out_dir = paths
+            .download_dir()
+            .or_else(|_| paths.app_data_dir())
+            .map_err(|e| e.to_string())?;
+        let name = format!(
+            "macrox-storyboard-{}-{}",
+            file_name_part(&id),
+            Local::now().format("%Y%m%d-%H%M%S")
+        );
+
+        let path = if frames.unwrap_or(false) {
+            let dir = out_dir.join(name);
+            fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+            for (i, image) in images.iter().enumerate() {
+                image
+                    .save(dir.join(format!("click-{:03}.png", i + 1)))
+                    .map_err(|e| format!("Failed to save storyboard frame: {}", e))?;
+            }
+            dir
+        } else {
+            fs::create_dir_all(&out_dir).map_err(|e| e.to_string())?;
+            let file = out_dir.join(format!("{}.gif", name));
+            fs::write(&file, storyboard::encode_gif(images)?).map_err(|e| e.to_string())?;
+            file
+        };
+        info!("Exported storyboard to {}", path.display());
+        Ok(path.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Events for edited script text, e.g. to save back into a macro
+#[tauri::command]
+fn parse_script_text(text: String) -> Result<Vec<MacroEvent>, String> {
+    script::parse_script(&text)
+}
+
+/// Write a macro into the downloads folder in `format`, or the configured
+/// export format, and return the path
+#[tauri::command]
+async fn export_macro(
+    app: tauri::AppHandle,
+    macro_data: Macro,
+    format: Option<ExportFormat>,
+) -> Result<String, String> {
+    run_blocking(app, move |app| {
+        let format = format.unwrap_or_else(|| load_app_settings_from_store(app).export_format);
+        let bytes = encoding::encode(&macro_data, format)?;
+
+        let paths = app.path();
+        let out_dir = paths
+            .download_dir()
+            .or_else(|_| paths.app_data_dir())
+            .map_err(|e| e.to_string())?;
+        fs::create_dir_all(&out_dir).map_err(|e| e.to_string())?;
+        let file = out_dir.join(format!(
+            "macrox-{}-{}.{}",
+            file_name_part(&macro_data.id),
+            Local::now().format("%Y%m%d-%H%M%S"),
+            encoding::extension(format)
+        ));
+        fs::write(&file, bytes).map_err(|e| e.to_string())?;
+
+        info!("Exported macro {} to {}", macro_data.name, file.display());
+        Ok(file.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// A macro id made safe to put in a file name. Ids come from the frontend
+/// and from imported files, so they may hold path separators.
+fn file_name_part(id: &str) -> String {
+    id.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '.' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Read a macro file in any export format. Without a path there is no file
+/// dialog yet, so nothing is imported.
+#[tauri::command]
+async fn import_macro(
+    app: tauri::AppHandle,
+    path: Option<String>,
+) -> Result<Option<Macro>, String> {
+    let Some(path) = path else {
+        info!("Import macro requested");
+        return Ok(None);
+    };
+    run_blocking(app, move |_| {
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let macro_data = encoding::decode(&bytes).map_err(|e| format!("{}: {}", path, e))?;
+        info!("Imported macro {} from {}", macro_data.name, path);
+        Ok(Some(macro_data))
+    })
+    .await
+}
+
+/// Update global hotkeys
+#[tauri::command]
+fn update_hotkeys(
+    app: tauri::AppHandle,
+    record_start: String,
+    record_stop: String,
+    playback_start: String,
+    playback_stop: String,
+    mark: String,
+) -> Result<(), String> {
+    let settings = HotkeySettings {
+        record_start: shortcut::normalize(&record_start)?,
+        record_stop: shortcut::normalize(&record_stop)?,
+        playback_start: shortcut::normalize(&playback_start)?,
+        playback_stop: shortcut::normalize(&playback_stop)?,
+        mark: shortcut::normalize(&mark)?,
+    };
+    hotkeys::apply_hotkeys(&app, &settings)?;
+    if settings != HotkeySettings::default() {
+        mark_onboarding_step(&app, OnboardingStep::Hotkeys);
+    }
+
+    info!("Hotkeys updated and saved successfully");
+
+    // Save to store
+    let store = app.store(SETTINGS_FILENAME).map_err(|e| e.to_string())?;
+    let _ = store.reload();
+
+    let _ = store.set(
+        "hotkeys".to_string(),
+        serde_json::to_value(&settings).map_err(|e| e.to_string())?,
+    );
+    let _ = store.save();
+
+    Ok(())
+}
+
+/// The canonical form of a hotkey typed by the user, or why it can't be used
+#[tauri::command]
+fn validate_hotkey(binding: String) -> Result<String, String> {
+    shortcut::normalize(&binding)
+}
+
+/// Change the leader hotkey, its timeout and key bindings
+#[tauri::command]
+fn update_leader_settings(
+    app: tauri::AppHandle,
+    mut settings: LeaderSettings,
+) -> Result<(), String> {
+    settings.hotkey = shortcut::normalize(&settings.hotkey)?;
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings.leader = settings;
+    hotkeys::apply_leader(&app, &app_settings.leader)?;
+
+    save_app_settings(&app, &app_settings)?;
+
+    Ok(())
+}
+
+/// Change the quick launcher hotkey
+#[tauri::command]
+fn update_launcher_settings(
+    app: tauri::AppHandle,
+    mut settings: LauncherSettings,
+) -> Result<(), String> {
+    settings.hotkey = shortcut::normalize(&settings.hotkey)?;
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings.launcher = settings;
+    hotkeys::apply_launcher(&app, &app_settings.launcher)?;
+
+    save_app_settings(&app, &app_settings)?;
+
+    Ok(())
+}
+
+/// Turn the retroactive capture on or off, or change its length and hotkey
+#[tauri::command]
+fn update_retro_settings(app: tauri::AppHandle, mut settings: RetroSettings) -> Result<(), String> {
+    settings.hotkey = shortcut::normalize(&settings.hotkey)?;
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings.retro = settings;
+    hotkeys::apply_retro(&app, &app_settings.retro)?;
+    apply_retro_buffer(&app, &app_settings.retro)?;
+
+    save_app_settings(&app, &app_settings)?;
+
+    Ok(())
+}
+
+fn apply_retro_buffer(app: &tauri::AppHandle, settings: &RetroSettings) -> Result<(), String> {
+    let buffer = app.state::<Arc<RetroBuffer>>();
+    buffer.set_seconds(settings.seconds);
+    if settings.enabled {
+        buffer.start(Box::new(RdevSource))
+    } else {
+        buffer.stop();
+        Ok(())
+    }
+}
+
+/// Save the input kept by the retroactive capture as a new macro
+#[tauri::command]
+pub(crate) fn save_retro_recording(app: tauri::AppHandle) -> Result<Macro, String> {
+    let buffer = app.state::<Arc<RetroBuffer>>();
+    if !buffer.is_running() {
+        return Err("Retroactive recording is off, enable it in the settings".to_string());
+    }
+    let events = buffer.take();
+    if events.is_empty() {
+        return Err("Nothing was captured to save".to_string());
+    }
+
+    let now = Utc::now();
+    let seconds = load_app_settings_from_store(&app).retro.seconds;
+    let saved = Macro {
+        id: now.timestamp_millis().to_string(),
+        name: format!(
+            "Last {} seconds ({})",
+            seconds,
+            Local::now().format("%H:%M")
+        ),
+        description: "Saved from the retroactive recording".to_string(),
+        events,
+        recording_settings: RecordingSettings::default(),
+        playback_settings: PlaybackSettings::default(),
+        created_at: now,
+        updated_at: now,
+        display_info: display::current_display_info(&app),
+        expires_at: None,
+        archived: false,
+        author: None,
+        source_url: None,
+        license: None,
+    };
+
+    let state = app.state::<AppState>();
+    let mut macros = state.macros.lock();
+    macros.push(Arc::new(saved.clone()));
+    save_macros_to_store(&state, &macros);
+    let visible: Vec<Arc<Macro>> = macros.iter().filter(|m| !m.archived).cloned().collect();
+    drop(macros);
+    events::emit(&app, BusEventType::MacrosChanged, visible);
+
+    info!("Saved retroactive recording: {}", saved.name);
+    Ok(saved)
+}
+
+/// Give a stored macro its own global hotkey, or remove it with None
+#[tauri::command]
+fn set_macro_hotkey(
+    app: tauri::AppHandle,
+    macro_id: String,
+    hotkey: Option<String>,
+) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings
+        .macro_hotkeys
+        .retain(|h| h.macro_id != macro_id);
+    if let Some(hotkey) = hotkey {
+        let hotkey = shortcut::normalize(&hotkey)?;
+        app_settings
+            .macro_hotkeys
+            .push(MacroHotkey { macro_id, hotkey });
+    }
+    save_app_settings(&app, &app_settings)?;
+    rebind_macro_hotkeys(&app)
+}
+
+/// Register the per-macro hotkeys of the active scene's macros that aren't
+/// archived, and no others
+fn rebind_macro_hotkeys(app: &tauri::AppHandle) -> Result<(), String> {
+    let app_settings = load_app_settings_from_store(app);
+    let scene = app_settings
+        .active_scene
+        .as_ref()
+        .and_then(|id| app_settings.scenes.iter().find(|s| &s.id == id));
+    let active = {
+        let state = app.state::<AppState>();
+        let macros = state.macros.lock();
+        scene::active_hotkeys(&app_settings.macro_hotkeys, &macros, scene)
+    };
+    hotkeys::apply_macro_hotkeys(app, &active)
+}
+
+/// Remove hotkeys, leader keys and scene entries of macros that no longer
+/// exist, and re-register the macro hotkeys so no shortcut outlives its macro
+#[tauri::command]
+fn reconcile_triggers(app: tauri::AppHandle) -> Result<TriggerCleanup, String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    let (mut cleanup, registered) = {
+        let state = app.state::<AppState>();
+        let macros = state.macros.lock();
+        let ids: HashSet<&str> = macros.iter().map(|m| m.id.as_str()).collect();
+        let registered: Vec<String> = app
+            .state::<HotkeyManager>()
+            .macro_ids()
+            .into_iter()
+            .filter(|id| !ids.contains(id.as_str()))
+            .collect();
+        (
+            triggers::remove_orphans(&mut app_settings, &ids),
+            registered,
+        )
+    };
+
+    if !cleanup.is_empty() {
+        info!(
+            "Removed triggers of deleted macros: {} hotkeys, {} leader keys, {} scene entries",
+            cleanup.macro_hotkeys.len(),
+            cleanup.leader_keys.len(),
+            cleanup.scene_entries
+        );
+        save_app_settings(&app, &app_settings)?;
+    }
+    cleanup.registered_shortcuts = registered;
+    rebind_macro_hotkeys(&app)?;
+    Ok(cleanup)
+}
+
+/// Add a scene, or replace the one with the same id
+#[tauri::command]
+fn save_scene(app: tauri::AppHandle, scene: Scene) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    let is_active = app_settings.active_scene.as_ref() == Some(&scene.id);
+    match app_settings.scenes.iter_mut().find(|s| s.id == scene.id) {
+        Some(existing) => *existing = scene,
+        None => app_settings.scenes.push(scene),
+    }
+    save_app_settings(&app, &app_settings)?;
+    if is_active {
+        rebind_macro_hotkeys(&app)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn delete_scene(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings.scenes.retain(|s| s.id != id);
+    let was_active = app_settings.active_scene.as_ref() == Some(&id);
+    if was_active {
+        app_settings.active_scene = None;
+    }
+    save_app_settings(&app, &app_settings)?;
+    if was_active {
+        rebind_macro_hotkeys(&app)?;
+    }
+    Ok(())
+}
+
+/// Switch the registered macro hotkeys to a scene's, or to every macro's with None
+#[tauri::command]
+fn activate_scene(app: tauri::AppHandle, id: Option<String>) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    if let Some(id) = &id {
+        let scene = app_settings
+            .scenes
+            .iter()
+            .find(|s| &s.id == id)
+            .ok_or_else(|| format!("Scene not found: {}", id))?;
+        info!("Activating scene: {}", scene.name);
+    }
+    app_settings.active_scene = id;
+    save_app_settings(&app, &app_settings)?;
+    rebind_macro_hotkeys(&app)
+}
+
+/// Change the Chrome DevTools connection used for browser steps
+#[tauri::command]
+fn update_browser_settings(app: tauri::AppHandle, settings: BrowserSettings) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings.browser = settings;
+    browser::apply_settings(&app, &app_settings.browser);
+
+    save_app_settings(&app, &app_settings)?;
+
+    Ok(())
+}
+
+/// Choose the engine the player injects input with
+#[tauri::command]
+fn update_input_backend(app: tauri::AppHandle, backend: InputBackend) -> Result<(), String> {
+    // Fail now rather than on the next playback
+    input::new_sink(backend)?;
+
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings.input_backend = backend;
+
+    save_app_settings(&app, &app_settings)?;
+
+    info!("Input backend set to {:?}", backend);
+    Ok(())
+}
+
+/// Turn the default for asking before destructive key combinations on or off
+#[tauri::command]
+fn update_destructive_confirm(app: tauri::AppHandle, enabled: bool) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings.confirm_destructive = enabled;
+
+    save_app_settings(&app, &app_settings)?;
+    Ok(())
+}
+
+/// Open or close the quick launcher palette
+#[tauri::command]
+fn toggle_launcher(app: tauri::AppHandle) -> Result<(), String> {
+    launcher::toggle(&app)
+}
+
+/// Show the window practice mode plays into
+#[tauri::command]
+fn open_practice_screen(app: tauri::AppHandle) -> Result<(), String> {
+    practice::open(&app)
+}
+
+/// Play a macro picked in the quick launcher, closing it first
+#[tauri::command]
+fn launch_macro(app: tauri::AppHandle, id: String) {
+    launcher::launch(&app, &id);
+}
+
+/// Get bus events newer than `seq`, so a reloaded frontend can catch up
+#[tauri::command]
+fn get_events_since(seq: u64, bus: State<'_, EventBus>) -> Vec<BusEvent> {
+    bus.events_since(seq)
+}
+
+/// Get current hotkeys
+#[tauri::command]
+fn get_hotkeys(app: tauri::AppHandle) -> Result<HotkeySettings, String> {
+    Ok(load_hotkeys_from_store(&app))
+}
+
+/// Update app settings
+#[tauri::command]
+fn update_app_settings(app: tauri::AppHandle, settings: serde_json::Value) -> Result<(), String> {
+    let serde_json::Value::Object(changes) = settings else {
+        return Err("App settings must be an object".to_string());
+    };
+
+    // The window code sends only the fields it changes; integrations are
+    // changed through their own commands
+    let stored = load_app_settings_from_store(&app);
+    let mut merged = serde_json::to_value(&stored).map_err(|e| e.to_string())?;
+    for (key, value) in changes {
+        if !SEPARATELY_UPDATED_SETTINGS.contains(&key.as_str()) {
+            merged[key] = value;
+        }
+    }
+    let settings: AppSettings =
+        serde_json::from_value(merged).map_err(|e| format!("Invalid app settings: {}", e))?;
+
+    // Update window state immediately
+    if let Some(window) = app.get_webview_window("main") {
+        window
+            .set_always_on_top(settings.always_on_top)
+            .map_err(|e| e.to_string())?;
+    }
+
+    save_app_settings(&app, &settings)?;
+
+    Ok(())
+}
+
+/// Change the MQTT broker connection and reconnect
+#[tauri::command]
+fn update_mqtt_settings(app: tauri::AppHandle, settings: MqttSettings) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings.mqtt = settings;
+    mqtt::apply_settings(&app, &app_settings.mqtt);
+
+    save_app_settings(&app, &app_settings)?;
+
+    Ok(())
+}
+
+/// Enable, disable or move the local control server
+#[tauri::command]
+fn update_control_server_settings(
+    app: tauri::AppHandle,
+    settings: ControlServerSettings,
+) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    // Tokens only change through mint/revoke_control_token
+    let tokens = std::mem::take(&mut app_settings.control_server.tokens);
+    app_settings.control_server = ControlServerSettings { tokens, ..settings };
+    ensure_control_token(&mut app_settings.control_server)?;
+    control_server::apply_settings(&app, &app_settings.control_server);
+
+    save_app_settings(&app, &app_settings)?;
+
+    Ok(())
+}
+
+/// A new token with a random secret and id
+fn new_control_token(name: &str, scope: TokenScope) -> Result<ControlToken, String> {
+    let mut random = [0u8; 40];
+    getrandom::getrandom(&mut random).map_err(|e| format!("Failed to create token: {}", e))?;
+    let (id, secret) = random.split_at(8);
+    Ok(ControlToken {
+        id: URL_SAFE_NO_PAD.encode(id),
+        name: name.to_string(),
+        token: URL_SAFE_NO_PAD.encode(secret),
+        scope,
+        created_at: Utc::now(),
+    })
+}
+
+/// The server refuses requests without a token, so an enabled server with
+/// none gets a full access one to start from. Returns whether it was created.
+fn ensure_control_token(settings: &mut ControlServerSettings) -> Result<bool, String> {
+    if !settings.enabled || !settings.tokens.is_empty() {
+        return Ok(false);
+    }
+    settings
+        .tokens
+        .push(new_control_token("Default", TokenScope::Full)?);
+    info!("Minted default control token");
+    Ok(true)
+}
+
+/// Create a control server token limited to `scope`
+#[tauri::command]
+fn mint_control_token(
+    app: tauri::AppHandle,
+    name: String,
+    scope: TokenScope,
+) -> Result<ControlToken, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Give the token a name".to_string());
+    }
+    if matches!(&scope, TokenScope::Trigger { macros } if macros.is_empty()) {
+        return Err("Choose at least one macro the token may play".to_string());
+    }
+
+    let token = new_control_token(name, scope)?;
+
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings.control_server.tokens.push(token.clone());
+    save_app_settings(&app, &app_settings)?;
+    info!("Minted control token '{}'", token.name);
+
+    Ok(token)
+}
+
+/// Invalidate a control server token, closing connections that use it.
+/// Revoking the last one leaves the server refusing every request.
+#[tauri::command]
+fn revoke_control_token(app: tauri::AppHandle, id: String) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    let tokens = &mut app_settings.control_server.tokens;
+    let before = tokens.len();
+    tokens.retain(|t| t.id != id);
+    if tokens.len() == before {
+        return Err(format!("Control token not found: {}", id));
+    }
+    save_app_settings(&app, &app_settings)?;
+    info!("Revoked control token {}", id);
+
+    Ok(())
+}
+
+/// Change how live updates are batched and throttled
+#[tauri::command]
+fn update_emitter_settings(
+    app: tauri::AppHandle,
+    settings: EmitterSettings,
+    bus: State<'_, EventBus>,
+) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings.emitter = settings;
+    bus.set_settings(app_settings.emitter.clone());
+
+    save_app_settings(&app, &app_settings)?;
+
+    Ok(())
+}
+
+/// Change the log level
+#[tauri::command]
+fn update_logging_settings(app: tauri::AppHandle, settings: LoggingSettings) -> Result<(), String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    app_settings.logging = settings;
+    logging::apply_settings(&app_settings.logging);
+
+    save_app_settings(&app, &app_settings)?;
+
+    Ok(())
+}
+
+/// Zip logs and anonymized settings for a bug report, returning the file path
+#[tauri::command]
+async fn export_diagnostics(app: tauri::AppHandle) -> Result<String, String> {
+    run_blocking(app, move |app| {
+        diagnostics::export(app).map(|path| path.to_string_lossy().to_string())
+    })
+    .await
+}
+
+/// Check shortcuts, input, storage and permissions, e.g. on first launch
+#[tauri::command]
+fn run_health_check(app: tauri::AppHandle) -> HealthReport {
+    health::run(&app)
+}
+
+/// Get current app settings
+#[tauri::command]
+fn get_app_settings(app: tauri::AppHandle) -> Result<AppSettings, String> {
+    Ok(load_app_settings_from_store(&app))
+}
+
+/// Onboarding state with the permissions step ticked off once the OS grants them
+fn refresh_onboarding(app: &tauri::AppHandle) -> OnboardingState {
+    let mut app_settings = load_app_settings_from_store(app);
+    if !app_settings.onboarding.is_done(OnboardingStep::Permissions)
+        && permissions::input_access_granted()
+    {
+        let _ = app_settings
+            .onboarding
+            .complete(OnboardingStep::Permissions);
+        if let Err(e) = save_app_settings(app, &app_settings) {
+            error!("Failed to save onboarding progress: {}", e);
+        }
+    }
+    app_settings.onboarding
+}
+
+/// Tick off a step reached through normal use, if it is open and unlocked
+fn mark_onboarding_step(app: &tauri::AppHandle, step: OnboardingStep) {
+    let mut app_settings = load_app_settings_from_store(app);
+    if let Ok(true) = app_settings.onboarding.complete(step) {
+        info!("Onboarding step done: {}", step.label());
+        if let Err(e) = save_app_settings(app, &app_settings) {
+            error!("Failed to save onboarding progress: {}", e);
+        }
+    }
+}
+
+/// Which first-run steps are done and which one the UI should show next
+#[tauri::command]
+fn get_onboarding_state(app: tauri::AppHandle) -> OnboardingStatus {
+    refresh_onboarding(&app).status()
+}
+
+/// Mark a first-run step done
+#[tauri::command]
+fn complete_onboarding_step(
+    app: tauri::AppHandle,
+    step: OnboardingStep,
+) -> Result<OnboardingStatus, String> {
+    let mut app_settings = load_app_settings_from_store(&app);
+    if step == OnboardingStep::Permissions && !permissions::input_access_granted() {
+        return Err("Allow MacroX under Privacy & Security > Accessibility first".to_string());
+    }
+    if app_settings.onboarding.complete(step)? {
+        save_app_settings(&app, &app_settings)?;
+    }
+    Ok(app_settings.onboarding.status())
+}
+
+/// Bring the main window to the front
+fn focus_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Play a stored macro, looked up by id or name, without blocking the caller
+pub(crate) fn play_stored_macro_in_background(app: &tauri::AppHandle, id_or_name: &str) {
+    let state = app.state::<AppState>();
+    let found = state
+        .macros
+        .lock()
+        .iter()
+        .find(|m| !m.archived && (m.id == id_or_name || m.name == id_or_name))
+        .cloned();
+
+    let Some(macro_data) = found else {
+        warn!("No macro matching '{}'", id_or_name);
+        return;
+    };
+    if let Err(e) = check_can_play(&state) {
+        warn!("{}", e);
+        return;
+    }
+
+    let library = state.macros.lock().clone();
+    let (control, lease) = match state.pool.claim(&macro_data, &library, &state.playback) {
+        Ok(claimed) => claimed,
+        Err(e) => {
+            warn!("{}", e);
+            return;
+        }
+    };
+    let current_display = display::current_display_info(app);
+    let options = RunOptions::load(app, &control, &macro_data);
+    let handle = app.clone();
+    thread::spawn(move || {
+        info!("Playing macro from command line: {}", macro_data.name);
+        let result = play_and_record(
+            &handle,
+            &control,
+            &macro_data,
+            current_display.as_ref(),
+            options,
+        );
+        drop(lease);
+        match result {
+            Ok(outputs) => {
+                note_run(&handle.state::<AppState>(), &macro_data.id);
+                publish_outputs(&handle, &macro_data.id, &outputs);
+            }
+            Err(e) => warn!("Playback failed: {}", e),
+        }
+    });
+}
+
+/// Handle command line arguments, either our own or forwarded from a second instance
+///
+/// Supported: `--play <macro id or name>`
+fn handle_cli_args(app: &tauri::AppHandle, args: &[String]) {
+    if let Some(target) = args
+        .iter()
+        .position(|arg| arg == "--play")
+        .and_then(|i| args.get(i + 1))
+    {
+        play_stored_macro_in_background(app, target);
+    }
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let mut builder = tauri::Builder::default();
+
+    // Must be registered first: a second launch forwards its arguments here and exits,
+    // so we never end up with duplicate global hotkeys or competing store writes
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            info!("Second instance launched with args: {:?}", args);
+            focus_main_window(app);
+            handle_cli_args(app, &args);
+        }));
+    }
+
+    builder
+        .plugin(logging::plugin())
+        .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_store::Builder::new().build())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .setup(|app| {
+            app.manage(EventBus::default());
+            app.manage(confirm::Confirmations::default());
+            app.manage(HotkeyManager::default());
+
+            // Try to unregister any existing shortcuts first
+            let _ = app.global_shortcut().unregister_all();
+
+            // Load saved hotkeys
+            let hotkeys = load_hotkeys_from_store(app.handle());
+            info!("Loaded hotkeys: {:?}", hotkeys);
+
+            // Load and apply app settings
+            let mut app_settings = load_app_settings_from_store(app.handle());
+            // Servers enabled before tokens existed were open to any local client
+            match ensure_control_token(&mut app_settings.control_server) {
+                Ok(true) => {
+                    if let Err(e) = save_app_settings(app.handle(), &app_settings) {
+                        warn!("{}", e);
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => warn!("{}", e),
+            }
+            app.state::<EventBus>()
+                .set_settings(app_settings.emitter.clone());
+            logging::apply_settings(&app_settings.logging);
+            debug!(settings = ?app_settings, "Loaded app settings");
+
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.set_always_on_top(app_settings.always_on_top);
+                if app_settings.start_minimized {
+                    let _ = window.minimize();
+                }
+                window_state::restore_and_track(&window, app_settings.window_geometry.as_ref());
+            }
+
+            // Register global shortcuts
+            hotkeys::apply_hotkeys(app.handle(), &hotkeys).unwrap_or_else(|e| warn!("{}", e));
+            hotkeys::apply_leader(app.handle(), &app_settings.leader)
+                .unwrap_or_else(|e| warn!("{}", e));
+            hotkeys::apply_launcher(app.handle(), &app_settings.launcher)
+                .unwrap_or_else(|e| warn!("{}", e));
+            hotkeys::apply_retro(app.handle(), &app_settings.retro)
+                .unwrap_or_else(|e| warn!("{}", e));
+            app.manage(Arc::new(RetroBuffer::new(app_settings.retro.seconds)));
+            apply_retro_buffer(app.handle(), &app_settings.retro)
+                .unwrap_or_else(|e| error!("Failed to start retroactive recording: {}", e));
+
+            info!("Hotkey setup completed");
+
+            // Load macros
+            let (loaded_macros, loaded_runs) = load_macros_from_store(app.handle());
+            info!("Loaded {} macros from store", loaded_macros.len());
+            let macros = Arc::new(Mutex::new(loaded_macros));
+            let last_runs = Arc::new(Mutex::new(loaded_runs));
+
+            let macros_path =
+                tauri_plugin_store::resolve_store_path(app.handle(), MACROS_FILENAME)?;
+            let persister = MacroPersister::spawn(macros_path.clone(), Arc::clone(&last_runs));
+
+            // Pick up edits made to the macros file outside the app
+            let macros_watcher = watcher::watch_macros_file(
+                app.handle(),
+                macros_path,
+                Arc::clone(&macros),
+                persister.clone(),
+            )
+            .map_err(|e| error!("Failed to watch macros file: {}", e))
+            .ok();
+
+            // Forward playback progress, including the ETA, to the frontend
+            let playback = Arc::new(PlaybackControl::default());
+            let handle = app.handle().clone();
+            playback.set_observer(Arc::new(move |status: &PlaybackStatus| {
+                events::emit_latest(&handle, BusEventType::PlaybackProgress, status);
+                mqtt::publish_status(&handle, status);
+            }));
+
+            // Browser steps are always playable; they fail with a hint while the integration is off
+            let browser_plugin = Arc::new(BrowserPlugin::new(app_settings.browser.clone()));
+            let mut plugins = PluginRegistry::default();
+            if let Err(e) = plugins.register(browser_plugin.clone()) {
+                error!("Failed to register browser plugin: {}", e);
+            }
+            app.manage(browser_plugin);
+
+            app.manage(AppState {
+                macros,
+                last_runs,
+                recorder: Arc::new(Mutex::new(None)),
+                last_recording_warnings: Mutex::new(RecordingWarningSummary::default()),
+                playback,
+                pool: Arc::new(PlaybackPool::default()),
+                app_handle: app.handle().clone(),
+                persister,
+                plugins: Arc::new(plugins),
+                history: Arc::new(RunHistory::default()),
+                _macros_watcher: macros_watcher,
+            });
+            restore_recovery_file(app.handle());
+
+            archive_stale_macros(&app.state::<AppState>());
+            if let Err(e) = reconcile_triggers(app.handle().clone()) {
+                warn!("{}", e);
+            }
+
+            app.manage(MqttBridge::default());
+            mqtt::apply_settings(app.handle(), &app_settings.mqtt);
+            app.manage(ControlServer::default());
+            control_server::apply_settings(app.handle(), &app_settings.control_server);
+
+            let args: Vec<String> = std::env::args().collect();
+            handle_cli_args(app.handle(), &args);
+
+            updates::check_on_startup(app.handle());
+
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            start_recording,
+            stop_recording,
+            record_from_step,
+            is_recording,
+            get_recording_warnings,
+            play_macro,
+            get_playback_status,
+            get_run_history,
+            estimate_macro_duration,
+            get_display_info,
+            list_input_devices,
+            get_cursor_position,
+            get_pixel_color,
+            get_wayland_capabilities,
+            get_platform_capabilities,
+            is_elevated,
+            relaunch_elevated,
+            calibrate_image_target,
+            calibrate_mouse,
+            generate_keymap_test_macro,
+            verify_keymap,
+            check_for_updates,
+            pause_playback,
+            resume_playback,
+            stop_playback,
+            get_running_macros,
+            answer_confirmation,
+            save_macro,
+            load_all_macros,
+            delete_macro,
+            reconcile_triggers,
+            list_archived_macros,
+            restore_macro,
+            retime_macro,
+            update_macro_metadata,
+            align_mouse_events,
+            diff_macros,
+            transcribe_macro,
+            lint_macro,
+            export_macro_as_text,
+            export_macro_storyboard,
+            parse_script_text,
+            get_plugin_event_types,
+            export_macro,
+            import_macro,
+            update_hotkeys,
+            validate_hotkey,
+            get_hotkeys,
+            get_events_since,
+            update_app_settings,
+            update_mqtt_settings,
+            update_control_server_settings,
+            mint_control_token,
+            revoke_control_token,
+            update_emitter_settings,
+            update_logging_settings,
+            update_leader_settings,
+            update_launcher_settings,
+            update_retro_settings,
+            save_retro_recording,
+            set_macro_hotkey,
+            save_scene,
+            delete_scene,
+            activate_scene,
+            update_browser_settings,
+            update_input_backend,
+            update_destructive_confirm,
+            toggle_launcher,
+            open_practice_screen,
+            launch_macro,
+            export_diagnostics,
+            run_health_check,
+            get_onboarding_state,
+            complete_onboarding_step,
+            get_missing_privilege_grants,
+            grant_privileged_events,
+            revoke_privileged_events,
+            get_app_settings
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                shutdown(app_handle);
+            }
+        });
+}
+
+/// Stop recording and playback before the process exits, so no input is
+/// left held down and an unfinished recording isn't lost
+fn shutdown(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+
+    state.playback.stop();
+    state.pool.stop_all();
+    let waiting_since = Instant::now();
+    while !(matches!(state.playback.status(), PlaybackStatus::Idle) && state.pool.is_empty())
+        && waiting_since.elapsed() < SHUTDOWN_WAIT
+    {
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    if let Some(mut recorder) = state.recorder.lock().take() {
+        let events = recorder.stop();
+        if !events.is_empty() {
+            let session = recorder.session(events);
+            if let Err(e) = save_recovery_file(app, recorder.settings(), session) {
+                error!("Failed to save unfinished recording: {}", e);
+            }
+        }
+    }
+
+    // Don't lose a debounced save that hasn't hit the disk yet
+    state.persister.flush();
+}
+
+fn save_recovery_file(
+    app: &tauri::AppHandle,
+    settings: &RecordingSettings,
+    session: RecordingSession,
+) -> Result<(), String> {
+    let now = Utc::now();
+    let recovered = Macro {
+        id: now.timestamp_millis().to_string(),
+        name: format!("{} (recovered)", session.metadata.suggested_name),
+        description: "Recording was still running when MacroX exited".to_string(),
+        events: session.events,
+        recording_settings: settings.clone(),
+        playback_settings: PlaybackSettings::default(),
+        created_at: now,
+        updated_at: now,
+        display_info: display::current_display_info(app),
+        expires_at: None,
+        archived: false,
+        author: None,
+        source_url: None,
+        license: None,
+    };
+
+    let path = tauri_plugin_store::resolve_store_path(app, RECOVERY_FILENAME)
+        .map_err(|e| e.to_string())?;
+    let bytes = serde_json::to_vec_pretty(&recovered).map_err(|e| e.to_string())?;
+    storage::write_atomic(&path, &bytes).map_err(|e| e.to_string())?;
+    info!("Saved unfinished recording to {}", path.display());
+    Ok(())
+}
+
+/// Add a recording saved by `shutdown` to the library
+fn restore_recovery_file(app: &tauri::AppHandle) {
+    let Ok(path) = tauri_plugin_store::resolve_store_path(app, RECOVERY_FILENAME) else {
+        return;
+    };
+    let Ok(bytes) = fs::read(&path) else {
+        return;
+    };
+
+    match serde_json::from_slice::<Macro>(&bytes) {
+        Ok(recovered) => {
+            info!("Restored unfinished recording: {}", recovered.name);
+            let state = app.state::<AppState>();
+            let mut macros = state.macros.lock();
+            macros.push(Arc::new(recovered));
+            save_macros_to_store(&state, &macros);
+        }
+        Err(e) => error!("Discarding unreadable recovery file: {}", e),
+    }
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,377 @@
+// Browser integration module
+//
+// Talks to Chrome (or another Chromium browser) over the DevTools Protocol so
+// clicks in web pages are recorded and replayed as DOM selectors instead of
+// screen coordinates. Chrome has to be started with
+// `--remote-debugging-port=<port>`, and only the most recently focused tab is
+// used. Event types:
+//   BrowserClick     {"selector": "#submit"}
+//   BrowserType      {"selector": "input[name=\"q\"]", "text": "..."}
+//   BrowserNavigate  {"url": "https://..."}
+//   BrowserReadText  {"selector": "#order-number", "outputVar": "orderNumber"}
+// Selector steps wait up to "timeoutMs" (default 5000) for the element.
+
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+use tracing::{error, warn};
+use tungstenite::{Message, WebSocket};
+
+use crate::macrox_core::plugin::EventPlugin;
+use crate::macrox_core::types::{BrowserSettings, MacroEvent};
+
+/// How often clicks captured in the page are collected while recording
+const CAPTURE_POLL: Duration = Duration::from_millis(250);
+/// Wait for a selector when the event doesn't set timeoutMs
+const DEFAULT_SELECTOR_TIMEOUT_MS: u64 = 5_000;
+/// Extra time a DevTools call gets on top of the page-side wait
+const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Installs a click listener in the page (once per document) and returns the
+/// clicks captured since the last call. Clicks are kept in sessionStorage so
+/// a click that navigates away isn't lost.
+const CAPTURE_SCRIPT: &str = r#"(() => {
+  window.__macroxCapturing = true;
+  if (!window.__macroxListener) {
+    window.__macroxListener = true;
+    const selectorFor = (el) => {
+      const tag = el.tagName.toLowerCase();
+      if (el.id) return '#' + CSS.escape(el.id);
+      for (const attr of ['data-testid', 'name', 'aria-label']) {
+        const value = el.getAttribute(attr);
+        if (value) return tag + '[' + attr + '=' + JSON.stringify(value) + ']';
+      }
+      const parent = el.parentElement;
+      if (!parent) return tag;
+      const index = Array.from(parent.children).filter((c) => c.tagName === el.tagName).indexOf(el) + 1;
+      return selectorFor(parent) + ' > ' + tag + ':nth-of-type(' + index + ')';
+    };
+    document.addEventListener('mousedown', (e) => {
+      if (!window.__macroxCapturing || !(e.target instanceof Element)) return;
+      const clicks = JSON.parse(sessionStorage.getItem('__macroxClicks') || '[]');
+      clicks.push({ selector: selectorFor(e.target), time: Date.now() });
+      sessionStorage.setItem('__macroxClicks', JSON.stringify(clicks));
+    }, true);
+  }
+  const clicks = JSON.parse(sessionStorage.getItem('__macroxClicks') || '[]');
+  sessionStorage.removeItem('__macroxClicks');
+  return clicks;
+})()"#;
+
+const STOP_CAPTURE_SCRIPT: &str = "window.__macroxCapturing = false";
+
+#[derive(Deserialize)]
+struct CapturedClick {
+    selector: String,
+    time: u64,
+}
+
+/// Plays and records the Browser* event types
+pub struct BrowserPlugin {
+    settings: Mutex<BrowserSettings>,
+    capture: Mutex<Option<Capture>>,
+}
+
+/// A running click capture: the polling thread and its stop flag
+struct Capture {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Vec<MacroEvent>>,
+}
+
+impl BrowserPlugin {
+    pub fn new(settings: BrowserSettings) -> Self {
+        Self {
+            settings: Mutex::new(settings),
+            capture: Mutex::new(None),
+        }
+    }
+
+    fn port(&self) -> Result<u16, String> {
+        let settings = self.settings.lock();
+        if !settings.enabled {
+            return Err("Browser integration is off, enable it in the settings".to_string());
+        }
+        Ok(settings.port)
+    }
+}
+
+/// Use new connection settings for the next browser step or recording
+pub fn apply_settings(app: &tauri::AppHandle, settings: &BrowserSettings) {
+    if let Some(plugin) = app.try_state::<Arc<BrowserPlugin>>() {
+        *plugin.settings.lock() = settings.clone();
+    }
+}
+
+impl EventPlugin for BrowserPlugin {
+    fn name(&self) -> &str {
+        "browser"
+    }
+
+    fn event_types(&self) -> Vec<String> {
+        vec![
+            "BrowserClick".to_string(),
+            "BrowserType".to_string(),
+            "BrowserNavigate".to_string(),
+            "BrowserReadText".to_string(),
+        ]
+    }
+
+    fn play(&self, event: &MacroEvent) -> Result<(), String> {
+        self.play_with_output(event).map(|_| ())
+    }
+
+    fn play_with_output(&self, event: &MacroEvent) -> Result<Option<String>, String> {
+        let mut tab = Tab::connect(self.port()?)?;
+        let field = |name: &str| {
+            event
+                .data
+                .get(name)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| format!("{} needs \"{}\"", event.event_type, name))
+        };
+        let timeout_ms = event
+            .data
+            .get("timeoutMs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_SELECTOR_TIMEOUT_MS);
+
+        match event.event_type.as_str() {
+            "BrowserClick" => {
+                let script = element_script(field("selector")?, timeout_ms, "el.click();");
+                tab.evaluate(&script, timeout_ms).map(|_| None)
+            }
+            "BrowserType" => {
+                let action = format!(
+                    "el.focus(); el.value = {}; \
+                     el.dispatchEvent(new Event('input', {{ bubbles: true }})); \
+                     el.dispatchEvent(new Event('change', {{ bubbles: true }}));",
+                    Value::from(field("text")?)
+                );
+                let script = element_script(field("selector")?, timeout_ms, &action);
+                tab.evaluate(&script, timeout_ms).map(|_| None)
+            }
+            "BrowserNavigate" => {
+                let result = tab.call("Page.navigate", json!({ "url": field("url")? }), 0)?;
+                match result.get("errorText").and_then(|e| e.as_str()) {
+                    Some(error) => Err(format!("Navigation failed: {}", error)),
+                    None => Ok(None),
+                }
+            }
+            "BrowserReadText" => {
+                // Form fields hold their text in value, everything else in innerText
+                let action = "resolve('value' in el ? String(el.value) : el.innerText.trim());";
+                let script = element_script(field("selector")?, timeout_ms, action);
+                let text = tab.evaluate(&script, timeout_ms)?;
+                Ok(text.as_str().map(str::to_string))
+            }
+            other => Err(format!("Unknown browser event: {}", other)),
+        }
+    }
+
+    fn start_recording(&self) {
+        let Ok(port) = self.port() else {
+            return;
+        };
+        let started = now_ms();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = Arc::clone(&stop);
+
+        let thread = thread::spawn(move || {
+            let mut tab: Option<Tab> = None;
+            let mut events = Vec::new();
+            let mut reported = false;
+            loop {
+                // One last collection after the stop request picks up the final clicks
+                let last = stopped.load(Ordering::SeqCst);
+                if tab.is_none() {
+                    match Tab::connect(port) {
+                        Ok(connected) => tab = Some(connected),
+                        Err(e) if !reported => {
+                            warn!("Browser capture unavailable: {}", e);
+                            reported = true;
+                        }
+                        Err(_) => {}
+                    }
+                }
+                if let Some(current) = tab.as_mut() {
+                    match current.evaluate(CAPTURE_SCRIPT, 0) {
+                        Ok(value) => {
+                            let clicks: Vec<CapturedClick> =
+                                serde_json::from_value(value).unwrap_or_default();
+                            events.extend(clicks.into_iter().filter(|c| c.time >= started).map(
+                                |c| MacroEvent {
+                                    event_type: "BrowserClick".to_string(),
+                                    timestamp: c.time,
+                                    data: json!({ "selector": c.selector, "replacesClick": true }),
+                                },
+                            ));
+                        }
+                        // The tab was closed or navigated mid-call; reconnect next time
+                        Err(e) => {
+                            error!("Browser capture failed: {}", e);
+                            tab = None;
+                        }
+                    }
+                }
+                if last {
+                    if let Some(current) = tab.as_mut() {
+                        let _ = current.evaluate(STOP_CAPTURE_SCRIPT, 0);
+                    }
+                    return events;
+                }
+                thread::sleep(CAPTURE_POLL);
+            }
+        });
+
+        *self.capture.lock() = Some(Capture { stop, thread });
+    }
+
+    fn stop_recording(&self) -> Vec<MacroEvent> {
+        let Some(capture) = self.capture.lock().take() else {
+            return Vec::new();
+        };
+        capture.stop.store(true, Ordering::SeqCst);
+        capture.thread.join().unwrap_or_default()
+    }
+}
+
+/// A promise that waits for `selector`, scrolls it into view and runs `action` on `el`
+fn element_script(selector: &str, timeout_ms: u64, action: &str) -> String {
+    format!(
+        r#"new Promise((resolve, reject) => {{
+  const selector = {selector};
+  const deadline = Date.now() + {timeout_ms};
+  const attempt = () => {{
+    const el = document.querySelector(selector);
+    if (el) {{
+      el.scrollIntoView({{ block: 'center' }});
+      {action}
+      resolve(true);
+    }} else if (Date.now() > deadline) {{
+      reject(new Error('No element matches ' + selector));
+    }} else {{
+      setTimeout(attempt, 100);
+    }}
+  }};
+  attempt();
+}})"#,
+        selector = Value::from(selector),
+    )
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// A DevTools connection to one browser tab
+struct Tab {
+    socket: WebSocket<TcpStream>,
+    next_id: u64,
+}
+
+impl Tab {
+    /// Connect to the most recently focused tab
+    fn connect(port: u16) -> Result<Self, String> {
+        let targets = get_json(port, "/json/list")?;
+        let url = targets
+            .as_array()
+            .and_then(|targets| targets.iter().find(|t| t["type"] == "page"))
+            .and_then(|t| t["webSocketDebuggerUrl"].as_str())
+            .ok_or_else(|| format!("No browser tab found on debugging port {}", port))?;
+
+        let stream = TcpStream::connect(("127.0.0.1", port))
+            .map_err(|e| format!("Can't reach the browser on port {}: {}", port, e))?;
+        let (socket, _) = tungstenite::client(url, stream).map_err(|e| e.to_string())?;
+        Ok(Self { socket, next_id: 1 })
+    }
+
+    /// Send one DevTools command and wait for its result, skipping other messages
+    fn call(&mut self, method: &str, params: Value, wait_ms: u64) -> Result<Value, String> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.socket
+            .get_mut()
+            .set_read_timeout(Some(CALL_TIMEOUT + Duration::from_millis(wait_ms)))
+            .map_err(|e| e.to_string())?;
+
+        let request = json!({ "id": id, "method": method, "params": params });
+        self.socket
+            .send(Message::Text(request.to_string()))
+            .map_err(|e| e.to_string())?;
+
+        loop {
+            let message = match self.socket.read().map_err(|e| e.to_string())? {
+                Message::Text(text) => text,
+                Message::Close(_) => return Err("Browser closed the connection".to_string()),
+                _ => continue,
+            };
+            let Ok(mut reply) = serde_json::from_str::<Value>(&message) else {
+                continue;
+            };
+            if reply["id"] != id {
+                continue;
+            }
+            if let Some(error) = reply.get("error") {
+                return Err(format!(
+                    "{} failed: {}",
+                    method,
+                    error["message"].as_str().unwrap_or("unknown error")
+                ));
+            }
+            return Ok(reply["result"].take());
+        }
+    }
+
+    /// Evaluate JavaScript in the page, awaiting promises, and return its value
+    fn evaluate(&mut self, expression: &str, wait_ms: u64) -> Result<Value, String> {
+        let mut result = self.call(
+            "Runtime.evaluate",
+            json!({ "expression": expression, "returnByValue": true, "awaitPromise": true }),
+            wait_ms,
+        )?;
+        if let Some(details) = result.get("exceptionDetails") {
+            let message = details["exception"]["description"]
+                .as_str()
+                .or_else(|| details["text"].as_str())
+                .unwrap_or("script error");
+            return Err(message.to_string());
+        }
+        Ok(result["result"]["value"].take())
+    }
+}
+
+/// GET a JSON document from the browser's DevTools HTTP endpoint
+fn get_json(port: u16, path: &str) -> Result<Value, String> {
+    let mut stream = TcpStream::connect(("127.0.0.1", port))
+        .map_err(|e| format!("Can't reach the browser on port {}: {}", port, e))?;
+    stream
+        .set_read_timeout(Some(CALL_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nConnection: close\r\n\r\n",
+        path, port
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|e| e.to_string())?;
+    let body = response
+        .split_once("\r\n\r\n")
+        .map(|(_, body)| body)
+        .ok_or_else(|| "Malformed response from the browser".to_string())?;
+    serde_json::from_str(body).map_err(|e| format!("Invalid JSON from the browser: {}", e))
+}
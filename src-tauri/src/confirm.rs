@@ -0,0 +1,118 @@
+// Playback confirmation module
+//
+// Lets the player ask the user before a destructive key combination or
+// before an "infinite" run goes past the repeat limit. The
+// playback thread publishes a confirmation request on the event bus and
+// blocks until the frontend answers, playback is stopped, or the request
+// times out; anything but an explicit yes stops the macro.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tracing::info;
+
+use crate::events::{self, BusEventType};
+use crate::macrox_core::player::{AskUser, PlaybackControl};
+
+/// Unanswered requests count as refused after this long
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often a waiting request checks whether playback was stopped
+const STOP_POLL: Duration = Duration::from_millis(200);
+
+/// Requests waiting for an answer from the frontend
+#[derive(Default)]
+pub struct Confirmations {
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, Sender<bool>>>,
+}
+
+/// What the player is asking about
+#[derive(Debug, Clone, Copy)]
+pub enum ConfirmKind {
+    /// A key combination that deletes, closes or similar
+    Destructive,
+    /// Another round of repetitions past the infinite repeat limit
+    RepeatLimit,
+}
+
+impl ConfirmKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ConfirmKind::Destructive => "destructive",
+            ConfirmKind::RepeatLimit => "repeatLimit",
+        }
+    }
+}
+
+/// A confirm hook for the player that asks the frontend about `macro_id`
+pub fn asker(
+    app: &tauri::AppHandle,
+    control: &Arc<PlaybackControl>,
+    macro_id: &str,
+    kind: ConfirmKind,
+) -> AskUser {
+    let app = app.clone();
+    let control = Arc::clone(control);
+    let macro_id = macro_id.to_string();
+    Arc::new(move |action| ask(&app, &control, &macro_id, kind, action))
+}
+
+fn ask(
+    app: &tauri::AppHandle,
+    control: &PlaybackControl,
+    macro_id: &str,
+    kind: ConfirmKind,
+    action: &str,
+) -> bool {
+    let Some(confirmations) = app.try_state::<Confirmations>() else {
+        return false;
+    };
+    let id = confirmations.next_id.fetch_add(1, Ordering::SeqCst);
+    let (tx, rx) = mpsc::channel();
+    confirmations.pending.lock().insert(id, tx);
+
+    info!("Asking before playing {}", action);
+    events::emit(
+        app,
+        BusEventType::ConfirmationRequested,
+        serde_json::json!({
+            "id": id,
+            "macroId": macro_id,
+            "kind": kind.as_str(),
+            "action": action,
+        }),
+    );
+
+    let deadline = Instant::now() + CONFIRM_TIMEOUT;
+    let approved = loop {
+        match rx.recv_timeout(STOP_POLL) {
+            Ok(approved) => break approved,
+            Err(RecvTimeoutError::Disconnected) => break false,
+            Err(RecvTimeoutError::Timeout) => {
+                if control.is_stopped() || Instant::now() >= deadline {
+                    break false;
+                }
+            }
+        }
+    };
+    confirmations.pending.lock().remove(&id);
+    approved
+}
+
+/// Deliver the user's answer to a waiting request
+pub fn answer(app: &tauri::AppHandle, id: u64, approved: bool) -> Result<(), String> {
+    let confirmations = app.state::<Confirmations>();
+    let sender = confirmations
+        .pending
+        .lock()
+        .remove(&id)
+        .ok_or_else(|| format!("Confirmation {} is no longer waiting", id))?;
+    // The playback thread may have given up in the meantime
+    let _ = sender.send(approved);
+    Ok(())
+}
@@ -0,0 +1,414 @@
+// Local control server module
+//
+// A WebSocket endpoint on 127.0.0.1 for Stream Deck style controllers. Each
+// message is a JSON object with an "action":
+//   {"action": "list"}                      -> {"event": "macros", "macros": [...]}
+//   {"action": "status"}                    -> {"event": "status", ...}
+//   {"action": "play", "macro": "<id|name>"}
+//   {"action": "stop"}
+//   {"action": "record-start"} / {"action": "record-stop"}
+// Status messages are also pushed whenever recording or playback changes, so
+// deck buttons can show live recording/playing icons.
+//
+// Plain HTTP requests on the same port get read-only JSON for dashboards:
+//   GET /api/macros   macro summaries
+//   GET /api/status   recording and playback state
+//   GET /api/history  recent runs, newest first (?limit=N)
+//
+// WebSocket upgrades sent by web pages, which carry an http(s) `Origin`, are
+// refused so a site open in the browser can't drive the local machine.
+//
+// Both need a token, as `Authorization: Bearer <token>` or `?token=<token>`,
+// and can only do what its scope allows.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+use tracing::{error, info, warn};
+use tungstenite::{Message, WebSocket};
+
+use crate::app::{load_app_settings_from_store, play_stored_macro_in_background, AppState};
+use crate::events::{self, BusEventType};
+use crate::macrox_core::access::{self, ControlAction};
+use crate::macrox_core::history::MAX_RUNS;
+use crate::macrox_core::types::{ControlServerSettings, PlaybackStatus, TokenScope};
+
+/// How often a connection checks for status changes to push
+const STATUS_POLL: Duration = Duration::from_millis(250);
+/// How long a client gets to send its request headers
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Server lifecycle, kept in Tauri's managed state
+#[derive(Default)]
+pub struct ControlServer {
+    /// Bumped on every reconfigure so stale listener threads exit
+    generation: Arc<AtomicU64>,
+}
+
+#[derive(Serialize)]
+struct StatusMessage {
+    event: &'static str,
+    recording: bool,
+    playback: PlaybackStatus,
+}
+
+/// Start, restart or stop the server to match the given settings
+pub fn apply_settings(app: &tauri::AppHandle, settings: &ControlServerSettings) {
+    let Some(server) = app.try_state::<ControlServer>() else {
+        return;
+    };
+    let generation = server.generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+    if !settings.enabled {
+        return;
+    }
+
+    let listener = match TcpListener::bind(("127.0.0.1", settings.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(
+                "Failed to start control server on port {}: {}",
+                settings.port, e
+            );
+            return;
+        }
+    };
+    // Non-blocking accept so the thread notices when it has been replaced
+    let _ = listener.set_nonblocking(true);
+    info!("Control server listening on 127.0.0.1:{}", settings.port);
+
+    let app = app.clone();
+    let current = Arc::clone(&server.generation);
+    thread::spawn(move || {
+        while current.load(Ordering::SeqCst) == generation {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let app = app.clone();
+                    let current = Arc::clone(&current);
+                    thread::spawn(move || {
+                        let result = match read_head(&stream) {
+                            Ok(head) if is_websocket_upgrade(&head) => {
+                                let token = access::presented_token(&head);
+                                match (web_origin(&head), authorize(&app, token.as_deref())) {
+                                    (Some(origin), _) => forbid(stream, &head, &origin),
+                                    (None, Ok(_)) => {
+                                        handle_connection(&app, stream, &current, generation, token)
+                                    }
+                                    (None, Err(e)) => refuse(stream, &head, &e),
+                                }
+                            }
+                            Ok(head) => handle_http(&app, stream, &head),
+                            Err(e) => Err(e),
+                        };
+                        if let Err(e) = result {
+                            warn!("Control connection closed: {}", e);
+                        }
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(STATUS_POLL),
+                Err(e) => error!("Control server accept failed: {}", e),
+            }
+        }
+        info!("Control server stopped");
+    });
+}
+
+/// Peek at the request line and headers without consuming them, so the
+/// WebSocket handshake can still read them
+fn read_head(stream: &TcpStream) -> Result<String, String> {
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    stream
+        .set_read_timeout(Some(STATUS_POLL))
+        .map_err(|e| e.to_string())?;
+
+    let started = Instant::now();
+    let mut buffer = [0u8; 4096];
+    loop {
+        let len = match stream.peek(&mut buffer) {
+            Ok(0) => return Err("Connection closed before request".to_string()),
+            Ok(len) => len,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => 0,
+            Err(e) => return Err(e.to_string()),
+        };
+        let head = String::from_utf8_lossy(&buffer[..len]);
+        if let Some(end) = head.find("\r\n\r\n") {
+            return Ok(head[..end].to_string());
+        }
+        if len == buffer.len() {
+            return Err("Request headers too large".to_string());
+        }
+        if started.elapsed() > HEADER_TIMEOUT {
+            return Err("Timed out waiting for request headers".to_string());
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn is_websocket_upgrade(head: &str) -> bool {
+    head.lines().skip(1).any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("upgrade")
+                && value.trim().eq_ignore_ascii_case("websocket")
+        })
+    })
+}
+
+/// The `Origin` of a request sent by a web page. Browsers let any page open a
+/// WebSocket to localhost, so those are turned away; deck software and
+/// scripts send no origin or a non-web one.
+fn web_origin(head: &str) -> Option<String> {
+    head.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        let value = value.trim();
+        let web = value.starts_with("http://") || value.starts_with("https://");
+        (name.trim().eq_ignore_ascii_case("origin") && web).then(|| value.to_string())
+    })
+}
+
+/// Scope of the presented token under the settings as saved now, so minted
+/// and revoked tokens apply to open connections too
+fn authorize(app: &tauri::AppHandle, token: Option<&str>) -> Result<TokenScope, String> {
+    access::authorize(&load_app_settings_from_store(app).control_server, token)
+}
+
+/// Turn away a request without a valid token
+fn refuse(stream: TcpStream, head: &str, message: &str) -> Result<(), String> {
+    respond(
+        stream,
+        head,
+        "401 Unauthorized",
+        json!({ "error": message }),
+    )
+}
+
+/// Turn away a WebSocket upgrade from a web page
+fn forbid(stream: TcpStream, head: &str, origin: &str) -> Result<(), String> {
+    let message = format!("WebSocket connections from {} are not allowed", origin);
+    respond(stream, head, "403 Forbidden", json!({ "error": message }))
+}
+
+fn handle_http(app: &tauri::AppHandle, stream: TcpStream, head: &str) -> Result<(), String> {
+    // Every endpoint is read-only, so any valid token will do
+    if let Err(e) = authorize(app, access::presented_token(head).as_deref()) {
+        return refuse(stream, head, &e);
+    }
+
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default();
+    let target = request_line.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let (status, body) = if method != "GET" {
+        (
+            "405 Method Not Allowed",
+            json!({ "error": "Only GET is supported" }),
+        )
+    } else {
+        match http_route(app, path, query) {
+            Some(body) => ("200 OK", body),
+            None => (
+                "404 Not Found",
+                json!({ "error": format!("No endpoint at {}", path) }),
+            ),
+        }
+    };
+    respond(stream, head, status, body)
+}
+
+fn respond(mut stream: TcpStream, head: &str, status: &str, body: Value) -> Result<(), String> {
+    // The request body, if any, is never read, so only the head is consumed
+    let mut discard = vec![0u8; head.len() + 4];
+    let _ = stream.read_exact(&mut discard);
+
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream
+        .write_all(response.as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+fn http_route(app: &tauri::AppHandle, path: &str, query: &str) -> Option<Value> {
+    match path.trim_end_matches('/') {
+        "/api/macros" => Some(macro_summaries(app)),
+        "/api/status" => serde_json::to_value(current_status(app)).ok(),
+        "/api/history" => {
+            let limit = query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(key, _)| *key == "limit")
+                .and_then(|(_, value)| value.parse().ok())
+                .unwrap_or(MAX_RUNS);
+            serde_json::to_value(app.state::<AppState>().history.recent(limit)).ok()
+        }
+        _ => None,
+    }
+}
+
+fn macro_summaries(app: &tauri::AppHandle) -> Value {
+    let state = app.state::<AppState>();
+    let macros = state.macros.lock();
+    Value::Array(
+        macros
+            .iter()
+            .filter(|m| !m.archived)
+            .map(|m| {
+                json!({
+                    "id": m.id,
+                    "name": m.name,
+                    "eventCount": m.events.len(),
+                    "durationMs": m.events.last().map(|e| e.timestamp).unwrap_or(0),
+                    "updatedAt": m.updated_at,
+                })
+            })
+            .collect(),
+    )
+}
+
+fn handle_connection(
+    app: &tauri::AppHandle,
+    stream: TcpStream,
+    current: &AtomicU64,
+    generation: u64,
+    token: Option<String>,
+) -> Result<(), String> {
+    let mut socket = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+    // Reads time out so status changes can be pushed between messages
+    socket
+        .get_mut()
+        .set_read_timeout(Some(STATUS_POLL))
+        .map_err(|e| e.to_string())?;
+
+    let mut last_summary = None;
+    while current.load(Ordering::SeqCst) == generation {
+        let scope = match authorize(app, token.as_deref()) {
+            Ok(scope) => scope,
+            Err(message) => {
+                send(
+                    &mut socket,
+                    &json!({ "event": "error", "message": message }),
+                )?;
+                break;
+            }
+        };
+
+        let status = current_status(app);
+        let summary = (status.recording, status.playback.summary());
+        if last_summary.as_ref() != Some(&summary) {
+            send(&mut socket, &status)?;
+            last_summary = Some(summary);
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let reply = match serde_json::from_str::<Value>(&text) {
+                    Ok(request) => handle_request(app, &request, &scope),
+                    Err(e) => Err(format!("Invalid JSON: {}", e)),
+                };
+                match reply {
+                    Ok(Some(reply)) => send(&mut socket, &reply)?,
+                    Ok(None) => {}
+                    Err(message) => send(
+                        &mut socket,
+                        &json!({ "event": "error", "message": message }),
+                    )?,
+                }
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    let _ = socket.close(None);
+    Ok(())
+}
+
+fn handle_request(
+    app: &tauri::AppHandle,
+    request: &Value,
+    scope: &TokenScope,
+) -> Result<Option<Value>, String> {
+    let action = request
+        .get("action")
+        .and_then(|a| a.as_str())
+        .ok_or_else(|| "Missing \"action\"".to_string())?;
+    let state = app.state::<AppState>();
+    let require = |needed: ControlAction| {
+        if access::allows(scope, needed) {
+            Ok(())
+        } else {
+            Err(format!("This token doesn't allow \"{}\"", action))
+        }
+    };
+
+    match action {
+        "list" => Ok(Some(
+            json!({ "event": "macros", "macros": macro_summaries(app) }),
+        )),
+        "status" => serde_json::to_value(current_status(app))
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        "play" => {
+            let target = request
+                .get("macro")
+                .and_then(|m| m.as_str())
+                .ok_or_else(|| "Missing \"macro\"".to_string())?;
+            let id = state
+                .macros
+                .lock()
+                .iter()
+                .find(|m| !m.archived && (m.id == target || m.name == target))
+                .map(|m| m.id.clone())
+                .ok_or_else(|| format!("No macro matching '{}'", target))?;
+            require(ControlAction::Play(&id))?;
+            play_stored_macro_in_background(app, &id);
+            Ok(None)
+        }
+        "stop" => {
+            require(ControlAction::Stop)?;
+            state.playback.stop();
+            state.pool.stop_all();
+            Ok(None)
+        }
+        // Recording goes through the same path as the hotkeys, so the window
+        // applies its recording settings and saves the result
+        "record-start" => {
+            require(ControlAction::Record)?;
+            events::emit(app, BusEventType::HotkeyRecordStart, ());
+            Ok(None)
+        }
+        "record-stop" => {
+            require(ControlAction::Record)?;
+            events::emit(app, BusEventType::HotkeyRecordStop, ());
+            Ok(None)
+        }
+        other => Err(format!("Unknown action: {}", other)),
+    }
+}
+
+fn current_status(app: &tauri::AppHandle) -> StatusMessage {
+    let state = app.state::<AppState>();
+    StatusMessage {
+        event: "status",
+        recording: state.recorder.lock().is_some(),
+        playback: state.playback.status(),
+    }
+}
+
+fn send(socket: &mut WebSocket<TcpStream>, message: &impl Serialize) -> Result<(), String> {
+    let text = serde_json::to_string(message).map_err(|e| e.to_string())?;
+    socket.send(Message::Text(text)).map_err(|e| e.to_string())
+}
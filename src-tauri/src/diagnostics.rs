@@ -0,0 +1,119 @@
+// Diagnostics export module
+//
+// Bundles the log files, an anonymized copy of the settings and some basic
+// system information into a zip that can be attached to bug reports. Macros
+// are left out since they may contain anything the user typed.
+
+use chrono::Local;
+use serde_json::json;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use tauri::Manager;
+use tracing::info;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::app::{load_app_settings_from_store, load_hotkeys_from_store, AppState};
+use crate::macrox_core::types::{AppSettings, TokenScope, REDACTED};
+
+/// Write the diagnostics zip to the downloads folder and return its path
+pub fn export(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let paths = app.path();
+    let out_dir = paths
+        .download_dir()
+        .or_else(|_| paths.app_data_dir())
+        .map_err(|e| e.to_string())?;
+    fs::create_dir_all(&out_dir).map_err(|e| e.to_string())?;
+    let path = out_dir.join(format!(
+        "macrox-diagnostics-{}.zip",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+
+    let file = File::create(&path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // Logs are optional, a fresh install may not have written any yet
+    if let Some(entries) = paths
+        .app_log_dir()
+        .ok()
+        .and_then(|dir| fs::read_dir(dir).ok())
+    {
+        for entry in entries.flatten() {
+            let log_path = entry.path();
+            if !log_path.is_file() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let mut log = File::open(&log_path).map_err(|e| e.to_string())?;
+            zip.start_file(format!("logs/{}", name), options)
+                .map_err(|e| e.to_string())?;
+            io::copy(&mut log, &mut zip).map_err(|e| e.to_string())?;
+        }
+    }
+
+    let settings = json!({
+        "appSettings": anonymized(load_app_settings_from_store(app)),
+        "hotkeys": load_hotkeys_from_store(app),
+    });
+    write_json(&mut zip, "settings.json", &settings, options)?;
+
+    let system = json!({
+        "version": app.package_info().version.to_string(),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "macroCount": app.state::<AppState>().macros.lock().len(),
+    });
+    write_json(&mut zip, "system.json", &system, options)?;
+
+    zip.finish().map_err(|e| e.to_string())?;
+    info!("Diagnostics exported to {}", path.display());
+    Ok(path)
+}
+
+fn write_json(
+    zip: &mut ZipWriter<File>,
+    name: &str,
+    value: &serde_json::Value,
+    options: SimpleFileOptions,
+) -> Result<(), String> {
+    let bytes = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    zip.write_all(&bytes).map_err(|e| e.to_string())
+}
+
+/// Settings with hosts, credentials and macro ids blanked out
+fn anonymized(mut settings: AppSettings) -> AppSettings {
+    if !settings.mqtt.host.is_empty() {
+        settings.mqtt.host = REDACTED.to_string();
+    }
+    settings.mqtt.username = settings.mqtt.username.map(|_| REDACTED.to_string());
+    settings.mqtt.password = settings.mqtt.password.map(|_| REDACTED.to_string());
+    settings.last_selected_macro_id = None;
+
+    // Tokens keep their name and scope so access problems can still be read
+    for token in settings.control_server.tokens.iter_mut() {
+        token.token = REDACTED.to_string();
+        if let TokenScope::Trigger { macros } = &mut token.scope {
+            for id in macros.iter_mut() {
+                *id = REDACTED.to_string();
+            }
+        }
+    }
+    for grant in settings.privilege_grants.iter_mut() {
+        grant.macro_id = REDACTED.to_string();
+    }
+    for binding in settings.macro_hotkeys.iter_mut() {
+        binding.macro_id = REDACTED.to_string();
+    }
+    for id in settings.leader.bindings.values_mut() {
+        *id = REDACTED.to_string();
+    }
+    for scene in settings.scenes.iter_mut() {
+        for id in scene.macro_ids.iter_mut() {
+            *id = REDACTED.to_string();
+        }
+    }
+    settings
+}
@@ -0,0 +1,45 @@
+// Display information module
+
+use crate::macrox_core::types::{DisplayInfo, Macro, ScreenRegion};
+
+/// Baseline DPI that corresponds to a scale factor of 1.0
+const BASE_DPI: f64 = 96.0;
+
+/// Describe the primary display as it is right now
+pub fn current_display_info(app: &tauri::AppHandle) -> Option<DisplayInfo> {
+    let monitor = app.primary_monitor().ok()??;
+    let scale_factor = monitor.scale_factor();
+    let size = monitor.size();
+
+    Some(DisplayInfo {
+        scale_factor,
+        dpi: (scale_factor * BASE_DPI).round() as u32,
+        width: size.width,
+        height: size.height,
+    })
+}
+
+/// Bounds of every connected monitor in physical pixels
+pub fn monitor_bounds(app: &tauri::AppHandle) -> Vec<ScreenRegion> {
+    app.available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|monitor| ScreenRegion {
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+        })
+        .collect()
+}
+
+/// Factor to multiply recorded absolute coordinates by so they land on the same
+/// UI element under the current display scaling. Returns 1.0 when unknown.
+pub fn coordinate_scale(macro_data: &Macro, current: Option<&DisplayInfo>) -> f64 {
+    match (macro_data.display_info.as_ref(), current) {
+        (Some(recorded), Some(current)) if recorded.scale_factor > 0.0 => {
+            current.scale_factor / recorded.scale_factor
+        }
+        _ => 1.0,
+    }
+}
@@ -0,0 +1,233 @@
+// Backend-to-frontend event bus
+//
+// Every event carries a type, a payload and a monotonically increasing
+// sequence id. Recent events are kept so a reloaded frontend can catch up
+// with `get_events_since`.
+//
+// High-frequency sources don't emit directly: `emit_batched` collects items
+// into one array event per batch window, and `emit_latest` only delivers the
+// newest payload of each window, so fast mouse motion can't flood the webview.
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+use crate::macrox_core::types::EmitterSettings;
+
+/// Channel the frontend listens on for all bus events
+pub const EVENT_CHANNEL: &str = "macrox:event";
+
+/// How many past events are kept for catch-up
+const HISTORY_LIMIT: usize = 500;
+
+/// Kinds of events pushed to the frontend
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BusEventType {
+    HotkeyRecordStart,
+    HotkeyRecordStop,
+    HotkeyPlaybackStart,
+    HotkeyPlaybackStop,
+    /// Payload is a typed RecordingWarning, tagged by "kind"
+    RecordingWarning,
+    /// Key capture stopped or resumed, e.g. under macOS secure input
+    RecordingBlocked,
+    MacrosChanged,
+    /// App settings were saved; payload is the full settings
+    SettingsChanged,
+    PlaybackProgress,
+    /// The player is waiting for the user to allow a destructive key combination
+    ConfirmationRequested,
+    /// Batch of events captured by the running recording
+    RecordedEvents,
+    /// A run finished; payload has the macro id and the values its events read
+    RunOutputs,
+    /// A run finished; payload has the macro id and its timing report
+    TimingReport,
+    /// A newer release was found at startup; payload is the update info
+    UpdateAvailable,
+    /// Batch of input a practice run would have sent to the desktop
+    PracticeActions,
+}
+
+/// A single event as delivered to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct BusEvent {
+    pub seq: u64,
+    #[serde(rename = "type")]
+    pub event_type: BusEventType,
+    pub payload: Value,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Default)]
+pub struct EventBus {
+    state: Mutex<BusState>,
+    pending: Mutex<PendingEmits>,
+    settings: Mutex<EmitterSettings>,
+}
+
+/// Items waiting for the current batch window to end
+#[derive(Default)]
+struct PendingEmits {
+    flush_scheduled: bool,
+    /// Arrays built by emit_batched, in first-seen order
+    batches: Vec<(BusEventType, Vec<Value>)>,
+    /// Newest payload per type from emit_latest
+    latest: Vec<(BusEventType, Value)>,
+    last_mouse_move: Option<Instant>,
+}
+
+#[derive(Default)]
+struct BusState {
+    last_seq: u64,
+    history: VecDeque<BusEvent>,
+}
+
+impl EventBus {
+    /// Assign the next sequence id, remember the event and push it to the frontend
+    pub fn publish(&self, app: &tauri::AppHandle, event_type: BusEventType, payload: Value) {
+        let event = {
+            let mut state = self.state.lock();
+            state.last_seq += 1;
+
+            let event = BusEvent {
+                seq: state.last_seq,
+                event_type,
+                payload,
+                timestamp: Utc::now(),
+            };
+
+            state.history.push_back(event.clone());
+            if state.history.len() > HISTORY_LIMIT {
+                state.history.pop_front();
+            }
+            event
+        };
+
+        let _ = app.emit(EVENT_CHANNEL, event);
+    }
+
+    pub fn set_settings(&self, settings: EmitterSettings) {
+        *self.settings.lock() = settings;
+    }
+
+    /// Add an item to this window's array event of `event_type`. Mouse moves
+    /// beyond the configured rate are dropped from the stream.
+    pub fn publish_batched(&self, app: &tauri::AppHandle, event_type: BusEventType, item: Value) {
+        let settings = self.settings.lock().clone();
+        let mut pending = self.pending.lock();
+
+        let is_move = matches!(
+            item.get("type").and_then(|t| t.as_str()),
+            Some("MouseMove" | "MouseMoveRelative")
+        );
+        if is_move && settings.max_mouse_moves_per_sec > 0 {
+            let interval = Duration::from_secs(1) / settings.max_mouse_moves_per_sec;
+            if pending
+                .last_mouse_move
+                .is_some_and(|t| t.elapsed() < interval)
+            {
+                return;
+            }
+            pending.last_mouse_move = Some(Instant::now());
+        }
+
+        match pending.batches.iter_mut().find(|(t, _)| *t == event_type) {
+            Some((_, items)) => items.push(item),
+            None => pending.batches.push((event_type, vec![item])),
+        }
+        self.schedule_flush(app, &mut pending, &settings);
+    }
+
+    /// Deliver only the newest payload of `event_type` at the end of the window
+    pub fn publish_latest(&self, app: &tauri::AppHandle, event_type: BusEventType, payload: Value) {
+        let settings = self.settings.lock().clone();
+        let mut pending = self.pending.lock();
+
+        match pending.latest.iter_mut().find(|(t, _)| *t == event_type) {
+            Some((_, latest)) => *latest = payload,
+            None => pending.latest.push((event_type, payload)),
+        }
+        self.schedule_flush(app, &mut pending, &settings);
+    }
+
+    fn schedule_flush(
+        &self,
+        app: &tauri::AppHandle,
+        pending: &mut PendingEmits,
+        settings: &EmitterSettings,
+    ) {
+        if pending.flush_scheduled {
+            return;
+        }
+        pending.flush_scheduled = true;
+
+        let app = app.clone();
+        let window = Duration::from_millis(settings.batch_window_ms);
+        thread::spawn(move || {
+            thread::sleep(window);
+            if let Some(bus) = app.try_state::<EventBus>() {
+                bus.flush(&app);
+            }
+        });
+    }
+
+    fn flush(&self, app: &tauri::AppHandle) {
+        let (batches, latest) = {
+            let mut pending = self.pending.lock();
+            pending.flush_scheduled = false;
+            (
+                std::mem::take(&mut pending.batches),
+                std::mem::take(&mut pending.latest),
+            )
+        };
+
+        for (event_type, items) in batches {
+            self.publish(app, event_type, Value::Array(items));
+        }
+        for (event_type, payload) in latest {
+            self.publish(app, event_type, payload);
+        }
+    }
+
+    /// Events with a sequence id greater than `seq`, oldest first
+    pub fn events_since(&self, seq: u64) -> Vec<BusEvent> {
+        self.state
+            .lock()
+            .history
+            .iter()
+            .filter(|e| e.seq > seq)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Publish an event through the app's managed EventBus
+pub fn emit(app: &tauri::AppHandle, event_type: BusEventType, payload: impl Serialize) {
+    let payload = serde_json::to_value(payload).unwrap_or(Value::Null);
+    if let Some(bus) = app.try_state::<EventBus>() {
+        bus.publish(app, event_type, payload);
+    }
+}
+
+/// Collect an item into the next batched event of this type
+pub fn emit_batched(app: &tauri::AppHandle, event_type: BusEventType, item: impl Serialize) {
+    let item = serde_json::to_value(item).unwrap_or(Value::Null);
+    if let Some(bus) = app.try_state::<EventBus>() {
+        bus.publish_batched(app, event_type, item);
+    }
+}
+
+/// Publish at most once per batch window, keeping only the newest payload
+pub fn emit_latest(app: &tauri::AppHandle, event_type: BusEventType, payload: impl Serialize) {
+    let payload = serde_json::to_value(payload).unwrap_or(Value::Null);
+    if let Some(bus) = app.try_state::<EventBus>() {
+        bus.publish_latest(app, event_type, payload);
+    }
+}
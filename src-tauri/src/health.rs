@@ -0,0 +1,124 @@
+// Startup health check module
+//
+// Quick probes of everything recording and playback depend on (global
+// shortcuts, input injection, the input hook, the settings store, OS
+// permissions), so the UI can say what is broken on first launch instead of
+// the user finding out when a hotkey or a macro silently does nothing.
+
+use chrono::Utc;
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+use tauri_plugin_store::StoreExt;
+use tracing::info;
+
+use crate::app::{load_app_settings_from_store, SETTINGS_FILENAME};
+use crate::macrox_core::elevation;
+use crate::macrox_core::input;
+use crate::macrox_core::permissions;
+use crate::macrox_core::types::{HealthCheck, HealthReport, HealthStatus, InputBackend};
+
+/// Registered and released again to see whether global shortcuts work
+const TEST_SHORTCUT: &str = "Ctrl+Alt+Shift+F24";
+
+/// Store key written and removed to check the settings can be saved
+const TEST_STORE_KEY: &str = "health_check";
+
+/// Run every probe. Takes a moment since the input hook needs time to fail.
+pub fn run(app: &tauri::AppHandle) -> HealthReport {
+    let backend = load_app_settings_from_store(app).input_backend;
+    let checks = vec![
+        check("shortcuts", "Global shortcuts", probe_shortcuts(app)),
+        check(
+            "inputInjection",
+            "Input injection",
+            probe_injection(backend),
+        ),
+        check("inputHook", "Input capture", input::check_input_hook()),
+        check("store", "Settings storage", probe_store(app)),
+        permissions_check(),
+        elevation_check(),
+    ];
+    let healthy = checks.iter().all(|c| c.status != HealthStatus::Failed);
+    info!(
+        "Health check: {}",
+        if healthy { "healthy" } else { "problems found" }
+    );
+    HealthReport { checks, healthy }
+}
+
+fn check(id: &str, label: &str, result: Result<(), String>) -> HealthCheck {
+    let (status, message) = match result {
+        Ok(()) => (HealthStatus::Ok, "Working".to_string()),
+        Err(e) => (HealthStatus::Failed, e),
+    };
+    HealthCheck {
+        id: id.to_string(),
+        label: label.to_string(),
+        status,
+        message,
+    }
+}
+
+fn probe_shortcuts(app: &tauri::AppHandle) -> Result<(), String> {
+    let shortcuts = app.global_shortcut();
+    shortcuts
+        .register(TEST_SHORTCUT)
+        .map_err(|e| format!("Can't register global shortcuts: {}", e))?;
+    let _ = shortcuts.unregister(TEST_SHORTCUT);
+    Ok(())
+}
+
+fn probe_injection(backend: InputBackend) -> Result<(), String> {
+    // The portal backend would pop up its permission dialog, so only check
+    // that the interface exists
+    #[cfg(target_os = "linux")]
+    if backend == InputBackend::Portal {
+        if crate::macrox_core::portal::capabilities().remote_desktop {
+            return Ok(());
+        }
+        return Err("The remote desktop portal isn't available".to_string());
+    }
+    input::new_sink(backend).map(|_| ())
+}
+
+fn probe_store(app: &tauri::AppHandle) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_FILENAME)
+        .map_err(|e| format!("Can't open the settings: {}", e))?;
+    let _ = store.set(TEST_STORE_KEY, Utc::now().to_rfc3339());
+    let saved = store.save();
+    let _ = store.delete(TEST_STORE_KEY);
+    saved
+        .and_then(|_| store.save())
+        .map_err(|e| format!("Can't save the settings: {}", e))
+}
+
+fn permissions_check() -> HealthCheck {
+    let mut missing = Vec::new();
+    if !permissions::input_access_granted() {
+        missing.push("Accessibility");
+    }
+    if !permissions::screen_capture_granted() {
+        missing.push("Screen Recording");
+    }
+    let result = if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Allow MacroX under Privacy & Security: {}",
+            missing.join(", ")
+        ))
+    };
+    check("permissions", "Permissions", result)
+}
+
+// Not a failure: only elevated windows are affected
+fn elevation_check() -> HealthCheck {
+    let mut result = check("elevation", "Administrator windows", Ok(()));
+    if cfg!(target_os = "windows") && !elevation::is_elevated() {
+        result.status = HealthStatus::Warning;
+        result.message =
+            "MacroX can't control windows running as administrator unless it runs elevated too"
+                .to_string();
+    }
+    result
+}
@@ -0,0 +1,265 @@
+// Global hotkey module
+//
+// Every global shortcut the app owns is an action id bound to a key
+// combination and an action. Rebinding one id only re-registers that
+// shortcut, so the record/playback hotkeys, the leader and launcher keys and
+// per-macro hotkeys all go through the same code.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use tracing::{error, info, warn};
+
+use crate::app::{
+    load_app_settings_from_store, play_stored_macro_in_background, save_retro_recording,
+};
+use crate::events::{self, BusEventType};
+use crate::launcher;
+use crate::macrox_core::input::RdevSource;
+use crate::macrox_core::leader;
+use crate::macrox_core::shortcut;
+use crate::macrox_core::types::{
+    HotkeySettings, LauncherSettings, LeaderSettings, MacroHotkey, RetroSettings,
+};
+
+const LEADER_ID: &str = "leader";
+const LAUNCHER_ID: &str = "launcher";
+const RETRO_ID: &str = "retro";
+/// Followed by the macro id
+const MACRO_ID_PREFIX: &str = "macro:";
+
+/// What pressing a hotkey does
+#[derive(Debug, Clone)]
+pub enum HotkeyAction {
+    /// Tell the frontend, which drives recording and playback from the UI
+    Emit(BusEventType),
+    ArmLeader,
+    ToggleLauncher,
+    PlayMacro(String),
+    /// Turn the retroactive buffer into a new macro
+    SaveRetro,
+}
+
+/// A hotkey from `HotkeySettings` and the bus event it emits
+struct FixedHotkey {
+    id: &'static str,
+    binding: fn(&HotkeySettings) -> &str,
+    event: BusEventType,
+}
+
+const FIXED_HOTKEYS: [FixedHotkey; 4] = [
+    FixedHotkey {
+        id: "recordStart",
+        binding: |h| h.record_start.as_str(),
+        event: BusEventType::HotkeyRecordStart,
+    },
+    FixedHotkey {
+        id: "recordStop",
+        binding: |h| h.record_stop.as_str(),
+        event: BusEventType::HotkeyRecordStop,
+    },
+    FixedHotkey {
+        id: "playbackStart",
+        binding: |h| h.playback_start.as_str(),
+        event: BusEventType::HotkeyPlaybackStart,
+    },
+    FixedHotkey {
+        id: "playbackStop",
+        binding: |h| h.playback_stop.as_str(),
+        event: BusEventType::HotkeyPlaybackStop,
+    },
+];
+
+/// Shortcuts currently registered, by action id
+#[derive(Default)]
+pub struct HotkeyManager {
+    bound: Mutex<HashMap<String, String>>,
+}
+
+impl HotkeyManager {
+    /// Bind `hotkey` to `action` under `id`, replacing whatever `id` had
+    pub fn bind(
+        &self,
+        app: &tauri::AppHandle,
+        id: &str,
+        hotkey: &str,
+        action: HotkeyAction,
+    ) -> Result<(), String> {
+        let hotkey =
+            shortcut::normalize(hotkey).map_err(|e| format!("Invalid {} hotkey: {}", id, e))?;
+        self.unbind(app, id);
+
+        let handle = app.clone();
+        let action_id = id.to_string();
+        app.global_shortcut()
+            .on_shortcut(hotkey.as_str(), move |_app, _shortcut, event| {
+                if event.state == ShortcutState::Pressed {
+                    info!("Hotkey pressed: {}", action_id);
+                    trigger(&handle, &action);
+                }
+            })
+            .map_err(|e| format!("Failed to register {} hotkey '{}': {:?}", id, hotkey, e))?;
+        self.bound.lock().insert(id.to_string(), hotkey);
+        Ok(())
+    }
+
+    pub fn unbind(&self, app: &tauri::AppHandle, id: &str) {
+        if let Some(hotkey) = self.bound.lock().remove(id) {
+            let _ = app.global_shortcut().unregister(hotkey.as_str());
+        }
+    }
+
+    /// Macros that currently have a global shortcut registered
+    pub fn macro_ids(&self) -> Vec<String> {
+        self.bound
+            .lock()
+            .keys()
+            .filter_map(|id| id.strip_prefix(MACRO_ID_PREFIX))
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+/// Bind the record and playback hotkeys
+pub fn apply_hotkeys(app: &tauri::AppHandle, settings: &HotkeySettings) -> Result<(), String> {
+    // Free all four first, the new settings may swap keys between them
+    let manager = app.state::<HotkeyManager>();
+    for hotkey in &FIXED_HOTKEYS {
+        manager.unbind(app, hotkey.id);
+    }
+    bind_all(
+        app,
+        FIXED_HOTKEYS.iter().map(|h| {
+            (
+                h.id.to_string(),
+                (h.binding)(settings).to_string(),
+                HotkeyAction::Emit(h.event),
+            )
+        }),
+    )
+}
+
+pub fn apply_leader(app: &tauri::AppHandle, settings: &LeaderSettings) -> Result<(), String> {
+    bind_if(
+        app,
+        LEADER_ID,
+        settings.enabled,
+        &settings.hotkey,
+        HotkeyAction::ArmLeader,
+    )
+}
+
+pub fn apply_launcher(app: &tauri::AppHandle, settings: &LauncherSettings) -> Result<(), String> {
+    bind_if(
+        app,
+        LAUNCHER_ID,
+        settings.enabled,
+        &settings.hotkey,
+        HotkeyAction::ToggleLauncher,
+    )
+}
+
+pub fn apply_retro(app: &tauri::AppHandle, settings: &RetroSettings) -> Result<(), String> {
+    bind_if(
+        app,
+        RETRO_ID,
+        settings.enabled,
+        &settings.hotkey,
+        HotkeyAction::SaveRetro,
+    )
+}
+
+/// Make `hotkeys` the only registered per-macro hotkeys
+pub fn apply_macro_hotkeys(app: &tauri::AppHandle, hotkeys: &[MacroHotkey]) -> Result<(), String> {
+    let manager = app.state::<HotkeyManager>();
+    let stale: Vec<String> = manager
+        .bound
+        .lock()
+        .keys()
+        .filter(|id| id.starts_with(MACRO_ID_PREFIX))
+        .cloned()
+        .collect();
+    for id in stale {
+        manager.unbind(app, &id);
+    }
+    bind_all(
+        app,
+        hotkeys.iter().map(|h| {
+            (
+                format!("{}{}", MACRO_ID_PREFIX, h.macro_id),
+                h.hotkey.clone(),
+                HotkeyAction::PlayMacro(h.macro_id.clone()),
+            )
+        }),
+    )
+}
+
+fn bind_if(
+    app: &tauri::AppHandle,
+    id: &str,
+    enabled: bool,
+    hotkey: &str,
+    action: HotkeyAction,
+) -> Result<(), String> {
+    let manager = app.state::<HotkeyManager>();
+    if enabled {
+        manager.bind(app, id, hotkey, action)
+    } else {
+        manager.unbind(app, id);
+        Ok(())
+    }
+}
+
+// One hotkey failing to register shouldn't keep the others from working
+fn bind_all(
+    app: &tauri::AppHandle,
+    bindings: impl Iterator<Item = (String, String, HotkeyAction)>,
+) -> Result<(), String> {
+    let manager = app.state::<HotkeyManager>();
+    let errors: Vec<String> = bindings
+        .filter_map(|(id, hotkey, action)| manager.bind(app, &id, &hotkey, action).err())
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+fn trigger(app: &tauri::AppHandle, action: &HotkeyAction) {
+    match action {
+        HotkeyAction::Emit(event_type) => events::emit(app, *event_type, ()),
+        HotkeyAction::ArmLeader => arm_leader(app),
+        HotkeyAction::ToggleLauncher => {
+            if let Err(e) = launcher::toggle(app) {
+                warn!("{}", e);
+            }
+        }
+        HotkeyAction::PlayMacro(id) => play_stored_macro_in_background(app, id),
+        HotkeyAction::SaveRetro => {
+            if let Err(e) = save_retro_recording(app.clone()) {
+                warn!("{}", e);
+            }
+        }
+    }
+}
+
+/// Play the macro bound to the next key pressed
+fn arm_leader(app: &tauri::AppHandle) {
+    let settings = load_app_settings_from_store(app).leader;
+    info!("Leader key armed for {}ms", settings.timeout_ms);
+
+    let handle = app.clone();
+    let result = leader::arm(
+        Box::new(RdevSource),
+        &settings,
+        Box::new(move |choice| match choice {
+            Some(id) => play_stored_macro_in_background(&handle, &id),
+            None => info!("Leader key released without choosing a macro"),
+        }),
+    );
+    if let Err(e) = result {
+        error!("Failed to arm leader key: {}", e);
+    }
+}
@@ -0,0 +1,48 @@
+// Quick launcher module
+//
+// A small always-on-top window, opened from a global hotkey, that lists the
+// macros filtered as you type and plays the selected one on Enter. It closes
+// as soon as it loses focus, like Spotlight.
+
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent};
+
+use crate::app::play_stored_macro_in_background;
+
+/// The frontend renders the launcher instead of the main UI in this window
+pub const LAUNCHER_LABEL: &str = "launcher";
+
+/// Open the launcher, or close it if it is already showing
+pub fn toggle(app: &tauri::AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(LAUNCHER_LABEL) {
+        return window.close().map_err(|e| e.to_string());
+    }
+
+    let window =
+        WebviewWindowBuilder::new(app, LAUNCHER_LABEL, WebviewUrl::App("index.html".into()))
+            .title("MacroX Launcher")
+            .inner_size(520.0, 360.0)
+            .resizable(false)
+            .decorations(false)
+            .always_on_top(true)
+            .skip_taskbar(true)
+            .center()
+            .focused(true)
+            .build()
+            .map_err(|e| format!("Failed to open launcher: {}", e))?;
+
+    let launcher = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Focused(false) = event {
+            let _ = launcher.close();
+        }
+    });
+    Ok(())
+}
+
+/// Close the launcher and play the chosen macro
+pub fn launch(app: &tauri::AppHandle, id: &str) {
+    if let Some(window) = app.get_webview_window(LAUNCHER_LABEL) {
+        let _ = window.close();
+    }
+    play_stored_macro_in_background(app, id);
+}
@@ -0,0 +1,37 @@
+// Logging module
+//
+// Log records go through tauri-plugin-log to stdout and to rotating files in
+// the app log dir. The app logs with `tracing` macros rather than printing,
+// and those events reach the same files through tracing's `log` fallback
+// since no tracing subscriber is installed.
+
+use tauri_plugin_log::log::{self, LevelFilter};
+use tauri_plugin_log::RotationStrategy;
+
+use crate::macrox_core::types::{LogLevel, LoggingSettings};
+
+/// A log file is rotated once it grows past this size
+const MAX_LOG_FILE_BYTES: u128 = 2 * 1024 * 1024;
+/// Rotated files kept next to the current one
+const KEPT_LOG_FILES: usize = 5;
+
+pub fn plugin<R: tauri::Runtime>() -> tauri::plugin::TauriPlugin<R> {
+    tauri_plugin_log::Builder::new()
+        // Dependencies stay at info; our own level is set by `apply_settings`
+        .level(LevelFilter::Info)
+        .level_for("macrox_lib", LevelFilter::Trace)
+        .max_file_size(MAX_LOG_FILE_BYTES)
+        .rotation_strategy(RotationStrategy::KeepSome(KEPT_LOG_FILES))
+        .build()
+}
+
+/// Change the log level without restarting
+pub fn apply_settings(settings: &LoggingSettings) {
+    log::set_max_level(match settings.level {
+        LogLevel::Error => LevelFilter::Error,
+        LogLevel::Warn => LevelFilter::Warn,
+        LogLevel::Info => LevelFilter::Info,
+        LogLevel::Debug => LevelFilter::Debug,
+        LogLevel::Trace => LevelFilter::Trace,
+    });
+}
@@ -0,0 +1,120 @@
+// Pointer acceleration module
+//
+// With pointer acceleration on, the OS scales relative mouse motion by how
+// fast it arrives, so MouseMoveRelative events replay longer or shorter than
+// they were recorded. Calibration sends a few relative moves, measures how far
+// the cursor actually went and derives the factor the player scales relative
+// moves by.
+
+use enigo::Coordinate;
+use serde::Serialize;
+use std::time::Duration;
+
+use super::input::InputSink;
+
+/// Relative moves sent along each axis, in pixels
+const CALIBRATION_STEPS: [i32; 4] = [10, 25, 50, 100];
+/// Time for the OS to apply a move before the cursor is read back
+const SETTLE: Duration = Duration::from_millis(30);
+
+/// One calibration move and where the cursor really went
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccelerationSample {
+    pub requested: i32,
+    pub actual: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MouseCalibration {
+    /// Relative moves are multiplied by this to cover the recorded distance
+    pub factor: f64,
+    pub samples: Vec<AccelerationSample>,
+}
+
+/// Measure relative moves from `origin` and put the cursor back there. The
+/// origin should be far enough from the screen edges for a 100px move.
+pub fn calibrate(
+    sink: &mut impl InputSink,
+    origin: (i32, i32),
+) -> Result<MouseCalibration, String> {
+    let mut samples = Vec::new();
+    for step in CALIBRATION_STEPS {
+        for (dx, dy) in [(step, 0), (0, step)] {
+            sink.move_mouse(origin.0, origin.1, Coordinate::Abs)?;
+            sink.wait(SETTLE);
+            sink.move_mouse(dx, dy, Coordinate::Rel)?;
+            sink.wait(SETTLE);
+            let (x, y) = sink.cursor_position()?;
+            samples.push(AccelerationSample {
+                requested: step,
+                actual: (x - origin.0) + (y - origin.1),
+            });
+        }
+    }
+    sink.move_mouse(origin.0, origin.1, Coordinate::Abs)?;
+
+    let requested: i32 = samples.iter().map(|s| s.requested).sum();
+    let actual: i32 = samples.iter().map(|s| s.actual).sum();
+    if actual <= 0 {
+        return Err("The cursor didn't follow the calibration moves".to_string());
+    }
+    Ok(MouseCalibration {
+        factor: requested as f64 / actual as f64,
+        samples,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macrox_core::input::FakeSink;
+    use enigo::{Axis, Button, Direction, Key};
+
+    /// Moves the cursor half again as far as asked, like an accelerated pointer
+    struct AcceleratedSink(FakeSink);
+
+    impl InputSink for AcceleratedSink {
+        fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> Result<(), String> {
+            match coordinate {
+                Coordinate::Abs => self.0.move_mouse(x, y, coordinate),
+                Coordinate::Rel => self.0.move_mouse(x * 3 / 2, y * 3 / 2, coordinate),
+            }
+        }
+
+        fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+            self.0.button(button, direction)
+        }
+
+        fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+            self.0.key(key, direction)
+        }
+
+        fn scroll(&mut self, amount: i32, axis: Axis) -> Result<(), String> {
+            self.0.scroll(amount, axis)
+        }
+
+        fn cursor_position(&self) -> Result<(i32, i32), String> {
+            self.0.cursor_position()
+        }
+
+        fn wait(&mut self, duration: Duration) {
+            self.0.wait(duration)
+        }
+    }
+
+    #[test]
+    fn factor_undoes_the_acceleration() {
+        let mut sink = AcceleratedSink(FakeSink::default());
+        let calibration = calibrate(&mut sink, (500, 400)).unwrap();
+
+        assert!((calibration.factor - 2.0 / 3.0).abs() < 0.01);
+        assert_eq!(
+            calibration.samples[0],
+            AccelerationSample {
+                requested: 10,
+                actual: 15
+            }
+        );
+        assert_eq!(sink.cursor_position().unwrap(), (500, 400));
+    }
+}
@@ -0,0 +1,130 @@
+// Control API access module
+//
+// Decides what a request to the local control server may do from the token
+// it presents. Every request needs a valid token, so the server refuses
+// everything until one has been minted, and the token's scope limits it to
+// reading, to playing chosen macros, or lets it do everything.
+
+use super::types::{ControlServerSettings, TokenScope};
+
+/// What a control request wants to do
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControlAction<'a> {
+    /// Status, the macro list and run history
+    Read,
+    /// Play the macro with this id
+    Play(&'a str),
+    Stop,
+    Record,
+}
+
+/// Scope of the presented token, or why the request is refused
+pub fn authorize(
+    settings: &ControlServerSettings,
+    presented: Option<&str>,
+) -> Result<TokenScope, String> {
+    if settings.tokens.is_empty() {
+        return Err("No control token has been minted yet".to_string());
+    }
+    let presented = presented.ok_or("A control token is required")?;
+    settings
+        .tokens
+        .iter()
+        .find(|t| same_secret(&t.token, presented))
+        .map(|t| t.scope.clone())
+        .ok_or_else(|| "Unknown or revoked control token".to_string())
+}
+
+/// Whether `scope` covers `action`
+pub fn allows(scope: &TokenScope, action: ControlAction) -> bool {
+    match (scope, action) {
+        (TokenScope::Full, _) => true,
+        (_, ControlAction::Read) => true,
+        (TokenScope::Trigger { .. }, ControlAction::Stop) => true,
+        (TokenScope::Trigger { macros }, ControlAction::Play(id)) => macros.iter().any(|m| m == id),
+        _ => false,
+    }
+}
+
+/// Token from an `Authorization: Bearer` header, or else a `token` query parameter
+pub fn presented_token(head: &str) -> Option<String> {
+    let mut lines = head.lines();
+    let target = lines.next()?.split_whitespace().nth(1).unwrap_or_default();
+
+    let header = lines.find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if !name.trim().eq_ignore_ascii_case("authorization") {
+            return None;
+        }
+        let (kind, token) = value.trim().split_once(' ')?;
+        kind.eq_ignore_ascii_case("bearer")
+            .then(|| token.trim().to_string())
+    });
+    header.or_else(|| {
+        let (_, query) = target.split_once('?')?;
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == "token")
+            .map(|(_, value)| value.to_string())
+    })
+}
+
+// Compares every byte so the time taken doesn't give away how much matched
+fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macrox_core::types::ControlToken;
+    use chrono::Utc;
+
+    fn token(secret: &str, scope: TokenScope) -> ControlToken {
+        ControlToken {
+            id: secret.to_string(),
+            name: secret.to_string(),
+            token: secret.to_string(),
+            scope,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn tokens_only_reach_their_scope() {
+        let mut settings = ControlServerSettings::default();
+        assert!(authorize(&settings, None).is_err());
+
+        settings.tokens = vec![
+            token(
+                "deck",
+                TokenScope::Trigger {
+                    macros: vec!["1".to_string()],
+                },
+            ),
+            token("dash", TokenScope::ReadOnly),
+        ];
+        assert!(authorize(&settings, None).is_err());
+        assert!(authorize(&settings, Some("nope")).is_err());
+
+        let deck = authorize(&settings, Some("deck")).unwrap();
+        assert!(allows(&deck, ControlAction::Play("1")));
+        assert!(allows(&deck, ControlAction::Stop));
+        assert!(!allows(&deck, ControlAction::Play("2")));
+        assert!(!allows(&deck, ControlAction::Record));
+
+        let dash = authorize(&settings, Some("dash")).unwrap();
+        assert!(allows(&dash, ControlAction::Read));
+        assert!(!allows(&dash, ControlAction::Stop));
+
+        let head = "GET /api/status?limit=5&token=abc HTTP/1.1\r\nHost: localhost";
+        assert_eq!(presented_token(head).as_deref(), Some("abc"));
+        let head = "GET / HTTP/1.1\r\nAuthorization: Bearer xyz\r\nUpgrade: websocket";
+        assert_eq!(presented_token(head).as_deref(), Some("xyz"));
+    }
+}
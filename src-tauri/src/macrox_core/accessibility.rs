@@ -0,0 +1,172 @@
+// Accessibility module
+//
+// Resolves clicks to the UI element under the cursor (name, role, automation
+// id) so playback can click the element wherever it is now, not where it was
+// while recording. Uses UI Automation on Windows; other platforms don't
+// report elements yet and playback falls back to the recorded coordinates.
+
+use super::types::UiElement;
+
+/// Looks up the element at a screen point; swapped for a fake in tests
+pub type ElementProbe = fn(i32, i32) -> Option<UiElement>;
+
+/// Finds the current centre of a recorded element, given where it was recorded
+pub type ElementLocator = fn(&UiElement, (i32, i32)) -> Option<(i32, i32)>;
+
+/// The element under a screen point, if it has anything to identify it by
+pub fn element_at(x: i32, y: i32) -> Option<UiElement> {
+    platform::element_at(x, y).filter(is_identifiable)
+}
+
+/// Centre of the element matching `element`, preferring the one at `hint`
+pub fn locate(element: &UiElement, hint: (i32, i32)) -> Option<(i32, i32)> {
+    if !is_identifiable(element) {
+        return None;
+    }
+    if platform::element_at(hint.0, hint.1).is_some_and(|found| is_same_element(element, &found)) {
+        return Some(hint);
+    }
+    platform::find(element)
+}
+
+/// Elements without a name or automation id can't be found again
+fn is_identifiable(element: &UiElement) -> bool {
+    !element.automation_id.is_empty() || !element.name.is_empty()
+}
+
+/// Automation ids are stable across languages and label changes, so they win
+/// over names when the recorded element had one
+pub fn is_same_element(recorded: &UiElement, found: &UiElement) -> bool {
+    if recorded.role != found.role {
+        return false;
+    }
+    if !recorded.automation_id.is_empty() {
+        return recorded.automation_id == found.automation_id;
+    }
+    recorded.name == found.name
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::core::VARIANT;
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED,
+    };
+    use windows::Win32::UI::Accessibility::{
+        CUIAutomation, IUIAutomation, IUIAutomationElement, TreeScope_Descendants,
+        UIA_AutomationIdPropertyId, UIA_LocalizedControlTypePropertyId, UIA_NamePropertyId,
+    };
+
+    use super::UiElement;
+
+    fn automation() -> Option<IUIAutomation> {
+        unsafe {
+            // Already initialized threads report S_FALSE, which is fine
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER).ok()
+        }
+    }
+
+    fn describe(element: &IUIAutomationElement) -> Option<UiElement> {
+        unsafe {
+            Some(UiElement {
+                name: element.CurrentName().ok()?.to_string(),
+                role: element.CurrentLocalizedControlType().ok()?.to_string(),
+                automation_id: element
+                    .CurrentAutomationId()
+                    .map(|id| id.to_string())
+                    .unwrap_or_default(),
+            })
+        }
+    }
+
+    pub fn element_at(x: i32, y: i32) -> Option<UiElement> {
+        let automation = automation()?;
+        let element = unsafe { automation.ElementFromPoint(POINT { x, y }) }.ok()?;
+        describe(&element)
+    }
+
+    pub fn find(target: &UiElement) -> Option<(i32, i32)> {
+        let automation = automation()?;
+        unsafe {
+            let role = automation
+                .CreatePropertyCondition(
+                    UIA_LocalizedControlTypePropertyId,
+                    &VARIANT::from(target.role.as_str()),
+                )
+                .ok()?;
+            let identity = if target.automation_id.is_empty() {
+                automation.CreatePropertyCondition(
+                    UIA_NamePropertyId,
+                    &VARIANT::from(target.name.as_str()),
+                )
+            } else {
+                automation.CreatePropertyCondition(
+                    UIA_AutomationIdPropertyId,
+                    &VARIANT::from(target.automation_id.as_str()),
+                )
+            }
+            .ok()?;
+            let condition = automation.CreateAndCondition(&role, &identity).ok()?;
+
+            // Searching the whole desktop is slow, but the window the element
+            // lived in may have moved or been replaced since recording
+            let root = automation.GetRootElement().ok()?;
+            let element = root.FindFirst(TreeScope_Descendants, &condition).ok()?;
+            let rect = element.CurrentBoundingRectangle().ok()?;
+            if rect.right <= rect.left || rect.bottom <= rect.top {
+                return None;
+            }
+            Some(((rect.left + rect.right) / 2, (rect.top + rect.bottom) / 2))
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::UiElement;
+
+    pub fn element_at(_x: i32, _y: i32) -> Option<UiElement> {
+        None
+    }
+
+    pub fn find(_target: &UiElement) -> Option<(i32, i32)> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(name: &str, role: &str, automation_id: &str) -> UiElement {
+        UiElement {
+            name: name.to_string(),
+            role: role.to_string(),
+            automation_id: automation_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn automation_ids_take_precedence_over_names() {
+        let recorded = element("Save", "button", "saveButton");
+
+        assert!(is_same_element(
+            &recorded,
+            &element("Speichern", "button", "saveButton")
+        ));
+        assert!(!is_same_element(
+            &recorded,
+            &element("Save", "button", "saveAsButton")
+        ));
+        assert!(!is_same_element(
+            &recorded,
+            &element("Save", "menu item", "saveButton")
+        ));
+        assert!(is_same_element(
+            &element("Save", "button", ""),
+            &element("Save", "button", "anything")
+        ));
+    }
+}
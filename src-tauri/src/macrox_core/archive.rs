@@ -0,0 +1,90 @@
+// Macro archive module
+//
+// Macros past their expiry date, or neither played nor edited for a set
+// number of days, get archived: still stored, but left out of the library,
+// hotkeys and summaries so stale macros stop claiming triggers.
+
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+
+use super::storage::LastRuns;
+use super::types::Macro;
+
+/// Whether `macro_data`, last run at `last_run`, should be archived at `now`
+pub fn is_stale(
+    macro_data: &Macro,
+    last_run: Option<DateTime<Utc>>,
+    unused_days: Option<u32>,
+    now: DateTime<Utc>,
+) -> bool {
+    if macro_data.expires_at.is_some_and(|expires| expires <= now) {
+        return true;
+    }
+    let last_used = last_run.map_or(macro_data.updated_at, |run| run.max(macro_data.updated_at));
+    unused_days.is_some_and(|days| now - last_used >= Duration::days(days as i64))
+}
+
+/// Archive every stale macro, returning the ones archived now
+pub fn archive_stale(
+    macros: &mut [Arc<Macro>],
+    last_runs: &LastRuns,
+    unused_days: Option<u32>,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    macros
+        .iter_mut()
+        .filter(|m| !m.archived && is_stale(m, last_runs.get(&m.id).copied(), unused_days, now))
+        .map(|m| {
+            Arc::make_mut(m).archived = true;
+            m.id.clone()
+        })
+        .collect()
+}
+
+/// Bring a macro back. Counts as an edit, and drops an expiry that has
+/// passed, so it isn't archived again straight away.
+pub fn restore(macro_data: &mut Macro, now: DateTime<Utc>) {
+    macro_data.archived = false;
+    if macro_data.expires_at.is_some_and(|expires| expires <= now) {
+        macro_data.expires_at = None;
+    }
+    macro_data.updated_at = now;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored_macro(id: &str, updated_at: DateTime<Utc>) -> Macro {
+        Macro {
+            updated_at,
+            ..Macro::for_test(id, Vec::new())
+        }
+    }
+
+    #[test]
+    fn archives_expired_and_unused_macros() {
+        let now = Utc::now();
+        let mut expired = stored_macro("expired", now);
+        expired.expires_at = Some(now - Duration::hours(1));
+        let last_runs = LastRuns::from([("run".to_string(), now - Duration::days(2))]);
+        let mut macros = vec![
+            Arc::new(expired),
+            Arc::new(stored_macro("unused", now - Duration::days(31))),
+            Arc::new(stored_macro("run", now - Duration::days(60))),
+        ];
+
+        assert_eq!(
+            archive_stale(&mut macros, &last_runs, None, now),
+            vec!["expired"]
+        );
+        assert_eq!(
+            archive_stale(&mut macros, &last_runs, Some(30), now),
+            vec!["unused"]
+        );
+
+        restore(Arc::make_mut(&mut macros[0]), now);
+        assert!(!macros[0].archived);
+        assert!(!is_stale(&macros[0], None, Some(30), now));
+    }
+}
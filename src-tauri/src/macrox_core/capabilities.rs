@@ -0,0 +1,184 @@
+// Platform capability module
+//
+// What this OS and the chosen input backend can do, for the UI to hide or
+// explain features up front instead of letting them fail when used. Each
+// entry says whether it works and, when it doesn't or only partly does, why.
+
+use serde::Serialize;
+
+use super::permissions;
+#[cfg(target_os = "linux")]
+use super::portal;
+use super::types::{InputBackend, WaylandCapabilities};
+
+/// One feature and whether it works here
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Capability {
+    pub supported: bool,
+    /// Why it isn't available or what limits it, shown next to the feature
+    pub note: Option<String>,
+}
+
+impl Capability {
+    fn yes() -> Self {
+        Self {
+            supported: true,
+            note: None,
+        }
+    }
+
+    fn no(note: &str) -> Self {
+        Self {
+            supported: false,
+            note: Some(note.to_string()),
+        }
+    }
+}
+
+/// Result of `get_platform_capabilities`
+#[derive(Debug, Clone, Serialize)]
+pub struct PlatformCapabilities {
+    /// "windows", "macos" or "linux"
+    pub os: String,
+    pub backend: InputBackend,
+    /// Backends that can be selected on this OS
+    #[serde(rename = "availableBackends")]
+    pub available_backends: Vec<InputBackend>,
+    pub recording: Capability,
+    pub playback: Capability,
+    #[serde(rename = "globalHotkeys")]
+    pub global_hotkeys: Capability,
+    /// Keys sent as hardware scan codes, which some games require
+    #[serde(rename = "rawScancodes")]
+    pub raw_scancodes: Capability,
+    #[serde(rename = "horizontalScroll")]
+    pub horizontal_scroll: Capability,
+    /// Playing into a background window by title
+    #[serde(rename = "windowTargeting")]
+    pub window_targeting: Capability,
+    pub gamepad: Capability,
+    pub wayland: WaylandCapabilities,
+}
+
+/// Capabilities of the running system with `backend`
+pub fn current(backend: InputBackend) -> PlatformCapabilities {
+    #[cfg(target_os = "linux")]
+    let wayland = portal::capabilities();
+    #[cfg(not(target_os = "linux"))]
+    let wayland = WaylandCapabilities::default();
+
+    let mut capabilities = report(std::env::consts::OS, backend, wayland);
+    if !permissions::input_access_granted() {
+        let missing = Capability::no(
+            "Allow MacroX under Privacy & Security > Accessibility in System Settings",
+        );
+        capabilities.recording = missing.clone();
+        capabilities.playback = missing;
+    }
+    capabilities
+}
+
+/// Capabilities on `os` with `backend`, given what the Wayland session offers
+pub fn report(
+    os: &str,
+    backend: InputBackend,
+    wayland: WaylandCapabilities,
+) -> PlatformCapabilities {
+    let windows = os == "windows";
+    let available_backends = match os {
+        "windows" => vec![InputBackend::Enigo, InputBackend::SendInput],
+        "linux" if wayland.is_wayland => vec![InputBackend::Enigo, InputBackend::Portal],
+        "linux" => vec![InputBackend::Enigo, InputBackend::Xdotool],
+        _ => vec![InputBackend::Enigo],
+    };
+
+    let recording = if wayland.is_wayland {
+        Capability::no(
+            "Wayland doesn't let apps capture global input; log in to an X11 session to record",
+        )
+    } else {
+        Capability::yes()
+    };
+    let playback = if !available_backends.contains(&backend) {
+        Capability::no(
+            "The selected input backend isn't available here; pick another one in the settings",
+        )
+    } else if wayland.is_wayland && backend != InputBackend::Portal {
+        if wayland.remote_desktop {
+            Capability::no("On Wayland, select the Portal input backend to play macros")
+        } else {
+            Capability::no(
+                "This Wayland desktop offers no remote desktop portal to play macros through",
+            )
+        }
+    } else {
+        Capability::yes()
+    };
+    let global_hotkeys = if wayland.is_wayland {
+        Capability::no(
+            "Wayland doesn't let apps register global shortcuts; bind them in the desktop settings",
+        )
+    } else {
+        Capability::yes()
+    };
+    let raw_scancodes = match (windows, backend) {
+        (true, InputBackend::SendInput) => Capability::yes(),
+        (true, _) => Capability::no("Select the SendInput backend to send scan codes"),
+        _ => Capability::no("Scan codes are only sent on Windows with the SendInput backend"),
+    };
+    let window_targeting = if windows {
+        Capability::yes()
+    } else {
+        Capability::no("Playing into a background window is only supported on Windows")
+    };
+
+    PlatformCapabilities {
+        os: os.to_string(),
+        backend,
+        available_backends,
+        recording,
+        playback,
+        global_hotkeys,
+        raw_scancodes,
+        horizontal_scroll: Capability::yes(),
+        window_targeting,
+        gamepad: Capability::no("Gamepad input isn't recorded or played yet"),
+        wayland,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wayland_explains_what_is_missing() {
+        let wayland = WaylandCapabilities {
+            is_wayland: true,
+            remote_desktop: true,
+            input_capture: false,
+        };
+        let linux = report("linux", InputBackend::Enigo, wayland);
+        assert!(!linux.recording.supported);
+        assert!(!linux.global_hotkeys.supported);
+        assert!(linux.playback.note.unwrap().contains("Portal"));
+        assert!(!linux.window_targeting.supported);
+
+        let windows = report(
+            "windows",
+            InputBackend::SendInput,
+            WaylandCapabilities::default(),
+        );
+        assert!(windows.playback.supported);
+        assert!(windows.raw_scancodes.supported);
+        assert!(windows.window_targeting.supported);
+        assert!(!windows.gamepad.supported);
+
+        let macos = report(
+            "macos",
+            InputBackend::Xdotool,
+            WaylandCapabilities::default(),
+        );
+        assert!(!macos.playback.supported);
+    }
+}
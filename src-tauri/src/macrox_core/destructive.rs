@@ -0,0 +1,97 @@
+// Destructive action module
+//
+// Recognises key combinations that tend to lose work when a macro fires them
+// into the wrong window (select all then delete, permanent delete, quitting
+// an app), so playback can stop and ask before pressing them.
+
+/// Follows the keys a macro presses and flags the press that completes a
+/// destructive combination
+#[derive(Debug, Default)]
+pub struct DestructiveDetector {
+    shift: bool,
+    control: bool,
+    alt: bool,
+    meta: bool,
+    /// The last non-modifier press was select all
+    selected_all: bool,
+}
+
+impl DestructiveDetector {
+    /// Note a key press, returning what it would do if it completes a
+    /// destructive combination. Key names are the recorder's ("Control", "a").
+    pub fn key_down(&mut self, key: &str) -> Option<&'static str> {
+        if let Some(held) = self.modifier(key) {
+            *held = true;
+            return None;
+        }
+
+        let selected_all = std::mem::take(&mut self.selected_all);
+        let is_delete = key == "Delete" || key == "Backspace";
+        if key == "Delete" && self.shift {
+            Some("Shift+Delete (permanently deletes the selection)")
+        } else if is_delete && selected_all {
+            Some("Select all, then Delete (clears everything)")
+        } else if key.eq_ignore_ascii_case("q") && self.meta {
+            Some("Cmd+Q (quits the application)")
+        } else if key == "F4" && self.alt {
+            Some("Alt+F4 (closes the window)")
+        } else {
+            self.selected_all = key.eq_ignore_ascii_case("a") && (self.control || self.meta);
+            None
+        }
+    }
+
+    pub fn key_up(&mut self, key: &str) {
+        if let Some(held) = self.modifier(key) {
+            *held = false;
+        }
+    }
+
+    fn modifier(&mut self, key: &str) -> Option<&mut bool> {
+        if key.starts_with("Shift") {
+            Some(&mut self.shift)
+        } else if key.starts_with("Control") {
+            Some(&mut self.control)
+        } else if key.starts_with("Alt") {
+            Some(&mut self.alt)
+        } else if key.starts_with("Meta") {
+            Some(&mut self.meta)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_select_all_then_delete_only_in_sequence() {
+        let mut detector = DestructiveDetector::default();
+        assert_eq!(detector.key_down("Control"), None);
+        assert_eq!(detector.key_down("a"), None);
+        detector.key_up("a");
+        detector.key_up("Control");
+        assert!(detector.key_down("Delete").is_some());
+
+        // Typing something in between makes the delete harmless
+        detector.key_down("Control");
+        detector.key_down("a");
+        detector.key_up("Control");
+        detector.key_down("x");
+        assert_eq!(detector.key_down("Backspace"), None);
+    }
+
+    #[test]
+    fn flags_modifier_combinations() {
+        let mut detector = DestructiveDetector::default();
+        assert_eq!(detector.key_down("Delete"), None);
+        detector.key_down("ShiftLeft");
+        assert!(detector.key_down("Delete").is_some());
+        detector.key_up("ShiftLeft");
+
+        detector.key_down("Meta");
+        assert!(detector.key_down("q").is_some());
+    }
+}
@@ -0,0 +1,320 @@
+// Input device module
+//
+// Works out which physical device (keyboard, macro pad, mouse) produced each
+// recorded key, button and wheel event, so a recording can be limited to one
+// device. The global hook used for recording doesn't say where input came
+// from, so devices are sampled separately (Windows raw input) and matched to
+// events by time afterwards. Other platforms don't report devices yet.
+
+use parking_lot::Mutex;
+
+use super::types::{DeviceKind, InputDevice, MacroEvent};
+
+/// A device sample this close to an event is taken to be its source
+const MATCH_WINDOW_MS: u64 = 50;
+
+/// One input report from a known device
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceSample {
+    /// Milliseconds since the Unix epoch, like recorded event timestamps
+    pub timestamp: u64,
+    pub kind: DeviceKind,
+    pub device: String,
+}
+
+/// Samples collected while a recording is running, None otherwise
+static CAPTURE: Mutex<Option<Vec<DeviceSample>>> = Mutex::new(None);
+
+/// Start collecting device samples. Returns false when the platform doesn't report devices.
+pub fn begin_capture() -> bool {
+    if !platform::start() {
+        return false;
+    }
+    *CAPTURE.lock() = Some(Vec::new());
+    true
+}
+
+/// Stop collecting and return what was sampled since `begin_capture`
+pub fn end_capture() -> Vec<DeviceSample> {
+    CAPTURE.lock().take().unwrap_or_default()
+}
+
+/// Keyboards and mice currently connected
+pub fn list_devices() -> Vec<InputDevice> {
+    platform::list_devices()
+}
+
+fn record_sample(sample: DeviceSample) {
+    if let Some(samples) = CAPTURE.lock().as_mut() {
+        samples.push(sample);
+    }
+}
+
+fn event_kind(event: &MacroEvent) -> Option<DeviceKind> {
+    match event.event_type.as_str() {
+        "KeyDown" | "KeyUp" => Some(DeviceKind::Keyboard),
+        "MouseDown" | "MouseUp" | "MouseWheel" => Some(DeviceKind::Mouse),
+        _ => None,
+    }
+}
+
+/// Stamp key, button and wheel events with the closest sampled device, then
+/// drop events of `only_device`'s kind that came from another device. Events
+/// with no matching sample are kept.
+pub fn attribute(
+    events: Vec<MacroEvent>,
+    samples: &[DeviceSample],
+    only_device: Option<&InputDevice>,
+) -> Vec<MacroEvent> {
+    events
+        .into_iter()
+        .filter_map(|mut event| {
+            let Some(kind) = event_kind(&event) else {
+                return Some(event);
+            };
+            let device = samples
+                .iter()
+                .filter(|s| {
+                    s.kind == kind && s.timestamp.abs_diff(event.timestamp) <= MATCH_WINDOW_MS
+                })
+                .min_by_key(|s| s.timestamp.abs_diff(event.timestamp))
+                .map(|s| s.device.clone());
+
+            match (device, only_device) {
+                (Some(device), Some(only)) if only.kind == kind && device != only.id => None,
+                (Some(device), _) => {
+                    if let Some(data) = event.data.as_object_mut() {
+                        data.insert("device".to_string(), serde_json::json!(device));
+                    }
+                    Some(event)
+                }
+                (None, _) => Some(event),
+            }
+        })
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::sync::OnceLock;
+    use std::thread;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use windows::core::w;
+    use windows::Win32::Foundation::{HANDLE, HWND};
+    use windows::Win32::UI::Input::{
+        GetRawInputData, GetRawInputDeviceInfoW, GetRawInputDeviceList, RegisterRawInputDevices,
+        HRAWINPUT, RAWINPUTDEVICE, RAWINPUTDEVICELIST, RAWINPUTHEADER, RIDEV_INPUTSINK,
+        RIDI_DEVICENAME, RID_HEADER, RIM_TYPEKEYBOARD, RIM_TYPEMOUSE,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DispatchMessageW, GetMessageW, HWND_MESSAGE, MSG, WINDOW_EX_STYLE,
+        WINDOW_STYLE, WM_INPUT,
+    };
+
+    use super::{record_sample, DeviceKind, DeviceSample, InputDevice};
+
+    /// Whether the raw input thread came up
+    static STARTED: OnceLock<bool> = OnceLock::new();
+
+    pub fn start() -> bool {
+        *STARTED.get_or_init(|| {
+            let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+            thread::spawn(move || unsafe {
+                // A message-only window that receives raw input even when unfocused
+                let hwnd = CreateWindowExW(
+                    WINDOW_EX_STYLE(0),
+                    w!("STATIC"),
+                    w!("MacroX raw input"),
+                    WINDOW_STYLE(0),
+                    0,
+                    0,
+                    0,
+                    0,
+                    HWND_MESSAGE,
+                    None,
+                    None,
+                    None,
+                );
+                // Generic desktop page: mouse (2) and keyboard (6)
+                let devices = [2u16, 6u16].map(|usage| RAWINPUTDEVICE {
+                    usUsagePage: 1,
+                    usUsage: usage,
+                    dwFlags: RIDEV_INPUTSINK,
+                    hwndTarget: hwnd,
+                });
+                let registered = hwnd.0 != 0
+                    && RegisterRawInputDevices(
+                        &devices,
+                        std::mem::size_of::<RAWINPUTDEVICE>() as u32,
+                    )
+                    .is_ok();
+                let _ = ready_tx.send(registered);
+                if !registered {
+                    return;
+                }
+
+                let mut msg = MSG::default();
+                while GetMessageW(&mut msg, HWND(0), 0, 0).as_bool() {
+                    if msg.message == WM_INPUT {
+                        handle_input(HRAWINPUT(msg.lParam.0));
+                    }
+                    DispatchMessageW(&msg);
+                }
+            });
+            ready_rx.recv().unwrap_or(false)
+        })
+    }
+
+    unsafe fn handle_input(input: HRAWINPUT) {
+        let mut header = RAWINPUTHEADER::default();
+        let mut size = std::mem::size_of::<RAWINPUTHEADER>() as u32;
+        let read = GetRawInputData(
+            input,
+            RID_HEADER,
+            Some(&mut header as *mut _ as *mut _),
+            &mut size,
+            std::mem::size_of::<RAWINPUTHEADER>() as u32,
+        );
+        if read == u32::MAX || header.hDevice.0 == 0 {
+            return;
+        }
+
+        let kind = match header.dwType {
+            t if t == RIM_TYPEKEYBOARD.0 => DeviceKind::Keyboard,
+            t if t == RIM_TYPEMOUSE.0 => DeviceKind::Mouse,
+            _ => return,
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        record_sample(DeviceSample {
+            timestamp,
+            kind,
+            device: device_name(header.hDevice),
+        });
+    }
+
+    /// The device's interface path, which stays the same across reconnects
+    unsafe fn device_name(device: HANDLE) -> String {
+        let mut len = 0u32;
+        GetRawInputDeviceInfoW(device, RIDI_DEVICENAME, None, &mut len);
+        let mut buffer = vec![0u16; len as usize];
+        let read = GetRawInputDeviceInfoW(
+            device,
+            RIDI_DEVICENAME,
+            Some(buffer.as_mut_ptr() as *mut _),
+            &mut len,
+        );
+        if read == u32::MAX {
+            return format!("{:#x}", device.0);
+        }
+        String::from_utf16_lossy(&buffer)
+            .trim_end_matches('\0')
+            .to_string()
+    }
+
+    pub fn list_devices() -> Vec<InputDevice> {
+        unsafe {
+            let entry_size = std::mem::size_of::<RAWINPUTDEVICELIST>() as u32;
+            let mut count = 0u32;
+            if GetRawInputDeviceList(None, &mut count, entry_size) == u32::MAX {
+                return Vec::new();
+            }
+            let mut list = vec![RAWINPUTDEVICELIST::default(); count as usize];
+            let read = GetRawInputDeviceList(Some(list.as_mut_ptr()), &mut count, entry_size);
+            if read == u32::MAX {
+                return Vec::new();
+            }
+
+            list.iter()
+                .take(read as usize)
+                .filter_map(|entry| {
+                    let kind = match entry.dwType {
+                        t if t == RIM_TYPEKEYBOARD => DeviceKind::Keyboard,
+                        t if t == RIM_TYPEMOUSE => DeviceKind::Mouse,
+                        _ => return None,
+                    };
+                    Some(InputDevice {
+                        id: device_name(entry.hDevice),
+                        kind,
+                    })
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::InputDevice;
+
+    pub fn start() -> bool {
+        false
+    }
+
+    pub fn list_devices() -> Vec<InputDevice> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(event_type: &str, timestamp: u64) -> MacroEvent {
+        MacroEvent {
+            event_type: event_type.to_string(),
+            timestamp,
+            data: json!({}),
+        }
+    }
+
+    fn sample(timestamp: u64, kind: DeviceKind, device: &str) -> DeviceSample {
+        DeviceSample {
+            timestamp,
+            kind,
+            device: device.to_string(),
+        }
+    }
+
+    #[test]
+    fn stamps_closest_device_and_filters() {
+        let events = vec![
+            event("KeyDown", 1000),
+            event("KeyDown", 2000),
+            event("MouseMove", 2005),
+            event("MouseDown", 3000),
+            event("KeyUp", 9000),
+        ];
+        let samples = vec![
+            sample(1004, DeviceKind::Keyboard, "pad"),
+            sample(1030, DeviceKind::Keyboard, "keyboard"),
+            sample(1998, DeviceKind::Keyboard, "keyboard"),
+            sample(3001, DeviceKind::Mouse, "mouse"),
+        ];
+
+        let all = attribute(events.clone(), &samples, None);
+        let devices: Vec<_> = all.iter().map(|e| e.data.get("device").cloned()).collect();
+        assert_eq!(
+            devices,
+            vec![
+                Some(json!("pad")),
+                Some(json!("keyboard")),
+                None,
+                Some(json!("mouse")),
+                None
+            ]
+        );
+
+        let pad = InputDevice {
+            id: "pad".to_string(),
+            kind: DeviceKind::Keyboard,
+        };
+        let pad_only = attribute(events, &samples, Some(&pad));
+        let kept: Vec<u64> = pad_only.iter().map(|e| e.timestamp).collect();
+        assert_eq!(kept, vec![1000, 2005, 3000, 9000]);
+    }
+}
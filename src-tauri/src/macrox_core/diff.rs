@@ -0,0 +1,349 @@
+// Macro comparison module
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use super::types::{Macro, MacroEvent};
+
+/// Above this many cells the LCS table is skipped for a positional comparison
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// One step that differs between two macros
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum EventChange {
+    Added {
+        #[serde(rename = "indexB")]
+        index_b: usize,
+        event: MacroEvent,
+    },
+    Removed {
+        #[serde(rename = "indexA")]
+        index_a: usize,
+        event: MacroEvent,
+    },
+    Changed {
+        #[serde(rename = "indexA")]
+        index_a: usize,
+        #[serde(rename = "indexB")]
+        index_b: usize,
+        before: MacroEvent,
+        after: MacroEvent,
+    },
+}
+
+/// A metadata or settings field that differs
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Structured difference between macro A and macro B
+#[derive(Debug, Clone, Serialize)]
+pub struct MacroDiff {
+    pub fields: Vec<FieldChange>,
+    pub events: Vec<EventChange>,
+    #[serde(rename = "unchangedEvents")]
+    pub unchanged_events: usize,
+}
+
+pub fn diff_macros(a: &Macro, b: &Macro) -> MacroDiff {
+    let mut fields = Vec::new();
+
+    if a.name != b.name {
+        fields.push(FieldChange {
+            field: "name".to_string(),
+            before: Value::from(a.name.clone()),
+            after: Value::from(b.name.clone()),
+        });
+    }
+    if a.description != b.description {
+        fields.push(FieldChange {
+            field: "description".to_string(),
+            before: Value::from(a.description.clone()),
+            after: Value::from(b.description.clone()),
+        });
+    }
+    diff_objects(
+        "recordingSettings",
+        serde_json::to_value(&a.recording_settings).unwrap_or_default(),
+        serde_json::to_value(&b.recording_settings).unwrap_or_default(),
+        &mut fields,
+    );
+    diff_objects(
+        "playbackSettings",
+        serde_json::to_value(&a.playback_settings).unwrap_or_default(),
+        serde_json::to_value(&b.playback_settings).unwrap_or_default(),
+        &mut fields,
+    );
+
+    let (events, unchanged_events) = diff_events(&a.events, &b.events);
+
+    MacroDiff {
+        fields,
+        events,
+        unchanged_events,
+    }
+}
+
+fn diff_objects(prefix: &str, before: Value, after: Value, out: &mut Vec<FieldChange>) {
+    let empty = serde_json::Map::new();
+    let before_map = before.as_object().unwrap_or(&empty);
+    let after_map = after.as_object().unwrap_or(&empty);
+
+    let mut keys: Vec<&String> = before_map.keys().chain(after_map.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        let old = before_map.get(key).cloned().unwrap_or(Value::Null);
+        let new = after_map.get(key).cloned().unwrap_or(Value::Null);
+        if old != new {
+            out.push(FieldChange {
+                field: format!("{}.{}", prefix, key),
+                before: old,
+                after: new,
+            });
+        }
+    }
+}
+
+/// Hash of a macro's steps, equal for macros `same_steps` considers identical.
+/// Only stable within one run of the app, so don't persist it.
+pub fn content_hash(events: &[MacroEvent]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    events.len().hash(&mut hasher);
+    for event in events {
+        event.event_type.hash(&mut hasher);
+        // Object keys are sorted, so equal data serializes the same way
+        event.data.to_string().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Whether two event lists have the same steps, ignoring timing
+pub fn same_steps(a: &[MacroEvent], b: &[MacroEvent]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_step(a, b))
+}
+
+/// The first macro other than `candidate` itself with the same steps
+pub fn find_duplicate<'a>(macros: &'a [Arc<Macro>], candidate: &Macro) -> Option<&'a Macro> {
+    if candidate.events.is_empty() {
+        return None;
+    }
+    let hash = content_hash(&candidate.events);
+    macros
+        .iter()
+        .find(|m| {
+            m.id != candidate.id
+                && content_hash(&m.events) == hash
+                && same_steps(&m.events, &candidate.events)
+        })
+        .map(|m| &**m)
+}
+
+/// Two events are the same step if type and data match; timing is ignored
+fn same_step(a: &MacroEvent, b: &MacroEvent) -> bool {
+    a.event_type == b.event_type && a.data == b.data
+}
+
+enum Op {
+    Keep,
+    Remove(usize),
+    Add(usize),
+}
+
+pub(crate) fn diff_events(a: &[MacroEvent], b: &[MacroEvent]) -> (Vec<EventChange>, usize) {
+    // Common prefix and suffix are cheap to strip before the LCS
+    let prefix = a
+        .iter()
+        .zip(b.iter())
+        .take_while(|(x, y)| same_step(x, y))
+        .count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| same_step(x, y))
+        .count();
+
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let ops = if a_mid.len() * b_mid.len() <= MAX_LCS_CELLS {
+        lcs_ops(a_mid, b_mid)
+    } else {
+        positional_ops(a_mid, b_mid)
+    };
+
+    let mut changes = Vec::new();
+    let mut unchanged = prefix + suffix;
+    let mut pending_removed: Vec<usize> = Vec::new();
+
+    for op in ops {
+        match op {
+            Op::Keep => {
+                unchanged += 1;
+                flush_removed(&mut pending_removed, a, prefix, &mut changes);
+            }
+            Op::Remove(i) => pending_removed.push(i),
+            Op::Add(j) => {
+                // A removal followed by an addition of the same event type is an edit
+                let paired = pending_removed
+                    .iter()
+                    .position(|&i| a_mid[i].event_type == b_mid[j].event_type);
+                match paired {
+                    Some(pos) => {
+                        let i = pending_removed.remove(pos);
+                        changes.push(EventChange::Changed {
+                            index_a: prefix + i,
+                            index_b: prefix + j,
+                            before: a_mid[i].clone(),
+                            after: b_mid[j].clone(),
+                        });
+                    }
+                    None => changes.push(EventChange::Added {
+                        index_b: prefix + j,
+                        event: b_mid[j].clone(),
+                    }),
+                }
+            }
+        }
+    }
+    flush_removed(&mut pending_removed, a, prefix, &mut changes);
+
+    (changes, unchanged)
+}
+
+fn flush_removed(
+    pending: &mut Vec<usize>,
+    a: &[MacroEvent],
+    offset: usize,
+    changes: &mut Vec<EventChange>,
+) {
+    for i in pending.drain(..) {
+        changes.push(EventChange::Removed {
+            index_a: offset + i,
+            event: a[offset + i].clone(),
+        });
+    }
+}
+
+fn lcs_ops(a: &[MacroEvent], b: &[MacroEvent]) -> Vec<Op> {
+    let (n, m) = (a.len(), b.len());
+    // table[i][j] = LCS length of a[i..] and b[j..]
+    let mut table = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if same_step(&a[i], &b[j]) {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if same_step(&a[i], &b[j]) {
+            ops.push(Op::Keep);
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            ops.push(Op::Remove(i));
+            i += 1;
+        } else {
+            ops.push(Op::Add(j));
+            j += 1;
+        }
+    }
+    ops.extend((i..n).map(Op::Remove));
+    ops.extend((j..m).map(Op::Add));
+    ops
+}
+
+fn positional_ops(a: &[MacroEvent], b: &[MacroEvent]) -> Vec<Op> {
+    let mut ops = Vec::new();
+    for k in 0..a.len().max(b.len()) {
+        match (a.get(k), b.get(k)) {
+            (Some(x), Some(y)) if same_step(x, y) => ops.push(Op::Keep),
+            (Some(_), Some(_)) => {
+                ops.push(Op::Remove(k));
+                ops.push(Op::Add(k));
+            }
+            (Some(_), None) => ops.push(Op::Remove(k)),
+            (None, Some(_)) => ops.push(Op::Add(k)),
+            (None, None) => {}
+        }
+    }
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(event_type: &str, key: &str) -> MacroEvent {
+        MacroEvent {
+            event_type: event_type.to_string(),
+            timestamp: 0,
+            data: serde_json::json!({ "key": key }),
+        }
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_steps() {
+        let a = vec![key("KeyDown", "a"), key("KeyDown", "b"), key("KeyUp", "c")];
+        let b = vec![
+            key("KeyDown", "a"),
+            key("KeyDown", "x"),
+            key("KeyDown", "d"),
+        ];
+
+        let (changes, unchanged) = diff_events(&a, &b);
+
+        assert_eq!(unchanged, 1);
+        assert!(matches!(
+            changes[0],
+            EventChange::Changed {
+                index_a: 1,
+                index_b: 1,
+                ..
+            }
+        ));
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, EventChange::Removed { index_a: 2, .. })));
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, EventChange::Added { index_b: 2, .. })));
+    }
+
+    #[test]
+    fn identical_streams_have_no_changes() {
+        let a = vec![key("KeyDown", "a"), key("KeyUp", "a")];
+        let (changes, unchanged) = diff_events(&a, &a.clone());
+        assert!(changes.is_empty());
+        assert_eq!(unchanged, 2);
+    }
+
+    #[test]
+    fn content_hash_ignores_timing() {
+        let a = vec![key("KeyDown", "a"), key("KeyUp", "a")];
+        let mut b = a.clone();
+        b[1].timestamp = 250;
+
+        assert_eq!(content_hash(&a), content_hash(&b));
+        assert!(same_steps(&a, &b));
+        assert_ne!(
+            content_hash(&a),
+            content_hash(&[key("KeyDown", "b"), key("KeyUp", "b")])
+        );
+    }
+}
@@ -0,0 +1,361 @@
+// Macro editing module
+//
+// Permanent changes to a recorded event stream, as opposed to playback
+// settings that are applied each time the macro plays.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::player::run_macro_targets;
+use super::types::{EventRange, Macro, MacroEvent, PlaybackSettings};
+
+/// Scale the gaps between events by `factor` (0.5 plays that part twice as
+/// fast), either everywhere or only between the events in `range`. Events after
+/// the range move with it so their own timing is kept.
+pub fn retime(
+    events: &mut [MacroEvent],
+    factor: f64,
+    range: Option<EventRange>,
+) -> Result<(), String> {
+    if !factor.is_finite() || factor <= 0.0 {
+        return Err(format!("Time factor must be above zero, got {}", factor));
+    }
+    let whole = EventRange {
+        start: 0,
+        end: events.len(),
+    };
+    let range = range.unwrap_or(whole);
+    if range != whole && (range.start >= range.end || range.end > events.len()) {
+        return Err(format!(
+            "Event range {}..{} is outside the macro ({} events)",
+            range.start,
+            range.end,
+            events.len()
+        ));
+    }
+
+    // Worked out in full before anything changes, so a factor too large to
+    // represent leaves the macro as it was
+    let too_long = || format!("Time factor {} makes the macro too long", factor);
+    let mut retimed: Vec<u64> = Vec::with_capacity(events.len());
+    for (i, event) in events.iter().enumerate() {
+        let Some(&previous) = retimed.last() else {
+            retimed.push(event.timestamp);
+            continue;
+        };
+        let gap = event.timestamp.saturating_sub(events[i - 1].timestamp);
+        let gap = if i > range.start && i < range.end {
+            let scaled = (gap as f64 * factor).round();
+            if scaled >= u64::MAX as f64 {
+                return Err(too_long());
+            }
+            scaled as u64
+        } else {
+            gap
+        };
+        retimed.push(previous.checked_add(gap).ok_or_else(too_long)?);
+    }
+    for (event, timestamp) in events.iter_mut().zip(retimed) {
+        event.timestamp = timestamp;
+    }
+    Ok(())
+}
+
+/// Round every recorded mouse position to the nearest multiple of `grid_px`
+/// and drop moves that land where the previous move already was. Returns how
+/// many moves were dropped.
+pub fn align_to_grid(events: &mut Vec<MacroEvent>, grid_px: u32) -> Result<usize, String> {
+    if grid_px == 0 {
+        return Err("Grid size must be at least 1 pixel".to_string());
+    }
+    let grid = grid_px as f64;
+    let snap = |v: i64| ((v as f64 / grid).round() * grid) as i64;
+
+    for event in events.iter_mut() {
+        let Some(data) = event.data.as_object_mut() else {
+            continue;
+        };
+        for axis in ["x", "y"] {
+            if let Some(v) = data.get(axis).and_then(|v| v.as_i64()) {
+                data.insert(axis.to_string(), serde_json::json!(snap(v)));
+            }
+        }
+    }
+
+    let before = events.len();
+    let mut last_move: Option<(i64, i64)> = None;
+    events.retain(|e| {
+        if e.event_type != "MouseMove" {
+            return true;
+        }
+        let position = (
+            e.data.get("x").and_then(|v| v.as_i64()).unwrap_or(0),
+            e.data.get("y").and_then(|v| v.as_i64()).unwrap_or(0),
+        );
+        last_move.replace(position) != Some(position)
+    });
+    Ok(before - events.len())
+}
+
+/// Cut `events` into runs of at most `max_events`. Each cut goes at the
+/// longest pause in the second half of the run where no key or button is
+/// held, or at the limit if there is no such place.
+pub fn split_at_idle(events: &[MacroEvent], max_events: usize) -> Vec<Vec<MacroEvent>> {
+    let max_events = max_events.max(2);
+
+    // free[i]: nothing is held between events i - 1 and i
+    let mut free = Vec::with_capacity(events.len());
+    let mut keys: HashSet<String> = HashSet::new();
+    let mut buttons = 0usize;
+    for event in events {
+        free.push(keys.is_empty() && buttons == 0);
+        let key = || {
+            event
+                .data
+                .get("key")
+                .map(|k| k.to_string())
+                .unwrap_or_default()
+        };
+        match event.event_type.as_str() {
+            "KeyDown" => {
+                keys.insert(key());
+            }
+            "KeyUp" => {
+                keys.remove(&key());
+            }
+            "MouseDown" => buttons += 1,
+            "MouseUp" => buttons = buttons.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    let mut parts = Vec::new();
+    let mut start = 0;
+    while events.len() - start > max_events {
+        let limit = start + max_events;
+        let cut = (start + max_events / 2 + 1..=limit)
+            .filter(|&i| free[i])
+            .max_by_key(|&i| {
+                (
+                    events[i].timestamp.saturating_sub(events[i - 1].timestamp),
+                    i,
+                )
+            })
+            .unwrap_or(limit);
+        parts.push(events[start..cut].to_vec());
+        start = cut;
+    }
+    parts.push(events[start..].to_vec());
+    parts
+}
+
+/// Split a macro with more than `max_events` events into sub-macros, turning
+/// it into a macro that plays them in turn with RunMacro events. Returns the
+/// macro and its sub-macros, none if it already fits or `max_events` is 0.
+pub fn split_macro(mut macro_data: Macro, max_events: usize) -> (Macro, Vec<Macro>) {
+    if max_events == 0 || macro_data.events.len() <= max_events {
+        return (macro_data, Vec::new());
+    }
+
+    let events = std::mem::take(&mut macro_data.events);
+    let runs = split_at_idle(&events, max_events);
+    drop(events);
+    let mut parts = Vec::with_capacity(runs.len());
+    let mut chain = Vec::with_capacity(runs.len());
+    // A sub-macro's own events take up its duration, so each RunMacro only
+    // waits for the pause between the previous part's end and this part's start
+    let mut at = runs[0][0].timestamp;
+    let mut previous_end = at;
+    for (i, mut events) in runs.into_iter().enumerate() {
+        let first = events[0].timestamp;
+        at += first.saturating_sub(previous_end);
+        previous_end = events[events.len() - 1].timestamp;
+        for event in events.iter_mut() {
+            event.timestamp = event.timestamp.saturating_sub(first);
+        }
+
+        let part = Macro {
+            id: format!("{}-part{}", macro_data.id, i + 1),
+            name: format!("{} (part {})", macro_data.name, i + 1),
+            description: format!("Part {} of {}", i + 1, macro_data.name),
+            events,
+            playback_settings: PlaybackSettings {
+                repeat_mode: "once".to_string(),
+                ..macro_data.playback_settings.clone()
+            },
+            ..macro_data.clone()
+        };
+        chain.push(MacroEvent {
+            event_type: "RunMacro".to_string(),
+            timestamp: at,
+            data: serde_json::json!({ "macroId": part.id }),
+        });
+        parts.push(part);
+    }
+
+    macro_data.events = chain;
+    (macro_data, parts)
+}
+
+/// Remove a macro along with the sub-macros `split_macro` made for it, and
+/// return the ids removed. Refused while another macro still plays any of
+/// them, since its RunMacro step would then fail.
+pub fn delete_macro(macros: &mut Vec<Arc<Macro>>, id: &str) -> Result<Vec<String>, String> {
+    let Some(target) = macros.iter().find(|m| m.id == id) else {
+        return Ok(Vec::new());
+    };
+    let part_prefix = format!("{}-part", id);
+    let mut removed: HashSet<&str> = run_macro_targets(target)
+        .filter(|target| target.starts_with(&part_prefix))
+        .collect();
+    removed.insert(id);
+
+    let caller = macros.iter().find(|m| {
+        !removed.contains(m.id.as_str()) && run_macro_targets(m).any(|t| removed.contains(t))
+    });
+    if let Some(caller) = caller {
+        return Err(format!(
+            "\"{}\" still plays this macro; remove that step or delete it first",
+            caller.name
+        ));
+    }
+
+    let removed: HashSet<String> = removed.into_iter().map(str::to_string).collect();
+    macros.retain(|m| !removed.contains(&m.id));
+    Ok(removed.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(timestamps: &[u64]) -> Vec<MacroEvent> {
+        timestamps
+            .iter()
+            .map(|&timestamp| MacroEvent {
+                event_type: "KeyDown".to_string(),
+                timestamp,
+                data: serde_json::json!({ "key": "a" }),
+            })
+            .collect()
+    }
+
+    fn timestamps(events: &[MacroEvent]) -> Vec<u64> {
+        events.iter().map(|e| e.timestamp).collect()
+    }
+
+    #[test]
+    fn scales_the_whole_macro() {
+        let mut macro_events = events(&[0, 100, 300]);
+        retime(&mut macro_events, 0.5, None).unwrap();
+        assert_eq!(timestamps(&macro_events), vec![0, 50, 150]);
+    }
+
+    #[test]
+    fn scales_a_range_and_shifts_what_follows() {
+        let mut macro_events = events(&[0, 100, 300, 400]);
+        retime(
+            &mut macro_events,
+            2.0,
+            Some(EventRange { start: 1, end: 3 }),
+        )
+        .unwrap();
+        assert_eq!(timestamps(&macro_events), vec![0, 100, 500, 600]);
+
+        assert!(retime(&mut macro_events, 0.0, None).is_err());
+        assert!(retime(
+            &mut macro_events,
+            1.0,
+            Some(EventRange { start: 2, end: 9 })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn refuses_factors_that_overflow_the_timestamps() {
+        let mut macro_events = events(&[0, 100, 300]);
+        assert!(retime(&mut macro_events, 1e300, None).is_err());
+        assert!(retime(&mut macro_events, u64::MAX as f64 / 250.0, None).is_err());
+        assert_eq!(timestamps(&macro_events), vec![0, 100, 300]);
+    }
+
+    #[test]
+    fn snaps_positions_and_merges_repeated_moves() {
+        let mut macro_events: Vec<MacroEvent> = [(11, 19), (9, 21), (14, 22), (31, 18)]
+            .iter()
+            .map(|&(x, y)| MacroEvent {
+                event_type: "MouseMove".to_string(),
+                timestamp: 0,
+                data: serde_json::json!({ "x": x, "y": y }),
+            })
+            .collect();
+
+        let merged = align_to_grid(&mut macro_events, 10).unwrap();
+        let positions: Vec<(i64, i64)> = macro_events
+            .iter()
+            .map(|e| (e.data["x"].as_i64().unwrap(), e.data["y"].as_i64().unwrap()))
+            .collect();
+        assert_eq!(merged, 2);
+        assert_eq!(positions, vec![(10, 20), (30, 20)]);
+        assert!(align_to_grid(&mut macro_events, 0).is_err());
+    }
+
+    fn tapped_macro() -> Macro {
+        let taps = [("a", 0), ("b", 20), ("c", 500), ("d", 520)];
+        let events = taps
+            .iter()
+            .flat_map(|&(key, at)| {
+                ["KeyDown", "KeyUp"].into_iter().zip([at, at + 10]).map(
+                    move |(event_type, timestamp)| MacroEvent {
+                        event_type: event_type.to_string(),
+                        timestamp,
+                        data: serde_json::json!({ "key": key }),
+                    },
+                )
+            })
+            .collect();
+        Macro {
+            name: "Long".to_string(),
+            ..Macro::for_test("long", events)
+        }
+    }
+
+    #[test]
+    fn splits_long_macros_at_the_pause_between_taps() {
+        let (chain, parts) = split_macro(tapped_macro(), 6);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[1].id, "long-part2");
+        assert_eq!(parts[1].events[0].data["key"], "c");
+        assert_eq!(timestamps(&parts[1].events), vec![0, 10, 20, 30]);
+        assert_eq!(timestamps(&chain.events), vec![0, 470]);
+        assert_eq!(chain.events[1].data["macroId"], "long-part2");
+
+        let (unsplit, parts) = split_macro(chain, 6);
+        assert!(parts.is_empty());
+        assert_eq!(unsplit.events.len(), 2);
+    }
+
+    #[test]
+    fn deleting_a_split_macro_takes_its_parts() {
+        let (chain, parts) = split_macro(tapped_macro(), 6);
+        let other = Macro {
+            id: "other".to_string(),
+            ..parts[0].clone()
+        };
+        let mut macros: Vec<Arc<Macro>> = std::iter::once(chain)
+            .chain(parts)
+            .chain(std::iter::once(other))
+            .map(Arc::new)
+            .collect();
+
+        // A part can't go while the chain still plays it
+        assert!(delete_macro(&mut macros, "long-part1").is_err());
+        assert_eq!(macros.len(), 4);
+
+        let mut removed = delete_macro(&mut macros, "long").unwrap();
+        removed.sort();
+        assert_eq!(removed, vec!["long", "long-part1", "long-part2"]);
+        assert_eq!(macros.len(), 1);
+        assert_eq!(macros[0].id, "other");
+    }
+}
@@ -0,0 +1,175 @@
+// Elevation module
+//
+// Windows silently drops input a normal process injects into windows of
+// elevated ("Run as administrator") processes. This detects that case so
+// playback can fail with a clear error instead of clicks just not happening,
+// and restarts MacroX elevated when the user asks for it. Other platforms
+// have no such barrier and never report a blocked window.
+
+/// Names the foreground window if input to it would be dropped; swapped for a
+/// fake in tests
+pub type ElevationCheck = fn() -> Option<String>;
+
+/// Whether MacroX itself runs elevated
+pub fn is_elevated() -> bool {
+    platform::is_process_elevated()
+}
+
+/// Names the foreground window when it runs elevated and MacroX doesn't
+pub fn blocked_foreground_window() -> Option<String> {
+    if platform::is_process_elevated() {
+        return None;
+    }
+    platform::foreground_window()
+        .filter(|&hwnd| platform::is_window_elevated(hwnd))
+        .map(|hwnd| {
+            let title = platform::window_title(hwnd);
+            if title.is_empty() {
+                "The focused window".to_string()
+            } else {
+                format!("'{}'", title)
+            }
+        })
+}
+
+/// Whether input to the window would be dropped because it runs elevated
+#[cfg(target_os = "windows")]
+pub fn is_window_blocked(hwnd: isize) -> bool {
+    !platform::is_process_elevated() && platform::is_window_elevated(hwnd)
+}
+
+/// The error playback fails with when the window it is typing into is elevated
+pub fn blocked_message(window: &str) -> String {
+    format!(
+        "{} runs as administrator, so Windows blocks input from MacroX. \
+         Restart MacroX as administrator to automate it.",
+        window
+    )
+}
+
+/// Start a new elevated copy of MacroX with the same arguments. The caller
+/// should exit once this succeeds.
+pub fn relaunch_elevated() -> Result<(), String> {
+    platform::relaunch_elevated()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::core::{w, HSTRING, PCWSTR};
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, HWND};
+    use windows::Win32::Security::{
+        GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY,
+    };
+    use windows::Win32::System::Threading::{
+        GetCurrentProcess, OpenProcess, OpenProcessToken, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId, SW_SHOWNORMAL,
+    };
+
+    fn is_token_elevated(process: HANDLE) -> bool {
+        unsafe {
+            let mut token = HANDLE::default();
+            if OpenProcessToken(process, TOKEN_QUERY, &mut token).is_err() {
+                return false;
+            }
+            let mut elevation = TOKEN_ELEVATION::default();
+            let mut size = 0;
+            let result = GetTokenInformation(
+                token,
+                TokenElevation,
+                Some(&mut elevation as *mut TOKEN_ELEVATION as *mut _),
+                std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+                &mut size,
+            );
+            let _ = CloseHandle(token);
+            result.is_ok() && elevation.TokenIsElevated != 0
+        }
+    }
+
+    pub fn is_process_elevated() -> bool {
+        is_token_elevated(unsafe { GetCurrentProcess() })
+    }
+
+    pub fn foreground_window() -> Option<isize> {
+        let hwnd = unsafe { GetForegroundWindow() };
+        (hwnd.0 != 0).then_some(hwnd.0)
+    }
+
+    pub fn is_window_elevated(hwnd: isize) -> bool {
+        unsafe {
+            let mut pid = 0;
+            GetWindowThreadProcessId(HWND(hwnd), Some(&mut pid));
+            if pid == 0 {
+                return false;
+            }
+            // Elevated processes can't be opened from a normal one; that
+            // refusal means the same thing as reading an elevated token
+            let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) else {
+                return true;
+            };
+            let elevated = is_token_elevated(process);
+            let _ = CloseHandle(process);
+            elevated
+        }
+    }
+
+    pub fn window_title(hwnd: isize) -> String {
+        let mut buffer = [0u16; 512];
+        let len = unsafe { GetWindowTextW(HWND(hwnd), &mut buffer) }.max(0) as usize;
+        String::from_utf16_lossy(&buffer[..len])
+    }
+
+    pub fn relaunch_elevated() -> Result<(), String> {
+        let exe = std::env::current_exe()
+            .map_err(|e| format!("Failed to find the MacroX executable: {}", e))?;
+        let args = std::env::args()
+            .skip(1)
+            .map(|arg| format!("\"{}\"", arg.replace('"', "\\\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // Shows the UAC prompt; values of 32 and below are errors
+        let result = unsafe {
+            ShellExecuteW(
+                HWND::default(),
+                w!("runas"),
+                &HSTRING::from(exe.as_os_str()),
+                &HSTRING::from(args),
+                PCWSTR::null(),
+                SW_SHOWNORMAL,
+            )
+        };
+        if result.0 <= 32 {
+            return Err(format!(
+                "Failed to restart MacroX as administrator (error {})",
+                result.0
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    pub fn is_process_elevated() -> bool {
+        false
+    }
+
+    pub fn foreground_window() -> Option<isize> {
+        None
+    }
+
+    pub fn is_window_elevated(_hwnd: isize) -> bool {
+        false
+    }
+
+    pub fn window_title(_hwnd: isize) -> String {
+        String::new()
+    }
+
+    pub fn relaunch_elevated() -> Result<(), String> {
+        Err("Restarting as administrator is only supported on Windows".to_string())
+    }
+}
@@ -0,0 +1,527 @@
+// Macro file encoding module
+//
+// Exported macros are written as one JSON document, as newline-delimited JSON
+// (the macro without its events on the first line, then one event per line)
+// for streaming tools, as MessagePack, or in a compact binary form for long
+// recordings. Imports tell the encodings apart from the bytes themselves.
+//
+// The binary form starts with "MXB" and a version byte, then the macro
+// without its events as MessagePack, the distinct event types, and each event
+// as a type index, the time since the previous event and its data.
+
+use serde_json::{Map, Number, Value};
+
+use super::types::{ExportFormat, Macro};
+
+const BINARY_MAGIC: &[u8] = b"MXB\x01";
+
+/// How deep arrays and maps may nest in an imported file, as in serde_json
+const MAX_NESTING: usize = 128;
+
+/// File extension for `format`
+pub fn extension(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Json => "json",
+        ExportFormat::Ndjson => "ndjson",
+        ExportFormat::MessagePack => "msgpack",
+        ExportFormat::Binary => "mxb",
+    }
+}
+
+pub fn encode(macro_data: &Macro, format: ExportFormat) -> Result<Vec<u8>, String> {
+    let mut value = serde_json::to_value(macro_data).map_err(|e| e.to_string())?;
+    match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(&value).map_err(|e| e.to_string()),
+        ExportFormat::Ndjson => {
+            let events = take_events(&mut value);
+            let mut lines = vec![value];
+            lines.extend(events);
+            let mut bytes = Vec::new();
+            for line in &lines {
+                serde_json::to_writer(&mut bytes, line).map_err(|e| e.to_string())?;
+                bytes.push(b'\n');
+            }
+            Ok(bytes)
+        }
+        ExportFormat::MessagePack => {
+            let mut bytes = Vec::new();
+            write_msgpack(&mut bytes, &value);
+            Ok(bytes)
+        }
+        ExportFormat::Binary => Ok(encode_binary(value)),
+    }
+}
+
+/// Which encoding `bytes` are in, if any
+pub fn detect(bytes: &[u8]) -> Option<ExportFormat> {
+    if bytes.starts_with(BINARY_MAGIC) {
+        return Some(ExportFormat::Binary);
+    }
+    match *bytes.iter().find(|b| !b.is_ascii_whitespace())? {
+        b'{' => {
+            let documents = serde_json::Deserializer::from_slice(bytes)
+                .into_iter::<Value>()
+                .take(2)
+                .count();
+            Some(if documents > 1 {
+                ExportFormat::Ndjson
+            } else {
+                ExportFormat::Json
+            })
+        }
+        // A MessagePack map
+        0x80..=0x8f | 0xde | 0xdf => Some(ExportFormat::MessagePack),
+        _ => None,
+    }
+}
+
+/// Read a macro in any of the export encodings
+pub fn decode(bytes: &[u8]) -> Result<Macro, String> {
+    let format = detect(bytes).ok_or("Not a MacroX macro file")?;
+    let value = match format {
+        ExportFormat::Json | ExportFormat::Ndjson => {
+            let mut documents = serde_json::Deserializer::from_slice(bytes).into_iter::<Value>();
+            let mut value = documents
+                .next()
+                .ok_or("The file is empty")?
+                .map_err(|e| e.to_string())?;
+            let events = documents
+                .collect::<Result<Vec<Value>, _>>()
+                .map_err(|e| e.to_string())?;
+            // NDJSON keeps the events on the lines after the macro
+            if value.get("events").is_none() {
+                set_events(&mut value, events)?;
+            }
+            value
+        }
+        ExportFormat::MessagePack => {
+            let mut reader = Reader::new(bytes, 0);
+            reader.msgpack()?
+        }
+        ExportFormat::Binary => decode_binary(bytes)?,
+    };
+    serde_json::from_value(value).map_err(|e| format!("Invalid macro: {}", e))
+}
+
+fn take_events(value: &mut Value) -> Vec<Value> {
+    match value.as_object_mut().and_then(|m| m.remove("events")) {
+        Some(Value::Array(events)) => events,
+        _ => Vec::new(),
+    }
+}
+
+fn set_events(value: &mut Value, events: Vec<Value>) -> Result<(), String> {
+    value
+        .as_object_mut()
+        .ok_or("The macro header is not an object")?
+        .insert("events".to_string(), Value::Array(events));
+    Ok(())
+}
+
+fn encode_binary(mut value: Value) -> Vec<u8> {
+    let events = take_events(&mut value);
+    let mut bytes = BINARY_MAGIC.to_vec();
+    let mut header = Vec::new();
+    write_msgpack(&mut header, &value);
+    write_varint(&mut bytes, header.len() as u64);
+    bytes.extend(header);
+
+    let mut types: Vec<&str> = Vec::new();
+    for event in &events {
+        let event_type = event["type"].as_str().unwrap_or_default();
+        if !types.contains(&event_type) {
+            types.push(event_type);
+        }
+    }
+    write_varint(&mut bytes, types.len() as u64);
+    for event_type in &types {
+        write_varint(&mut bytes, event_type.len() as u64);
+        bytes.extend_from_slice(event_type.as_bytes());
+    }
+
+    write_varint(&mut bytes, events.len() as u64);
+    let mut last_timestamp = 0i64;
+    for event in &events {
+        let event_type = event["type"].as_str().unwrap_or_default();
+        let index = types.iter().position(|t| *t == event_type).unwrap_or(0);
+        let timestamp = event["timestamp"].as_i64().unwrap_or(0);
+        write_varint(&mut bytes, index as u64);
+        write_varint(&mut bytes, zigzag(timestamp - last_timestamp));
+        write_msgpack(&mut bytes, &event["data"]);
+        last_timestamp = timestamp;
+    }
+    bytes
+}
+
+fn decode_binary(bytes: &[u8]) -> Result<Value, String> {
+    let mut reader = Reader::new(bytes, BINARY_MAGIC.len());
+    let header_len = reader.varint()? as usize;
+    let header_end = reader
+        .pos
+        .checked_add(header_len)
+        .ok_or("Corrupt macro header")?;
+    let mut value = reader.msgpack()?;
+    if reader.pos != header_end {
+        return Err("Corrupt macro header".to_string());
+    }
+
+    let mut types = Vec::new();
+    for _ in 0..reader.varint()? {
+        let len = reader.varint()? as usize;
+        let name = std::str::from_utf8(reader.take(len)?).map_err(|e| e.to_string())?;
+        types.push(name.to_string());
+    }
+
+    let count = reader.varint()? as usize;
+    let mut events = Vec::with_capacity(count.min(bytes.len()));
+    let mut timestamp = 0i64;
+    for _ in 0..count {
+        let index = reader.varint()? as usize;
+        let event_type = types
+            .get(index)
+            .ok_or_else(|| format!("Event type {} is not in the file", index))?;
+        timestamp = timestamp
+            .checked_add(unzigzag(reader.varint()?))
+            .ok_or("Corrupt event timestamp")?;
+        let data = reader.msgpack()?;
+        events.push(serde_json::json!({
+            "type": event_type,
+            "timestamp": timestamp,
+            "data": data,
+        }));
+    }
+    set_events(&mut value, events)?;
+    Ok(value)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+/// Small negative deltas, e.g. from hand-edited timestamps, stay short
+fn zigzag(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
+fn unzigzag(n: u64) -> i64 {
+    (n >> 1) as i64 ^ -((n & 1) as i64)
+}
+
+fn write_msgpack(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => out.push(0xc0),
+        Value::Bool(b) => out.push(if *b { 0xc3 } else { 0xc2 }),
+        Value::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                write_uint(out, u);
+            } else if let Some(i) = n.as_i64() {
+                write_int(out, i);
+            } else {
+                out.push(0xcb);
+                out.extend(n.as_f64().unwrap_or(0.0).to_be_bytes());
+            }
+        }
+        Value::String(s) => {
+            let len = s.len();
+            if len < 32 {
+                out.push(0xa0 | len as u8);
+            } else {
+                write_len(out, len, [0xd9, 0xda, 0xdb]);
+            }
+            out.extend_from_slice(s.as_bytes());
+        }
+        Value::Array(items) => {
+            if items.len() < 16 {
+                out.push(0x90 | items.len() as u8);
+            } else {
+                write_len(out, items.len(), [0, 0xdc, 0xdd]);
+            }
+            for item in items {
+                write_msgpack(out, item);
+            }
+        }
+        Value::Object(map) => {
+            if map.len() < 16 {
+                out.push(0x80 | map.len() as u8);
+            } else {
+                write_len(out, map.len(), [0, 0xde, 0xdf]);
+            }
+            for (key, item) in map {
+                write_msgpack(out, &Value::String(key.clone()));
+                write_msgpack(out, item);
+            }
+        }
+    }
+}
+
+fn write_uint(out: &mut Vec<u8>, u: u64) {
+    if u < 0x80 {
+        out.push(u as u8);
+    } else if u <= u8::MAX as u64 {
+        out.extend([0xcc, u as u8]);
+    } else if u <= u16::MAX as u64 {
+        out.push(0xcd);
+        out.extend((u as u16).to_be_bytes());
+    } else if u <= u32::MAX as u64 {
+        out.push(0xce);
+        out.extend((u as u32).to_be_bytes());
+    } else {
+        out.push(0xcf);
+        out.extend(u.to_be_bytes());
+    }
+}
+
+fn write_int(out: &mut Vec<u8>, i: i64) {
+    if i >= -32 {
+        out.push(i as u8);
+    } else if i >= i8::MIN as i64 {
+        out.extend([0xd0, i as u8]);
+    } else if i >= i16::MIN as i64 {
+        out.push(0xd1);
+        out.extend((i as i16).to_be_bytes());
+    } else if i >= i32::MIN as i64 {
+        out.push(0xd2);
+        out.extend((i as i32).to_be_bytes());
+    } else {
+        out.push(0xd3);
+        out.extend(i.to_be_bytes());
+    }
+}
+
+/// Length with the 8, 16 or 32 bit marker; a 0 marker means that size doesn't exist
+fn write_len(out: &mut Vec<u8>, len: usize, markers: [u8; 3]) {
+    if len <= u8::MAX as usize && markers[0] != 0 {
+        out.extend([markers[0], len as u8]);
+    } else if len <= u16::MAX as usize {
+        out.push(markers[1]);
+        out.extend((len as u16).to_be_bytes());
+    } else {
+        out.push(markers[2]);
+        out.extend((len as u32).to_be_bytes());
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Arrays and maps currently open, so a crafted file can't overflow the stack
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], pos: usize) -> Self {
+        Self {
+            bytes,
+            pos,
+            depth: 0,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or("The file ends early")?;
+        let taken = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn uint(&mut self, size: usize) -> Result<u64, String> {
+        Ok(self
+            .take(size)?
+            .iter()
+            .fold(0u64, |n, b| (n << 8) | *b as u64))
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            n |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err("Corrupt length in the file".to_string())
+    }
+
+    fn msgpack(&mut self) -> Result<Value, String> {
+        let marker = self.byte()?;
+        Ok(match marker {
+            0x00..=0x7f => Value::from(marker),
+            0xe0..=0xff => Value::from(marker as i8),
+            0xc0 => Value::Null,
+            0xc2 => Value::Bool(false),
+            0xc3 => Value::Bool(true),
+            0xcc => Value::from(self.uint(1)?),
+            0xcd => Value::from(self.uint(2)?),
+            0xce => Value::from(self.uint(4)?),
+            0xcf => Value::from(self.uint(8)?),
+            0xd0 => Value::from(self.uint(1)? as u8 as i8),
+            0xd1 => Value::from(self.uint(2)? as u16 as i16),
+            0xd2 => Value::from(self.uint(4)? as u32 as i32),
+            0xd3 => Value::from(self.uint(8)? as i64),
+            0xca => float(f32::from_bits(self.uint(4)? as u32) as f64),
+            0xcb => float(f64::from_bits(self.uint(8)?)),
+            0xa0..=0xbf => self.string((marker & 0x1f) as usize)?,
+            0xd9 => self.sized(1, Self::string)?,
+            0xda => self.sized(2, Self::string)?,
+            0xdb => self.sized(4, Self::string)?,
+            0x90..=0x9f => self.array((marker & 0x0f) as usize)?,
+            0xdc => self.sized(2, Self::array)?,
+            0xdd => self.sized(4, Self::array)?,
+            0x80..=0x8f => self.map((marker & 0x0f) as usize)?,
+            0xde => self.sized(2, Self::map)?,
+            0xdf => self.sized(4, Self::map)?,
+            _ => return Err(format!("Unsupported MessagePack type 0x{:02x}", marker)),
+        })
+    }
+
+    fn sized(
+        &mut self,
+        size: usize,
+        read: fn(&mut Self, usize) -> Result<Value, String>,
+    ) -> Result<Value, String> {
+        let len = self.uint(size)? as usize;
+        read(self, len)
+    }
+
+    fn string(&mut self, len: usize) -> Result<Value, String> {
+        let s = std::str::from_utf8(self.take(len)?).map_err(|e| e.to_string())?;
+        Ok(Value::String(s.to_string()))
+    }
+
+    fn array(&mut self, len: usize) -> Result<Value, String> {
+        self.nested(|reader| {
+            // Every item takes at least a byte, so a bad length can't reserve much
+            let mut items = Vec::with_capacity(len.min(reader.bytes.len() - reader.pos));
+            for _ in 0..len {
+                items.push(reader.msgpack()?);
+            }
+            Ok(Value::Array(items))
+        })
+    }
+
+    fn map(&mut self, len: usize) -> Result<Value, String> {
+        self.nested(|reader| {
+            let mut map = Map::new();
+            for _ in 0..len {
+                let Value::String(key) = reader.msgpack()? else {
+                    return Err("MessagePack map keys must be strings".to_string());
+                };
+                map.insert(key, reader.msgpack()?);
+            }
+            Ok(Value::Object(map))
+        })
+    }
+
+    fn nested(
+        &mut self,
+        read: impl FnOnce(&mut Self) -> Result<Value, String>,
+    ) -> Result<Value, String> {
+        if self.depth >= MAX_NESTING {
+            return Err("The file nests values too deeply".to_string());
+        }
+        self.depth += 1;
+        let value = read(self);
+        self.depth -= 1;
+        value
+    }
+}
+
+fn float(f: f64) -> Value {
+    Number::from_f64(f).map_or(Value::Null, Value::Number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded_macro() -> Macro {
+        let events = serde_json::from_value(serde_json::json!([
+            { "type": "MouseMove", "timestamp": 0, "data": { "x": 120.5, "y": -4.0 } },
+            { "type": "KeyDown", "timestamp": 40, "data": { "key": "a" } },
+            { "type": "KeyUp", "timestamp": 95, "data": { "key": "a" } },
+            { "type": "MouseMove", "timestamp": 70_000, "data": { "x": 3, "y": 4 } }
+        ]))
+        .unwrap();
+        Macro {
+            name: "Fill form".to_string(),
+            description: "Fills the **signup** form".to_string(),
+            author: Some("Ada".to_string()),
+            source_url: Some("https://example.com/fill-form".to_string()),
+            license: Some("MIT".to_string()),
+            ..Macro::for_test("m1", events)
+        }
+    }
+
+    #[test]
+    fn every_format_is_detected_and_reads_back() {
+        let original = recorded_macro();
+        for format in [
+            ExportFormat::Json,
+            ExportFormat::Ndjson,
+            ExportFormat::MessagePack,
+            ExportFormat::Binary,
+        ] {
+            let bytes = encode(&original, format).unwrap();
+            assert_eq!(detect(&bytes), Some(format));
+            let decoded = decode(&bytes).unwrap();
+            assert_eq!(
+                serde_json::to_value(&decoded).unwrap(),
+                serde_json::to_value(&original).unwrap(),
+                "{:?}",
+                format
+            );
+        }
+        assert!(decode(b"not a macro").is_err());
+    }
+
+    #[test]
+    fn messagepack_uses_the_standard_markers() {
+        let mut bytes = Vec::new();
+        write_msgpack(&mut bytes, &serde_json::json!({ "a": [1, -1, 300, null] }));
+        assert_eq!(
+            bytes,
+            [0x81, 0xa1, b'a', 0x94, 0x01, 0xff, 0xcd, 0x01, 0x2c, 0xc0]
+        );
+    }
+
+    #[test]
+    fn corrupt_files_are_refused_without_panicking() {
+        let mut nested = vec![0x81, 0xa1, b'a'];
+        nested.resize(100_000, 0x91);
+        assert!(decode(&nested).is_err());
+
+        let mut huge_header = BINARY_MAGIC.to_vec();
+        write_varint(&mut huge_header, u64::MAX);
+        assert!(decode(&huge_header).is_err());
+
+        // Two events whose timestamp deltas add up past i64::MAX
+        let mut bytes = BINARY_MAGIC.to_vec();
+        let mut header = Vec::new();
+        write_msgpack(&mut header, &serde_json::json!({}));
+        write_varint(&mut bytes, header.len() as u64);
+        bytes.extend_from_slice(&header);
+        write_varint(&mut bytes, 1);
+        write_varint(&mut bytes, 1);
+        bytes.push(b'K');
+        write_varint(&mut bytes, 2);
+        for _ in 0..2 {
+            write_varint(&mut bytes, 0);
+            write_varint(&mut bytes, zigzag(i64::MAX));
+            write_msgpack(&mut bytes, &Value::Null);
+        }
+        assert!(decode(&bytes).is_err());
+    }
+}
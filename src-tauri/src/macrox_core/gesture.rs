@@ -0,0 +1,190 @@
+// Touchpad gesture module
+//
+// The global input hook never sees touchpad gestures themselves, only what
+// the OS turns them into: on Windows a pinch arrives as Ctrl+wheel and a
+// three-finger swipe as the virtual desktop or task view shortcut. Recordings
+// fold those sequences back into Pinch and Swipe events, and playback sends
+// the closest input the platform has for each.
+
+use super::types::MacroEvent;
+
+/// Directions a swipe can go, with the shortcut the OS sends for it
+#[cfg(target_os = "windows")]
+const SWIPES: &[(&str, &[&str])] = &[
+    ("left", &["Control", "Meta", "LeftArrow"]),
+    ("right", &["Control", "Meta", "RightArrow"]),
+    ("up", &["Meta", "Tab"]),
+    ("down", &["Meta", "d"]),
+];
+#[cfg(target_os = "macos")]
+const SWIPES: &[(&str, &[&str])] = &[
+    ("left", &["Control", "LeftArrow"]),
+    ("right", &["Control", "RightArrow"]),
+    ("up", &["Control", "UpArrow"]),
+    ("down", &["Control", "DownArrow"]),
+];
+// Desktops only switch sideways by shortcut on most Linux desktops
+#[cfg(all(unix, not(target_os = "macos")))]
+const SWIPES: &[(&str, &[&str])] = &[
+    ("left", &["Control", "Alt", "LeftArrow"]),
+    ("right", &["Control", "Alt", "RightArrow"]),
+];
+
+/// Keys that play a swipe in `direction`, modifiers first
+pub fn swipe_keys(direction: &str) -> Option<&'static [&'static str]> {
+    SWIPES
+        .iter()
+        .find(|(d, _)| *d == direction)
+        .map(|(_, keys)| *keys)
+}
+
+/// Replace the key and wheel sequences gestures turn into with Pinch and Swipe events
+pub fn detect(events: Vec<MacroEvent>) -> Vec<MacroEvent> {
+    let mut detected = Vec::with_capacity(events.len());
+    let mut i = 0;
+    while i < events.len() {
+        match chord_end(&events, i).and_then(|end| Some((end, fold(&events[i..end])?))) {
+            Some((end, gesture)) => {
+                detected.push(gesture);
+                i = end;
+            }
+            None => {
+                detected.push(events[i].clone());
+                i += 1;
+            }
+        }
+    }
+    detected
+}
+
+/// End of the key chord starting at `start`: from a key press until every key
+/// pressed in it is released again, with only wheel events in between
+fn chord_end(events: &[MacroEvent], start: usize) -> Option<usize> {
+    if events[start].event_type != "KeyDown" {
+        return None;
+    }
+    let mut held: Vec<&str> = Vec::new();
+    for (i, event) in events.iter().enumerate().skip(start) {
+        let key = event.data.get("key").and_then(|k| k.as_str());
+        match (event.event_type.as_str(), key) {
+            ("KeyDown", Some(key)) => {
+                if !held.contains(&key) {
+                    held.push(key);
+                }
+            }
+            ("KeyUp", Some(key)) => {
+                held.retain(|k| *k != key);
+                if held.is_empty() {
+                    return Some(i + 1);
+                }
+            }
+            ("MouseWheel", _) => {}
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Modifiers lose their side, so both Control keys read as "Control"
+fn base_key(key: &str) -> &str {
+    ["Control", "Meta", "Shift"]
+        .into_iter()
+        .find(|m| key.starts_with(m))
+        .unwrap_or(key)
+}
+
+/// The gesture a chord stands for, if any
+fn fold(chord: &[MacroEvent]) -> Option<MacroEvent> {
+    let mut keys: Vec<&str> = Vec::new();
+    for event in chord.iter().filter(|e| e.event_type == "KeyDown") {
+        if let Some(key) = event.data.get("key").and_then(|k| k.as_str()) {
+            if !keys.contains(&base_key(key)) {
+                keys.push(base_key(key));
+            }
+        }
+    }
+    let wheel: Vec<&MacroEvent> = chord
+        .iter()
+        .filter(|e| e.event_type == "MouseWheel")
+        .collect();
+    let timestamp = chord[0].timestamp;
+
+    if !wheel.is_empty() {
+        if keys != ["Control"] {
+            return None;
+        }
+        let delta = |axis: &str| -> i64 {
+            wheel
+                .iter()
+                .filter_map(|e| e.data.get(axis).and_then(|v| v.as_i64()))
+                .sum()
+        };
+        return Some(MacroEvent {
+            event_type: "Pinch".to_string(),
+            timestamp,
+            data: serde_json::json!({ "delta_y": delta("delta_y"), "delta_x": delta("delta_x") }),
+        });
+    }
+
+    let (direction, _) = SWIPES
+        .iter()
+        .find(|(_, swipe)| swipe.len() == keys.len() && swipe.iter().all(|k| keys.contains(k)))?;
+    Some(MacroEvent {
+        event_type: "Swipe".to_string(),
+        timestamp,
+        data: serde_json::json!({ "direction": direction }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(event_type: &str, key: &str, timestamp: u64) -> MacroEvent {
+        MacroEvent {
+            event_type: event_type.to_string(),
+            timestamp,
+            data: serde_json::json!({ "key": key }),
+        }
+    }
+
+    fn wheel(delta_y: i64, timestamp: u64) -> MacroEvent {
+        MacroEvent {
+            event_type: "MouseWheel".to_string(),
+            timestamp,
+            data: serde_json::json!({ "delta_x": 0, "delta_y": delta_y }),
+        }
+    }
+
+    #[test]
+    fn ctrl_wheel_becomes_a_pinch_and_typing_is_left_alone() {
+        let events = vec![
+            key("KeyDown", "Control", 0),
+            wheel(1, 10),
+            wheel(2, 20),
+            key("KeyUp", "Control", 30),
+            key("KeyDown", "Control", 40),
+            key("KeyDown", "c", 50),
+            key("KeyUp", "c", 60),
+            key("KeyUp", "Control", 70),
+        ];
+
+        let detected = detect(events);
+        assert_eq!(detected.len(), 5);
+        assert_eq!(detected[0].event_type, "Pinch");
+        assert_eq!(detected[0].data["delta_y"], 3);
+        assert_eq!(detected[1].data["key"], "Control");
+    }
+
+    #[test]
+    fn desktop_shortcut_becomes_a_swipe() {
+        let keys = swipe_keys("right").unwrap();
+        let mut events: Vec<MacroEvent> = keys.iter().map(|k| key("KeyDown", k, 0)).collect();
+        events.extend(keys.iter().rev().map(|k| key("KeyUp", k, 5)));
+
+        let detected = detect(events);
+        assert_eq!(detected.len(), 1);
+        assert_eq!(detected[0].event_type, "Swipe");
+        assert_eq!(detected[0].data["direction"], "right");
+    }
+}
@@ -0,0 +1,67 @@
+// Run history module
+//
+// Keeps the most recent playback runs in memory so status pages and the
+// control server can show what ran, when, and how it ended.
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+
+use super::types::RunRecord;
+
+/// Number of runs kept before the oldest are dropped
+pub const MAX_RUNS: usize = 100;
+
+/// Recent playback runs, oldest first
+#[derive(Default)]
+pub struct RunHistory {
+    runs: Mutex<VecDeque<RunRecord>>,
+}
+
+impl RunHistory {
+    pub fn push(&self, run: RunRecord) {
+        let mut runs = self.runs.lock();
+        if runs.len() == MAX_RUNS {
+            runs.pop_front();
+        }
+        runs.push_back(run);
+    }
+
+    /// Up to `limit` runs, newest first
+    pub fn recent(&self, limit: usize) -> Vec<RunRecord> {
+        self.runs.lock().iter().rev().take(limit).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macrox_core::types::RunOutcome;
+    use chrono::Utc;
+
+    fn run(macro_id: &str) -> RunRecord {
+        RunRecord {
+            macro_id: macro_id.to_string(),
+            macro_name: macro_id.to_string(),
+            started_at: Utc::now(),
+            duration_ms: 10,
+            outcome: RunOutcome::Completed,
+            error: None,
+            outputs: Default::default(),
+            timing: None,
+        }
+    }
+
+    #[test]
+    fn keeps_newest_runs_first() {
+        let history = RunHistory::default();
+        for i in 0..MAX_RUNS + 5 {
+            history.push(run(&i.to_string()));
+        }
+
+        let recent = history.recent(usize::MAX);
+        assert_eq!(recent.len(), MAX_RUNS);
+        assert_eq!(recent[0].macro_id, (MAX_RUNS + 4).to_string());
+        assert_eq!(recent.last().unwrap().macro_id, "5");
+        assert_eq!(history.recent(2).len(), 2);
+    }
+}
@@ -0,0 +1,289 @@
+// Input backend abstraction module
+//
+// The player injects input through an InputSink and the recorder captures it
+// from an InputSource. The real implementations wrap enigo and rdev; the
+// fakes let the rest of the crate be exercised without touching the OS.
+
+use enigo::{Axis, Button, Coordinate, Direction, Enigo, Key, Keyboard, Mouse, Settings};
+use parking_lot::Mutex;
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tracing::warn;
+
+use super::types::InputBackend;
+
+/// Something that can inject input events
+pub trait InputSink {
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> Result<(), String>;
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String>;
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String>;
+    fn scroll(&mut self, amount: i32, axis: Axis) -> Result<(), String>;
+    fn cursor_position(&self) -> Result<(i32, i32), String>;
+
+    /// Wait between events. Fakes override this so tests don't actually sleep.
+    fn wait(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Receives captured input events; returning false unsubscribes it
+pub type InputCallback = Box<dyn FnMut(rdev::Event) -> bool + Send>;
+
+impl<T: InputSink + ?Sized> InputSink for Box<T> {
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> Result<(), String> {
+        (**self).move_mouse(x, y, coordinate)
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+        (**self).button(button, direction)
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        (**self).key(key, direction)
+    }
+
+    fn scroll(&mut self, amount: i32, axis: Axis) -> Result<(), String> {
+        (**self).scroll(amount, axis)
+    }
+
+    fn cursor_position(&self) -> Result<(i32, i32), String> {
+        (**self).cursor_position()
+    }
+
+    fn wait(&mut self, duration: Duration) {
+        (**self).wait(duration)
+    }
+}
+
+/// Something that delivers captured input events to a callback
+pub trait InputSource: Send {
+    /// Deliver events until the callback returns false. May return before that
+    /// if events keep being delivered from another thread.
+    fn listen(self: Box<Self>, callback: InputCallback) -> Result<(), String>;
+}
+
+/// Injects input through enigo
+pub struct EnigoSink {
+    enigo: Enigo,
+}
+
+impl EnigoSink {
+    pub fn new() -> Result<Self, String> {
+        let enigo = Enigo::new(&Settings::default())
+            .map_err(|e| format!("Failed to create Enigo: {:?}", e))?;
+
+        Ok(Self { enigo })
+    }
+}
+
+impl InputSink for EnigoSink {
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> Result<(), String> {
+        self.enigo
+            .move_mouse(x, y, coordinate)
+            .map_err(|e| format!("Mouse move error: {:?}", e))
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+        self.enigo
+            .button(button, direction)
+            .map_err(|e| format!("Mouse button {:?} error: {:?}", direction, e))
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        self.enigo
+            .key(key, direction)
+            .map_err(|e| format!("Key {:?} error: {:?}", direction, e))
+    }
+
+    fn scroll(&mut self, amount: i32, axis: Axis) -> Result<(), String> {
+        self.enigo
+            .scroll(amount, axis)
+            .map_err(|e| format!("Mouse wheel error: {:?}", e))
+    }
+
+    fn cursor_position(&self) -> Result<(i32, i32), String> {
+        self.enigo
+            .location()
+            .map_err(|e| format!("Mouse location error: {:?}", e))
+    }
+}
+
+/// Create the sink for a backend, failing if it isn't available on this platform
+pub fn new_sink(backend: InputBackend) -> Result<Box<dyn InputSink>, String> {
+    match backend {
+        InputBackend::Enigo => Ok(Box::new(EnigoSink::new()?)),
+        #[cfg(target_os = "windows")]
+        InputBackend::SendInput => Ok(Box::new(super::send_input::SendInputSink)),
+        #[cfg(target_os = "linux")]
+        InputBackend::Xdotool => Ok(Box::new(super::xdotool::XdotoolSink)),
+        #[cfg(target_os = "linux")]
+        InputBackend::Portal => Ok(Box::new(super::portal::PortalSink::new()?)),
+        #[allow(unreachable_patterns)]
+        other => Err(format!(
+            "The {:?} input backend isn't available on this platform",
+            other
+        )),
+    }
+}
+
+/// Captures global input through rdev.
+///
+/// rdev only supports one listener per process, so a single background
+/// listener is shared by every RdevSource (recorder, user input guard, ...).
+pub struct RdevSource;
+
+impl InputSource for RdevSource {
+    fn listen(self: Box<Self>, callback: InputCallback) -> Result<(), String> {
+        let hub = HUB.get_or_init(InputHub::start);
+        if let Some(error) = hub.error.lock().clone() {
+            return Err(error);
+        }
+        hub.subscribers.lock().push(callback);
+        Ok(())
+    }
+}
+
+/// Start the shared input hook if needed and report whether it is running.
+/// Hook failures surface right away, so a short wait is enough to see them.
+pub fn check_input_hook() -> Result<(), String> {
+    let hub = HUB.get_or_init(InputHub::start);
+    thread::sleep(HOOK_STARTUP_WAIT);
+    match hub.error.lock().clone() {
+        Some(error) => Err(format!("Input hook failed: {}", error)),
+        None => Ok(()),
+    }
+}
+
+/// How long `check_input_hook` gives the hook to fail
+const HOOK_STARTUP_WAIT: Duration = Duration::from_millis(300);
+
+static HUB: OnceLock<InputHub> = OnceLock::new();
+
+struct InputHub {
+    subscribers: Arc<Mutex<Vec<InputCallback>>>,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl InputHub {
+    fn start() -> Self {
+        let subscribers: Arc<Mutex<Vec<InputCallback>>> = Arc::new(Mutex::new(Vec::new()));
+        let error = Arc::new(Mutex::new(None));
+
+        let listeners = Arc::clone(&subscribers);
+        let listen_error = Arc::clone(&error);
+        thread::spawn(move || {
+            let result = rdev::listen(move |event| {
+                listeners
+                    .lock()
+                    .retain_mut(|callback| callback(event.clone()));
+            });
+            if let Err(e) = result {
+                warn!("Input listen error: {:?}", e);
+                *listen_error.lock() = Some(format!("{:?}", e));
+            }
+        });
+
+        Self { subscribers, error }
+    }
+}
+
+/// A call made against a FakeSink
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum SinkCall {
+    MoveMouse(i32, i32, Coordinate),
+    Button(Button, Direction),
+    Key(Key, Direction),
+    Scroll(i32, Axis),
+    Wait(Duration),
+}
+
+/// Records every call instead of injecting it
+#[cfg(test)]
+#[derive(Default, Clone)]
+pub struct FakeSink {
+    pub calls: Arc<Mutex<Vec<SinkCall>>>,
+    pub cursor: (i32, i32),
+}
+
+#[cfg(test)]
+impl FakeSink {
+    pub fn calls(&self) -> Vec<SinkCall> {
+        self.calls.lock().clone()
+    }
+
+    /// Calls other than waits, in order
+    pub fn input_calls(&self) -> Vec<SinkCall> {
+        self.calls()
+            .into_iter()
+            .filter(|c| !matches!(c, SinkCall::Wait(_)))
+            .collect()
+    }
+
+    /// Total time the player asked to wait
+    pub fn total_wait(&self) -> Duration {
+        self.calls()
+            .iter()
+            .filter_map(|c| match c {
+                SinkCall::Wait(d) => Some(*d),
+                _ => None,
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+impl InputSink for FakeSink {
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> Result<(), String> {
+        self.cursor = match coordinate {
+            Coordinate::Abs => (x, y),
+            Coordinate::Rel => (self.cursor.0 + x, self.cursor.1 + y),
+        };
+        self.calls
+            .lock()
+            .push(SinkCall::MoveMouse(x, y, coordinate));
+        Ok(())
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+        self.calls.lock().push(SinkCall::Button(button, direction));
+        Ok(())
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        self.calls.lock().push(SinkCall::Key(key, direction));
+        Ok(())
+    }
+
+    fn scroll(&mut self, amount: i32, axis: Axis) -> Result<(), String> {
+        self.calls.lock().push(SinkCall::Scroll(amount, axis));
+        Ok(())
+    }
+
+    fn cursor_position(&self) -> Result<(i32, i32), String> {
+        Ok(self.cursor)
+    }
+
+    fn wait(&mut self, duration: Duration) {
+        self.calls.lock().push(SinkCall::Wait(duration));
+    }
+}
+
+/// Replays a fixed list of events and returns
+#[cfg(test)]
+pub struct FakeSource {
+    pub events: Vec<rdev::Event>,
+}
+
+#[cfg(test)]
+impl InputSource for FakeSource {
+    fn listen(self: Box<Self>, mut callback: InputCallback) -> Result<(), String> {
+        for event in self.events {
+            if !callback(event) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
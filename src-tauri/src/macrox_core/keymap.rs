@@ -0,0 +1,215 @@
+// Key map self-test module
+//
+// Builds a macro that taps every key the player can press and clicks every
+// mouse button, then records while playing it and diffs what was captured
+// against what was sent. A key that comes back as something else, or not at
+// all, points at a gap between the rdev names the recorder writes and the
+// enigo keys the player presses on this platform.
+
+use chrono::Utc;
+use serde::Serialize;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::diff::{self, EventChange};
+use super::input::{InputSink, InputSource};
+use super::player::{self, PlaybackControl, Player};
+use super::recorder::Recorder;
+use super::types::{HotkeySettings, Macro, MacroEvent, PlaybackSettings, RecordingSettings};
+
+/// Keys that change a lock state, tapped twice so they end up as they were
+const TOGGLE_KEYS: &[&str] = &["CapsLock"];
+
+pub const KEYMAP_TEST_BUTTONS: &[&str] = &["Left", "Middle", "Right"];
+
+/// Time between the steps of the test macro
+const STEP_MS: u64 = 40;
+
+/// Time for the listener to start before playing, and for the last events to arrive after
+const SETTLE: Duration = Duration::from_millis(300);
+
+/// Result of playing the test macro while recording it
+#[derive(Debug, Clone, Serialize)]
+pub struct KeymapReport {
+    pub passed: bool,
+    #[serde(rename = "sentEvents")]
+    pub sent_events: usize,
+    #[serde(rename = "recordedEvents")]
+    pub recorded_events: usize,
+    /// Where the recording differs from what was played
+    pub changes: Vec<EventChange>,
+}
+
+/// Settings the test macro is recorded with: keys and buttons only, no positions
+pub fn recording_settings() -> RecordingSettings {
+    RecordingSettings {
+        record_mouse_movement: false,
+        record_key_combos: true,
+        distinguish_modifier_sides: false,
+        ..RecordingSettings::default()
+    }
+}
+
+/// Every key the player presses as itself: letters, digits and the named
+/// keys, leaving out the `bound` hotkeys so the test doesn't trigger them.
+/// Escape goes first to close the context menu the right click may open,
+/// Meta last since it can open a start menu.
+pub fn test_keys(bound: &[&str]) -> Vec<&'static str> {
+    const LETTERS_AND_DIGITS: &str = "abcdefghijklmnopqrstuvwxyz0123456789";
+    let typed = LETTERS_AND_DIGITS
+        .char_indices()
+        .map(|(i, _)| &LETTERS_AND_DIGITS[i..i + 1]);
+    let named = player::named_keys().filter(|key| !["Escape", "Meta"].contains(key));
+
+    std::iter::once("Escape")
+        .chain(typed)
+        .chain(named)
+        .chain(std::iter::once("Meta"))
+        .filter(|key| !bound.iter().any(|hotkey| hotkey.eq_ignore_ascii_case(key)))
+        .collect()
+}
+
+/// A macro pressing and releasing every test button and key in turn. It types
+/// into whatever has focus, so run it over a scratch text field.
+pub fn test_macro(bound: &[&str]) -> Macro {
+    let inputs = KEYMAP_TEST_BUTTONS
+        .iter()
+        .map(|button| ("Mouse", "button", *button))
+        .chain(test_keys(bound).into_iter().map(|key| ("Key", "key", key)))
+        .flat_map(|input| {
+            let times = if TOGGLE_KEYS.contains(&input.2) { 2 } else { 1 };
+            std::iter::repeat_n(input, times)
+        });
+
+    let mut events = Vec::new();
+    for (prefix, field, name) in inputs {
+        for action in ["Down", "Up"] {
+            events.push(MacroEvent {
+                event_type: format!("{}{}", prefix, action),
+                timestamp: events.len() as u64 * STEP_MS,
+                data: serde_json::json!({ field: name }),
+            });
+        }
+    }
+
+    let now = Utc::now();
+    Macro {
+        id: format!("keymap-test-{}", now.timestamp_millis()),
+        name: "Key map self-test".to_string(),
+        description: "Presses every supported key and mouse button once. \
+            Play it over a scratch text field."
+            .to_string(),
+        events,
+        recording_settings: recording_settings(),
+        playback_settings: PlaybackSettings::default(),
+        created_at: now,
+        updated_at: now,
+        display_info: None,
+        expires_at: None,
+        archived: false,
+        author: None,
+        source_url: None,
+        license: None,
+    }
+}
+
+/// Key and button events only, reduced to the key or button they name
+fn input_stream(events: &[MacroEvent]) -> Vec<MacroEvent> {
+    events
+        .iter()
+        .filter_map(|event| {
+            let field = match event.event_type.as_str() {
+                "KeyDown" | "KeyUp" => "key",
+                "MouseDown" | "MouseUp" => "button",
+                _ => return None,
+            };
+            Some(MacroEvent {
+                event_type: event.event_type.clone(),
+                timestamp: 0,
+                data: serde_json::json!({ field: event.data.get(field) }),
+            })
+        })
+        .collect()
+}
+
+/// Diff the keys and buttons that were sent against the ones recorded
+pub fn compare(sent: &[MacroEvent], recorded: &[MacroEvent]) -> KeymapReport {
+    let sent = input_stream(sent);
+    let recorded = input_stream(recorded);
+    let (changes, _) = diff::diff_events(&sent, &recorded);
+    KeymapReport {
+        passed: changes.is_empty(),
+        sent_events: sent.len(),
+        recorded_events: recorded.len(),
+        changes,
+    }
+}
+
+/// Play the test macro through `sink` while recording from `source`. Keys
+/// in `hotkeys` and the other `bound` hotkeys are left out.
+pub fn verify<S: InputSink>(
+    sink: S,
+    source: Box<dyn InputSource>,
+    hotkeys: HotkeySettings,
+    bound: &[&str],
+) -> Result<KeymapReport, String> {
+    let recorder_hotkeys = [
+        hotkeys.record_start.as_str(),
+        hotkeys.record_stop.as_str(),
+        hotkeys.playback_start.as_str(),
+        hotkeys.playback_stop.as_str(),
+        hotkeys.mark.as_str(),
+    ];
+    let skipped: Vec<&str> = recorder_hotkeys.iter().chain(bound).copied().collect();
+    let test = test_macro(&skipped);
+    let mut recorder = Recorder::with_source(test.recording_settings.clone(), None, source);
+    recorder.start(hotkeys)?;
+    thread::sleep(SETTLE);
+
+    let mut player = Player::with_sink(sink, Arc::new(PlaybackControl::default()));
+    let played = player.play_macro(&test);
+    thread::sleep(SETTLE);
+    let recorded = recorder.stop();
+    played?;
+
+    Ok(compare(&test.events, &recorded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_keys_that_record_as_something_else() {
+        let keys = test_keys(&["F9", "ctrl+shift+r"]);
+        assert!(keys.contains(&"F10") && keys.contains(&"Kp5") && keys.contains(&"Slash"));
+        assert!(!keys.contains(&"F9"));
+        assert_eq!((keys[0], keys[keys.len() - 1]), ("Escape", "Meta"));
+
+        let test = test_macro(&[]);
+        let toggles = TOGGLE_KEYS
+            .iter()
+            .filter(|key| test_keys(&[]).contains(key));
+        assert_eq!(
+            test.events.len(),
+            (KEYMAP_TEST_BUTTONS.len() + test_keys(&[]).len() + toggles.count()) * 2
+        );
+        assert!(compare(&test.events, &test.events).passed);
+
+        // Keys the player has no name for are pressed as their first character
+        let mut recorded = test.events.clone();
+        for event in recorded.iter_mut() {
+            if event.data["key"] == "Delete" {
+                event.data = serde_json::json!({ "key": "D" });
+            }
+        }
+        recorded.retain(|e| e.data["button"] != "Middle");
+
+        let report = compare(&test.events, &recorded);
+        assert!(!report.passed);
+        assert_eq!(report.sent_events, test.events.len());
+        assert_eq!(report.recorded_events, test.events.len() - 2);
+        assert_eq!(report.changes.len(), 4);
+    }
+}
@@ -0,0 +1,123 @@
+// Leader key module
+//
+// Pressing the leader hotkey arms a short window in which the next key press
+// picks a macro to play, like tmux's prefix key, so any number of macros can
+// be bound without a global shortcut each. The window is a temporary input
+// subscription that ends after one key or when it times out.
+
+use parking_lot::Mutex;
+use rdev::EventType;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use super::input::InputSource;
+use super::recorder::rdev_key_to_string;
+use super::types::LeaderSettings;
+
+/// Receives the bound macro id, or None when the window timed out or the key wasn't bound
+pub type LeaderChoice = Box<dyn FnOnce(Option<String>) + Send>;
+
+/// Wait for the next key press and report the macro bound to it
+pub fn arm(
+    source: Box<dyn InputSource>,
+    settings: &LeaderSettings,
+    on_choice: LeaderChoice,
+) -> Result<(), String> {
+    let armed = Arc::new(AtomicBool::new(true));
+    let on_choice = Arc::new(Mutex::new(Some(on_choice)));
+
+    // Give up once the window closes, even if no key is ever pressed
+    let timeout = Duration::from_millis(settings.timeout_ms);
+    let expired = Arc::clone(&armed);
+    let expire_choice = Arc::clone(&on_choice);
+    thread::spawn(move || {
+        thread::sleep(timeout);
+        if expired.swap(false, Ordering::SeqCst) {
+            if let Some(choose) = expire_choice.lock().take() {
+                choose(None);
+            }
+        }
+    });
+
+    let bindings = settings.bindings.clone();
+    let leader_key = settings
+        .hotkey
+        .rsplit('+')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    source.listen(Box::new(move |event: rdev::Event| {
+        if !armed.load(Ordering::SeqCst) {
+            return false;
+        }
+        let EventType::KeyPress(key) = event.event_type else {
+            return true;
+        };
+
+        // Modifiers still held from the leader chord, and repeats of its
+        // main key, don't pick anything
+        let name = rdev_key_to_string(key, false);
+        if matches!(name.as_str(), "Shift" | "Control" | "Alt" | "Meta")
+            || name.eq_ignore_ascii_case(&leader_key)
+        {
+            return true;
+        }
+
+        if armed.swap(false, Ordering::SeqCst) {
+            if let Some(choose) = on_choice.lock().take() {
+                choose(bindings.get(&name).cloned());
+            }
+        }
+        false
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macrox_core::input::FakeSource;
+    use rdev::Key;
+    use std::collections::BTreeMap;
+    use std::sync::mpsc;
+    use std::time::UNIX_EPOCH;
+
+    fn press(key: Key) -> rdev::Event {
+        rdev::Event {
+            time: UNIX_EPOCH,
+            name: None,
+            event_type: EventType::KeyPress(key),
+        }
+    }
+
+    fn settings() -> LeaderSettings {
+        LeaderSettings {
+            enabled: true,
+            hotkey: "Alt+Space".to_string(),
+            timeout_ms: 10_000,
+            bindings: BTreeMap::from([("a".to_string(), "macro-a".to_string())]),
+        }
+    }
+
+    fn choose(events: Vec<rdev::Event>) -> Option<String> {
+        let (tx, rx) = mpsc::channel();
+        arm(
+            Box::new(FakeSource { events }),
+            &settings(),
+            Box::new(move |choice| tx.send(choice).unwrap()),
+        )
+        .unwrap();
+        rx.recv().unwrap()
+    }
+
+    #[test]
+    fn next_key_picks_the_bound_macro() {
+        assert_eq!(
+            choose(vec![press(Key::Alt), press(Key::KeyA), press(Key::KeyB)]),
+            Some("macro-a".to_string())
+        );
+        assert_eq!(choose(vec![press(Key::Space), press(Key::KeyB)]), None);
+    }
+}
@@ -0,0 +1,253 @@
+// Macro lint module
+//
+// Static checks that catch macros likely to misbehave before they are played:
+// keys left held down, clicks off every screen, waits that look like
+// mistakes, bursts faster than input injection can keep up with, the app's
+// own hotkeys inside the event stream, and links to macros that no longer exist.
+
+use std::collections::HashSet;
+
+use super::types::{LintKind, LintWarning, MacroEvent, ScreenRegion};
+
+/// Pauses longer than this are reported
+const LONG_WAIT_MS: u64 = 60_000;
+/// More events than this with no delay between them are reported
+const BURST_LIMIT: usize = 100;
+
+/// What a macro is checked against
+#[derive(Default)]
+pub struct LintContext {
+    /// Bounds of the connected displays; off-screen checks are skipped when empty
+    pub displays: Vec<ScreenRegion>,
+    /// Keys bound to the app's global hotkeys, e.g. "F9"
+    pub hotkey_keys: Vec<String>,
+    /// Ids of all stored macros
+    pub macro_ids: HashSet<String>,
+}
+
+/// Warnings for a macro's events, in event order
+pub fn lint_events(events: &[MacroEvent], context: &LintContext) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let mut held: Vec<(String, usize)> = Vec::new();
+    let mut cursor: Option<(i64, i64)> = None;
+    let mut burst_start = 0;
+
+    let mut warn = |kind: LintKind, index: Option<usize>, message: String| {
+        warnings.push(LintWarning {
+            kind,
+            event_index: index,
+            message,
+        })
+    };
+
+    for (i, event) in events.iter().enumerate() {
+        let data = &event.data;
+        let str_field = |field: &str| data.get(field).and_then(|v| v.as_str());
+        let position = match (
+            data.get("x").and_then(|v| v.as_i64()),
+            data.get("y").and_then(|v| v.as_i64()),
+        ) {
+            (Some(x), Some(y)) => Some((x, y)),
+            _ => None,
+        };
+
+        if i > 0 {
+            let gap = event.timestamp.saturating_sub(events[i - 1].timestamp);
+            if gap > LONG_WAIT_MS {
+                warn(
+                    LintKind::LongWait,
+                    Some(i),
+                    format!("Waits {:.0}s before this event", gap as f64 / 1000.0),
+                );
+            }
+            if gap > 0 {
+                burst_start = i;
+            } else if i - burst_start == BURST_LIMIT {
+                warn(
+                    LintKind::Burst,
+                    Some(burst_start),
+                    format!(
+                        "More than {} events play with no delay, input may be dropped",
+                        BURST_LIMIT
+                    ),
+                );
+            }
+        }
+
+        match event.event_type.as_str() {
+            "MouseMove" => cursor = position.or(cursor),
+            "MouseDown" | "KeyDown" => {
+                let name = str_field("button").or(str_field("key")).unwrap_or_default();
+                if event.event_type == "KeyDown"
+                    && context
+                        .hotkey_keys
+                        .iter()
+                        .any(|k| k.eq_ignore_ascii_case(name))
+                {
+                    warn(
+                        LintKind::HotkeyKey,
+                        Some(i),
+                        format!("Presses {}, which is one of MacroX's hotkeys", name),
+                    );
+                }
+                if event.event_type == "MouseDown" {
+                    if let Some((x, y)) = position.or(cursor) {
+                        let on_screen = context.displays.is_empty()
+                            || context
+                                .displays
+                                .iter()
+                                .any(|d| d.contains(x as i32, y as i32));
+                        if !on_screen {
+                            warn(
+                                LintKind::OffScreenClick,
+                                Some(i),
+                                format!("Clicks at {},{}, outside every display", x, y),
+                            );
+                        }
+                    }
+                }
+                held.push((format!("{}:{}", event.event_type, name), i));
+            }
+            "MouseUp" | "KeyUp" => {
+                let name = str_field("button").or(str_field("key")).unwrap_or_default();
+                let down_type = event.event_type.replace("Up", "Down");
+                let id = format!("{}:{}", down_type, name);
+                match held.iter().rposition(|(held_id, _)| *held_id == id) {
+                    Some(pos) => {
+                        held.remove(pos);
+                    }
+                    None => warn(
+                        LintKind::UnbalancedInput,
+                        Some(i),
+                        format!("Releases {} without pressing it first", name),
+                    ),
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(id) = str_field("macroId") {
+            if !context.macro_ids.contains(id) {
+                warn(
+                    LintKind::MissingMacro,
+                    Some(i),
+                    format!("Refers to macro {}, which doesn't exist", id),
+                );
+            }
+        }
+    }
+
+    for (id, i) in held {
+        let name = id.split_once(':').map(|(_, name)| name).unwrap_or(&id);
+        warn(
+            LintKind::UnbalancedInput,
+            Some(i),
+            format!("Presses {} and never releases it", name),
+        );
+    }
+
+    warnings.sort_by_key(|w| w.event_index);
+    warnings
+}
+
+/// The key part of a hotkey like "Ctrl+Shift+F9"
+pub fn hotkey_key(hotkey: &str) -> String {
+    hotkey
+        .rsplit('+')
+        .next()
+        .unwrap_or(hotkey)
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(event_type: &str, timestamp: u64, data: serde_json::Value) -> MacroEvent {
+        MacroEvent {
+            event_type: event_type.to_string(),
+            timestamp,
+            data,
+        }
+    }
+
+    fn kinds(warnings: &[LintWarning]) -> Vec<LintKind> {
+        warnings.iter().map(|w| w.kind).collect()
+    }
+
+    #[test]
+    fn clean_macro_has_no_warnings() {
+        let events = vec![
+            event("MouseMove", 0, json!({ "x": 10, "y": 10 })),
+            event("MouseDown", 50, json!({ "button": "Left" })),
+            event("MouseUp", 100, json!({ "button": "Left" })),
+            event("KeyDown", 150, json!({ "key": "a" })),
+            event("KeyUp", 200, json!({ "key": "a" })),
+        ];
+        let context = LintContext {
+            displays: vec![ScreenRegion {
+                x: 0,
+                y: 0,
+                width: 1920,
+                height: 1080,
+            }],
+            hotkey_keys: vec![hotkey_key("Ctrl+F9")],
+            ..Default::default()
+        };
+
+        assert!(lint_events(&events, &context).is_empty());
+    }
+
+    #[test]
+    fn reports_each_problem() {
+        let events = vec![
+            event("KeyDown", 0, json!({ "key": "ShiftLeft" })),
+            event(
+                "MouseDown",
+                10,
+                json!({ "button": "Left", "x": -50, "y": 5 }),
+            ),
+            event("MouseUp", 20, json!({ "button": "Left" })),
+            event("KeyDown", 120_000, json!({ "key": "F9" })),
+            event("KeyUp", 120_010, json!({ "key": "F9" })),
+            event("KeyUp", 120_020, json!({ "key": "a" })),
+            event("Echo", 120_030, json!({ "macroId": "gone" })),
+        ];
+        let context = LintContext {
+            displays: vec![ScreenRegion {
+                x: 0,
+                y: 0,
+                width: 1920,
+                height: 1080,
+            }],
+            hotkey_keys: vec!["F9".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            kinds(&lint_events(&events, &context)),
+            vec![
+                LintKind::UnbalancedInput,
+                LintKind::OffScreenClick,
+                LintKind::LongWait,
+                LintKind::HotkeyKey,
+                LintKind::UnbalancedInput,
+                LintKind::MissingMacro,
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_zero_delay_bursts_once() {
+        let events: Vec<MacroEvent> = (0..250)
+            .map(|_| event("MouseMove", 0, json!({ "x": 1, "y": 1 })))
+            .collect();
+
+        assert_eq!(
+            kinds(&lint_events(&events, &LintContext::default())),
+            vec![LintKind::Burst]
+        );
+    }
+}
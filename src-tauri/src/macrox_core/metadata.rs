@@ -0,0 +1,38 @@
+// Recording metadata module
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use super::types::MacroEvent;
+
+/// Suggested name for a fresh recording, used to pre-fill the save dialog
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingMetadata {
+    #[serde(rename = "suggestedName")]
+    pub suggested_name: String,
+    #[serde(rename = "durationMs")]
+    pub duration_ms: u64,
+    #[serde(rename = "dominantApplication")]
+    pub dominant_application: Option<String>,
+}
+
+pub fn summarize(
+    events: &[MacroEvent],
+    started_at: DateTime<Local>,
+    dominant_application: Option<String>,
+) -> RecordingMetadata {
+    // Timestamps are normalized to start at 0, so the last one is the duration
+    let duration_ms = events.last().map(|e| e.timestamp).unwrap_or(0);
+
+    let mut suggested_name = format!("Recording {}", started_at.format("%Y-%m-%d %H:%M"));
+    if let Some(app) = dominant_application.as_ref() {
+        suggested_name.push_str(" — ");
+        suggested_name.push_str(app);
+    }
+
+    RecordingMetadata {
+        suggested_name,
+        duration_ms,
+        dominant_application,
+    }
+}
@@ -0,0 +1,53 @@
+// Core recording, playback and storage logic
+//
+// Nothing in here depends on the Tauri runtime, so it builds with
+// `--no-default-features` for the CLI, tests, or other frontends.
+
+pub mod acceleration;
+pub mod access;
+pub mod accessibility;
+pub mod archive;
+pub mod capabilities;
+pub mod destructive;
+pub mod devices;
+pub mod diff;
+pub mod editing;
+pub mod elevation;
+pub mod encoding;
+pub mod gesture;
+pub mod history;
+pub mod input;
+pub mod keymap;
+pub mod leader;
+pub mod lint;
+pub mod metadata;
+pub mod onboarding;
+pub mod permissions;
+pub mod player;
+pub mod plugin;
+pub mod pool;
+#[cfg(target_os = "linux")]
+pub mod portal;
+pub mod practice;
+pub mod privilege;
+pub mod recorder;
+pub mod release;
+pub mod retro;
+pub mod scene;
+pub mod screen;
+pub mod script;
+pub mod secure_input;
+#[cfg(target_os = "windows")]
+pub mod send_input;
+pub mod shortcut;
+pub mod storage;
+pub mod storyboard;
+pub mod timing;
+pub mod transcript;
+pub mod triggers;
+pub mod types;
+pub mod window;
+#[cfg(target_os = "windows")]
+pub mod window_target;
+#[cfg(target_os = "linux")]
+pub mod xdotool;
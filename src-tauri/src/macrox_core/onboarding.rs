@@ -0,0 +1,112 @@
+// Onboarding module
+//
+// First-run steps the UI walks a new user through. Which steps are done is
+// kept with the app settings, and the order they unlock in is decided here so
+// the shell can refuse actions (like recording) that an earlier step gates.
+
+use serde::{Deserialize, Serialize};
+
+/// A first-run step, in the order the UI presents them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OnboardingStep {
+    /// OS permissions needed to capture and inject input
+    Permissions,
+    FirstRecording,
+    /// Hotkeys changed from the defaults
+    Hotkeys,
+}
+
+pub const STEPS: [OnboardingStep; 3] = [
+    OnboardingStep::Permissions,
+    OnboardingStep::FirstRecording,
+    OnboardingStep::Hotkeys,
+];
+
+impl OnboardingStep {
+    pub fn label(self) -> &'static str {
+        match self {
+            OnboardingStep::Permissions => "Grant permissions",
+            OnboardingStep::FirstRecording => "Record a macro",
+            OnboardingStep::Hotkeys => "Customize hotkeys",
+        }
+    }
+
+    /// The step that has to be done before this one
+    fn requires(self) -> Option<OnboardingStep> {
+        match self {
+            OnboardingStep::FirstRecording => Some(OnboardingStep::Permissions),
+            OnboardingStep::Permissions | OnboardingStep::Hotkeys => None,
+        }
+    }
+}
+
+/// Steps done so far, as stored in the app settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OnboardingState {
+    #[serde(default)]
+    pub completed: Vec<OnboardingStep>,
+}
+
+/// Onboarding progress as shown to the UI
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OnboardingStatus {
+    pub completed: Vec<OnboardingStep>,
+    /// First step not done yet
+    #[serde(rename = "nextStep")]
+    pub next_step: Option<OnboardingStep>,
+    pub finished: bool,
+}
+
+impl OnboardingState {
+    pub fn is_done(&self, step: OnboardingStep) -> bool {
+        self.completed.contains(&step)
+    }
+
+    /// Mark `step` done, returning whether that changed anything. Fails while
+    /// the step it depends on is still open.
+    pub fn complete(&mut self, step: OnboardingStep) -> Result<bool, String> {
+        if self.is_done(step) {
+            return Ok(false);
+        }
+        if let Some(required) = step.requires().filter(|r| !self.is_done(*r)) {
+            return Err(format!(
+                "'{}' has to be done before '{}'",
+                required.label(),
+                step.label()
+            ));
+        }
+        self.completed.push(step);
+        Ok(true)
+    }
+
+    pub fn status(&self) -> OnboardingStatus {
+        let next_step = STEPS.into_iter().find(|s| !self.is_done(*s));
+        OnboardingStatus {
+            completed: STEPS.into_iter().filter(|s| self.is_done(*s)).collect(),
+            next_step,
+            finished: next_step.is_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_unlock_in_order() {
+        let mut state = OnboardingState::default();
+        assert!(state.complete(OnboardingStep::FirstRecording).is_err());
+        assert_eq!(state.complete(OnboardingStep::Hotkeys), Ok(true));
+        assert_eq!(state.status().next_step, Some(OnboardingStep::Permissions));
+
+        assert_eq!(state.complete(OnboardingStep::Permissions), Ok(true));
+        assert_eq!(state.complete(OnboardingStep::Permissions), Ok(false));
+        assert_eq!(state.complete(OnboardingStep::FirstRecording), Ok(true));
+
+        let status = state.status();
+        assert!(status.finished);
+        assert_eq!(status.completed, STEPS.to_vec());
+    }
+}
@@ -0,0 +1,48 @@
+// OS permission module
+//
+// macOS only lets apps listed under Privacy & Security watch or inject input
+// (Accessibility) and capture the screen (Screen Recording). Without them
+// recording and playback quietly do nothing, so they are checked up front.
+// Other platforms don't gate either and always report them as granted.
+
+/// Whether MacroX may capture and inject input
+pub fn input_access_granted() -> bool {
+    platform::input_access_granted()
+}
+
+/// Whether MacroX may capture the screen (click screenshots, image search)
+pub fn screen_capture_granted() -> bool {
+    platform::screen_capture_granted()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> u8;
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+    }
+
+    pub fn input_access_granted() -> bool {
+        unsafe { AXIsProcessTrusted() != 0 }
+    }
+
+    pub fn screen_capture_granted() -> bool {
+        unsafe { CGPreflightScreenCaptureAccess() }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    pub fn input_access_granted() -> bool {
+        true
+    }
+
+    pub fn screen_capture_granted() -> bool {
+        true
+    }
+}
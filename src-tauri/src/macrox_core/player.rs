@@ -0,0 +1,2469 @@
+// Event playback module
+
+use enigo::{Axis, Button, Coordinate, Direction, Key};
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Once, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use super::accessibility::ElementLocator;
+use super::destructive::DestructiveDetector;
+use super::elevation::{self, ElevationCheck};
+use super::gesture;
+use super::input::{EnigoSink, InputSink, InputSource, RdevSource};
+use super::plugin::PluginRegistry;
+use super::privilege;
+use super::recorder::rdev_key_to_string;
+use super::screen::{self, ScreenGrabber, DEFAULT_MATCH_TOLERANCE, PATCH_SIZE};
+use super::timing::{TimingRecorder, TimingReport};
+use super::types::{
+    ClickVerification, DurationEstimate, HumanOverride, Macro, MacroEvent, PlaybackSettings,
+    PlaybackStatus, PrivilegeGrant, RunOutputs, ScreenRegion, UiElement,
+};
+use super::window::WindowLocator;
+
+/// Longest single wait, so stop requests are noticed during long delays
+const WAIT_SLICE: Duration = Duration::from_millis(50);
+
+/// Input seen this soon after an injected event is assumed to be its echo
+const INJECTION_GRACE: Duration = Duration::from_millis(50);
+
+/// User input is ignored this long after playback starts or resumes
+const OVERRIDE_ARM_DELAY: Duration = Duration::from_millis(1000);
+
+/// How far from the recorded position a moved click target is searched for
+const CLICK_SEARCH_RADIUS: u32 = 96;
+
+/// How often WaitForStill samples its region
+const STILL_POLL: Duration = Duration::from_millis(100);
+/// Largest frame-to-frame difference WaitForStill treats as no change
+const STILL_TOLERANCE: f64 = 0.002;
+/// WaitForStill gives up after this long unless the event sets timeoutMs
+const DEFAULT_STILL_TIMEOUT_MS: u64 = 30_000;
+
+/// Shortest time between the presses of a KeyTurbo event
+const MIN_TURBO_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Longest wait between retries of a failed run, however many came before
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+/// How deep RunMacro events may nest, so a macro running itself fails instead of hanging
+const MAX_MACRO_DEPTH: usize = 8;
+
+/// How far an "infinite" run goes before asking whether to continue
+#[derive(Clone, Default)]
+pub struct IterationCap {
+    /// Repetitions between questions, 0 for no limit
+    pub max_iterations: u32,
+    /// Asked whether to run another `max_iterations`; without it the run ends at the cap
+    pub confirm: Option<AskUser>,
+}
+
+/// Asks the user whether to go on, given a description of what comes next;
+/// returning false stops playback. Blocks the playback thread until answered.
+pub type AskUser = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Called whenever the playback status changes
+pub type StatusObserver = Arc<dyn Fn(&PlaybackStatus) + Send + Sync>;
+
+/// Looks up the stored macro a RunMacro event plays, by id
+pub type MacroResolver = Arc<dyn Fn(&str) -> Option<Arc<Macro>> + Send + Sync>;
+
+/// Playback state shared between the player and commands
+#[derive(Default)]
+pub struct PlaybackControl {
+    status: Mutex<PlaybackStatus>,
+    paused: AtomicBool,
+    stopped: AtomicBool,
+    observer: Mutex<Option<StatusObserver>>,
+    human_override: Mutex<HumanOverride>,
+    armed_at: Mutex<Option<Instant>>,
+    last_injection: Mutex<Option<Instant>>,
+    /// Set once the key that started a "held" mode run is let go
+    trigger_released: AtomicBool,
+    /// Bumped for every trigger watch so older watchers retire
+    trigger_watches: AtomicU64,
+}
+
+impl PlaybackControl {
+    pub fn status(&self) -> PlaybackStatus {
+        self.status.lock().clone()
+    }
+
+    /// Report progress, e.g. to forward it to the frontend
+    pub fn set_observer(&self, observer: StatusObserver) {
+        *self.observer.lock() = Some(observer);
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        if !paused {
+            *self.armed_at.lock() = Some(Instant::now());
+        }
+        self.paused.store(paused, Ordering::SeqCst);
+    }
+
+    /// Ask the running macro to stop before its next event
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    /// The trigger key was let go; a "held" mode run ends after its current iteration
+    pub fn release_trigger(&self) {
+        self.trigger_released.store(true, Ordering::SeqCst);
+    }
+
+    fn is_trigger_released(&self) -> bool {
+        self.trigger_released.load(Ordering::SeqCst)
+    }
+
+    /// Real user input was seen; pause or stop if the playing macro asks for it
+    pub fn user_input(&self) {
+        let action = *self.human_override.lock();
+        if action == HumanOverride::Off {
+            return;
+        }
+        if self
+            .armed_at
+            .lock()
+            .is_some_and(|t| t.elapsed() < OVERRIDE_ARM_DELAY)
+            || self.recently_injected()
+        {
+            return;
+        }
+
+        match action {
+            HumanOverride::Pause if !self.is_paused() => {
+                info!("User input detected, pausing playback");
+                self.set_paused(true);
+            }
+            HumanOverride::Stop if !self.is_stopped() => {
+                info!("User input detected, stopping playback");
+                self.stop();
+            }
+            _ => {}
+        }
+    }
+
+    fn set_human_override(&self, action: HumanOverride) {
+        *self.armed_at.lock() = Some(Instant::now());
+        *self.human_override.lock() = action;
+    }
+
+    fn mark_injected(&self) {
+        *self.last_injection.lock() = Some(Instant::now());
+    }
+
+    fn recently_injected(&self) -> bool {
+        self.last_injection
+            .lock()
+            .is_some_and(|t| t.elapsed() < INJECTION_GRACE)
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Whether the last run was asked to stop
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    fn set_status(&self, status: PlaybackStatus) {
+        let observer = self.observer.lock().clone();
+        if let Some(observer) = observer {
+            observer(&status);
+        }
+        *self.status.lock() = status;
+    }
+}
+
+pub struct Player<S: InputSink = EnigoSink> {
+    sink: S,
+    control: Arc<PlaybackControl>,
+    coordinate_scale: f64,
+    /// Relative moves are multiplied by this to undo pointer acceleration
+    relative_scale: f64,
+    /// Rounding left over from scaled relative moves, carried into the next one
+    relative_remainder: (f64, f64),
+    /// Added to absolute coordinates when playing at the cursor
+    offset: (i32, i32),
+    /// Finds the current bounds of the windows a macro was recorded in
+    window_locator: Option<WindowLocator>,
+    /// How far each event's window has moved since recording
+    window_offsets: Vec<(i32, i32)>,
+    window_offset: (i32, i32),
+    /// Clicks outside this region abort playback
+    region: Option<ScreenRegion>,
+    plugins: Arc<PluginRegistry>,
+    /// Screen access for checking clicks against their recorded screenshots
+    screen: Option<ScreenGrabber>,
+    click_verification: ClickVerification,
+    /// Finds recorded click elements on screen when the macro clicks by element
+    element_locator: Option<ElementLocator>,
+    click_by_element: bool,
+    /// Reports a foreground window that would silently drop injected input
+    elevation_check: Option<ElevationCheck>,
+    /// Asked before pressing keys that complete a destructive combination
+    confirm_destructive: Option<AskUser>,
+    destructive: DestructiveDetector,
+    /// Current repetition's counter text, when the macro has a counter
+    counter: Option<String>,
+    /// Values read by events so far in this run
+    outputs: RunOutputs,
+    /// Where the last verified click was moved to, so its release lands there too
+    relocated: Option<(i32, i32)>,
+    /// Keys and buttons pressed but not yet released, in press order
+    held: Vec<HeldInput>,
+    /// Privileged event types each macro may use, including ones run by RunMacro
+    grants: Vec<PrivilegeGrant>,
+    iteration_cap: IterationCap,
+    timing: TimingRecorder,
+    /// Finds the macros RunMacro events play; without it they fail
+    macro_resolver: Option<MacroResolver>,
+    /// RunMacro events currently being played
+    depth: usize,
+}
+
+/// A key or button the player is holding down
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HeldInput {
+    Key(Key),
+    Button(Button),
+}
+
+impl Player<EnigoSink> {
+    pub fn new(control: Arc<PlaybackControl>) -> Result<Self, String> {
+        Ok(Self::with_sink(EnigoSink::new()?, control))
+    }
+}
+
+impl<S: InputSink> Player<S> {
+    pub fn with_sink(sink: S, control: Arc<PlaybackControl>) -> Self {
+        Self {
+            sink,
+            control,
+            coordinate_scale: 1.0,
+            relative_scale: 1.0,
+            relative_remainder: (0.0, 0.0),
+            offset: (0, 0),
+            window_locator: None,
+            window_offsets: Vec::new(),
+            window_offset: (0, 0),
+            region: None,
+            plugins: Arc::new(PluginRegistry::default()),
+            screen: None,
+            click_verification: ClickVerification::Off,
+            element_locator: None,
+            click_by_element: false,
+            elevation_check: None,
+            confirm_destructive: None,
+            destructive: DestructiveDetector::default(),
+            counter: None,
+            outputs: RunOutputs::new(),
+            relocated: None,
+            held: Vec::new(),
+            grants: Vec::new(),
+            iteration_cap: IterationCap::default(),
+            timing: TimingRecorder::default(),
+            macro_resolver: None,
+            depth: 0,
+        }
+    }
+
+    /// Scale absolute mouse coordinates, e.g. when display scaling changed since recording
+    pub fn set_coordinate_scale(&mut self, scale: f64) {
+        self.coordinate_scale = scale;
+    }
+
+    /// Scale relative mouse moves, from `calibrate_mouse`'s acceleration factor
+    pub fn set_relative_move_scale(&mut self, scale: f64) {
+        self.relative_scale = scale;
+    }
+
+    /// Hand event types the player doesn't know to these plugins
+    pub fn set_plugins(&mut self, plugins: Arc<PluginRegistry>) {
+        self.plugins = plugins;
+    }
+
+    /// Enable click verification; without a grabber recorded screenshots are ignored
+    pub fn set_screen_grabber(&mut self, grabber: ScreenGrabber) {
+        self.screen = Some(grabber);
+    }
+
+    /// Follow windows that moved since recording; without a locator coordinates stay put
+    pub fn set_window_locator(&mut self, locator: WindowLocator) {
+        self.window_locator = Some(locator);
+    }
+
+    /// Let RunMacro events play stored macros
+    pub fn set_macro_resolver(&mut self, resolver: MacroResolver) {
+        self.macro_resolver = Some(resolver);
+    }
+
+    /// Enable clicking by element; without a locator recorded elements are ignored
+    pub fn set_element_locator(&mut self, locator: ElementLocator) {
+        self.element_locator = Some(locator);
+    }
+
+    /// Fail presses aimed at a window that would drop them instead of playing blind
+    pub fn set_elevation_check(&mut self, check: ElevationCheck) {
+        self.elevation_check = Some(check);
+    }
+
+    /// Ask before playing destructive key combinations, stopping if refused
+    pub fn set_destructive_confirm(&mut self, confirm: AskUser) {
+        self.confirm_destructive = Some(confirm);
+    }
+
+    /// Allow privileged events in the macros granted them in the settings
+    pub fn set_privilege_grants(&mut self, grants: Vec<PrivilegeGrant>) {
+        self.grants = grants;
+    }
+
+    /// Guard "infinite" repeats against running away, e.g. with no delays set
+    pub fn set_iteration_cap(&mut self, cap: IterationCap) {
+        self.iteration_cap = cap;
+    }
+
+    /// Intended against actual timing of the last run
+    pub fn timing_report(&self) -> TimingReport {
+        self.timing.report()
+    }
+
+    /// Play the macro, returning the values its events read
+    pub fn play_macro(&mut self, macro_data: &Macro) -> Result<RunOutputs, String> {
+        self.check_privileges(macro_data)?;
+        // Macros run through RunMacro need their own grants, not the caller's
+        if let Some(resolve) = &self.macro_resolver {
+            for sub in reachable_macros(macro_data, resolve.as_ref()) {
+                self.check_privileges(&sub)?;
+            }
+        }
+        self.control.stopped.store(false, Ordering::SeqCst);
+        self.outputs.clear();
+        self.control
+            .set_human_override(macro_data.playback_settings.human_override);
+        info!(
+            macro_id = %macro_data.id,
+            events = macro_data.events.len(),
+            repeat_mode = %macro_data.playback_settings.repeat_mode,
+            speed = macro_data.playback_settings.speed,
+            "Playback started"
+        );
+        let mut result = self.play_events(macro_data);
+        let settings = &macro_data.playback_settings;
+        let mut retries = 0;
+        while let Err(e) = &result {
+            if !settings.retry_on_failure
+                || retries >= settings.max_retries
+                || self.control.is_stopped()
+            {
+                break;
+            }
+            let backoff = retry_backoff(settings.backoff_ms, retries);
+            retries += 1;
+            warn!(
+                macro_id = %macro_data.id,
+                error = %e,
+                retry = retries,
+                backoff_ms = backoff.as_millis() as u64,
+                "Playback failed, retrying"
+            );
+            // Each attempt starts from scratch, with nothing held from the failed one
+            self.release_held();
+            self.wait(backoff);
+            if self.control.is_stopped() {
+                break;
+            }
+            self.outputs.clear();
+            result = self.play_events(macro_data);
+        }
+        match &result {
+            Ok(()) => info!(macro_id = %macro_data.id, "Playback finished"),
+            Err(e) => warn!(macro_id = %macro_data.id, error = %e, "Playback failed"),
+        }
+        // Stopping or failing mid-macro must not leave keys or buttons stuck down
+        self.release_held();
+        self.control.set_human_override(HumanOverride::Off);
+        self.control.set_paused(false);
+        self.control.set_status(PlaybackStatus::Idle);
+        result.map(|()| std::mem::take(&mut self.outputs))
+    }
+
+    fn check_privileges(&self, macro_data: &Macro) -> Result<(), String> {
+        let granted = self
+            .grants
+            .iter()
+            .find(|g| g.macro_id == macro_data.id)
+            .map(|g| g.event_types.as_slice())
+            .unwrap_or_default();
+        let missing = privilege::missing_grants(&macro_data.events, &self.plugins, granted);
+        if !missing.is_empty() {
+            return Err(format!(
+                "'{}' uses privileged events ({}) and needs permission to play",
+                macro_data.name,
+                missing.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    fn play_events(&mut self, macro_data: &Macro) -> Result<(), String> {
+        let events = &macro_data.events;
+        let settings = &macro_data.playback_settings;
+
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let repeat_count = match settings.repeat_mode.as_str() {
+            "once" => 1,
+            "count" => settings.repeat_count,
+            // Runs until stopped, out of time or the trigger key is released
+            "infinite" | "duration" | "held" => u32::MAX,
+            _ => 1,
+        };
+        let deadline = (settings.repeat_mode == "duration")
+            .then(|| Duration::from_secs_f64(settings.repeat_duration_minutes.max(0.0) * 60.0));
+
+        self.region = macro_data
+            .recording_settings
+            .region
+            .map(|r| r.scaled(self.coordinate_scale));
+        self.offset = (0, 0);
+        self.click_verification = settings.click_verification;
+        self.click_by_element = settings.click_by_element;
+        self.relocated = None;
+        self.destructive = DestructiveDetector::default();
+        self.timing = TimingRecorder::new(events);
+        if settings.play_at_cursor {
+            if let Some((x, y)) = cursor_anchor(events) {
+                let (cx, cy) = self.sink.cursor_position()?;
+                let (ax, ay) = self.scale_point(x, y);
+                self.offset = (cx - ax, cy - ay);
+            }
+        }
+        // Playing at the cursor already places the macro, so windows aren't followed there
+        self.window_offsets = match self.window_locator {
+            Some(locate) if !settings.play_at_cursor => {
+                window_offsets(events, locate, self.coordinate_scale)
+            }
+            _ => Vec::new(),
+        };
+        self.window_offset = (0, 0);
+
+        let delays = event_delays(events, settings);
+        let estimate = estimate_duration(events, settings);
+        let started_at = Instant::now();
+        let eta = |i: usize, iteration: u32| {
+            remaining_ms(
+                &delays,
+                settings,
+                &estimate,
+                i,
+                iteration,
+                started_at.elapsed(),
+            )
+        };
+
+        for iteration in 0..repeat_count {
+            debug!(iteration = iteration + 1, "Playing macro iteration");
+            self.counter = settings.counter.map(|c| c.value_at(iteration));
+
+            for i in 0..events.len() {
+                let event = &events[i];
+
+                // Hold here while paused
+                while self.control.is_paused() && !self.control.is_stopped() {
+                    self.control.set_status(PlaybackStatus::Paused {
+                        macro_id: macro_data.id.clone(),
+                        event_index: i,
+                        iteration,
+                        elapsed_ms: started_at.elapsed().as_millis() as u64,
+                        eta_ms: eta(i, iteration),
+                    });
+                    thread::sleep(Duration::from_millis(50));
+                }
+
+                if self.control.is_stopped() {
+                    info!(event_index = i, iteration, "Playback stopped");
+                    return Ok(());
+                }
+
+                self.control.set_status(PlaybackStatus::Playing {
+                    macro_id: macro_data.id.clone(),
+                    event_index: i,
+                    iteration,
+                    elapsed_ms: started_at.elapsed().as_millis() as u64,
+                    eta_ms: eta(i, iteration),
+                });
+
+                let intended = Duration::from_millis(delays[i]);
+                let wait_started = Instant::now();
+                if delays[i] > 0 {
+                    self.wait(intended);
+                }
+                let injection_started = Instant::now();
+
+                self.window_offset = self.window_offsets.get(i).copied().unwrap_or_default();
+                self.simulate_event(event)?;
+                self.control.mark_injected();
+                self.timing.record(
+                    i,
+                    intended,
+                    injection_started - wait_started,
+                    injection_started.elapsed(),
+                );
+            }
+
+            if settings.repeat_mode == "held" && self.control.is_trigger_released() {
+                info!(iterations = iteration + 1, "Trigger key released");
+                break;
+            }
+
+            if deadline.is_some_and(|d| started_at.elapsed() >= d) {
+                info!(iterations = iteration + 1, "Repeat duration reached");
+                break;
+            }
+
+            if settings.repeat_mode == "infinite" && !self.continue_past_cap(iteration + 1) {
+                break;
+            }
+
+            // Delay between repetitions
+            if iteration < repeat_count - 1 {
+                self.wait(Duration::from_millis(settings.repeat_delay_ms));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// At every multiple of the cap, ask whether an infinite run should go on
+    fn continue_past_cap(&self, iterations: u32) -> bool {
+        let cap = self.iteration_cap.max_iterations;
+        if cap == 0 || iterations % cap != 0 {
+            return true;
+        }
+        let approved = self.iteration_cap.confirm.as_ref().is_some_and(|confirm| {
+            confirm(&format!(
+                "{} more repetitions after {} so far",
+                cap, iterations
+            ))
+        });
+        if !approved {
+            info!(iterations, "Repeat limit reached");
+        }
+        approved
+    }
+
+    /// Keep a value read by `event` under its "outputVar", or its event type without one
+    fn store_output(&mut self, event: &MacroEvent, value: String) {
+        let name = event
+            .data
+            .get("outputVar")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&event.event_type);
+        debug!(name, "Stored run output");
+        self.outputs.insert(name.to_string(), value);
+    }
+
+    /// Wait in slices so a stop request cuts long delays short
+    fn wait(&mut self, duration: Duration) {
+        let mut remaining = duration;
+        while !remaining.is_zero() && !self.control.is_stopped() {
+            let slice = remaining.min(WAIT_SLICE);
+            self.sink.wait(slice);
+            remaining -= slice;
+        }
+    }
+
+    /// Scale a relative move, keeping the rounding so runs of small moves don't drift
+    fn scale_relative(&mut self, dx: i64, dy: i64) -> (i32, i32) {
+        let x = dx as f64 * self.relative_scale + self.relative_remainder.0;
+        let y = dy as f64 * self.relative_scale + self.relative_remainder.1;
+        self.relative_remainder = (x - x.round(), y - y.round());
+        (x.round() as i32, y.round() as i32)
+    }
+
+    fn simulate_event(&mut self, event: &MacroEvent) -> Result<(), String> {
+        match event.event_type.as_str() {
+            "MouseMove" => {
+                if let (Some(x), Some(y)) = (
+                    event.data.get("x").and_then(|v| v.as_i64()),
+                    event.data.get("y").and_then(|v| v.as_i64()),
+                ) {
+                    self.move_absolute(x, y)?;
+                }
+            }
+            "MouseMoveRelative" => {
+                if let (Some(dx), Some(dy)) = (
+                    event.data.get("dx").and_then(|v| v.as_i64()),
+                    event.data.get("dy").and_then(|v| v.as_i64()),
+                ) {
+                    let (dx, dy) = self.scale_relative(dx, dy);
+                    self.sink.move_mouse(dx, dy, Coordinate::Rel)?;
+                }
+            }
+            "MouseDown" => {
+                self.move_to_click_position(event)?;
+                self.move_to_click_element(event)?;
+                self.check_region()?;
+                self.verify_click_target(event)?;
+                self.check_elevation()?;
+                if let Some(button_str) = event.data.get("button").and_then(|v| v.as_str()) {
+                    let button = convert_to_enigo_button(button_str);
+                    self.sink.button(button, Direction::Press)?;
+                    self.held.push(HeldInput::Button(button));
+                }
+            }
+            "MouseUp" => {
+                match self.relocated.take() {
+                    Some((x, y)) => self.sink.move_mouse(x, y, Coordinate::Abs)?,
+                    None => self.move_to_click_position(event)?,
+                }
+                if let Some(button_str) = event.data.get("button").and_then(|v| v.as_str()) {
+                    let button = convert_to_enigo_button(button_str);
+                    self.sink.button(button, Direction::Release)?;
+                    self.forget_held(HeldInput::Button(button));
+                }
+            }
+            "KeyDown" => {
+                if let Some(key_str) = event.data.get("key").and_then(|v| v.as_str()) {
+                    self.check_elevation()?;
+                    self.confirm_if_destructive(key_str)?;
+                    self.simulate_key(key_str, Direction::Press)?;
+                }
+            }
+            "KeyUp" => {
+                if let Some(key_str) = event.data.get("key").and_then(|v| v.as_str()) {
+                    self.destructive.key_up(key_str);
+                    self.simulate_key(key_str, Direction::Release)?;
+                }
+            }
+            "MouseWheel" => {
+                if let Some(delta_y) = event.data.get("delta_y").and_then(|v| v.as_i64()) {
+                    let scroll_amount = delta_y as i32;
+                    self.sink.scroll(scroll_amount, Axis::Vertical)?;
+                }
+            }
+            "Pinch" => {
+                // Touchpads zoom with Ctrl+wheel, which is also the only way to send it
+                let delta_y = event.data.get("delta_y").and_then(|v| v.as_i64());
+                self.simulate_key("Control", Direction::Press)?;
+                self.sink
+                    .scroll(delta_y.unwrap_or(0) as i32, Axis::Vertical)?;
+                self.simulate_key("Control", Direction::Release)?;
+            }
+            "Swipe" => {
+                let direction = event
+                    .data
+                    .get("direction")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default();
+                let keys = gesture::swipe_keys(direction).ok_or_else(|| {
+                    format!("Swipe {} can't be played on this platform", direction)
+                })?;
+                for key in keys {
+                    self.simulate_key(key, Direction::Press)?;
+                }
+                for key in keys.iter().rev() {
+                    self.simulate_key(key, Direction::Release)?;
+                }
+            }
+            "WaitForStill" => self.wait_for_still(event)?,
+            "TypeText" => self.type_text(event)?,
+            "KeyTurbo" => self.key_turbo(event)?,
+            "RunMacro" => self.run_macro(event)?,
+            // Only there to find your way around the recording
+            "Marker" | "WindowFocus" => {}
+            _ => match self.plugins.handler_for(&event.event_type) {
+                Some(plugin) => {
+                    if let Some(value) = plugin.play_with_output(event)? {
+                        self.store_output(event, value);
+                    }
+                }
+                None => warn!(event_type = %event.event_type, "Unknown event type"),
+            },
+        }
+
+        Ok(())
+    }
+
+    // Helper to move to recorded absolute coordinates, applying the display scale
+    fn move_absolute(&mut self, x: i64, y: i64) -> Result<(), String> {
+        let (x, y) = self.scale_point(x, y);
+        let (dx, dy) = self.shift();
+        self.sink.move_mouse(x + dx, y + dy, Coordinate::Abs)
+    }
+
+    // Everything added to recorded coordinates: the cursor offset and window movement
+    fn shift(&self) -> (i32, i32) {
+        (
+            self.offset.0 + self.window_offset.0,
+            self.offset.1 + self.window_offset.1,
+        )
+    }
+
+    fn scale_point(&self, x: i64, y: i64) -> (i32, i32) {
+        (
+            (x as f64 * self.coordinate_scale).round() as i32,
+            (y as f64 * self.coordinate_scale).round() as i32,
+        )
+    }
+
+    // Clicks recorded with recordClickPositions carry their own coordinates
+    fn move_to_click_position(&mut self, event: &MacroEvent) -> Result<(), String> {
+        if let (Some(x), Some(y)) = (
+            event.data.get("x").and_then(|v| v.as_i64()),
+            event.data.get("y").and_then(|v| v.as_i64()),
+        ) {
+            self.move_absolute(x, y)?;
+        }
+        Ok(())
+    }
+
+    // Clicks recorded with recordClickElements go to wherever their element is
+    // now; if it can't be found they stay at the recorded coordinates
+    fn move_to_click_element(&mut self, event: &MacroEvent) -> Result<(), String> {
+        if !self.click_by_element {
+            return Ok(());
+        }
+        let (Some(locate), Some(element)) = (
+            self.element_locator,
+            event
+                .data
+                .get("element")
+                .and_then(|v| serde_json::from_value::<UiElement>(v.clone()).ok()),
+        ) else {
+            return Ok(());
+        };
+
+        let position = self.sink.cursor_position()?;
+        match locate(&element, position) {
+            Some(target) if target != position => {
+                info!(element = %element.name, from = ?position, to = ?target, "Click element moved");
+                self.sink.move_mouse(target.0, target.1, Coordinate::Abs)?;
+                self.relocated = Some(target);
+            }
+            Some(_) => {}
+            None => debug!(element = %element.name, "Click element not found, using coordinates"),
+        }
+        Ok(())
+    }
+
+    // Windows drops input to elevated windows without any error, so check
+    // before each press rather than letting the rest of the macro run blind
+    fn check_elevation(&self) -> Result<(), String> {
+        match self.elevation_check.and_then(|check| check()) {
+            Some(window) => Err(elevation::blocked_message(&window)),
+            None => Ok(()),
+        }
+    }
+
+    fn confirm_if_destructive(&mut self, key: &str) -> Result<(), String> {
+        let Some(action) = self.destructive.key_down(key) else {
+            return Ok(());
+        };
+        let Some(confirm) = &self.confirm_destructive else {
+            return Ok(());
+        };
+        info!(action, "Asking before a destructive key combination");
+        if confirm(action) {
+            Ok(())
+        } else {
+            Err(format!("Playback stopped before {}", action))
+        }
+    }
+
+    // Refuse to click outside the macro's screen region
+    fn check_region(&self) -> Result<(), String> {
+        if let Some(region) = self.region {
+            let (x, y) = self.sink.cursor_position()?;
+            if !region.contains(x, y) {
+                return Err(format!(
+                    "Click at ({}, {}) is outside the macro's screen region, playback aborted",
+                    x, y
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Sample a region until it stays unchanged for stillMs, e.g. until a page
+    // has finished loading. Time is counted in poll intervals.
+    fn wait_for_still(&mut self, event: &MacroEvent) -> Result<(), String> {
+        let grab = self
+            .screen
+            .ok_or_else(|| "WaitForStill needs screen capture".to_string())?;
+        let field = |name: &str| event.data.get(name).and_then(|v| v.as_i64());
+        let (Some(x), Some(y), Some(width), Some(height)) =
+            (field("x"), field("y"), field("width"), field("height"))
+        else {
+            return Err("WaitForStill needs x, y, width and height".to_string());
+        };
+        let (x, y) = self.scale_point(x, y);
+        let (dx, dy) = self.shift();
+        let region = ScreenRegion {
+            x: x + dx,
+            y: y + dy,
+            width: (width as f64 * self.coordinate_scale).round() as u32,
+            height: (height as f64 * self.coordinate_scale).round() as u32,
+        };
+        let still_ms = field("stillMs").unwrap_or(500).max(0) as u64;
+        let timeout_ms = field("timeoutMs").map_or(DEFAULT_STILL_TIMEOUT_MS, |t| t.max(0) as u64);
+
+        let mut previous = grab(region)?;
+        let mut waited_ms = 0;
+        let mut still_for_ms = 0;
+        while still_for_ms < still_ms {
+            if self.control.is_stopped() {
+                return Ok(());
+            }
+            if waited_ms >= timeout_ms {
+                return Err(format!(
+                    "Screen region at ({}, {}) was still changing after {}ms",
+                    region.x, region.y, timeout_ms
+                ));
+            }
+
+            self.wait(STILL_POLL);
+            waited_ms += STILL_POLL.as_millis() as u64;
+
+            let current = grab(region)?;
+            if screen::difference(&previous, &current) <= STILL_TOLERANCE {
+                still_for_ms += STILL_POLL.as_millis() as u64;
+            } else {
+                still_for_ms = 0;
+            }
+            previous = current;
+        }
+        Ok(())
+    }
+
+    // Compare the screen under the cursor with the click's recorded screenshot,
+    // optionally moving to where the target went
+    fn verify_click_target(&mut self, event: &MacroEvent) -> Result<(), String> {
+        if self.click_verification == ClickVerification::Off {
+            return Ok(());
+        }
+        let (Some(grab), Some(encoded)) = (
+            self.screen,
+            event.data.get("screenshot").and_then(|v| v.as_str()),
+        ) else {
+            return Ok(());
+        };
+
+        let reference = screen::decode_png(encoded)?;
+        let tolerance = event
+            .data
+            .get("matchTolerance")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(DEFAULT_MATCH_TOLERANCE);
+        let (x, y) = self.sink.cursor_position()?;
+        let current = grab(screen::region_around(
+            x,
+            y,
+            reference.width.max(reference.height),
+        ))?;
+        if screen::difference(&reference, &current) <= tolerance {
+            return Ok(());
+        }
+
+        if self.click_verification == ClickVerification::Relocate {
+            let area = screen::region_around(
+                x,
+                y,
+                PATCH_SIZE.max(reference.width) + 2 * CLICK_SEARCH_RADIUS,
+            );
+            if let Some(((left, top), score)) = screen::locate(&reference, &grab(area)?) {
+                if score <= tolerance {
+                    let target = (
+                        area.x + (left + reference.width / 2) as i32,
+                        area.y + (top + reference.height / 2) as i32,
+                    );
+                    info!(from = ?(x, y), to = ?target, "Click target moved");
+                    self.sink.move_mouse(target.0, target.1, Coordinate::Abs)?;
+                    self.relocated = Some(target);
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(format!(
+            "Click target at ({}, {}) doesn't match the recording, playback aborted",
+            x, y
+        ))
+    }
+
+    /// Type `text`, with `{counter}` replaced by this repetition's counter
+    fn type_text(&mut self, event: &MacroEvent) -> Result<(), String> {
+        let Some(text) = event.data.get("text").and_then(|v| v.as_str()) else {
+            return Err("TypeText needs text".to_string());
+        };
+        let text = match &self.counter {
+            Some(counter) => text.replace("{counter}", counter),
+            None => text.to_string(),
+        };
+        self.check_elevation()?;
+        for ch in text.chars() {
+            let key = match ch {
+                '\n' => Key::Return,
+                '\t' => Key::Tab,
+                _ => Key::Unicode(ch),
+            };
+            self.sink.key(key, Direction::Click)?;
+        }
+        Ok(())
+    }
+
+    // Press and release a key every intervalMs for durationMs, so "spam space
+    // for ten seconds" is one event. Neither is scaled by the playback speed.
+    fn key_turbo(&mut self, event: &MacroEvent) -> Result<(), String> {
+        let Some(key) = event.data.get("key").and_then(|v| v.as_str()) else {
+            return Err("KeyTurbo needs a key".to_string());
+        };
+        let (Some(interval_ms), Some(duration_ms)) = (
+            event.data.get("intervalMs").and_then(|v| v.as_u64()),
+            event.data.get("durationMs").and_then(|v| v.as_u64()),
+        ) else {
+            return Err("KeyTurbo needs intervalMs and durationMs".to_string());
+        };
+        let interval = Duration::from_millis(interval_ms).max(MIN_TURBO_INTERVAL);
+        let presses = (duration_ms / interval.as_millis() as u64).max(1);
+
+        self.check_elevation()?;
+        self.confirm_if_destructive(key)?;
+        for _ in 0..presses {
+            while self.control.is_paused() && !self.control.is_stopped() {
+                self.sink.wait(WAIT_SLICE);
+            }
+            if self.control.is_stopped() {
+                break;
+            }
+            self.simulate_key(key, Direction::Press)?;
+            self.simulate_key(key, Direction::Release)?;
+            self.wait(interval);
+        }
+        self.destructive.key_up(key);
+        Ok(())
+    }
+
+    // Play another stored macro's events in place, once and at its own speed
+    fn run_macro(&mut self, event: &MacroEvent) -> Result<(), String> {
+        let Some(id) = event.data.get("macroId").and_then(|v| v.as_str()) else {
+            return Err("RunMacro needs a macroId".to_string());
+        };
+        let resolve = self
+            .macro_resolver
+            .clone()
+            .ok_or_else(|| "RunMacro can't look up macros here".to_string())?;
+        let sub = resolve(id).ok_or_else(|| format!("RunMacro: macro not found: {}", id))?;
+        if self.depth >= MAX_MACRO_DEPTH {
+            return Err(format!(
+                "RunMacro nested more than {} deep at '{}', does a macro run itself?",
+                MAX_MACRO_DEPTH, sub.name
+            ));
+        }
+        // Checked again here since the library may have changed since playback started
+        self.check_privileges(&sub)?;
+
+        debug!(macro_id = id, events = sub.events.len(), "Running macro");
+        let delays = event_delays(&sub.events, &sub.playback_settings);
+        self.depth += 1;
+        let result = self.play_nested(&sub.events, &delays);
+        self.depth -= 1;
+        result
+    }
+
+    fn play_nested(&mut self, events: &[MacroEvent], delays: &[u64]) -> Result<(), String> {
+        for (event, &delay) in events.iter().zip(delays) {
+            while self.control.is_paused() && !self.control.is_stopped() {
+                self.sink.wait(WAIT_SLICE);
+            }
+            if self.control.is_stopped() {
+                break;
+            }
+            self.wait(Duration::from_millis(delay));
+            self.simulate_event(event)?;
+            self.control.mark_injected();
+        }
+        Ok(())
+    }
+
+    // Helper to simulate key press/release
+    fn simulate_key(&mut self, key_str: &str, direction: Direction) -> Result<(), String> {
+        // Handle single character keys (alphanumeric, symbols)
+        if key_str.len() == 1 {
+            let ch = key_str.chars().next().unwrap();
+            // For single chars, we handle modifiers correctly by respecting the event direction.
+            // We use Key::Unicode to ensure the specific character is targeted.
+            let key = enigo::Key::Unicode(ch);
+            return self.press_key(key, direction);
+        }
+
+        // Handle special Named keys
+        let key = string_to_enigo_key(key_str);
+        self.press_key(key, direction)
+    }
+
+    fn press_key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        self.sink.key(key, direction)?;
+        match direction {
+            Direction::Press => self.held.push(HeldInput::Key(key)),
+            Direction::Release => self.forget_held(HeldInput::Key(key)),
+            Direction::Click => {}
+        }
+        Ok(())
+    }
+
+    fn forget_held(&mut self, input: HeldInput) {
+        if let Some(pos) = self.held.iter().rposition(|h| *h == input) {
+            self.held.remove(pos);
+        }
+    }
+
+    /// Release everything still held, most recent first
+    fn release_held(&mut self) {
+        while let Some(input) = self.held.pop() {
+            let result = match input {
+                HeldInput::Key(key) => self.sink.key(key, Direction::Release),
+                HeldInput::Button(button) => self.sink.button(button, Direction::Release),
+            };
+            if let Err(e) = result {
+                warn!(?input, error = %e, "Failed to release held input");
+            }
+        }
+    }
+}
+
+/// Watch for the release of `hotkey`'s main key (e.g. "F11" in "Shift+F11")
+/// for a "held" mode run. Without a watch such a run repeats until stopped.
+pub fn watch_trigger_release(
+    control: &Arc<PlaybackControl>,
+    source: Box<dyn InputSource>,
+    hotkey: &str,
+) -> Result<(), String> {
+    control.trigger_released.store(false, Ordering::SeqCst);
+    let watch = control.trigger_watches.fetch_add(1, Ordering::SeqCst) + 1;
+    let key = hotkey
+        .rsplit('+')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    let control = Arc::clone(control);
+    source.listen(Box::new(move |event: rdev::Event| {
+        if control.trigger_watches.load(Ordering::SeqCst) != watch {
+            return false;
+        }
+        match event.event_type {
+            rdev::EventType::KeyRelease(released)
+                if rdev_key_to_string(released, false).eq_ignore_ascii_case(&key) =>
+            {
+                control.release_trigger();
+                false
+            }
+            _ => true,
+        }
+    }))
+}
+
+/// Controls real user input is forwarded to; each drops out once its run has
+/// let go of it
+static OVERRIDE_WATCHES: Mutex<Vec<Weak<PlaybackControl>>> = parking_lot::const_mutex(Vec::new());
+
+/// Forward real user input to `control` for as long as it's in use. One
+/// listener per process serves every control, so scoped runs playing side by
+/// side each see the input.
+///
+/// Only presses and movement count, so releasing the key that started
+/// playback doesn't trip the override.
+pub fn watch_user_input(control: &Arc<PlaybackControl>) {
+    add_override_watch(control);
+
+    static STARTED: Once = Once::new();
+    STARTED.call_once(|| {
+        let result = Box::new(RdevSource).listen(Box::new(|event: rdev::Event| {
+            if matches!(
+                event.event_type,
+                rdev::EventType::KeyPress(_)
+                    | rdev::EventType::ButtonPress(_)
+                    | rdev::EventType::MouseMove { .. }
+                    | rdev::EventType::Wheel { .. }
+            ) {
+                forward_user_input();
+            }
+            true
+        }));
+        if let Err(e) = result {
+            warn!(error = %e, "Failed to watch user input");
+        }
+    });
+}
+
+fn add_override_watch(control: &Arc<PlaybackControl>) {
+    let mut watches = OVERRIDE_WATCHES.lock();
+    watches.retain(|w| w.strong_count() > 0);
+    if !watches.iter().any(|w| w.as_ptr() == Arc::as_ptr(control)) {
+        watches.push(Arc::downgrade(control));
+    }
+}
+
+fn forward_user_input() {
+    let controls: Vec<_> = OVERRIDE_WATCHES
+        .lock()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect();
+    // What one run injects reaches the listener too and must not trip another run's override
+    if controls.iter().any(|c| c.recently_injected()) {
+        return;
+    }
+    for control in controls {
+        control.user_input();
+    }
+}
+
+/// Ids of the macros `macro_data` plays through RunMacro events
+pub(crate) fn run_macro_targets(macro_data: &Macro) -> impl Iterator<Item = &str> {
+    macro_data
+        .events
+        .iter()
+        .filter(|e| e.event_type == "RunMacro")
+        .filter_map(|e| e.data.get("macroId")?.as_str())
+}
+
+/// Every stored macro `macro_data` can reach through RunMacro events, each
+/// once, going no deeper than playback does. Ids `resolve` doesn't know are
+/// skipped; playing them fails anyway.
+pub fn reachable_macros(
+    macro_data: &Macro,
+    resolve: &dyn Fn(&str) -> Option<Arc<Macro>>,
+) -> Vec<Arc<Macro>> {
+    let mut seen = HashSet::from([macro_data.id.clone()]);
+    let mut reached: Vec<Arc<Macro>> = Vec::new();
+    let mut level = run_macro_targets(macro_data)
+        .filter_map(resolve)
+        .collect::<Vec<_>>();
+    for _ in 0..MAX_MACRO_DEPTH {
+        let mut next = Vec::new();
+        for sub in level {
+            if seen.insert(sub.id.clone()) {
+                next.extend(run_macro_targets(&sub).filter_map(resolve));
+                reached.push(sub);
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        level = next;
+    }
+    reached
+}
+
+/// Expected runtime from event timestamps, speed, repeat mode and repeat delay
+pub fn estimate_duration(events: &[MacroEvent], settings: &PlaybackSettings) -> DurationEstimate {
+    // KeyTurbo events hold up the macro for their whole duration
+    let turbo_ms: u64 = events
+        .iter()
+        .filter(|e| e.event_type == "KeyTurbo")
+        .filter_map(|e| e.data.get("durationMs").and_then(|v| v.as_u64()))
+        .fold(0, u64::saturating_add);
+    let single_run_ms = event_delays(events, settings)
+        .into_iter()
+        .fold(turbo_ms, u64::saturating_add);
+
+    let (iterations, total_ms) = match settings.repeat_mode.as_str() {
+        "infinite" | "held" => (None, None),
+        "duration" => (
+            None,
+            Some((settings.repeat_duration_minutes.max(0.0) * 60_000.0) as u64),
+        ),
+        mode => {
+            let count = if mode == "count" {
+                settings.repeat_count
+            } else {
+                1
+            };
+            // Huge counts and delays from the settings pin the estimate instead of overflowing
+            let delays = (count.saturating_sub(1) as u64).saturating_mul(settings.repeat_delay_ms);
+            let total_ms = single_run_ms
+                .saturating_mul(count as u64)
+                .saturating_add(delays);
+            (Some(count), Some(total_ms))
+        }
+    };
+
+    DurationEstimate {
+        single_run_ms,
+        iterations,
+        total_ms,
+    }
+}
+
+/// Wait before retry number `retry` (zero-based): `backoff_ms`, doubling each time
+fn retry_backoff(backoff_ms: u64, retry: u32) -> Duration {
+    let factor = 2u64.saturating_pow(retry);
+    Duration::from_millis(backoff_ms.saturating_mul(factor)).min(MAX_RETRY_BACKOFF)
+}
+
+/// Wait before each event at the chosen speed. Mouse movement while a button
+/// is held is stretched by the drag speed factor.
+pub fn event_delays(events: &[MacroEvent], settings: &PlaybackSettings) -> Vec<u64> {
+    let drag_factor = if settings.drag_speed_factor > 0.0 {
+        settings.drag_speed_factor
+    } else {
+        1.0
+    };
+    let mut buttons_held = 0usize;
+
+    events
+        .iter()
+        .enumerate()
+        .map(|(i, event)| {
+            let gap = match i {
+                0 => 0,
+                _ => event.timestamp.saturating_sub(events[i - 1].timestamp),
+            };
+            let dragging = buttons_held > 0
+                && matches!(event.event_type.as_str(), "MouseMove" | "MouseMoveRelative");
+            match event.event_type.as_str() {
+                "MouseDown" => buttons_held += 1,
+                "MouseUp" => buttons_held = buttons_held.saturating_sub(1),
+                _ => {}
+            }
+
+            let factor = if dragging { drag_factor } else { 1.0 };
+            (gap as f64 / settings.speed * factor) as u64
+        })
+        .collect()
+}
+
+/// Time left once event `index` of `iteration` is reached
+fn remaining_ms(
+    delays: &[u64],
+    settings: &PlaybackSettings,
+    estimate: &DurationEstimate,
+    index: usize,
+    iteration: u32,
+    elapsed: Duration,
+) -> Option<u64> {
+    if settings.repeat_mode == "duration" {
+        return estimate
+            .total_ms
+            .map(|total| total.saturating_sub(elapsed.as_millis() as u64));
+    }
+
+    let iterations = estimate.iterations?;
+    let this_run = delays
+        .iter()
+        .skip(index + 1)
+        .fold(0, |sum, &d| sum.saturating_add(d));
+    let runs_left = iterations.saturating_sub(iteration + 1) as u64;
+    let per_run = estimate
+        .single_run_ms
+        .saturating_add(settings.repeat_delay_ms);
+
+    Some(this_run.saturating_add(runs_left.saturating_mul(per_run)))
+}
+
+fn event_position(event: &MacroEvent) -> Option<(i64, i64)> {
+    Some((
+        event.data.get("x").and_then(|v| v.as_i64())?,
+        event.data.get("y").and_then(|v| v.as_i64())?,
+    ))
+}
+
+/// Where the event at `index` happened: its own position or the last one before it
+pub fn click_position(events: &[MacroEvent], index: usize) -> Option<(i64, i64)> {
+    events.get(..=index)?.iter().rev().find_map(event_position)
+}
+
+/// For every event, how far the window focused when it was recorded has moved
+/// since. WindowFocus events carry the recorded bounds; windows that can't be
+/// found now, and events before the first focus change, aren't shifted.
+fn window_offsets(events: &[MacroEvent], locate: WindowLocator, scale: f64) -> Vec<(i32, i32)> {
+    let mut current: HashMap<String, Option<ScreenRegion>> = HashMap::new();
+    let mut offset = (0, 0);
+    events
+        .iter()
+        .map(|event| {
+            if event.event_type == "WindowFocus" {
+                let app = event.data.get("app").and_then(|v| v.as_str());
+                let recorded = event
+                    .data
+                    .get("window")
+                    .and_then(|v| serde_json::from_value::<ScreenRegion>(v.clone()).ok());
+                offset = match (app, recorded) {
+                    (Some(app), Some(recorded)) => {
+                        let now = *current
+                            .entry(app.to_string())
+                            .or_insert_with(|| locate(app));
+                        let recorded = recorded.scaled(scale);
+                        now.map_or((0, 0), |now| (now.x - recorded.x, now.y - recorded.y))
+                    }
+                    _ => (0, 0),
+                };
+                if offset != (0, 0) {
+                    debug!(?app, ?offset, "Window moved since recording");
+                }
+            }
+            offset
+        })
+        .collect()
+}
+
+/// Position of the first click, or of the first positioned event if nothing was clicked
+fn cursor_anchor(events: &[MacroEvent]) -> Option<(i64, i64)> {
+    let mut last = None;
+    for event in events {
+        if let Some(p) = event_position(event) {
+            last = Some(p);
+        }
+        if event.event_type == "MouseDown" && last.is_some() {
+            return last;
+        }
+    }
+    events.iter().find_map(event_position)
+}
+
+fn convert_to_enigo_button(button_str: &str) -> Button {
+    match button_str {
+        "Left" => Button::Left,
+        "Right" => Button::Right,
+        "Middle" => Button::Middle,
+        _ => Button::Left,
+    }
+}
+
+/// Key names, as the recorder writes them, that play as a key of their own.
+/// Names not listed here or below are typed as their first character, which
+/// covers letters and digits.
+const NAMED_KEYS: &[(&str, enigo::Key)] = {
+    use enigo::Key::*;
+    &[
+        ("Enter", Return),
+        ("Space", Space),
+        ("Backspace", Backspace),
+        ("Delete", Delete),
+        ("Tab", Tab),
+        ("Escape", Escape),
+        ("Shift", Shift),
+        ("Control", Control),
+        ("Alt", Alt),
+        ("Meta", Meta),
+        ("CapsLock", CapsLock),
+        ("LeftArrow", LeftArrow),
+        ("RightArrow", RightArrow),
+        ("UpArrow", UpArrow),
+        ("DownArrow", DownArrow),
+        ("Home", Home),
+        ("End", End),
+        ("PageUp", PageUp),
+        ("PageDown", PageDown),
+        #[cfg(not(target_os = "macos"))]
+        ("Insert", Insert),
+        ("F1", F1),
+        ("F2", F2),
+        ("F3", F3),
+        ("F4", F4),
+        ("F5", F5),
+        ("F6", F6),
+        ("F7", F7),
+        ("F8", F8),
+        ("F9", F9),
+        ("F10", F10),
+        ("F11", F11),
+        ("F12", F12),
+        ("F13", F13),
+        ("F14", F14),
+        ("F15", F15),
+        ("F16", F16),
+        ("F17", F17),
+        ("F18", F18),
+        ("F19", F19),
+        ("F20", F20),
+        #[cfg(not(target_os = "macos"))]
+        ("F21", F21),
+        #[cfg(not(target_os = "macos"))]
+        ("F22", F22),
+        #[cfg(not(target_os = "macos"))]
+        ("F23", F23),
+        #[cfg(not(target_os = "macos"))]
+        ("F24", F24),
+        // Punctuation goes by the character on a US layout
+        ("Minus", Unicode('-')),
+        ("Equal", Unicode('=')),
+        ("LeftBracket", Unicode('[')),
+        ("RightBracket", Unicode(']')),
+        ("SemiColon", Unicode(';')),
+        ("Quote", Unicode('\'')),
+        ("BackQuote", Unicode('`')),
+        ("BackSlash", Unicode('\\')),
+        ("Comma", Unicode(',')),
+        ("Dot", Unicode('.')),
+        ("Slash", Unicode('/')),
+    ]
+};
+
+/// Numpad keys. Enigo only names them on Windows; elsewhere they are sent as
+/// the X keysym or the macOS virtual key code.
+#[cfg(target_os = "windows")]
+const KEYPAD_KEYS: &[(&str, enigo::Key)] = {
+    use enigo::Key::*;
+    &[
+        ("Kp0", Numpad0),
+        ("Kp1", Numpad1),
+        ("Kp2", Numpad2),
+        ("Kp3", Numpad3),
+        ("Kp4", Numpad4),
+        ("Kp5", Numpad5),
+        ("Kp6", Numpad6),
+        ("Kp7", Numpad7),
+        ("Kp8", Numpad8),
+        ("Kp9", Numpad9),
+        ("KpPlus", Add),
+        ("KpMinus", Subtract),
+        ("KpMultiply", Multiply),
+        ("KpDivide", Divide),
+    ]
+};
+#[cfg(target_os = "macos")]
+const KEYPAD_KEYS: &[(&str, enigo::Key)] = {
+    use enigo::Key::Other;
+    &[
+        ("Kp0", Other(0x52)),
+        ("Kp1", Other(0x53)),
+        ("Kp2", Other(0x54)),
+        ("Kp3", Other(0x55)),
+        ("Kp4", Other(0x56)),
+        ("Kp5", Other(0x57)),
+        ("Kp6", Other(0x58)),
+        ("Kp7", Other(0x59)),
+        ("Kp8", Other(0x5b)),
+        ("Kp9", Other(0x5c)),
+        ("KpPlus", Other(0x45)),
+        ("KpMinus", Other(0x4e)),
+        ("KpMultiply", Other(0x43)),
+        ("KpDivide", Other(0x4b)),
+    ]
+};
+#[cfg(all(unix, not(target_os = "macos")))]
+const KEYPAD_KEYS: &[(&str, enigo::Key)] = {
+    use enigo::Key::Other;
+    &[
+        ("Kp0", Other(0xffb0)),
+        ("Kp1", Other(0xffb1)),
+        ("Kp2", Other(0xffb2)),
+        ("Kp3", Other(0xffb3)),
+        ("Kp4", Other(0xffb4)),
+        ("Kp5", Other(0xffb5)),
+        ("Kp6", Other(0xffb6)),
+        ("Kp7", Other(0xffb7)),
+        ("Kp8", Other(0xffb8)),
+        ("Kp9", Other(0xffb9)),
+        ("KpPlus", Other(0xffab)),
+        ("KpMinus", Other(0xffad)),
+        ("KpMultiply", Other(0xffaa)),
+        ("KpDivide", Other(0xffaf)),
+    ]
+};
+
+/// Side-specific modifiers, recorded with distinguishModifierSides
+const SIDED_MODIFIER_KEYS: &[(&str, enigo::Key)] = {
+    use enigo::Key::*;
+    &[
+        ("ShiftLeft", LShift),
+        ("ShiftRight", RShift),
+        ("ControlLeft", LControl),
+        ("ControlRight", RControl),
+        ("MetaLeft", Meta),
+        #[cfg(target_os = "windows")]
+        ("MetaRight", RWin),
+        #[cfg(target_os = "macos")]
+        ("MetaRight", RCommand),
+        #[cfg(all(unix, not(target_os = "macos")))]
+        ("MetaRight", Meta),
+        #[cfg(target_os = "windows")]
+        ("AltGr", RMenu),
+        #[cfg(target_os = "macos")]
+        ("AltGr", ROption),
+        #[cfg(all(unix, not(target_os = "macos")))]
+        ("AltGr", Alt),
+    ]
+};
+
+/// Names of the keys that play as a key of their own, apart from the
+/// side-specific modifiers
+pub(crate) fn named_keys() -> impl Iterator<Item = &'static str> {
+    NAMED_KEYS.iter().chain(KEYPAD_KEYS).map(|(name, _)| *name)
+}
+
+fn string_to_enigo_key(key_str: &str) -> enigo::Key {
+    let named = NAMED_KEYS
+        .iter()
+        .chain(KEYPAD_KEYS)
+        .chain(SIDED_MODIFIER_KEYS)
+        .find(|(name, _)| *name == key_str);
+    if let Some((_, key)) = named {
+        return *key;
+    }
+    if key_str.chars().count() != 1 {
+        warn!(key = key_str, "Unknown key string");
+    }
+    enigo::Key::Unicode(key_str.chars().next().unwrap_or('?'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macrox_core::input::{FakeSink, SinkCall};
+    use crate::macrox_core::types::{CounterSettings, InputScope, RecordingSettings};
+    use chrono::Utc;
+    use enigo::Key;
+
+    fn event(event_type: &str, timestamp: u64, data: serde_json::Value) -> MacroEvent {
+        MacroEvent {
+            event_type: event_type.to_string(),
+            timestamp,
+            data,
+        }
+    }
+
+    fn test_macro(
+        events: Vec<MacroEvent>,
+        speed: f64,
+        repeat_mode: &str,
+        repeat_count: u32,
+    ) -> Macro {
+        Macro {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            description: String::new(),
+            events,
+            recording_settings: RecordingSettings::default(),
+            playback_settings: PlaybackSettings {
+                speed,
+                repeat_mode: repeat_mode.to_string(),
+                repeat_count,
+                play_at_cursor: false,
+                repeat_delay_ms: 500,
+                repeat_duration_minutes: 0.0,
+                human_override: HumanOverride::Off,
+                target_window: None,
+                drag_speed_factor: 1.0,
+                click_verification: ClickVerification::Off,
+                click_by_element: false,
+                counter: None,
+                confirm_destructive: None,
+                retry_on_failure: false,
+                max_retries: 3,
+                backoff_ms: 1000,
+                input_scope: InputScope::All,
+            },
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            display_info: None,
+            expires_at: None,
+            archived: false,
+            author: None,
+            source_url: None,
+            license: None,
+        }
+    }
+
+    fn play(macro_data: &Macro) -> FakeSink {
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.play_macro(macro_data).unwrap();
+        sink
+    }
+
+    #[test]
+    fn releases_inputs_left_held_at_the_end() {
+        let events = vec![
+            event("KeyDown", 0, serde_json::json!({ "key": "Shift" })),
+            event("MouseDown", 10, serde_json::json!({ "button": "Left" })),
+            event("KeyDown", 20, serde_json::json!({ "key": "a" })),
+            event("KeyUp", 30, serde_json::json!({ "key": "a" })),
+        ];
+
+        let sink = play(&test_macro(events, 1.0, "once", 1));
+        let calls = sink.input_calls();
+        assert_eq!(
+            calls[calls.len() - 2..],
+            [
+                SinkCall::Button(Button::Left, Direction::Release),
+                SinkCall::Key(Key::Shift, Direction::Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn privileged_events_need_a_grant() {
+        let macro_data = test_macro(
+            vec![event(
+                "KeyDown",
+                0,
+                serde_json::json!({ "key": "x", "masked": true }),
+            )],
+            1.0,
+            "once",
+            1,
+        );
+        let mut player =
+            Player::with_sink(FakeSink::default(), Arc::new(PlaybackControl::default()));
+        assert!(player.play_macro(&macro_data).is_err());
+
+        player.set_privilege_grants(vec![PrivilegeGrant {
+            macro_id: macro_data.id.clone(),
+            event_types: vec![privilege::MASKED_INPUT.to_string()],
+        }]);
+        assert!(player.play_macro(&macro_data).is_ok());
+    }
+
+    #[test]
+    fn delays_follow_timestamps_and_speed() {
+        let events = vec![
+            event("KeyDown", 0, serde_json::json!({ "key": "a" })),
+            event("KeyUp", 100, serde_json::json!({ "key": "a" })),
+            event("KeyDown", 300, serde_json::json!({ "key": "b" })),
+        ];
+
+        let sink = play(&test_macro(events.clone(), 1.0, "once", 1));
+        assert_eq!(sink.total_wait(), Duration::from_millis(300));
+
+        let sink = play(&test_macro(events, 2.0, "once", 1));
+        assert_eq!(sink.total_wait(), Duration::from_millis(150));
+    }
+
+    #[test]
+    fn repeats_with_delay_between_iterations() {
+        let events = vec![event(
+            "MouseDown",
+            0,
+            serde_json::json!({ "button": "Left" }),
+        )];
+
+        let sink = play(&test_macro(events, 1.0, "count", 3));
+        assert_eq!(
+            sink.input_calls(),
+            vec![SinkCall::Button(Button::Left, Direction::Press); 3]
+        );
+        assert_eq!(sink.total_wait(), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn estimate_includes_speed_repeats_and_delay() {
+        let events = vec![
+            event("KeyDown", 0, serde_json::json!({ "key": "a" })),
+            event("KeyUp", 400, serde_json::json!({ "key": "a" })),
+        ];
+        let macro_data = test_macro(events, 2.0, "count", 3);
+
+        let estimate = estimate_duration(&macro_data.events, &macro_data.playback_settings);
+        assert_eq!(estimate.single_run_ms, 200);
+        assert_eq!(estimate.total_ms, Some(3 * 200 + 2 * 500));
+
+        let remaining = remaining_ms(
+            &event_delays(&macro_data.events, &macro_data.playback_settings),
+            &macro_data.playback_settings,
+            &estimate,
+            1,
+            1,
+            Duration::ZERO,
+        );
+        assert_eq!(remaining, Some(700));
+    }
+
+    #[test]
+    fn estimate_of_huge_repeats_saturates() {
+        let events = vec![
+            event("KeyDown", 0, serde_json::json!({ "key": "a" })),
+            event("KeyUp", 400, serde_json::json!({ "key": "a" })),
+        ];
+        let mut macro_data = test_macro(events, 1.0, "count", u32::MAX);
+        macro_data.playback_settings.repeat_delay_ms = u64::MAX / 2;
+
+        let estimate = estimate_duration(&macro_data.events, &macro_data.playback_settings);
+        assert_eq!(estimate.total_ms, Some(u64::MAX));
+
+        let remaining = remaining_ms(
+            &event_delays(&macro_data.events, &macro_data.playback_settings),
+            &macro_data.playback_settings,
+            &estimate,
+            0,
+            0,
+            Duration::ZERO,
+        );
+        assert_eq!(remaining, Some(u64::MAX));
+    }
+
+    #[test]
+    fn user_input_applies_override_only_once_armed() {
+        let control = PlaybackControl::default();
+        control.user_input();
+        assert!(!control.is_paused());
+
+        control.set_human_override(HumanOverride::Pause);
+        control.user_input();
+        assert!(!control.is_paused(), "ignored right after playback starts");
+
+        *control.armed_at.lock() = Some(Instant::now() - OVERRIDE_ARM_DELAY);
+        control.mark_injected();
+        control.user_input();
+        assert!(!control.is_paused(), "echo of injected input");
+
+        *control.last_injection.lock() = Some(Instant::now() - INJECTION_GRACE);
+        control.user_input();
+        assert!(control.is_paused());
+    }
+
+    #[test]
+    fn user_input_reaches_every_watched_control() {
+        let armed = || {
+            let control = Arc::new(PlaybackControl::default());
+            control.set_human_override(HumanOverride::Pause);
+            *control.armed_at.lock() = Some(Instant::now() - OVERRIDE_ARM_DELAY);
+            control
+        };
+        let (typing, clicking) = (armed(), armed());
+        add_override_watch(&typing);
+        add_override_watch(&clicking);
+
+        typing.mark_injected();
+        forward_user_input();
+        assert!(!clicking.is_paused(), "echo of the other run's input");
+
+        *typing.last_injection.lock() = Some(Instant::now() - INJECTION_GRACE);
+        forward_user_input();
+        assert!(typing.is_paused());
+        assert!(clicking.is_paused());
+
+        // A finished run's control is let go and no longer watched
+        let watched = Arc::downgrade(&typing);
+        drop(typing);
+        add_override_watch(&clicking);
+        assert!(!OVERRIDE_WATCHES.lock().iter().any(|w| w.ptr_eq(&watched)));
+    }
+
+    #[test]
+    fn unknown_event_types_go_to_plugins() {
+        use crate::macrox_core::plugin::tests::EchoPlugin;
+
+        let plugin = Arc::new(EchoPlugin::default());
+        let mut registry = PluginRegistry::default();
+        registry.register(plugin.clone()).unwrap();
+
+        let events = vec![
+            event("Echo", 0, serde_json::json!({ "text": "hi" })),
+            event(
+                "Echo",
+                0,
+                serde_json::json!({ "text": "A-1042", "outputVar": "orderNumber" }),
+            ),
+            event("KeyDown", 0, serde_json::json!({ "key": "a" })),
+        ];
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.set_plugins(Arc::new(registry));
+        let outputs = player
+            .play_macro(&test_macro(events, 1.0, "once", 1))
+            .unwrap();
+
+        assert_eq!(plugin.played.lock()[0].data["text"], "hi");
+        assert_eq!(sink.input_calls().len(), 1);
+        assert_eq!(outputs["Echo"], "hi");
+        assert_eq!(outputs["orderNumber"], "A-1042");
+    }
+
+    #[test]
+    fn drag_speed_only_slows_movement_while_a_button_is_held() {
+        let events = vec![
+            event("MouseMove", 0, serde_json::json!({ "x": 0, "y": 0 })),
+            event("MouseMove", 100, serde_json::json!({ "x": 5, "y": 0 })),
+            event("MouseDown", 200, serde_json::json!({ "button": "Left" })),
+            event("MouseMove", 300, serde_json::json!({ "x": 10, "y": 0 })),
+            event("MouseMove", 400, serde_json::json!({ "x": 20, "y": 0 })),
+            event("MouseUp", 500, serde_json::json!({ "button": "Left" })),
+            event("MouseMove", 600, serde_json::json!({ "x": 30, "y": 0 })),
+        ];
+        let mut macro_data = test_macro(events, 1.0, "once", 1);
+        macro_data.playback_settings.drag_speed_factor = 3.0;
+
+        assert_eq!(
+            event_delays(&macro_data.events, &macro_data.playback_settings),
+            vec![0, 100, 100, 300, 300, 100, 100]
+        );
+        assert_eq!(play(&macro_data).total_wait(), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn repeat_delay_is_configurable() {
+        let events = vec![event("KeyDown", 0, serde_json::json!({ "key": "a" }))];
+        let mut macro_data = test_macro(events, 1.0, "count", 3);
+        macro_data.playback_settings.repeat_delay_ms = 20;
+
+        let sink = play(&macro_data);
+        assert_eq!(sink.total_wait(), Duration::from_millis(40));
+    }
+
+    #[test]
+    fn duration_mode_stops_when_time_is_up() {
+        let events = vec![event("KeyDown", 0, serde_json::json!({ "key": "a" }))];
+        let mut macro_data = test_macro(events, 1.0, "duration", 1);
+        macro_data.playback_settings.repeat_duration_minutes = 0.0;
+
+        let sink = play(&macro_data);
+        assert_eq!(sink.input_calls().len(), 1);
+    }
+
+    #[test]
+    fn play_at_cursor_offsets_from_first_click() {
+        let events = vec![
+            event("MouseMove", 0, serde_json::json!({ "x": 100, "y": 200 })),
+            event("MouseDown", 10, serde_json::json!({ "button": "Left" })),
+            event("MouseMove", 20, serde_json::json!({ "x": 130, "y": 210 })),
+        ];
+        let mut macro_data = test_macro(events, 1.0, "once", 1);
+        macro_data.playback_settings.play_at_cursor = true;
+
+        let sink = FakeSink {
+            cursor: (500, 40),
+            ..FakeSink::default()
+        };
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.play_macro(&macro_data).unwrap();
+
+        assert_eq!(
+            sink.input_calls(),
+            vec![
+                SinkCall::MoveMouse(500, 40, Coordinate::Abs),
+                SinkCall::Button(Button::Left, Direction::Press),
+                SinkCall::MoveMouse(530, 50, Coordinate::Abs),
+            ]
+        );
+    }
+
+    #[test]
+    fn coordinates_follow_a_moved_window() {
+        fn moved_editor(app: &str) -> Option<ScreenRegion> {
+            (app == "Editor").then_some(ScreenRegion {
+                x: 250,
+                y: 80,
+                width: 800,
+                height: 600,
+            })
+        }
+
+        let window = |app: &str, x: i32, y: i32| {
+            serde_json::json!({
+                "app": app,
+                "window": { "x": x, "y": y, "width": 800, "height": 600 }
+            })
+        };
+        let events = vec![
+            event("MouseMove", 0, serde_json::json!({ "x": 10, "y": 10 })),
+            event("WindowFocus", 0, window("Editor", 200, 100)),
+            event("MouseMove", 0, serde_json::json!({ "x": 300, "y": 300 })),
+            event("WindowFocus", 0, window("Browser", 0, 0)),
+            event("MouseMove", 0, serde_json::json!({ "x": 40, "y": 50 })),
+        ];
+        let macro_data = test_macro(events, 1.0, "once", 1);
+
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.set_window_locator(moved_editor);
+        player.play_macro(&macro_data).unwrap();
+
+        assert_eq!(
+            sink.input_calls(),
+            vec![
+                SinkCall::MoveMouse(10, 10, Coordinate::Abs),
+                SinkCall::MoveMouse(350, 280, Coordinate::Abs),
+                // Not running now, so left where it was recorded
+                SinkCall::MoveMouse(40, 50, Coordinate::Abs),
+            ]
+        );
+    }
+
+    #[test]
+    fn clicks_outside_region_abort_playback() {
+        let events = vec![
+            event("MouseMove", 0, serde_json::json!({ "x": 50, "y": 50 })),
+            event("MouseDown", 0, serde_json::json!({ "button": "Left" })),
+            event("MouseMove", 0, serde_json::json!({ "x": 150, "y": 50 })),
+            event("MouseDown", 0, serde_json::json!({ "button": "Left" })),
+        ];
+        let mut macro_data = test_macro(events, 1.0, "once", 1);
+        macro_data.recording_settings.region = Some(ScreenRegion {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 100,
+        });
+
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        assert!(player.play_macro(&macro_data).is_err());
+
+        let presses = sink
+            .input_calls()
+            .into_iter()
+            .filter(|c| matches!(c, SinkCall::Button(..)))
+            .count();
+        assert_eq!(presses, 1);
+    }
+
+    /// A fake screen with a bright square at (200, 100) on a repeating gradient
+    fn fake_screen(region: ScreenRegion) -> Result<screen::Patch, String> {
+        let mut rgba = Vec::new();
+        for y in region.y..region.y + region.height as i32 {
+            for x in region.x..region.x + region.width as i32 {
+                let on_square = (200..204).contains(&x) && (100..104).contains(&y);
+                let value = if on_square {
+                    255
+                } else {
+                    (x + y).rem_euclid(64) as u8
+                };
+                rgba.extend([value, value, value, 255]);
+            }
+        }
+        Ok(screen::Patch {
+            width: region.width,
+            height: region.height,
+            rgba,
+        })
+    }
+
+    #[test]
+    fn click_verification_aborts_or_relocates() {
+        let reference = fake_screen(screen::region_around(202, 102, PATCH_SIZE)).unwrap();
+        let events = vec![
+            event("MouseMove", 0, serde_json::json!({ "x": 150, "y": 60 })),
+            event(
+                "MouseDown",
+                0,
+                serde_json::json!({ "button": "Left", "screenshot": screen::encode_png(&reference).unwrap() }),
+            ),
+            event("MouseUp", 0, serde_json::json!({ "button": "Left" })),
+        ];
+        let mut macro_data = test_macro(events, 1.0, "once", 1);
+
+        let play_with = |macro_data: &Macro| {
+            let sink = FakeSink::default();
+            let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+            player.set_screen_grabber(fake_screen);
+            (player.play_macro(macro_data), sink)
+        };
+
+        macro_data.playback_settings.click_verification = ClickVerification::Verify;
+        let (result, sink) = play_with(&macro_data);
+        assert!(result.is_err());
+        assert!(!sink
+            .input_calls()
+            .iter()
+            .any(|c| matches!(c, SinkCall::Button(..))));
+
+        macro_data.playback_settings.click_verification = ClickVerification::Relocate;
+        let (result, sink) = play_with(&macro_data);
+        assert!(result.is_ok());
+        assert_eq!(
+            sink.input_calls(),
+            vec![
+                SinkCall::MoveMouse(150, 60, Coordinate::Abs),
+                SinkCall::MoveMouse(202, 102, Coordinate::Abs),
+                SinkCall::Button(Button::Left, Direction::Press),
+                SinkCall::MoveMouse(202, 102, Coordinate::Abs),
+                SinkCall::Button(Button::Left, Direction::Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn clicks_follow_their_element_when_clicking_by_element() {
+        fn moved_button(element: &UiElement, _: (i32, i32)) -> Option<(i32, i32)> {
+            (element.automation_id == "ok").then_some((300, 200))
+        }
+
+        let events = vec![
+            event("MouseMove", 0, serde_json::json!({ "x": 150, "y": 60 })),
+            event(
+                "MouseDown",
+                0,
+                serde_json::json!({
+                    "button": "Left",
+                    "element": { "name": "OK", "role": "button", "automationId": "ok" }
+                }),
+            ),
+            event("MouseUp", 0, serde_json::json!({ "button": "Left" })),
+        ];
+        let mut macro_data = test_macro(events, 1.0, "once", 1);
+        macro_data.playback_settings.click_by_element = true;
+
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.set_element_locator(moved_button);
+        player.play_macro(&macro_data).unwrap();
+
+        assert_eq!(
+            sink.input_calls(),
+            vec![
+                SinkCall::MoveMouse(150, 60, Coordinate::Abs),
+                SinkCall::MoveMouse(300, 200, Coordinate::Abs),
+                SinkCall::Button(Button::Left, Direction::Press),
+                SinkCall::MoveMouse(300, 200, Coordinate::Abs),
+                SinkCall::Button(Button::Left, Direction::Release),
+            ]
+        );
+    }
+
+    #[test]
+    fn presses_into_an_elevated_window_abort_playback() {
+        fn elevated_window() -> Option<String> {
+            Some("'Task Manager'".to_string())
+        }
+
+        let events = vec![
+            event("MouseMove", 0, serde_json::json!({ "x": 10, "y": 10 })),
+            event("KeyDown", 0, serde_json::json!({ "key": "a" })),
+            event("KeyUp", 0, serde_json::json!({ "key": "a" })),
+        ];
+        let macro_data = test_macro(events, 1.0, "once", 1);
+
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.set_elevation_check(elevated_window);
+        let error = player.play_macro(&macro_data).unwrap_err();
+
+        assert!(error.contains("'Task Manager' runs as administrator"));
+        assert_eq!(
+            sink.input_calls(),
+            vec![SinkCall::MoveMouse(10, 10, Coordinate::Abs)]
+        );
+    }
+
+    #[test]
+    fn infinite_runs_ask_before_going_past_the_cap() {
+        use std::sync::atomic::AtomicU32;
+
+        let events = vec![event("KeyDown", 0, serde_json::json!({ "key": "a" }))];
+        let macro_data = test_macro(events, 1.0, "infinite", 1);
+        let asked = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&asked);
+
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.set_iteration_cap(IterationCap {
+            max_iterations: 3,
+            // Go on once, then stop
+            confirm: Some(Arc::new(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst) == 0
+            })),
+        });
+        player.play_macro(&macro_data).unwrap();
+
+        assert_eq!(asked.load(Ordering::SeqCst), 2);
+        let presses = sink
+            .input_calls()
+            .iter()
+            .filter(|c| matches!(c, SinkCall::Key(_, Direction::Press)))
+            .count();
+        assert_eq!(presses, 6);
+    }
+
+    #[test]
+    fn failed_runs_are_retried_with_backoff() {
+        use std::sync::atomic::AtomicU32;
+
+        // Blocked for the first two attempts
+        static CHECKS: AtomicU32 = AtomicU32::new(0);
+        fn flaky_window() -> Option<String> {
+            (CHECKS.fetch_add(1, Ordering::SeqCst) < 2).then(|| "'Installer'".to_string())
+        }
+
+        let events = vec![
+            event("KeyDown", 0, serde_json::json!({ "key": "a" })),
+            event("KeyUp", 0, serde_json::json!({ "key": "a" })),
+        ];
+        let mut macro_data = test_macro(events, 1.0, "once", 1);
+        macro_data.playback_settings.retry_on_failure = true;
+        macro_data.playback_settings.backoff_ms = 100;
+
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.set_elevation_check(flaky_window);
+        player.play_macro(&macro_data).unwrap();
+
+        assert_eq!(sink.total_wait(), Duration::from_millis(100 + 200));
+        assert_eq!(sink.input_calls().len(), 2);
+        assert_eq!(retry_backoff(1000, 30), MAX_RETRY_BACKOFF);
+    }
+
+    #[test]
+    fn wait_for_still_waits_until_region_settles() {
+        use std::sync::atomic::AtomicU32;
+
+        // Changes on the first three samples, then stays the same
+        static SAMPLES: AtomicU32 = AtomicU32::new(0);
+        fn settling_screen(region: ScreenRegion) -> Result<screen::Patch, String> {
+            let sample = SAMPLES.fetch_add(1, Ordering::SeqCst).min(3) as u8;
+            Ok(screen::Patch {
+                width: region.width,
+                height: region.height,
+                rgba: vec![sample * 60; (region.width * region.height * 4) as usize],
+            })
+        }
+
+        let events = vec![event(
+            "WaitForStill",
+            0,
+            serde_json::json!({ "x": 0, "y": 0, "width": 10, "height": 10, "stillMs": 300 }),
+        )];
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.set_screen_grabber(settling_screen);
+        player
+            .play_macro(&test_macro(events, 1.0, "once", 1))
+            .unwrap();
+
+        // Three changing polls, then three still ones
+        assert_eq!(sink.total_wait(), STILL_POLL * 6);
+    }
+
+    #[test]
+    fn applies_coordinate_scale_to_absolute_moves_only() {
+        let events = vec![
+            event("MouseMove", 0, serde_json::json!({ "x": 100, "y": 200 })),
+            event(
+                "MouseMoveRelative",
+                0,
+                serde_json::json!({ "dx": 10, "dy": -4 }),
+            ),
+        ];
+
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.set_coordinate_scale(1.5);
+        player
+            .play_macro(&test_macro(events, 1.0, "once", 1))
+            .unwrap();
+
+        assert_eq!(
+            sink.input_calls(),
+            vec![
+                SinkCall::MoveMouse(150, 300, Coordinate::Abs),
+                SinkCall::MoveMouse(10, -4, Coordinate::Rel),
+            ]
+        );
+    }
+
+    #[test]
+    fn scaled_relative_moves_carry_their_rounding() {
+        let events = (0..3)
+            .map(|i| {
+                event(
+                    "MouseMoveRelative",
+                    i,
+                    serde_json::json!({ "dx": 3, "dy": 0 }),
+                )
+            })
+            .collect();
+
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.set_relative_move_scale(0.5);
+        player
+            .play_macro(&test_macro(events, 1.0, "once", 1))
+            .unwrap();
+
+        // 1.5 pixels each: rounded up, then the half carried into the next
+        assert_eq!(
+            sink.input_calls(),
+            vec![
+                SinkCall::MoveMouse(2, 0, Coordinate::Rel),
+                SinkCall::MoveMouse(1, 0, Coordinate::Rel),
+                SinkCall::MoveMouse(2, 0, Coordinate::Rel),
+            ]
+        );
+    }
+
+    /// Stops playback after a number of injected events
+    struct StoppingSink {
+        control: Arc<PlaybackControl>,
+        remaining: usize,
+        injected: usize,
+    }
+
+    impl StoppingSink {
+        fn injected(&mut self) -> Result<(), String> {
+            self.injected += 1;
+            self.remaining = self.remaining.saturating_sub(1);
+            if self.remaining == 0 {
+                self.control.stop();
+            }
+            Ok(())
+        }
+    }
+
+    impl InputSink for StoppingSink {
+        fn move_mouse(&mut self, _: i32, _: i32, _: Coordinate) -> Result<(), String> {
+            self.injected()
+        }
+
+        fn button(&mut self, _: Button, _: Direction) -> Result<(), String> {
+            self.injected()
+        }
+
+        fn key(&mut self, _: Key, _: Direction) -> Result<(), String> {
+            self.injected()
+        }
+
+        fn scroll(&mut self, _: i32, _: Axis) -> Result<(), String> {
+            self.injected()
+        }
+
+        fn cursor_position(&self) -> Result<(i32, i32), String> {
+            Ok((0, 0))
+        }
+
+        fn wait(&mut self, _: Duration) {}
+    }
+
+    #[test]
+    fn stop_cancels_infinite_playback() {
+        let control = Arc::new(PlaybackControl::default());
+        let sink = StoppingSink {
+            control: Arc::clone(&control),
+            remaining: 5,
+            injected: 0,
+        };
+        let events = vec![event("KeyDown", 0, serde_json::json!({ "key": "x" }))];
+
+        let mut player = Player::with_sink(sink, Arc::clone(&control));
+        player
+            .play_macro(&test_macro(events, 1.0, "infinite", 1))
+            .unwrap();
+
+        assert_eq!(player.sink.injected, 5);
+        assert!(matches!(control.status(), PlaybackStatus::Idle));
+    }
+
+    #[test]
+    fn held_mode_stops_after_the_iteration_the_trigger_is_released_in() {
+        use crate::macrox_core::input::FakeSource;
+
+        let control = Arc::new(PlaybackControl::default());
+        let release = |key| rdev::Event {
+            time: std::time::UNIX_EPOCH,
+            name: None,
+            event_type: rdev::EventType::KeyRelease(key),
+        };
+        let source = FakeSource {
+            events: vec![release(rdev::Key::KeyA), release(rdev::Key::F11)],
+        };
+        watch_trigger_release(&control, Box::new(source), "Shift+F11").unwrap();
+
+        let sink = FakeSink::default();
+        let events = vec![event("KeyDown", 0, serde_json::json!({ "key": "x" }))];
+        let mut player = Player::with_sink(sink.clone(), Arc::clone(&control));
+        player
+            .play_macro(&test_macro(events, 1.0, "held", 1))
+            .unwrap();
+
+        let presses = sink
+            .input_calls()
+            .into_iter()
+            .filter(|c| matches!(c, SinkCall::Key(_, Direction::Press)))
+            .count();
+        assert_eq!(presses, 1);
+    }
+
+    #[test]
+    fn maps_key_names() {
+        assert_eq!(string_to_enigo_key("Enter"), Key::Return);
+        assert_eq!(string_to_enigo_key("Shift"), Key::Shift);
+        assert_eq!(string_to_enigo_key("ShiftLeft"), Key::LShift);
+        assert_eq!(string_to_enigo_key("ControlRight"), Key::RControl);
+        assert_eq!(string_to_enigo_key("F5"), Key::F5);
+        assert_eq!(string_to_enigo_key("Minus"), Key::Unicode('-'));
+    }
+
+    #[test]
+    fn single_characters_are_sent_as_unicode() {
+        let events = vec![event("KeyDown", 0, serde_json::json!({ "key": "q" }))];
+
+        let sink = play(&test_macro(events, 1.0, "once", 1));
+        assert_eq!(
+            sink.input_calls(),
+            vec![SinkCall::Key(Key::Unicode('q'), Direction::Press)]
+        );
+    }
+
+    #[test]
+    fn type_text_fills_in_the_counter_each_repetition() {
+        let events = vec![event(
+            "TypeText",
+            0,
+            serde_json::json!({ "text": "{counter}\n" }),
+        )];
+        let mut macro_data = test_macro(events, 1.0, "count", 3);
+        macro_data.playback_settings.counter = Some(CounterSettings {
+            start: 8,
+            step: 1,
+            pad_digits: 2,
+        });
+
+        let typed: String = play(&macro_data)
+            .input_calls()
+            .into_iter()
+            .map(|c| match c {
+                SinkCall::Key(Key::Unicode(ch), Direction::Click) => ch,
+                SinkCall::Key(Key::Return, Direction::Click) => ' ',
+                other => panic!("unexpected call {:?}", other),
+            })
+            .collect();
+        assert_eq!(typed, "08 09 10 ");
+    }
+
+    #[test]
+    fn key_turbo_presses_the_key_each_interval() {
+        let events = vec![event(
+            "KeyTurbo",
+            0,
+            serde_json::json!({ "key": "Space", "intervalMs": 50, "durationMs": 1000 }),
+        )];
+        let macro_data = test_macro(events, 1.0, "once", 1);
+
+        let sink = play(&macro_data);
+        let presses = sink
+            .input_calls()
+            .iter()
+            .filter(|c| matches!(c, SinkCall::Key(_, Direction::Press)))
+            .count();
+        assert_eq!(presses, 20);
+        assert_eq!(sink.total_wait(), Duration::from_millis(1000));
+        assert_eq!(
+            estimate_duration(&macro_data.events, &macro_data.playback_settings).single_run_ms,
+            1000
+        );
+    }
+
+    #[test]
+    fn run_macro_plays_the_stored_macro_in_place() {
+        let part = Arc::new(test_macro(
+            vec![
+                event("KeyDown", 0, serde_json::json!({ "key": "b" })),
+                event("KeyUp", 40, serde_json::json!({ "key": "b" })),
+            ],
+            1.0,
+            "once",
+            1,
+        ));
+        let run = |id: &str| event("RunMacro", 100, serde_json::json!({ "macroId": id }));
+        let events = vec![
+            event("KeyDown", 0, serde_json::json!({ "key": "a" })),
+            run("test"),
+        ];
+
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        let library = Arc::clone(&part);
+        player.set_macro_resolver(Arc::new(move |id: &str| {
+            (id == library.id).then(|| Arc::clone(&library))
+        }));
+        player
+            .play_macro(&test_macro(events, 1.0, "once", 1))
+            .unwrap();
+
+        assert_eq!(
+            sink.input_calls()[..3],
+            [
+                SinkCall::Key(Key::Unicode('a'), Direction::Press),
+                SinkCall::Key(Key::Unicode('b'), Direction::Press),
+                SinkCall::Key(Key::Unicode('b'), Direction::Release),
+            ]
+        );
+        assert_eq!(sink.total_wait(), Duration::from_millis(140));
+
+        // A macro that runs itself gives up instead of recursing forever
+        let looping = Arc::new(test_macro(vec![run("test")], 1.0, "once", 1));
+        let mut player =
+            Player::with_sink(FakeSink::default(), Arc::new(PlaybackControl::default()));
+        let library = Arc::clone(&looping);
+        player.set_macro_resolver(Arc::new(move |_: &str| Some(Arc::clone(&library))));
+        let error = player.play_macro(&looping).unwrap_err();
+        assert!(error.contains("nested"));
+    }
+
+    #[test]
+    fn run_macro_needs_the_run_macros_own_grant() {
+        let mut child = test_macro(
+            vec![event(
+                "KeyDown",
+                0,
+                serde_json::json!({ "key": "x", "masked": true }),
+            )],
+            1.0,
+            "once",
+            1,
+        );
+        child.id = "child".to_string();
+        let child = Arc::new(child);
+        let mut parent = test_macro(
+            vec![event(
+                "RunMacro",
+                0,
+                serde_json::json!({ "macroId": "child" }),
+            )],
+            1.0,
+            "once",
+            1,
+        );
+        parent.id = "parent".to_string();
+
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        let library = Arc::clone(&child);
+        player.set_macro_resolver(Arc::new(move |id: &str| {
+            (id == library.id).then(|| Arc::clone(&library))
+        }));
+        let grant = |macro_id: &str| PrivilegeGrant {
+            macro_id: macro_id.to_string(),
+            event_types: vec![privilege::MASKED_INPUT.to_string()],
+        };
+
+        // The parent's grant doesn't carry over to the macro it runs
+        player.set_privilege_grants(vec![grant("parent")]);
+        let error = player.play_macro(&parent).unwrap_err();
+        assert!(error.contains("uses privileged events (MaskedInput)"));
+        assert!(sink.input_calls().is_empty());
+
+        player.set_privilege_grants(vec![grant("child")]);
+        assert!(player.play_macro(&parent).is_ok());
+    }
+
+    #[test]
+    fn refused_destructive_combinations_are_not_pressed() {
+        let events = vec![
+            event("KeyDown", 0, serde_json::json!({ "key": "Shift" })),
+            event("KeyDown", 10, serde_json::json!({ "key": "Delete" })),
+            event("KeyUp", 20, serde_json::json!({ "key": "Delete" })),
+            event("KeyUp", 30, serde_json::json!({ "key": "Shift" })),
+        ];
+        let sink = FakeSink::default();
+        let mut player = Player::with_sink(sink.clone(), Arc::new(PlaybackControl::default()));
+        player.set_destructive_confirm(Arc::new(|_| false));
+
+        let result = player.play_macro(&test_macro(events, 1.0, "once", 1));
+        assert!(result.unwrap_err().contains("Shift+Delete"));
+        assert!(!sink
+            .input_calls()
+            .contains(&SinkCall::Key(Key::Delete, Direction::Press)));
+    }
+}
@@ -0,0 +1,149 @@
+// Event plugin module
+//
+// Plugins add new macro event types (an HTTP request step, an MQTT publish,
+// ...) with their own playback and, optionally, recording handlers. The
+// player hands any event type it doesn't know to the registry.
+
+use std::sync::Arc;
+use tracing::info;
+
+use super::types::MacroEvent;
+
+/// A provider of custom macro event types
+pub trait EventPlugin: Send + Sync {
+    /// Unique plugin name, used in logs
+    fn name(&self) -> &str;
+
+    /// Event types handled by this plugin, e.g. `["HttpRequest"]`
+    fn event_types(&self) -> Vec<String>;
+
+    /// Carry out one of this plugin's events during playback
+    fn play(&self, event: &MacroEvent) -> Result<(), String>;
+
+    /// Like `play`, for events that read something (text on screen, command
+    /// output). A value returned here is stored in the run's outputs under the
+    /// event's "outputVar".
+    fn play_with_output(&self, event: &MacroEvent) -> Result<Option<String>, String> {
+        self.play(event).map(|()| None)
+    }
+
+    /// Whether events of this type run commands, touch the clipboard or type
+    /// secrets, so macros using them need permission to play
+    fn is_privileged(&self, _event_type: &str) -> bool {
+        false
+    }
+
+    /// Turn captured input into one of this plugin's events, if it wants to record it
+    fn record(&self, _event: &rdev::Event) -> Option<MacroEvent> {
+        None
+    }
+
+    /// Called when a recording starts, for plugins that capture outside the input hook
+    fn start_recording(&self) {}
+
+    /// Called when a recording stops. Returned events keep their Unix millisecond
+    /// timestamps; one with `"replacesClick": true` stands in for the recorded
+    /// mouse click closest to it.
+    fn stop_recording(&self) -> Vec<MacroEvent> {
+        Vec::new()
+    }
+}
+
+/// The set of plugins available to the player and recorder
+#[derive(Default, Clone)]
+pub struct PluginRegistry {
+    plugins: Vec<Arc<dyn EventPlugin>>,
+}
+
+impl PluginRegistry {
+    /// Add a plugin. Fails if one of its event types is already taken.
+    pub fn register(&mut self, plugin: Arc<dyn EventPlugin>) -> Result<(), String> {
+        for event_type in plugin.event_types() {
+            if let Some(existing) = self.handler_for(&event_type) {
+                return Err(format!(
+                    "Event type '{}' is already handled by plugin '{}'",
+                    event_type,
+                    existing.name()
+                ));
+            }
+        }
+
+        info!("Registered event plugin: {}", plugin.name());
+        self.plugins.push(plugin);
+        Ok(())
+    }
+
+    pub fn handler_for(&self, event_type: &str) -> Option<&Arc<dyn EventPlugin>> {
+        self.plugins
+            .iter()
+            .find(|p| p.event_types().iter().any(|t| t == event_type))
+    }
+
+    /// All event types added by plugins
+    pub fn event_types(&self) -> Vec<String> {
+        self.plugins.iter().flat_map(|p| p.event_types()).collect()
+    }
+
+    /// Events produced by plugins for one captured input event
+    pub fn record(&self, event: &rdev::Event) -> Vec<MacroEvent> {
+        self.plugins
+            .iter()
+            .filter_map(|p| p.record(event))
+            .collect()
+    }
+
+    pub fn start_recording(&self) {
+        for plugin in &self.plugins {
+            plugin.start_recording();
+        }
+    }
+
+    /// Events captured by plugins since `start_recording`
+    pub fn stop_recording(&self) -> Vec<MacroEvent> {
+        self.plugins
+            .iter()
+            .flat_map(|p| p.stop_recording())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    /// Remembers the events it was asked to play, and outputs their "text"
+    #[derive(Default)]
+    pub struct EchoPlugin {
+        pub played: Mutex<Vec<MacroEvent>>,
+    }
+
+    impl EventPlugin for EchoPlugin {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn event_types(&self) -> Vec<String> {
+            vec!["Echo".to_string()]
+        }
+
+        fn play(&self, event: &MacroEvent) -> Result<(), String> {
+            self.played.lock().push(event.clone());
+            Ok(())
+        }
+
+        fn play_with_output(&self, event: &MacroEvent) -> Result<Option<String>, String> {
+            self.play(event)?;
+            Ok(event.data["text"].as_str().map(str::to_string))
+        }
+    }
+
+    #[test]
+    fn rejects_duplicate_event_types() {
+        let mut registry = PluginRegistry::default();
+        registry.register(Arc::new(EchoPlugin::default())).unwrap();
+
+        assert!(registry.register(Arc::new(EchoPlugin::default())).is_err());
+        assert_eq!(registry.event_types(), vec!["Echo".to_string()]);
+    }
+}
@@ -0,0 +1,234 @@
+// Playback pool module
+//
+// Tracks which macros are playing and which input channels each one drives.
+// Macros marked keyboard-only or mouse-only, or aimed at different windows,
+// can run side by side; a macro that would share a channel with a running one
+// is refused instead of interleaving input with it.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use super::player::{self, PlaybackControl};
+use super::types::{InputScope, Macro};
+
+/// Something a playing macro sends input to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputChannel {
+    Keyboard,
+    Mouse,
+    /// Input posted straight to the window with this title
+    Window(String),
+}
+
+impl std::fmt::Display for InputChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputChannel::Keyboard => write!(f, "the keyboard"),
+            InputChannel::Mouse => write!(f, "the mouse"),
+            InputChannel::Window(title) => write!(f, "window '{}'", title),
+        }
+    }
+}
+
+/// The channels `macro_data` drives, counting the macros in `library` it plays
+/// through RunMacro. Fails when those events don't fit its scope, e.g. a
+/// keyboard-only macro with mouse clicks.
+pub fn channels(macro_data: &Macro, library: &[Arc<Macro>]) -> Result<Vec<InputChannel>, String> {
+    let settings = &macro_data.playback_settings;
+    if let Some(title) = &settings.target_window {
+        return Ok(vec![InputChannel::Window(title.clone())]);
+    }
+
+    let (allowed, outside): (_, &[&str]) = match settings.input_scope {
+        InputScope::All => return Ok(vec![InputChannel::Keyboard, InputChannel::Mouse]),
+        // A pinch is played as Ctrl+wheel, so it needs both
+        InputScope::Keyboard => (InputChannel::Keyboard, &["Mouse", "Pinch"]),
+        InputScope::Mouse => (InputChannel::Mouse, &["Key", "TypeText", "Pinch", "Swipe"]),
+    };
+    let uses_outside = |m: &Macro| {
+        m.events
+            .iter()
+            .any(|e| outside.iter().any(|p| e.event_type.starts_with(p)))
+    };
+    if uses_outside(macro_data) {
+        return Err(format!(
+            "'{}' is marked as only using {} but has other input events",
+            macro_data.name, allowed
+        ));
+    }
+    let resolve = |id: &str| library.iter().find(|m| m.id == id).cloned();
+    if let Some(sub) = player::reachable_macros(macro_data, &resolve)
+        .into_iter()
+        .find(|m| uses_outside(m))
+    {
+        return Err(format!(
+            "'{}' is marked as only using {} but runs '{}', which has other input events",
+            macro_data.name, allowed, sub.name
+        ));
+    }
+    Ok(vec![allowed])
+}
+
+/// A macro currently playing in the pool
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningMacro {
+    #[serde(rename = "macroId")]
+    pub macro_id: String,
+    #[serde(rename = "macroName")]
+    pub macro_name: String,
+    pub channels: Vec<InputChannel>,
+}
+
+struct PoolRun {
+    id: u64,
+    info: RunningMacro,
+    control: Arc<PlaybackControl>,
+}
+
+/// Macros playing right now and the channels they hold
+#[derive(Default)]
+pub struct PlaybackPool {
+    runs: Mutex<Vec<PoolRun>>,
+    next_id: AtomicU64,
+}
+
+/// A place in the pool, given up when dropped
+pub struct PoolLease {
+    pool: Arc<PlaybackPool>,
+    id: u64,
+}
+
+impl Drop for PoolLease {
+    fn drop(&mut self) {
+        self.pool.runs.lock().retain(|r| r.id != self.id);
+    }
+}
+
+impl PlaybackPool {
+    /// Reserve the channels `macro_data` drives and return the control to play
+    /// it with. Macros driving both keyboard and mouse get `shared`, the
+    /// control the UI reports on; scoped ones get their own.
+    pub fn claim(
+        self: &Arc<Self>,
+        macro_data: &Macro,
+        library: &[Arc<Macro>],
+        shared: &Arc<PlaybackControl>,
+    ) -> Result<(Arc<PlaybackControl>, PoolLease), String> {
+        let channels = channels(macro_data, library)?;
+        let mut runs = self.runs.lock();
+        for run in runs.iter() {
+            if let Some(channel) = channels.iter().find(|c| run.info.channels.contains(c)) {
+                return Err(format!(
+                    "'{}' can't start while '{}' is using {}",
+                    macro_data.name, run.info.macro_name, channel
+                ));
+            }
+        }
+
+        let control = if channels == [InputChannel::Keyboard, InputChannel::Mouse] {
+            Arc::clone(shared)
+        } else {
+            Arc::new(PlaybackControl::default())
+        };
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        runs.push(PoolRun {
+            id,
+            info: RunningMacro {
+                macro_id: macro_data.id.clone(),
+                macro_name: macro_data.name.clone(),
+                channels,
+            },
+            control: Arc::clone(&control),
+        });
+        let lease = PoolLease {
+            pool: Arc::clone(self),
+            id,
+        };
+        Ok((control, lease))
+    }
+
+    pub fn running(&self) -> Vec<RunningMacro> {
+        self.runs.lock().iter().map(|r| r.info.clone()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.lock().is_empty()
+    }
+
+    /// Ask every running macro to stop before its next event
+    pub fn stop_all(&self) {
+        for run in self.runs.lock().iter() {
+            run.control.stop();
+        }
+    }
+
+    pub fn set_paused_all(&self, paused: bool) {
+        for run in self.runs.lock().iter() {
+            run.control.set_paused(paused);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macrox_core::types::MacroEvent;
+
+    fn scoped_macro(name: &str, scope: InputScope, event_type: &str) -> Macro {
+        let event = MacroEvent {
+            event_type: event_type.to_string(),
+            timestamp: 0,
+            data: serde_json::json!({}),
+        };
+        let mut macro_data = Macro::for_test(name, vec![event]);
+        macro_data.playback_settings.input_scope = scope;
+        macro_data
+    }
+
+    #[test]
+    fn scoped_macros_share_the_pool_until_channels_overlap() {
+        let pool = Arc::new(PlaybackPool::default());
+        let shared = Arc::new(PlaybackControl::default());
+        let typing = scoped_macro("typing", InputScope::Keyboard, "KeyDown");
+        let clicking = scoped_macro("clicking", InputScope::Mouse, "MouseDown");
+        let both = scoped_macro("both", InputScope::All, "KeyDown");
+
+        let (typing_control, typing_lease) = pool.claim(&typing, &[], &shared).unwrap();
+        let (_, clicking_lease) = pool.claim(&clicking, &[], &shared).unwrap();
+        assert!(!Arc::ptr_eq(&typing_control, &shared));
+        assert_eq!(pool.running().len(), 2);
+
+        let error = pool.claim(&both, &[], &shared).unwrap_err();
+        assert!(error.contains("'typing' is using the keyboard"));
+
+        drop(typing_lease);
+        drop(clicking_lease);
+        let (control, _lease) = pool.claim(&both, &[], &shared).unwrap();
+        assert!(Arc::ptr_eq(&control, &shared));
+    }
+
+    #[test]
+    fn scope_has_to_match_the_events() {
+        let mislabelled = scoped_macro("m", InputScope::Keyboard, "MouseDown");
+        assert!(channels(&mislabelled, &[]).is_err());
+    }
+
+    #[test]
+    fn scope_covers_the_macros_run_through_run_macro() {
+        let mut typing = scoped_macro("typing", InputScope::Keyboard, "RunMacro");
+        typing.events[0].data = serde_json::json!({ "macroId": "clicking" });
+        let clicking = scoped_macro("clicking", InputScope::All, "MouseDown");
+
+        let error = channels(&typing, &[Arc::new(clicking)]).unwrap_err();
+        assert!(error.contains("runs 'clicking'"));
+
+        let keys = scoped_macro("clicking", InputScope::All, "KeyDown");
+        assert_eq!(
+            channels(&typing, &[Arc::new(keys)]).unwrap(),
+            vec![InputChannel::Keyboard]
+        );
+    }
+}
@@ -0,0 +1,391 @@
+// Wayland remote desktop portal module (Linux only)
+//
+// Wayland compositors don't let clients inject or capture global input, so
+// enigo and rdev see nothing there. Playback goes through the
+// xdg-desktop-portal RemoteDesktop interface instead: the user approves a
+// remote desktop session once per run and the compositor replays our
+// pointer and keyboard events. A ScreenCast source is attached to the session
+// because absolute pointer motion is only defined relative to a stream.
+//
+// Recording would need the InputCapture portal and an EIS (libei) client; it
+// is detected and reported but not implemented yet.
+
+use enigo::{Axis, Button, Coordinate, Direction, Key};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+use super::input::InputSink;
+use super::types::WaylandCapabilities;
+
+const PORTAL_DESTINATION: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const REMOTE_DESKTOP: &str = "org.freedesktop.portal.RemoteDesktop";
+const SCREEN_CAST: &str = "org.freedesktop.portal.ScreenCast";
+const INPUT_CAPTURE: &str = "org.freedesktop.portal.InputCapture";
+
+/// RemoteDesktop device types
+const DEVICE_KEYBOARD: u32 = 1;
+const DEVICE_POINTER: u32 = 2;
+/// ScreenCast source type for whole monitors
+const SOURCE_MONITOR: u32 = 1;
+
+/// Linux input event codes for mouse buttons
+const BTN_LEFT: i32 = 0x110;
+const BTN_RIGHT: i32 = 0x111;
+const BTN_MIDDLE: i32 = 0x112;
+const BTN_SIDE: i32 = 0x113;
+const BTN_EXTRA: i32 = 0x114;
+
+/// The approved session, shared by every PortalSink so the user is only
+/// asked once per run
+static SESSION: Mutex<Option<Arc<Session>>> = Mutex::new(None);
+
+/// Whether we run under Wayland and which portal interfaces are available
+pub fn capabilities() -> WaylandCapabilities {
+    let is_wayland = std::env::var_os("WAYLAND_DISPLAY").is_some()
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland");
+    let available = |interface: &str| {
+        Connection::session()
+            .and_then(|connection| {
+                Proxy::new(&connection, PORTAL_DESTINATION, PORTAL_PATH, interface)?
+                    .get_property::<u32>("version")
+            })
+            .is_ok()
+    };
+
+    WaylandCapabilities {
+        is_wayland,
+        remote_desktop: available(REMOTE_DESKTOP),
+        input_capture: available(INPUT_CAPTURE),
+    }
+}
+
+/// A started RemoteDesktop session with its monitor streams
+struct Session {
+    connection: Connection,
+    handle: String,
+    /// (PipeWire node id, position, size) of each shared monitor
+    streams: Vec<(u32, (i32, i32), (i32, i32))>,
+}
+
+impl Session {
+    fn start() -> Result<Self, String> {
+        let connection = Connection::session()
+            .map_err(|e| format!("Failed to connect to the session bus: {}", e))?;
+        let remote = Proxy::new(&connection, PORTAL_DESTINATION, PORTAL_PATH, REMOTE_DESKTOP)
+            .map_err(|e| format!("Remote desktop portal unavailable: {}", e))?;
+        let cast = Proxy::new(&connection, PORTAL_DESTINATION, PORTAL_PATH, SCREEN_CAST)
+            .map_err(|e| format!("Screen cast portal unavailable: {}", e))?;
+
+        let results = request(&connection, "CreateSession", |token| {
+            let options = options(token, [("session_handle_token", Value::from(token))]);
+            remote.call_method("CreateSession", &(options,))
+        })?;
+        let handle = results
+            .get("session_handle")
+            .and_then(|v| String::try_from(v.clone()).ok())
+            .ok_or_else(|| "The portal didn't return a session".to_string())?;
+        let session = ObjectPath::try_from(handle.as_str()).map_err(|e| e.to_string())?;
+
+        request(&connection, "SelectDevices", |token| {
+            let types = DEVICE_KEYBOARD | DEVICE_POINTER;
+            let options = options(token, [("types", Value::from(types))]);
+            remote.call_method("SelectDevices", &(&session, options))
+        })?;
+        request(&connection, "SelectSources", |token| {
+            let options = options(
+                token,
+                [
+                    ("types", Value::from(SOURCE_MONITOR)),
+                    ("multiple", Value::from(true)),
+                ],
+            );
+            cast.call_method("SelectSources", &(&session, options))
+        })?;
+        // Shows the compositor's "allow remote control" dialog
+        let results = request(&connection, "Start", |token| {
+            remote.call_method("Start", &(&session, "", options(token, [])))
+        })?;
+
+        let streams = results
+            .get("streams")
+            .and_then(|v| Vec::<(u32, HashMap<String, OwnedValue>)>::try_from(v.clone()).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(node, properties)| {
+                let pair = |name: &str| {
+                    properties
+                        .get(name)
+                        .and_then(|v| <(i32, i32)>::try_from(v.clone()).ok())
+                        .unwrap_or_default()
+                };
+                (node, pair("position"), pair("size"))
+            })
+            .collect();
+
+        Ok(Self {
+            connection,
+            handle,
+            streams,
+        })
+    }
+
+    fn notify<B>(&self, method: &str, body: &B) -> Result<(), String>
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        Proxy::new(
+            &self.connection,
+            PORTAL_DESTINATION,
+            PORTAL_PATH,
+            REMOTE_DESKTOP,
+        )
+        .and_then(|remote| remote.call_method(method, body))
+        .map(|_| ())
+        .map_err(|e| format!("Remote desktop {} failed: {}", method, e))
+    }
+
+    fn session_path(&self) -> ObjectPath<'_> {
+        ObjectPath::try_from(self.handle.as_str()).expect("portal returned a valid session path")
+    }
+
+    /// The stream showing a desktop point and the point relative to it
+    fn stream_at(&self, x: i32, y: i32) -> Option<(u32, f64, f64)> {
+        self.streams
+            .iter()
+            .find(|(_, (sx, sy), (w, h))| x >= *sx && y >= *sy && x < sx + w && y < sy + h)
+            .or(self.streams.first())
+            .map(|(node, (sx, sy), _)| (*node, (x - sx) as f64, (y - sy) as f64))
+    }
+}
+
+/// Portal calls answer through a Request object's Response signal. Its path
+/// is derived from our bus name and a token, so we listen before calling.
+fn request<F>(
+    connection: &Connection,
+    method: &str,
+    call: F,
+) -> Result<HashMap<String, OwnedValue>, String>
+where
+    F: FnOnce(&str) -> zbus::Result<zbus::Message>,
+{
+    static TOKENS: AtomicU32 = AtomicU32::new(0);
+    let token = format!("macrox{}", TOKENS.fetch_add(1, Ordering::SeqCst));
+    let sender = connection
+        .unique_name()
+        .map(|name| name.trim_start_matches(':').replace('.', "_"))
+        .ok_or_else(|| "Not connected to the session bus".to_string())?;
+    let path = format!("{}/request/{}/{}", PORTAL_PATH, sender, token);
+
+    let request = Proxy::new(
+        connection,
+        PORTAL_DESTINATION,
+        path.as_str(),
+        "org.freedesktop.portal.Request",
+    )
+    .map_err(|e| e.to_string())?;
+    let mut responses = request
+        .receive_signal("Response")
+        .map_err(|e| e.to_string())?;
+
+    call(&token).map_err(|e| format!("Portal {} failed: {}", method, e))?;
+
+    let response = responses
+        .next()
+        .ok_or_else(|| format!("Portal {} got no response", method))?;
+    let (code, results): (u32, HashMap<String, OwnedValue>) = response
+        .body()
+        .deserialize()
+        .map_err(|e| format!("Unexpected portal {} response: {}", method, e))?;
+    match code {
+        0 => Ok(results),
+        1 => Err("Remote control was denied".to_string()),
+        _ => Err(format!("Portal {} failed", method)),
+    }
+}
+
+/// Option dict for a portal request, with its handle token
+fn options<'a, const N: usize>(
+    token: &'a str,
+    extra: [(&'a str, Value<'a>); N],
+) -> HashMap<&'a str, Value<'a>> {
+    let mut options: HashMap<_, _> = extra.into_iter().collect();
+    options.insert("handle_token", Value::from(token));
+    options
+}
+
+/// Injects input through the xdg-desktop-portal RemoteDesktop interface
+pub struct PortalSink {
+    session: Arc<Session>,
+    /// The portal doesn't report the cursor, so it is tracked from our own moves
+    cursor: Option<(i32, i32)>,
+}
+
+impl PortalSink {
+    /// Reuse the approved session, or ask the user for one
+    pub fn new() -> Result<Self, String> {
+        let mut shared = SESSION.lock();
+        let session = match shared.as_ref() {
+            Some(session) => Arc::clone(session),
+            None => {
+                let session = Arc::new(Session::start()?);
+                *shared = Some(Arc::clone(&session));
+                session
+            }
+        };
+        Ok(Self {
+            session,
+            cursor: None,
+        })
+    }
+}
+
+impl InputSink for PortalSink {
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> Result<(), String> {
+        let session = self.session.session_path();
+        let no_options = HashMap::<&str, Value>::new();
+        match coordinate {
+            Coordinate::Abs => {
+                let (stream, sx, sy) = self
+                    .session
+                    .stream_at(x, y)
+                    .ok_or_else(|| "No monitor was shared with MacroX".to_string())?;
+                self.session.notify(
+                    "NotifyPointerMotionAbsolute",
+                    &(&session, no_options, stream, sx, sy),
+                )?;
+                self.cursor = Some((x, y));
+            }
+            Coordinate::Rel => {
+                self.session.notify(
+                    "NotifyPointerMotion",
+                    &(&session, no_options, x as f64, y as f64),
+                )?;
+                self.cursor = self.cursor.map(|(cx, cy)| (cx + x, cy + y));
+            }
+        }
+        Ok(())
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+        let code = match button {
+            Button::Left => BTN_LEFT,
+            Button::Right => BTN_RIGHT,
+            Button::Middle => BTN_MIDDLE,
+            Button::Back => BTN_SIDE,
+            Button::Forward => BTN_EXTRA,
+            Button::ScrollUp => return self.scroll(-1, Axis::Vertical),
+            Button::ScrollDown => return self.scroll(1, Axis::Vertical),
+            Button::ScrollLeft => return self.scroll(-1, Axis::Horizontal),
+            Button::ScrollRight => return self.scroll(1, Axis::Horizontal),
+        };
+        let session = self.session.session_path();
+        for state in states(direction) {
+            self.session.notify(
+                "NotifyPointerButton",
+                &(&session, HashMap::<&str, Value>::new(), code, state),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        let keysym = keysym(key).ok_or_else(|| format!("Key {:?} has no keysym", key))?;
+        let session = self.session.session_path();
+        for state in states(direction) {
+            self.session.notify(
+                "NotifyKeyboardKeysym",
+                &(&session, HashMap::<&str, Value>::new(), keysym, state),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn scroll(&mut self, amount: i32, axis: Axis) -> Result<(), String> {
+        let axis = match axis {
+            Axis::Vertical => 0u32,
+            Axis::Horizontal => 1u32,
+        };
+        self.session.notify(
+            "NotifyPointerAxisDiscrete",
+            &(
+                &self.session.session_path(),
+                HashMap::<&str, Value>::new(),
+                axis,
+                amount,
+            ),
+        )
+    }
+
+    fn cursor_position(&self) -> Result<(i32, i32), String> {
+        self.cursor.ok_or_else(|| {
+            "The cursor position isn't known on Wayland until the macro moves the mouse".to_string()
+        })
+    }
+}
+
+/// Pressed (1) and released (0) states to send for a direction
+fn states(direction: Direction) -> Vec<u32> {
+    match direction {
+        Direction::Press => vec![1],
+        Direction::Release => vec![0],
+        Direction::Click => vec![1, 0],
+    }
+}
+
+/// X keysym for a key, which is what the portal takes
+fn keysym(key: Key) -> Option<i32> {
+    let keysym = match key {
+        // Latin-1 keysyms equal their code point, the rest use the Unicode range
+        Key::Unicode(ch) if (ch as u32) < 0x100 => ch as i32,
+        Key::Unicode(ch) => 0x0100_0000 | ch as i32,
+        // Numpad keys are passed as raw keysyms
+        Key::Other(keysym) => keysym as i32,
+        Key::Return => 0xff0d,
+        Key::Tab => 0xff09,
+        Key::Space => 0x0020,
+        Key::Backspace => 0xff08,
+        Key::Escape => 0xff1b,
+        Key::Delete => 0xffff,
+        Key::Home => 0xff50,
+        Key::End => 0xff57,
+        Key::PageUp => 0xff55,
+        Key::PageDown => 0xff56,
+        Key::LeftArrow => 0xff51,
+        Key::UpArrow => 0xff52,
+        Key::RightArrow => 0xff53,
+        Key::DownArrow => 0xff54,
+        Key::Shift | Key::LShift => 0xffe1,
+        Key::RShift => 0xffe2,
+        Key::Control | Key::LControl => 0xffe3,
+        Key::RControl => 0xffe4,
+        Key::CapsLock => 0xffe5,
+        Key::Alt => 0xffe9,
+        Key::Meta => 0xffeb,
+        other => {
+            // F1 is 0xffbe and the rest follow it
+            let name = format!("{:?}", other);
+            let number: i32 = name.strip_prefix('F')?.parse().ok()?;
+            return (1..=35).contains(&number).then_some(0xffbe + number - 1);
+        }
+    };
+    Some(keysym)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_keys_to_keysyms() {
+        assert_eq!(keysym(Key::Unicode('a')), Some(0x61));
+        assert_eq!(keysym(Key::Unicode('€')), Some(0x0100_20ac));
+        assert_eq!(keysym(Key::Return), Some(0xff0d));
+        assert_eq!(keysym(Key::F12), Some(0xffc9));
+        assert_eq!(keysym(Key::Break), None);
+    }
+}
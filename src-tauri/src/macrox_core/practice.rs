@@ -0,0 +1,153 @@
+// Practice playback module
+//
+// In practice mode the player drives a sink that touches nothing: it keeps a
+// virtual cursor and reports every click, key and scroll with the screen
+// position it would have hit. The app shows those in its own window, so a
+// macro or a setting can be tried out without the real desktop reacting.
+
+use enigo::{Axis, Button, Coordinate, Direction, Key};
+use serde::Serialize;
+use std::sync::Arc;
+
+use super::input::InputSink;
+
+/// Something the macro would have done to the desktop
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum PracticeAction {
+    Move {
+        x: i32,
+        y: i32,
+    },
+    Click {
+        x: i32,
+        y: i32,
+        button: String,
+        pressed: bool,
+    },
+    Key {
+        key: String,
+        /// Character typed by the press, if any
+        text: Option<String>,
+        pressed: bool,
+    },
+    Scroll {
+        x: i32,
+        y: i32,
+        amount: i32,
+        horizontal: bool,
+    },
+}
+
+pub type PracticeObserver = Arc<dyn Fn(&PracticeAction) + Send + Sync>;
+
+/// Reports input instead of injecting it
+pub struct PracticeSink {
+    cursor: (i32, i32),
+    observer: PracticeObserver,
+}
+
+impl PracticeSink {
+    /// Start with the virtual cursor at `cursor`
+    pub fn new(cursor: (i32, i32), observer: PracticeObserver) -> Self {
+        Self { cursor, observer }
+    }
+}
+
+/// Press and Click both put the key or button down
+fn is_press(direction: Direction) -> bool {
+    !matches!(direction, Direction::Release)
+}
+
+impl InputSink for PracticeSink {
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> Result<(), String> {
+        self.cursor = match coordinate {
+            Coordinate::Abs => (x, y),
+            Coordinate::Rel => (self.cursor.0 + x, self.cursor.1 + y),
+        };
+        (self.observer)(&PracticeAction::Move {
+            x: self.cursor.0,
+            y: self.cursor.1,
+        });
+        Ok(())
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+        (self.observer)(&PracticeAction::Click {
+            x: self.cursor.0,
+            y: self.cursor.1,
+            button: format!("{:?}", button),
+            pressed: is_press(direction),
+        });
+        Ok(())
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        let (name, text) = match key {
+            Key::Unicode(c) => (c.to_string(), is_press(direction).then(|| c.to_string())),
+            other => (format!("{:?}", other), None),
+        };
+        (self.observer)(&PracticeAction::Key {
+            key: name,
+            text,
+            pressed: is_press(direction),
+        });
+        Ok(())
+    }
+
+    fn scroll(&mut self, amount: i32, axis: Axis) -> Result<(), String> {
+        (self.observer)(&PracticeAction::Scroll {
+            x: self.cursor.0,
+            y: self.cursor.1,
+            amount,
+            horizontal: matches!(axis, Axis::Horizontal),
+        });
+        Ok(())
+    }
+
+    fn cursor_position(&self) -> Result<(i32, i32), String> {
+        Ok(self.cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+
+    #[test]
+    fn clicks_land_where_the_virtual_cursor_is() {
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&actions);
+        let mut sink = PracticeSink::new(
+            (100, 100),
+            Arc::new(move |action: &PracticeAction| seen.lock().push(action.clone())),
+        );
+
+        sink.move_mouse(20, -10, Coordinate::Rel).unwrap();
+        sink.button(Button::Left, Direction::Press).unwrap();
+        sink.key(Key::Unicode('a'), Direction::Click).unwrap();
+        sink.key(Key::Shift, Direction::Release).unwrap();
+
+        let actions = actions.lock();
+        assert_eq!(actions[0], PracticeAction::Move { x: 120, y: 90 });
+        assert_eq!(
+            actions[1],
+            PracticeAction::Click {
+                x: 120,
+                y: 90,
+                button: "Left".to_string(),
+                pressed: true
+            }
+        );
+        assert_eq!(
+            actions[2],
+            PracticeAction::Key {
+                key: "a".to_string(),
+                text: Some("a".to_string()),
+                pressed: true
+            }
+        );
+        assert_eq!(sink.cursor_position().unwrap(), (120, 90));
+    }
+}
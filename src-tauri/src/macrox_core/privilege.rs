@@ -0,0 +1,91 @@
+// Privileged events module
+//
+// Some events do more than replay input: plugin events their plugin marks as
+// privileged (running commands, touching the clipboard), and input with
+// `"masked": true`, which types secrets hidden in the editor. A macro that
+// contains any of them only plays once the user has granted it permission,
+// and the grant covers the privileged event types present at that time.
+//
+// The event names RunCommand, SetClipboard, PasteClipboard and TypeSecret are
+// reserved for future built-in events of this kind; the player has none yet.
+
+use std::collections::BTreeSet;
+
+use super::plugin::PluginRegistry;
+use super::types::MacroEvent;
+
+/// Reported in place of the event type for events with `"masked": true`,
+/// whatever their type
+pub const MASKED_INPUT: &str = "MaskedInput";
+
+/// The name a privileged event is granted under, or None for ordinary events
+pub fn privilege(event: &MacroEvent, plugins: &PluginRegistry) -> Option<String> {
+    if event.data.get("masked").and_then(|m| m.as_bool()) == Some(true) {
+        return Some(MASKED_INPUT.to_string());
+    }
+    plugins
+        .handler_for(&event.event_type)
+        .is_some_and(|p| p.is_privileged(&event.event_type))
+        .then(|| event.event_type.clone())
+}
+
+/// Privileges used by a macro, sorted and without duplicates
+pub fn privileged_event_types(events: &[MacroEvent], plugins: &PluginRegistry) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(|e| privilege(e, plugins))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Privileges used by a macro that `granted` doesn't cover
+pub fn missing_grants(
+    events: &[MacroEvent],
+    plugins: &PluginRegistry,
+    granted: &[String],
+) -> Vec<String> {
+    privileged_event_types(events, plugins)
+        .into_iter()
+        .filter(|t| !granted.contains(t))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event(event_type: &str, data: serde_json::Value) -> MacroEvent {
+        MacroEvent {
+            event_type: event_type.to_string(),
+            timestamp: 0,
+            data,
+        }
+    }
+
+    #[test]
+    fn finds_privileged_types_not_yet_granted() {
+        let plugins = PluginRegistry::default();
+        let events = vec![
+            event("KeyDown", json!({ "key": "a" })),
+            event("TypeText", json!({ "text": "hunter2", "masked": true })),
+            event("KeyDown", json!({ "key": "b", "masked": true })),
+            // Reserved, but not privileged until the player can play it
+            event("SetClipboard", json!({ "text": "hi" })),
+        ];
+
+        assert_eq!(
+            privileged_event_types(&events, &plugins),
+            vec![MASKED_INPUT.to_string()]
+        );
+        assert_eq!(
+            missing_grants(&events, &plugins, &[MASKED_INPUT.to_string()]),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            missing_grants(&events, &plugins, &[]),
+            vec![MASKED_INPUT.to_string()]
+        );
+    }
+}
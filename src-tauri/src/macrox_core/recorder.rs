@@ -0,0 +1,1408 @@
+// Event recording and playback module
+
+use chrono::{DateTime, Local, Utc};
+use parking_lot::Mutex;
+use rdev::{Event, EventType};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use super::accessibility::{self, ElementProbe};
+use super::devices;
+use super::gesture;
+use super::input::{InputSource, RdevSource};
+use super::metadata::{self, RecordingMetadata};
+use super::plugin::PluginRegistry;
+use super::screen::{self, ScreenGrabber, PATCH_SIZE};
+use super::secure_input::{self, SecureInputProbe};
+use super::types::{
+    DropReason, HotkeySettings, MacroEvent, MouseButton, RecordingSession, RecordingSettings,
+    RecordingWarning, RecordingWarningSummary, ScreenRegion,
+};
+use super::window::{self, ActiveWindow};
+
+/// Called when the recorder skips or drops something
+pub type WarningCallback = Arc<dyn Fn(RecordingWarning) + Send + Sync>;
+
+/// Called with every event as it is captured, e.g. to show it live
+pub type EventObserver = Arc<dyn Fn(&MacroEvent) + Send + Sync>;
+
+/// Returns the focused application and its window bounds
+pub type AppProbe = fn() -> Option<ActiveWindow>;
+
+/// Called when key capture becomes blocked (true) or works again (false)
+pub type BlockObserver = Arc<dyn Fn(bool) + Send + Sync>;
+
+/// Minimum time between foreground application lookups
+const APP_PROBE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Longest wait in `stop` for click screenshots and elements still being looked up
+const CLICK_DETAILS_WAIT: Duration = Duration::from_secs(2);
+
+/// How often secure input is checked while recording
+const SECURE_INPUT_POLL: Duration = Duration::from_millis(500);
+
+/// A plugin event this close to a recorded click is taken to be the same click
+const CLICK_MATCH_MS: u64 = 250;
+
+/// A button released further than this many pixels from where it was pressed was dragged
+const DRAG_THRESHOLD: i64 = 5;
+
+/// Events one recording keeps at most, so a forgotten recording can't use up memory
+pub const MAX_RECORDED_EVENTS: usize = 500_000;
+
+/// `BufferNearLimit` is raised once the recording holds this many events
+const NEAR_LIMIT_EVENTS: usize = MAX_RECORDED_EVENTS / 10 * 9;
+
+pub struct Recorder {
+    events: Arc<Mutex<Vec<MacroEvent>>>,
+    is_recording: Arc<Mutex<bool>>,
+    settings: RecordingSettings,
+    on_warning: Option<WarningCallback>,
+    source: Option<Box<dyn InputSource>>,
+    app_probe: Option<AppProbe>,
+    app_counts: Arc<Mutex<HashMap<String, usize>>>,
+    started_at: Option<DateTime<Local>>,
+    prefix: Vec<MacroEvent>,
+    plugins: Arc<PluginRegistry>,
+    screen: Option<ScreenGrabber>,
+    element_probe: Option<ElementProbe>,
+    secure_input_probe: Option<SecureInputProbe>,
+    on_blocked: Option<BlockObserver>,
+    /// Extra click data ("screenshot", "element") by event index, filled in by a worker thread
+    click_details: Arc<Mutex<Vec<(usize, &'static str, serde_json::Value)>>>,
+    pending_clicks: Arc<AtomicUsize>,
+    /// Whether device samples are being collected for this recording
+    sampling_devices: bool,
+    on_event: Option<EventObserver>,
+    /// Every warning raised during the recording, for the session summary
+    warnings: Arc<Mutex<Vec<RecordingWarning>>>,
+    dropped: Arc<AtomicUsize>,
+    stopped_at: Option<DateTime<Local>>,
+}
+
+impl Recorder {
+    pub fn new(settings: RecordingSettings, on_warning: Option<WarningCallback>) -> Self {
+        let mut recorder = Self::with_source(settings, on_warning, Box::new(RdevSource));
+        recorder.set_app_probe(window::active_window);
+        recorder.set_screen_grabber(screen::capture);
+        recorder.set_element_probe(accessibility::element_at);
+        if cfg!(target_os = "macos") {
+            recorder.set_secure_input_probe(secure_input::is_enabled);
+        }
+        recorder
+    }
+
+    /// Record from a custom source. Application tracking is off unless a probe is set.
+    pub fn with_source(
+        settings: RecordingSettings,
+        on_warning: Option<WarningCallback>,
+        source: Box<dyn InputSource>,
+    ) -> Self {
+        Self {
+            events: Arc::new(Mutex::new(Vec::new())),
+            is_recording: Arc::new(Mutex::new(false)),
+            settings,
+            on_warning,
+            source: Some(source),
+            app_probe: None,
+            app_counts: Arc::new(Mutex::new(HashMap::new())),
+            started_at: None,
+            prefix: Vec::new(),
+            plugins: Arc::new(PluginRegistry::default()),
+            screen: None,
+            element_probe: None,
+            secure_input_probe: None,
+            on_blocked: None,
+            click_details: Arc::new(Mutex::new(Vec::new())),
+            pending_clicks: Arc::new(AtomicUsize::new(0)),
+            sampling_devices: false,
+            on_event: None,
+            warnings: Arc::new(Mutex::new(Vec::new())),
+            dropped: Arc::new(AtomicUsize::new(0)),
+            stopped_at: None,
+        }
+    }
+
+    /// Sample the focused application on clicks and key presses
+    pub fn set_app_probe(&mut self, probe: AppProbe) {
+        self.app_probe = Some(probe);
+    }
+
+    /// Capture click screenshots with this grabber when the settings ask for them
+    pub fn set_screen_grabber(&mut self, grabber: ScreenGrabber) {
+        self.screen = Some(grabber);
+    }
+
+    /// Look up clicked UI elements with this probe when the settings ask for them
+    pub fn set_element_probe(&mut self, probe: ElementProbe) {
+        self.element_probe = Some(probe);
+    }
+
+    /// Watch for secure input, which hides keystrokes from the recorder
+    pub fn set_secure_input_probe(&mut self, probe: SecureInputProbe) {
+        self.secure_input_probe = Some(probe);
+    }
+
+    /// Be told when secure input starts or stops hiding keystrokes
+    pub fn set_block_observer(&mut self, observer: BlockObserver) {
+        self.on_blocked = Some(observer);
+    }
+
+    pub fn set_event_observer(&mut self, observer: EventObserver) {
+        self.on_event = Some(observer);
+    }
+
+    /// Let plugins add their own events while recording
+    pub fn set_plugins(&mut self, plugins: Arc<PluginRegistry>) {
+        self.plugins = plugins;
+    }
+
+    pub fn settings(&self) -> &RecordingSettings {
+        &self.settings
+    }
+
+    /// Continue an existing macro: recorded events are appended after these
+    pub fn set_prefix(&mut self, events: Vec<MacroEvent>) {
+        self.prefix = events;
+    }
+
+    pub fn start(&mut self, hotkeys: HotkeySettings) -> Result<(), String> {
+        let source = self
+            .source
+            .take()
+            .ok_or_else(|| "Recorder has already been started".to_string())?;
+
+        *self.is_recording.lock() = true;
+        self.events.lock().clear();
+        self.app_counts.lock().clear();
+        self.warnings.lock().clear();
+        self.dropped.store(0, Ordering::SeqCst);
+        self.started_at = Some(Local::now());
+        self.stopped_at = None;
+
+        let events = Arc::clone(&self.events);
+        let is_recording = Arc::clone(&self.is_recording);
+        let settings = self.settings.clone();
+        let warn = self.warner();
+        let dropped = Arc::clone(&self.dropped);
+        let app_probe = self.app_probe;
+        let app_counts = Arc::clone(&self.app_counts);
+        let hotkeys = hotkeys.clone();
+        let plugins = Arc::clone(&self.plugins);
+        self.click_details.lock().clear();
+        if self.settings.record_devices || self.settings.device_filter.is_some() {
+            self.sampling_devices = devices::begin_capture();
+            if !self.sampling_devices {
+                warn(RecordingWarning::PermissionDegraded {
+                    capability: "devices".to_string(),
+                    message: "Input devices can't be told apart on this platform".to_string(),
+                });
+            }
+        }
+        let grab = self
+            .screen
+            .filter(|_| self.settings.record_click_screenshots);
+        let probe = self
+            .element_probe
+            .filter(|_| self.settings.record_click_elements);
+        let click_requests =
+            (grab.is_some() || probe.is_some()).then(|| self.spawn_click_worker(grab, probe));
+        let pending_clicks = Arc::clone(&self.pending_clicks);
+        let on_event = self.on_event.clone();
+        info!(
+            region = ?self.settings.region,
+            screenshots = grab.is_some(),
+            elements = probe.is_some(),
+            devices = self.sampling_devices,
+            prefix_events = self.prefix.len(),
+            "Recording started"
+        );
+
+        self.plugins.start_recording();
+        if let Some(probe) = self
+            .secure_input_probe
+            .filter(|_| self.settings.record_keyboard)
+        {
+            self.watch_secure_input(probe);
+        }
+
+        // Spawn listener thread
+        thread::spawn(move || {
+            let mut last_position: Option<(i32, i32)> = None;
+            let mut last_app_probe: Option<Instant> = None;
+            let mut cursor: Option<(i32, i32)> = None;
+            let mut markers = 0;
+            let mut mark_held = false;
+            let mut pressed_at: Option<(i32, i32)> = None;
+            let mut last_app: Option<String> = None;
+            let mut dropped_reasons: Vec<DropReason> = Vec::new();
+            let (drop_count, drop_warn) = (Arc::clone(&dropped), Arc::clone(&warn));
+            // Counts the drop and warns the first time each reason comes up
+            let mut drop_event = move |reason: DropReason| {
+                drop_count.fetch_add(1, Ordering::SeqCst);
+                if !dropped_reasons.contains(&reason) {
+                    dropped_reasons.push(reason);
+                    drop_warn(RecordingWarning::EventDropped { reason });
+                }
+            };
+
+            let callback = move |event: Event| {
+                // Unsubscribe once the recording is over
+                if !*is_recording.lock() {
+                    return false;
+                }
+
+                if let EventType::MouseMove { x, y } = event.event_type {
+                    cursor = Some((x as i32, y as i32));
+                }
+
+                // The mark key flags a point in the recording and is never captured itself
+                if let EventType::KeyPress(key) | EventType::KeyRelease(key) = event.event_type {
+                    if rdev_key_to_string(key, false) == hotkeys.mark {
+                        let pressed = matches!(event.event_type, EventType::KeyPress(_));
+                        // Auto-repeat sends more presses while the key is held
+                        if pressed && !mark_held {
+                            markers += 1;
+                            let marker = marker_event(event.time, markers);
+                            if let Some(observe) = on_event.as_ref() {
+                                observe(&marker);
+                            }
+                            events.lock().push(marker);
+                        }
+                        mark_held = pressed;
+                        return true;
+                    }
+                }
+
+                let event_time = event.time;
+                let plugin_events = plugins.record(&event);
+
+                // Mouse input outside the macro's region is not captured
+                if let (Some(region), Some((x, y))) = (settings.region, cursor) {
+                    let is_mouse = matches!(
+                        event.event_type,
+                        EventType::MouseMove { .. }
+                            | EventType::ButtonPress(_)
+                            | EventType::ButtonRelease(_)
+                            | EventType::Wheel { .. }
+                    );
+                    if is_mouse && !region.contains(x, y) {
+                        drop_event(DropReason::OutsideRegion);
+                        return true;
+                    }
+                }
+
+                let mut converted = convert_rdev_event(event, &settings);
+                if settings.record_click_positions {
+                    converted = converted.and_then(|e| stamp_click_position(e, cursor));
+                } else if settings.relative_mouse_movement {
+                    converted = converted.and_then(|e| to_relative_move(e, &mut last_position));
+                }
+                if settings.detect_drags {
+                    converted = converted.map(|e| mark_drag(e, &mut pressed_at, cursor));
+                }
+
+                if let Some(macro_event) = converted {
+                    // Check if event matches a hotkey (simple check for single keys like F-keys)
+                    if let Some(key_str) = macro_event.data.get("key").and_then(|k| k.as_str()) {
+                        let is_hotkey = key_str == hotkeys.record_stop
+                            || key_str == hotkeys.record_start
+                            || key_str == hotkeys.playback_start
+                            || key_str == hotkeys.playback_stop;
+
+                        if is_hotkey {
+                            dropped.fetch_add(1, Ordering::SeqCst);
+                            warn(RecordingWarning::HotkeyIgnored {
+                                key: key_str.to_string(),
+                            });
+                            return true;
+                        }
+                    }
+
+                    // Track which application the user is working in
+                    if let Some(probe) = app_probe {
+                        let is_action =
+                            matches!(macro_event.event_type.as_str(), "MouseDown" | "KeyDown");
+                        let due = last_app_probe.is_none_or(|t| t.elapsed() >= APP_PROBE_INTERVAL);
+                        if is_action && due {
+                            last_app_probe = Some(Instant::now());
+                            if let Some(ActiveWindow { app, bounds }) = probe() {
+                                if settings.record_window_focus && last_app.as_ref() != Some(&app) {
+                                    let focus = focus_event(macro_event.timestamp, &app, bounds);
+                                    if let Some(observe) = on_event.as_ref() {
+                                        observe(&focus);
+                                    }
+                                    events.lock().push(focus);
+                                }
+                                last_app = Some(app.clone());
+                                *app_counts.lock().entry(app).or_insert(0) += 1;
+                            }
+                        }
+                    }
+
+                    if let Some(observe) = on_event.as_ref() {
+                        observe(&macro_event);
+                    }
+                    let is_click = macro_event.event_type == "MouseDown";
+                    let mut events = events.lock();
+                    if events.len() >= MAX_RECORDED_EVENTS {
+                        drop(events);
+                        drop_event(DropReason::BufferFull);
+                        return true;
+                    }
+                    events.push(macro_event);
+                    if events.len() == NEAR_LIMIT_EVENTS {
+                        warn(RecordingWarning::BufferNearLimit {
+                            events: NEAR_LIMIT_EVENTS,
+                            limit: MAX_RECORDED_EVENTS,
+                        });
+                    }
+
+                    // Capturing is slow, so it happens off the input hook thread
+                    if let (true, Some(requests), Some((x, y))) =
+                        (is_click, click_requests.as_ref(), cursor)
+                    {
+                        pending_clicks.fetch_add(1, Ordering::SeqCst);
+                        if requests.send((events.len() - 1, x, y)).is_err() {
+                            pending_clicks.fetch_sub(1, Ordering::SeqCst);
+                        }
+                    }
+                }
+
+                if !plugin_events.is_empty() {
+                    let timestamp = event_time
+                        .duration_since(UNIX_EPOCH)
+                        .map(|d| d.as_millis() as u64)
+                        .unwrap_or(0);
+                    events.lock().extend(plugin_events.into_iter().map(|mut e| {
+                        e.timestamp = timestamp;
+                        e
+                    }));
+                }
+                true
+            };
+
+            if let Err(e) = source.listen(Box::new(callback)) {
+                warn!(error = %e, "Input listen error");
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn stop(&mut self) -> Vec<MacroEvent> {
+        *self.is_recording.lock() = false;
+        self.stopped_at = Some(Local::now());
+
+        // Give the listener thread a moment to finish processing
+        thread::sleep(Duration::from_millis(100));
+
+        let waiting_since = Instant::now();
+        while self.pending_clicks.load(Ordering::SeqCst) > 0
+            && waiting_since.elapsed() < CLICK_DETAILS_WAIT
+        {
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let mut events = self.events.lock().clone();
+        for (index, field, value) in self.click_details.lock().drain(..) {
+            if let Some(data) = events.get_mut(index).and_then(|e| e.data.as_object_mut()) {
+                data.insert(field.to_string(), value);
+            }
+        }
+
+        if self.sampling_devices {
+            self.sampling_devices = false;
+            let samples = devices::end_capture();
+            let captured = events.len();
+            events = devices::attribute(events, &samples, self.settings.device_filter.as_ref());
+            self.dropped
+                .fetch_add(captured - events.len(), Ordering::SeqCst);
+            if events.len() < captured {
+                (self.warner())(RecordingWarning::EventDropped {
+                    reason: DropReason::DeviceFilter,
+                });
+            }
+        }
+        let plugin_events = self.plugins.stop_recording();
+        if !plugin_events.is_empty() {
+            events = merge_plugin_events(events, plugin_events);
+        }
+        if self.settings.record_gestures {
+            events = gesture::detect(events);
+        }
+        if !self.settings.record_key_combos {
+            events = drop_key_combos(events);
+        }
+        // The dead zone is a distance filter too, and the wider of the two applies
+        let min_distance = self
+            .settings
+            .mouse_min_distance
+            .max(self.settings.mouse_dead_zone);
+        if self.settings.mouse_sample_ms > 0 || min_distance > 0 {
+            events = thin_mouse_moves(events, self.settings.mouse_sample_ms, min_distance);
+        }
+        let first_timestamp = events.first().map(|e| e.timestamp);
+
+        // Normalize timestamps to start from 0
+        let recorded: Vec<MacroEvent> = if let Some(first_event) = events.first() {
+            let start_timestamp = first_event.timestamp;
+            events
+                .into_iter()
+                .map(|mut e| {
+                    e.timestamp = e.timestamp.saturating_sub(start_timestamp);
+                    e
+                })
+                .collect()
+        } else {
+            events
+        };
+
+        info!(
+            events = recorded.len(),
+            dropped = self.dropped.load(Ordering::SeqCst),
+            "Recording stopped"
+        );
+
+        if self.prefix.is_empty() {
+            return recorded;
+        }
+
+        // Keep the pause between the end of the prefix and the first new event
+        let gap = match (first_timestamp, self.started_at) {
+            (Some(first), Some(started_at)) => {
+                first.saturating_sub(started_at.timestamp_millis().max(0) as u64)
+            }
+            _ => 0,
+        };
+        let offset = self.prefix.last().map(|e| e.timestamp).unwrap_or(0) + gap;
+
+        let mut combined = self.prefix.clone();
+        combined.extend(recorded.into_iter().map(|mut e| {
+            e.timestamp += offset;
+            e
+        }));
+        combined
+    }
+
+    /// Thread that captures click screenshots and elements; exits once the sender is dropped
+    fn spawn_click_worker(
+        &self,
+        grab: Option<ScreenGrabber>,
+        probe: Option<ElementProbe>,
+    ) -> mpsc::Sender<(usize, i32, i32)> {
+        let (sender, receiver) = mpsc::channel::<(usize, i32, i32)>();
+        let details = Arc::clone(&self.click_details);
+        let pending = Arc::clone(&self.pending_clicks);
+        let warn = self.warner();
+
+        thread::spawn(move || {
+            for (index, x, y) in receiver {
+                if let Some(grab) = grab {
+                    let result = grab(screen::region_around(x, y, PATCH_SIZE))
+                        .and_then(|p| screen::encode_png(&p));
+                    match result {
+                        Ok(encoded) => {
+                            details
+                                .lock()
+                                .push((index, "screenshot", serde_json::json!(encoded)))
+                        }
+                        Err(e) => warn(RecordingWarning::PermissionDegraded {
+                            capability: "screenCapture".to_string(),
+                            message: format!("Click screenshot failed: {}", e),
+                        }),
+                    }
+                }
+                // Clicks on unlabelled surfaces simply keep their coordinates
+                if let Some(element) = probe.and_then(|probe| probe(x, y)) {
+                    if let Ok(value) = serde_json::to_value(&element) {
+                        details.lock().push((index, "element", value));
+                    }
+                }
+                pending.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+
+        sender
+    }
+
+    // Keystrokes just stop arriving under secure input, so poll for it and
+    // tell the user instead of leaving them to find the gap afterwards
+    fn watch_secure_input(&self, probe: SecureInputProbe) {
+        let is_recording = Arc::clone(&self.is_recording);
+        let warn = self.warner();
+        let on_blocked = self.on_blocked.clone();
+        thread::spawn(move || {
+            let mut blocked = false;
+            while *is_recording.lock() {
+                let now_blocked = probe();
+                if now_blocked != blocked {
+                    blocked = now_blocked;
+                    if blocked {
+                        warn(RecordingWarning::PermissionDegraded {
+                            capability: "keyboard".to_string(),
+                            message: "Secure input is on (usually a focused password field), \
+                                      keystrokes aren't being recorded"
+                                .to_string(),
+                        });
+                    } else {
+                        info!("Secure input turned off, recording keystrokes again");
+                    }
+                    if let Some(observe) = on_blocked.as_ref() {
+                        observe(blocked);
+                    }
+                }
+                thread::sleep(SECURE_INPUT_POLL);
+            }
+        });
+    }
+
+    /// Passes warnings to the callback and keeps them for the session summary
+    fn warner(&self) -> WarningCallback {
+        let warnings = Arc::clone(&self.warnings);
+        let on_warning = self.on_warning.clone();
+        Arc::new(move |warning: RecordingWarning| {
+            warn!(kind = warning.kind(), message = %warning, "Recording warning");
+            if let Some(warn) = on_warning.as_ref() {
+                warn(warning.clone());
+            }
+            warnings.lock().push(warning);
+        })
+    }
+
+    /// The warnings raised so far, counted by kind
+    pub fn warning_summary(&self) -> RecordingWarningSummary {
+        let warnings = self.warnings.lock().clone();
+        let mut counts = BTreeMap::new();
+        for warning in &warnings {
+            *counts.entry(warning.kind().to_string()).or_insert(0) += 1;
+        }
+        RecordingWarningSummary {
+            total: warnings.len(),
+            counts,
+            dropped_events: self.dropped.load(Ordering::SeqCst),
+            warnings,
+        }
+    }
+
+    /// Wrap the events returned by `stop` with capture statistics and warnings
+    pub fn session(&self, events: Vec<MacroEvent>) -> RecordingSession {
+        let mut counts = BTreeMap::new();
+        for event in &events {
+            *counts.entry(event.event_type.clone()).or_insert(0) += 1;
+        }
+
+        let started_at = self.started_at.unwrap_or_else(Local::now);
+        let stopped_at = self.stopped_at.unwrap_or_else(Local::now);
+        let metadata = self.summarize(&events);
+
+        RecordingSession {
+            events,
+            started_at: started_at.with_timezone(&Utc),
+            duration_ms: (stopped_at - started_at).num_milliseconds().max(0) as u64,
+            counts,
+            dropped_events: self.dropped.load(Ordering::SeqCst),
+            warnings: self.warnings.lock().clone(),
+            metadata,
+        }
+    }
+
+    /// Suggested name for the events returned by `stop`
+    pub fn summarize(&self, events: &[MacroEvent]) -> RecordingMetadata {
+        let dominant_application = self
+            .app_counts
+            .lock()
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(app, _)| app.clone());
+
+        metadata::summarize(
+            events,
+            self.started_at.unwrap_or_else(Local::now),
+            dominant_application,
+        )
+    }
+}
+
+/// A Marker event, numbered in the order they were dropped
+fn marker_event(time: SystemTime, number: u32) -> MacroEvent {
+    let timestamp = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    MacroEvent {
+        event_type: "Marker".to_string(),
+        timestamp,
+        data: serde_json::json!({ "label": format!("Marker {}", number) }),
+    }
+}
+
+pub(crate) fn convert_rdev_event(event: Event, settings: &RecordingSettings) -> Option<MacroEvent> {
+    // Convert SystemTime to milliseconds
+    let timestamp = event.time.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64;
+
+    match event.event_type {
+        EventType::MouseMove { x, y } => {
+            if settings.record_mouse_movement {
+                Some(MacroEvent {
+                    event_type: "MouseMove".to_string(),
+                    timestamp,
+                    data: serde_json::json!({
+                        "x": x as i32,
+                        "y": y as i32,
+                    }),
+                })
+            } else {
+                None
+            }
+        }
+        EventType::ButtonPress(button) => {
+            if settings.record_mouse_clicks {
+                Some(MacroEvent {
+                    event_type: "MouseDown".to_string(),
+                    timestamp,
+                    data: serde_json::json!({
+                        "button": convert_mouse_button(button),
+                    }),
+                })
+            } else {
+                None
+            }
+        }
+        EventType::ButtonRelease(button) => {
+            if settings.record_mouse_clicks {
+                Some(MacroEvent {
+                    event_type: "MouseUp".to_string(),
+                    timestamp,
+                    data: serde_json::json!({
+                        "button": convert_mouse_button(button),
+                    }),
+                })
+            } else {
+                None
+            }
+        }
+        EventType::KeyPress(key) => {
+            if settings.record_keyboard {
+                Some(MacroEvent {
+                    event_type: "KeyDown".to_string(),
+                    timestamp,
+                    data: serde_json::json!({
+                        "key": rdev_key_to_string(key, settings.distinguish_modifier_sides),
+                    }),
+                })
+            } else {
+                None
+            }
+        }
+        EventType::KeyRelease(key) => {
+            if settings.record_keyboard {
+                Some(MacroEvent {
+                    event_type: "KeyUp".to_string(),
+                    timestamp,
+                    data: serde_json::json!({
+                        "key": rdev_key_to_string(key, settings.distinguish_modifier_sides),
+                    }),
+                })
+            } else {
+                None
+            }
+        }
+        EventType::Wheel { delta_x, delta_y } => {
+            if settings.records_wheel() {
+                Some(MacroEvent {
+                    event_type: "MouseWheel".to_string(),
+                    timestamp,
+                    data: serde_json::json!({
+                        "delta_x": delta_x,
+                        "delta_y": delta_y,
+                    }),
+                })
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Turn an absolute MouseMove into a MouseMoveRelative delta from the previous sample.
+/// Other events pass through untouched.
+fn to_relative_move(
+    event: MacroEvent,
+    last_position: &mut Option<(i32, i32)>,
+) -> Option<MacroEvent> {
+    if event.event_type != "MouseMove" {
+        return Some(event);
+    }
+
+    let x = event.data.get("x").and_then(|v| v.as_i64())? as i32;
+    let y = event.data.get("y").and_then(|v| v.as_i64())? as i32;
+
+    // The first sample only establishes the origin
+    let (last_x, last_y) = last_position.replace((x, y))?;
+    let (dx, dy) = (x - last_x, y - last_y);
+    if dx == 0 && dy == 0 {
+        return None;
+    }
+
+    Some(MacroEvent {
+        event_type: "MouseMoveRelative".to_string(),
+        timestamp: event.timestamp,
+        data: serde_json::json!({
+            "dx": dx,
+            "dy": dy,
+        }),
+    })
+}
+
+/// Drop MouseMove events and add the cursor position to button events
+fn stamp_click_position(mut event: MacroEvent, cursor: Option<(i32, i32)>) -> Option<MacroEvent> {
+    match event.event_type.as_str() {
+        "MouseMove" => None,
+        "MouseDown" | "MouseUp" => {
+            if let (Some((x, y)), Some(data)) = (cursor, event.data.as_object_mut()) {
+                data.insert("x".to_string(), serde_json::json!(x));
+                data.insert("y".to_string(), serde_json::json!(y));
+            }
+            Some(event)
+        }
+        _ => Some(event),
+    }
+}
+
+/// Record where a dragged button was released. MouseUp already moves to its
+/// x/y before releasing, so the drag ends in the right place on playback.
+fn mark_drag(
+    mut event: MacroEvent,
+    pressed_at: &mut Option<(i32, i32)>,
+    cursor: Option<(i32, i32)>,
+) -> MacroEvent {
+    match event.event_type.as_str() {
+        "MouseDown" => *pressed_at = cursor,
+        "MouseUp" => {
+            if let (Some((from_x, from_y)), Some((x, y))) = (pressed_at.take(), cursor) {
+                let (dx, dy) = ((x - from_x) as i64, (y - from_y) as i64);
+                if dx * dx + dy * dy > DRAG_THRESHOLD * DRAG_THRESHOLD {
+                    if let Some(data) = event.data.as_object_mut() {
+                        data.insert("x".to_string(), serde_json::json!(x));
+                        data.insert("y".to_string(), serde_json::json!(y));
+                        data.insert("drag".to_string(), serde_json::json!(true));
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    event
+}
+
+/// Tells playback which application had focus from here on and where its
+/// window was, so coordinates can follow the window if it moved
+fn focus_event(timestamp: u64, app: &str, window: ScreenRegion) -> MacroEvent {
+    MacroEvent {
+        event_type: "WindowFocus".to_string(),
+        timestamp,
+        data: serde_json::json!({ "app": app, "window": window }),
+    }
+}
+
+/// Leave out shortcuts: Control, Alt and Meta and every key pressed while one
+/// of them is held
+fn drop_key_combos(events: Vec<MacroEvent>) -> Vec<MacroEvent> {
+    let mut modifiers: HashSet<String> = HashSet::new();
+    let mut swallowed: HashSet<String> = HashSet::new();
+    events
+        .into_iter()
+        .filter(|e| {
+            let Some(key) = e.data.get("key").and_then(|k| k.as_str()) else {
+                return true;
+            };
+            // AltGr types characters on many layouts
+            let is_modifier = key != "AltGr"
+                && ["Control", "Alt", "Meta"]
+                    .iter()
+                    .any(|m| key.starts_with(m));
+            match e.event_type.as_str() {
+                "KeyDown" if is_modifier => {
+                    modifiers.insert(key.to_string());
+                    false
+                }
+                "KeyUp" if is_modifier => {
+                    modifiers.remove(key);
+                    false
+                }
+                "KeyDown" if !modifiers.is_empty() => {
+                    swallowed.insert(key.to_string());
+                    false
+                }
+                "KeyUp" => !swallowed.remove(key),
+                _ => true,
+            }
+        })
+        .collect()
+}
+
+/// Keep a MouseMove only once `min_ms` have passed and the cursor moved
+/// `min_distance` pixels since the last one kept. The last move before any
+/// other event is always kept so clicks land where they were made.
+fn thin_mouse_moves(events: Vec<MacroEvent>, min_ms: u64, min_distance: u32) -> Vec<MacroEvent> {
+    let min_distance = min_distance as i64;
+    let mut kept: Vec<MacroEvent> = Vec::with_capacity(events.len());
+    let mut last_kept: Option<(u64, i64, i64)> = None;
+    let mut skipped: Option<(MacroEvent, (u64, i64, i64))> = None;
+    for event in events {
+        let position = (event.event_type == "MouseMove")
+            .then(|| {
+                Some((
+                    event.data.get("x")?.as_i64()?,
+                    event.data.get("y")?.as_i64()?,
+                ))
+            })
+            .flatten();
+        let Some((x, y)) = position else {
+            if let Some((skipped_move, sample)) = skipped.take() {
+                last_kept = Some(sample);
+                kept.push(skipped_move);
+            }
+            kept.push(event);
+            continue;
+        };
+
+        let sample = (event.timestamp, x, y);
+        let far_enough = last_kept.is_none_or(|(t, last_x, last_y)| {
+            let (dx, dy) = (x - last_x, y - last_y);
+            event.timestamp.saturating_sub(t) >= min_ms
+                && dx * dx + dy * dy >= min_distance * min_distance
+        });
+        if far_enough {
+            last_kept = Some(sample);
+            skipped = None;
+            kept.push(event);
+        } else {
+            skipped = Some((event, sample));
+        }
+    }
+    if let Some((skipped_move, _)) = skipped {
+        kept.push(skipped_move);
+    }
+    kept
+}
+
+/// Insert events captured by plugins in timestamp order, dropping the mouse
+/// clicks they stand in for
+fn merge_plugin_events(mut events: Vec<MacroEvent>, extra: Vec<MacroEvent>) -> Vec<MacroEvent> {
+    for mut event in extra {
+        let replaces_click = event
+            .data
+            .as_object_mut()
+            .and_then(|data| data.remove("replacesClick"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        if replaces_click {
+            let closest = events
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| e.event_type == "MouseDown")
+                .map(|(i, e)| (i, e.timestamp.abs_diff(event.timestamp)))
+                .filter(|(_, gap)| *gap <= CLICK_MATCH_MS)
+                .min_by_key(|(_, gap)| *gap)
+                .map(|(i, _)| i);
+            if let Some(down) = closest {
+                let button = events[down].data.get("button").cloned();
+                let up = events[down..].iter().position(|e| {
+                    e.event_type == "MouseUp" && e.data.get("button") == button.as_ref()
+                });
+                if let Some(offset) = up {
+                    events.remove(down + offset);
+                }
+                events.remove(down);
+            }
+        }
+
+        let index = events.partition_point(|e| e.timestamp <= event.timestamp);
+        events.insert(index, event);
+    }
+    events
+}
+
+fn convert_mouse_button(button: rdev::Button) -> MouseButton {
+    match button {
+        rdev::Button::Left => MouseButton::Left,
+        rdev::Button::Right => MouseButton::Right,
+        rdev::Button::Middle => MouseButton::Middle,
+        _ => MouseButton::Left, // Default fallback
+    }
+}
+
+pub(crate) fn rdev_key_to_string(key: rdev::Key, distinguish_modifier_sides: bool) -> String {
+    use rdev::Key::*;
+
+    if distinguish_modifier_sides {
+        match key {
+            ShiftLeft | ShiftRight | ControlLeft | ControlRight | AltGr | MetaLeft | MetaRight => {
+                return format!("{:?}", key)
+            }
+            _ => {}
+        }
+    }
+
+    match key {
+        // Alphanumeric
+        KeyA => "a",
+        KeyB => "b",
+        KeyC => "c",
+        KeyD => "d",
+        KeyE => "e",
+        KeyF => "f",
+        KeyG => "g",
+        KeyH => "h",
+        KeyI => "i",
+        KeyJ => "j",
+        KeyK => "k",
+        KeyL => "l",
+        KeyM => "m",
+        KeyN => "n",
+        KeyO => "o",
+        KeyP => "p",
+        KeyQ => "q",
+        KeyR => "r",
+        KeyS => "s",
+        KeyT => "t",
+        KeyU => "u",
+        KeyV => "v",
+        KeyW => "w",
+        KeyX => "x",
+        KeyY => "y",
+        KeyZ => "z",
+        Num1 => "1",
+        Num2 => "2",
+        Num3 => "3",
+        Num4 => "4",
+        Num5 => "5",
+        Num6 => "6",
+        Num7 => "7",
+        Num8 => "8",
+        Num9 => "9",
+        Num0 => "0",
+
+        // Special
+        Return => "Enter",
+        Space => "Space",
+        Backspace => "Backspace",
+        Tab => "Tab",
+        Escape => "Escape",
+        ShiftLeft | ShiftRight => "Shift",
+        ControlLeft | ControlRight => "Control",
+        Alt | AltGr => "Alt",
+        MetaLeft | MetaRight => "Meta",
+
+        // Fallback to debug string for others
+        _ => return format!("{:?}", key),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macrox_core::input::FakeSource;
+    use rdev::Key;
+    use std::time::SystemTime;
+
+    fn settings() -> RecordingSettings {
+        RecordingSettings::default()
+    }
+
+    fn rdev_event(event_type: EventType, millis: u64) -> Event {
+        Event {
+            time: UNIX_EPOCH + Duration::from_millis(millis),
+            name: None,
+            event_type,
+        }
+    }
+
+    fn record(settings: RecordingSettings, events: Vec<Event>) -> Vec<MacroEvent> {
+        let mut recorder = Recorder::with_source(settings, None, Box::new(FakeSource { events }));
+        recorder.start(HotkeySettings::default()).unwrap();
+        recorder.stop()
+    }
+
+    #[test]
+    fn normalizes_timestamps_and_skips_hotkeys() {
+        let events = record(
+            settings(),
+            vec![
+                rdev_event(EventType::KeyPress(Key::KeyA), 5_000),
+                rdev_event(EventType::KeyRelease(Key::KeyA), 5_040),
+                rdev_event(EventType::KeyPress(Key::F10), 5_100),
+            ],
+        );
+
+        let summary: Vec<(&str, u64)> = events
+            .iter()
+            .map(|e| (e.event_type.as_str(), e.timestamp))
+            .collect();
+        assert_eq!(summary, vec![("KeyDown", 0), ("KeyUp", 40)]);
+    }
+
+    #[test]
+    fn session_reports_dropped_hotkeys_and_warnings() {
+        let events = vec![
+            rdev_event(EventType::KeyPress(Key::KeyA), 5_000),
+            rdev_event(EventType::KeyRelease(Key::KeyA), 5_040),
+            rdev_event(EventType::KeyPress(Key::F10), 5_100),
+        ];
+        let mut recorder = Recorder::with_source(settings(), None, Box::new(FakeSource { events }));
+        recorder.start(HotkeySettings::default()).unwrap();
+        let events = recorder.stop();
+        let session = recorder.session(events);
+
+        assert_eq!(session.events.len(), 2);
+        assert_eq!(session.counts.get("KeyDown"), Some(&1));
+        assert_eq!(session.counts.get("KeyUp"), Some(&1));
+        assert_eq!(session.dropped_events, 1);
+        assert_eq!(
+            session.warnings,
+            vec![RecordingWarning::HotkeyIgnored {
+                key: "F10".to_string()
+            }]
+        );
+        assert_eq!(recorder.warning_summary().counts["hotkeyIgnored"], 1);
+    }
+
+    #[test]
+    fn mark_key_drops_a_marker_instead_of_being_recorded() {
+        let mut settings = settings();
+        settings.record_keyboard = false;
+        let events = record(
+            settings,
+            vec![
+                rdev_event(EventType::MouseMove { x: 1.0, y: 1.0 }, 5_000),
+                rdev_event(EventType::KeyPress(Key::F8), 5_200),
+                rdev_event(EventType::KeyPress(Key::F8), 5_230),
+                rdev_event(EventType::KeyRelease(Key::F8), 5_260),
+                rdev_event(EventType::KeyPress(Key::F8), 5_900),
+            ],
+        );
+
+        let summary: Vec<(&str, u64)> = events
+            .iter()
+            .map(|e| (e.event_type.as_str(), e.timestamp))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("MouseMove", 0), ("Marker", 200), ("Marker", 900)]
+        );
+        assert_eq!(events[2].data["label"], "Marker 2");
+    }
+
+    #[test]
+    fn warns_when_secure_input_hides_keystrokes() {
+        let mut recorder = Recorder::with_source(
+            settings(),
+            None,
+            Box::new(FakeSource { events: Vec::new() }),
+        );
+        recorder.set_secure_input_probe(|| true);
+        let blocked = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&blocked);
+        recorder.set_block_observer(Arc::new(move |b| seen.lock().push(b)));
+
+        recorder.start(HotkeySettings::default()).unwrap();
+        let events = recorder.stop();
+        let session = recorder.session(events);
+
+        assert_eq!(*blocked.lock(), vec![true]);
+        assert_eq!(session.warnings.len(), 1);
+        assert!(session.warnings[0]
+            .to_string()
+            .starts_with("Secure input is on"));
+    }
+
+    #[test]
+    fn respects_capture_toggles() {
+        let mut settings = settings();
+        settings.record_mouse_movement = false;
+
+        let events = record(
+            settings,
+            vec![
+                rdev_event(EventType::MouseMove { x: 1.0, y: 2.0 }, 0),
+                rdev_event(EventType::ButtonPress(rdev::Button::Left), 10),
+            ],
+        );
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, "MouseDown");
+    }
+
+    #[test]
+    fn wheel_follows_clicks_in_settings_saved_before_the_split() {
+        let legacy: RecordingSettings = serde_json::from_value(serde_json::json!({
+            "recordMouseMovement": true,
+            "recordMouseClicks": false,
+            "recordKeyboard": true
+        }))
+        .unwrap();
+        assert!(!legacy.records_wheel());
+
+        let mut settings = settings();
+        settings.record_mouse_clicks = false;
+        assert!(settings.records_wheel());
+    }
+
+    #[test]
+    fn key_combos_can_be_left_out() {
+        let mut settings = settings();
+        settings.record_key_combos = false;
+
+        let events = record(
+            settings,
+            vec![
+                rdev_event(EventType::KeyPress(Key::ControlLeft), 0),
+                rdev_event(EventType::KeyPress(Key::KeyC), 10),
+                rdev_event(EventType::KeyRelease(Key::ControlLeft), 20),
+                rdev_event(EventType::KeyRelease(Key::KeyC), 30),
+                rdev_event(EventType::KeyPress(Key::KeyA), 40),
+                rdev_event(EventType::KeyRelease(Key::KeyA), 50),
+            ],
+        );
+
+        let keys: Vec<(&str, &str)> = events
+            .iter()
+            .map(|e| (e.event_type.as_str(), e.data["key"].as_str().unwrap()))
+            .collect();
+        assert_eq!(keys, vec![("KeyDown", "a"), ("KeyUp", "a")]);
+    }
+
+    #[test]
+    fn thinned_mouse_moves_keep_the_position_of_clicks() {
+        let moves: Vec<MacroEvent> = (0..10)
+            .map(|i| MacroEvent {
+                event_type: "MouseMove".to_string(),
+                timestamp: i * 10,
+                data: serde_json::json!({ "x": i, "y": 0 }),
+            })
+            .chain(std::iter::once(MacroEvent {
+                event_type: "MouseDown".to_string(),
+                timestamp: 100,
+                data: serde_json::json!({ "button": "Left" }),
+            }))
+            .collect();
+
+        let xs: Vec<Option<i64>> = thin_mouse_moves(moves, 30, 0)
+            .iter()
+            .map(|e| e.data.get("x").and_then(|x| x.as_i64()))
+            .collect();
+        assert_eq!(xs, vec![Some(0), Some(3), Some(6), Some(9), None]);
+    }
+
+    #[test]
+    fn modifier_sides_are_collapsed_unless_requested() {
+        assert_eq!(rdev_key_to_string(Key::ShiftRight, false), "Shift");
+        assert_eq!(rdev_key_to_string(Key::ShiftRight, true), "ShiftRight");
+        assert_eq!(rdev_key_to_string(Key::ControlLeft, true), "ControlLeft");
+        assert_eq!(rdev_key_to_string(Key::KeyA, true), "a");
+    }
+
+    #[test]
+    fn relative_moves_are_deltas_from_previous_sample() {
+        let mut settings = settings();
+        settings.relative_mouse_movement = true;
+
+        let events = record(
+            settings,
+            vec![
+                rdev_event(EventType::MouseMove { x: 100.0, y: 100.0 }, 0),
+                rdev_event(EventType::MouseMove { x: 110.0, y: 95.0 }, 10),
+                rdev_event(EventType::MouseMove { x: 110.0, y: 95.0 }, 20),
+                rdev_event(EventType::MouseMove { x: 100.0, y: 100.0 }, 30),
+            ],
+        );
+
+        let deltas: Vec<_> = events.iter().map(|e| e.data.clone()).collect();
+        assert_eq!(
+            deltas,
+            vec![
+                serde_json::json!({ "dx": 10, "dy": -5 }),
+                serde_json::json!({ "dx": -10, "dy": 5 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn dead_zone_ignores_jitter_but_not_real_moves() {
+        let mut settings = settings();
+        settings.mouse_dead_zone = 3;
+
+        let events = record(
+            settings,
+            vec![
+                rdev_event(EventType::MouseMove { x: 100.0, y: 100.0 }, 0),
+                rdev_event(EventType::MouseMove { x: 101.0, y: 99.0 }, 10),
+                rdev_event(EventType::MouseMove { x: 99.0, y: 101.0 }, 20),
+                rdev_event(EventType::MouseMove { x: 104.0, y: 100.0 }, 30),
+                // A slow move that ends inside the zone right before a click
+                rdev_event(EventType::MouseMove { x: 105.0, y: 100.0 }, 40),
+                rdev_event(EventType::MouseMove { x: 106.0, y: 101.0 }, 50),
+                rdev_event(EventType::ButtonPress(rdev::Button::Left), 60),
+            ],
+        );
+
+        let xs: Vec<Option<i64>> = events
+            .iter()
+            .map(|e| e.data.get("x").and_then(|x| x.as_i64()))
+            .collect();
+        assert_eq!(xs, vec![Some(100), Some(104), Some(106), None]);
+    }
+
+    #[test]
+    fn click_positions_replace_mouse_movement() {
+        let mut settings = settings();
+        settings.record_click_positions = true;
+
+        let events = record(
+            settings,
+            vec![
+                rdev_event(EventType::MouseMove { x: 40.0, y: 60.0 }, 0),
+                rdev_event(EventType::ButtonPress(rdev::Button::Left), 10),
+                rdev_event(EventType::MouseMove { x: 50.0, y: 60.0 }, 20),
+                rdev_event(EventType::ButtonRelease(rdev::Button::Left), 30),
+            ],
+        );
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].data["x"], 40);
+        assert_eq!(events[1].data["x"], 50);
+        assert_eq!(events[1].data["y"], 60);
+    }
+
+    #[test]
+    fn region_drops_mouse_events_outside_it() {
+        let mut settings = settings();
+        settings.region = Some(ScreenRegion {
+            x: 0,
+            y: 0,
+            width: 100,
+            height: 100,
+        });
+
+        let events = record(
+            settings,
+            vec![
+                rdev_event(EventType::MouseMove { x: 50.0, y: 50.0 }, 0),
+                rdev_event(EventType::ButtonPress(rdev::Button::Left), 10),
+                rdev_event(EventType::MouseMove { x: 250.0, y: 50.0 }, 20),
+                rdev_event(EventType::ButtonPress(rdev::Button::Left), 30),
+                rdev_event(EventType::KeyPress(Key::KeyA), 40),
+            ],
+        );
+
+        let types: Vec<&str> = events.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(types, vec!["MouseMove", "MouseDown", "KeyDown"]);
+    }
+
+    #[test]
+    fn prefix_events_come_first() {
+        let prefix = vec![MacroEvent {
+            event_type: "KeyDown".to_string(),
+            timestamp: 120,
+            data: serde_json::json!({ "key": "q" }),
+        }];
+        let mut recorder = Recorder::with_source(
+            settings(),
+            None,
+            Box::new(FakeSource {
+                events: vec![
+                    rdev_event(EventType::KeyPress(Key::KeyA), 5_000),
+                    rdev_event(EventType::KeyRelease(Key::KeyA), 5_040),
+                ],
+            }),
+        );
+        recorder.set_prefix(prefix);
+        recorder.start(HotkeySettings::default()).unwrap();
+        let events = recorder.stop();
+
+        let timestamps: Vec<u64> = events.iter().map(|e| e.timestamp).collect();
+        assert_eq!(timestamps, vec![120, 120, 160]);
+        assert_eq!(events[1].data["key"], "a");
+    }
+
+    #[test]
+    fn click_screenshots_are_attached_to_mouse_down() {
+        fn grey_screen(region: ScreenRegion) -> Result<screen::Patch, String> {
+            Ok(screen::Patch {
+                width: region.width,
+                height: region.height,
+                rgba: vec![128; (region.width * region.height * 4) as usize],
+            })
+        }
+
+        let mut settings = settings();
+        settings.record_click_screenshots = true;
+        let mut recorder = Recorder::with_source(
+            settings,
+            None,
+            Box::new(FakeSource {
+                events: vec![
+                    rdev_event(EventType::MouseMove { x: 40.0, y: 30.0 }, 0),
+                    rdev_event(EventType::ButtonPress(rdev::Button::Left), 10),
+                    rdev_event(EventType::ButtonRelease(rdev::Button::Left), 20),
+                ],
+            }),
+        );
+        recorder.set_screen_grabber(grey_screen);
+        recorder.start(HotkeySettings::default()).unwrap();
+        let events = recorder.stop();
+
+        let screenshot = events[1].data["screenshot"].as_str().unwrap();
+        assert_eq!(screen::decode_png(screenshot).unwrap().width, PATCH_SIZE);
+        assert!(events[2].data.get("screenshot").is_none());
+    }
+
+    #[test]
+    fn converts_event_time_to_millis() {
+        let event = Event {
+            time: SystemTime::UNIX_EPOCH + Duration::from_millis(1234),
+            name: None,
+            event_type: EventType::KeyPress(Key::Space),
+        };
+        let converted = convert_rdev_event(event, &settings()).unwrap();
+        assert_eq!(converted.timestamp, 1234);
+        assert_eq!(converted.data["key"], "Space");
+    }
+
+    #[test]
+    fn plugin_events_replace_the_click_they_captured() {
+        let event = |event_type: &str, timestamp: u64, data: serde_json::Value| MacroEvent {
+            event_type: event_type.to_string(),
+            timestamp,
+            data,
+        };
+        let recorded = vec![
+            event("MouseDown", 1_000, serde_json::json!({ "button": "Left" })),
+            event("MouseUp", 1_080, serde_json::json!({ "button": "Left" })),
+            event("KeyDown", 1_500, serde_json::json!({ "key": "a" })),
+            event("MouseDown", 3_000, serde_json::json!({ "button": "Left" })),
+            event("MouseUp", 3_060, serde_json::json!({ "button": "Left" })),
+        ];
+        let captured = vec![event(
+            "BrowserClick",
+            3_020,
+            serde_json::json!({ "selector": "#submit", "replacesClick": true }),
+        )];
+
+        let merged = merge_plugin_events(recorded, captured);
+        let types: Vec<&str> = merged.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(
+            types,
+            vec!["MouseDown", "MouseUp", "KeyDown", "BrowserClick"]
+        );
+        assert_eq!(merged[3].data, serde_json::json!({ "selector": "#submit" }));
+    }
+}
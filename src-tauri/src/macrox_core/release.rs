@@ -0,0 +1,142 @@
+// Release check module
+//
+// Picks the newest published release out of the GitHub releases API response
+// and compares its tag with the running version. Fetching the list and
+// installing the update happen in the app layer.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// One entry of the GitHub releases API
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubRelease {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Release notes, as markdown
+    #[serde(default)]
+    pub body: Option<String>,
+    pub html_url: String,
+    #[serde(default)]
+    pub published_at: Option<String>,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+/// Result of `check_for_updates`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UpdateInfo {
+    #[serde(rename = "currentVersion")]
+    pub current_version: String,
+    #[serde(rename = "latestVersion")]
+    pub latest_version: String,
+    #[serde(rename = "updateAvailable")]
+    pub update_available: bool,
+    /// Notes of the latest release, as markdown
+    pub notes: String,
+    /// Release page to open in the browser
+    pub url: String,
+    #[serde(rename = "publishedAt")]
+    pub published_at: Option<String>,
+}
+
+/// Numeric parts of a version like "v1.2.3-beta.1", and whether it's a pre-release
+fn parse_version(version: &str) -> Option<(Vec<u64>, bool)> {
+    let version = version.trim().trim_start_matches('v');
+    let (numbers, pre) = match version.split_once('-') {
+        Some((numbers, _)) => (numbers, true),
+        None => (version, false),
+    };
+    let parts = numbers
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    Some((parts, pre))
+}
+
+/// Order two versions; missing parts count as 0 and a pre-release comes before its release
+pub fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    let (a, a_pre) = parse_version(a)?;
+    let (b, b_pre) = parse_version(b)?;
+    let len = a.len().max(b.len());
+    let part = |v: &[u64], i: usize| v.get(i).copied().unwrap_or(0);
+    let numbers = (0..len)
+        .map(|i| part(&a, i).cmp(&part(&b, i)))
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal);
+    // false < true, so the pre-release is the smaller one
+    Some(numbers.then(b_pre.cmp(&a_pre)))
+}
+
+/// The newest release that isn't a draft, compared against `current`
+pub fn latest_update(
+    releases: &[GithubRelease],
+    current: &str,
+    include_prereleases: bool,
+) -> Result<UpdateInfo, String> {
+    let latest = releases
+        .iter()
+        .filter(|r| !r.draft && (include_prereleases || !r.prerelease))
+        .filter(|r| parse_version(&r.tag_name).is_some())
+        .max_by(|a, b| compare_versions(&a.tag_name, &b.tag_name).unwrap_or(Ordering::Equal))
+        .ok_or("No published releases found")?;
+
+    let update_available = compare_versions(&latest.tag_name, current)
+        .ok_or_else(|| format!("Unrecognized version: {}", current))?
+        .is_gt();
+    Ok(UpdateInfo {
+        current_version: current.to_string(),
+        latest_version: latest.tag_name.trim_start_matches('v').to_string(),
+        update_available,
+        notes: latest.body.clone().unwrap_or_default(),
+        url: latest.html_url.clone(),
+        published_at: latest.published_at.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(tag: &str, prerelease: bool) -> GithubRelease {
+        GithubRelease {
+            tag_name: tag.to_string(),
+            name: None,
+            body: Some(format!("Notes for {}", tag)),
+            html_url: format!(
+                "https://github.com/royce-mathew/MacroX/releases/tag/{}",
+                tag
+            ),
+            published_at: None,
+            prerelease,
+            draft: false,
+        }
+    }
+
+    #[test]
+    fn picks_the_newest_stable_release() {
+        assert_eq!(
+            compare_versions("v0.10.0", "0.9.9"),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(
+            compare_versions("1.0.0-beta.2", "1.0"),
+            Some(Ordering::Less)
+        );
+
+        let releases = vec![
+            release("v0.2.0", false),
+            release("v0.10.0", false),
+            release("v0.11.0-beta.1", true),
+        ];
+        let info = latest_update(&releases, "0.2.0", false).unwrap();
+        assert!(info.update_available);
+        assert_eq!(info.latest_version, "0.10.0");
+        assert_eq!(info.notes, "Notes for v0.10.0");
+
+        let info = latest_update(&releases, "0.11.0-beta.1", true).unwrap();
+        assert!(!info.update_available);
+    }
+}
@@ -0,0 +1,152 @@
+// Retroactive recording module
+//
+// An opt-in capture that keeps only the last few seconds of input in memory,
+// so something just done by hand can still be saved as a macro afterwards.
+// Nothing leaves the buffer unless it is taken for a new recording.
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use super::input::InputSource;
+use super::recorder::convert_rdev_event;
+use super::types::{MacroEvent, RecordingSettings};
+
+/// The last `seconds` of converted input events
+pub struct RetroBuffer {
+    events: Mutex<VecDeque<MacroEvent>>,
+    window_ms: AtomicU64,
+    running: Arc<AtomicBool>,
+}
+
+impl RetroBuffer {
+    pub fn new(seconds: u32) -> Self {
+        Self {
+            events: Mutex::new(VecDeque::new()),
+            window_ms: AtomicU64::new(seconds as u64 * 1000),
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn set_seconds(&self, seconds: u32) {
+        self.window_ms
+            .store(seconds as u64 * 1000, Ordering::SeqCst);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Start keeping input from `source`; does nothing if already running
+    pub fn start(self: &Arc<Self>, source: Box<dyn InputSource>) -> Result<(), String> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let buffer = Arc::clone(self);
+        let settings = RecordingSettings::default();
+        let result = source.listen(Box::new(move |event| {
+            if !buffer.is_running() {
+                return false;
+            }
+            if let Some(event) = convert_rdev_event(event, &settings) {
+                buffer.push(event);
+            }
+            true
+        }));
+        if result.is_err() {
+            self.running.store(false, Ordering::SeqCst);
+        }
+        result
+    }
+
+    /// Stop capturing and forget everything kept so far
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        self.events.lock().clear();
+    }
+
+    fn push(&self, event: MacroEvent) {
+        let oldest = event
+            .timestamp
+            .saturating_sub(self.window_ms.load(Ordering::SeqCst));
+        let mut events = self.events.lock();
+        while events.front().is_some_and(|e| e.timestamp < oldest) {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Empty the buffer into a recording with timestamps from zero. Keys
+    /// still held down are left out: they are the hotkey that asked for it.
+    pub fn take(&self) -> Vec<MacroEvent> {
+        let mut events: Vec<MacroEvent> = self.events.lock().drain(..).collect();
+        let held: Vec<usize> = events
+            .iter()
+            .enumerate()
+            .filter(|(i, e)| {
+                e.event_type == "KeyDown"
+                    && !events[i + 1..].iter().any(|later| {
+                        later.event_type == "KeyUp" && later.data["key"] == e.data["key"]
+                    })
+            })
+            .map(|(i, _)| i)
+            .collect();
+        for i in held.into_iter().rev() {
+            events.remove(i);
+        }
+
+        if let Some(start) = events.first().map(|e| e.timestamp) {
+            for event in &mut events {
+                event.timestamp -= start;
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macrox_core::input::FakeSource;
+    use rdev::{Event, EventType, Key};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn rdev_event(event_type: EventType, millis: u64) -> Event {
+        Event {
+            time: UNIX_EPOCH + Duration::from_millis(millis),
+            name: None,
+            event_type,
+        }
+    }
+
+    #[test]
+    fn keeps_the_last_seconds_without_the_saving_hotkey() {
+        let buffer = Arc::new(RetroBuffer::new(2));
+        let events = vec![
+            rdev_event(EventType::KeyPress(Key::KeyA), 1_000),
+            rdev_event(EventType::KeyRelease(Key::KeyA), 1_050),
+            rdev_event(EventType::KeyPress(Key::KeyB), 4_000),
+            rdev_event(EventType::KeyRelease(Key::KeyB), 4_050),
+            rdev_event(EventType::MouseMove { x: 5.0, y: 5.0 }, 4_500),
+            rdev_event(EventType::KeyPress(Key::ControlLeft), 5_000),
+            rdev_event(EventType::KeyPress(Key::KeyS), 5_010),
+        ];
+        buffer.start(Box::new(FakeSource { events })).unwrap();
+
+        let summary: Vec<(String, u64)> = buffer
+            .take()
+            .into_iter()
+            .map(|e| (e.event_type, e.timestamp))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("KeyDown".to_string(), 0),
+                ("KeyUp".to_string(), 50),
+                ("MouseMove".to_string(), 500),
+            ]
+        );
+        assert!(buffer.take().is_empty());
+    }
+}
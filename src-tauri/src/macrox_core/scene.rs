@@ -0,0 +1,56 @@
+// Scene module
+//
+// A scene is a named group of macros, e.g. "Photoshop" and "Excel". While a
+// scene is active only its macros' hotkeys are registered, so macros in
+// different scenes can share the same keys.
+
+use std::sync::Arc;
+
+use super::types::{Macro, MacroHotkey, Scene};
+
+/// The per-macro hotkeys to register: those of macros that aren't archived
+/// and, when a scene is active, belong to it
+pub fn active_hotkeys(
+    hotkeys: &[MacroHotkey],
+    macros: &[Arc<Macro>],
+    scene: Option<&Scene>,
+) -> Vec<MacroHotkey> {
+    hotkeys
+        .iter()
+        .filter(|h| macros.iter().any(|m| m.id == h.macro_id && !m.archived))
+        .filter(|h| scene.map_or(true, |s| s.macro_ids.contains(&h.macro_id)))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hotkey(macro_id: &str, hotkey: &str) -> MacroHotkey {
+        MacroHotkey {
+            macro_id: macro_id.to_string(),
+            hotkey: hotkey.to_string(),
+        }
+    }
+
+    #[test]
+    fn only_the_active_scene_keeps_its_hotkeys() {
+        let macros = vec![
+            Arc::new(Macro::for_test("crop", Vec::new())),
+            Arc::new(Macro::for_test("sum", Vec::new())),
+        ];
+        let hotkeys = vec![hotkey("crop", "F5"), hotkey("sum", "F5")];
+        let excel = Scene {
+            id: "excel".to_string(),
+            name: "Excel".to_string(),
+            macro_ids: vec!["sum".to_string()],
+        };
+
+        assert_eq!(
+            active_hotkeys(&hotkeys, &macros, Some(&excel)),
+            vec![hotkey("sum", "F5")]
+        );
+        assert_eq!(active_hotkeys(&hotkeys, &macros, None), hotkeys);
+    }
+}
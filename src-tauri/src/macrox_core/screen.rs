@@ -0,0 +1,330 @@
+// Screen capture module
+//
+// Small screenshot patches around clicks: captured while recording, stored
+// as base64 PNG in the click event, and compared against the screen at
+// playback time to check (or find) the click target.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use std::io::Cursor;
+use xcap::image::{self, ImageFormat, RgbaImage};
+use xcap::Monitor;
+
+use super::types::{ImageCalibration, ImageMatch, PixelColor, ScreenRegion};
+
+/// Width and height of the patch captured around a click
+pub const PATCH_SIZE: u32 = 48;
+
+/// Largest screenshot difference still accepted as the same click target,
+/// unless the click sets its own "matchTolerance"
+pub const DEFAULT_MATCH_TOLERANCE: f64 = 0.08;
+
+/// Matches reported by `calibrate`
+const CALIBRATION_MATCHES: usize = 5;
+
+/// Captures a screen region; swapped for a fake in tests
+pub type ScreenGrabber = fn(ScreenRegion) -> Result<Patch, String>;
+
+/// RGBA pixels of a screen region
+#[derive(Debug, Clone, PartialEq)]
+pub struct Patch {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl Patch {
+    fn luma(&self) -> Vec<f32> {
+        self.rgba
+            .chunks_exact(4)
+            .map(|p| 0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32)
+            .collect()
+    }
+}
+
+/// The region of `size` pixels centred on a point
+pub fn region_around(x: i32, y: i32, size: u32) -> ScreenRegion {
+    ScreenRegion {
+        x: x - size as i32 / 2,
+        y: y - size as i32 / 2,
+        width: size,
+        height: size,
+    }
+}
+
+/// Capture a region from the monitor containing its centre. Parts outside
+/// that monitor are cut off.
+pub fn capture(region: ScreenRegion) -> Result<Patch, String> {
+    let center_x = region.x + region.width as i32 / 2;
+    let center_y = region.y + region.height as i32 / 2;
+    let monitor = Monitor::from_point(center_x, center_y).map_err(|e| e.to_string())?;
+    let screen = monitor.capture_image().map_err(|e| e.to_string())?;
+
+    let left = (region.x - monitor.x()).clamp(0, screen.width() as i32) as u32;
+    let top = (region.y - monitor.y()).clamp(0, screen.height() as i32) as u32;
+    let width = region.width.min(screen.width() - left);
+    let height = region.height.min(screen.height() - top);
+    let cropped = image::imageops::crop_imm(&screen, left, top, width, height).to_image();
+
+    Ok(Patch {
+        width,
+        height,
+        rgba: cropped.into_raw(),
+    })
+}
+
+/// Color of the screen pixel at a point
+pub fn pixel_color(grab: ScreenGrabber, x: i32, y: i32) -> Result<PixelColor, String> {
+    let patch = grab(ScreenRegion {
+        x,
+        y,
+        width: 1,
+        height: 1,
+    })?;
+    match patch.rgba[..] {
+        [r, g, b, ..] => Ok(PixelColor {
+            r,
+            g,
+            b,
+            hex: format!("#{:02x}{:02x}{:02x}", r, g, b),
+        }),
+        _ => Err(format!("({}, {}) is not on any screen", x, y)),
+    }
+}
+
+/// Encode a patch as base64 PNG for storing in an event
+pub fn encode_png(patch: &Patch) -> Result<String, String> {
+    let image = RgbaImage::from_raw(patch.width, patch.height, patch.rgba.clone())
+        .ok_or_else(|| "Patch size doesn't match its pixels".to_string())?;
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(BASE64.encode(bytes))
+}
+
+pub fn decode_png(encoded: &str) -> Result<Patch, String> {
+    let bytes = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("Invalid screenshot data: {}", e))?;
+    let image = image::load_from_memory(&bytes)
+        .map_err(|e| format!("Invalid screenshot image: {}", e))?
+        .to_rgba8();
+    Ok(Patch {
+        width: image.width(),
+        height: image.height(),
+        rgba: image.into_raw(),
+    })
+}
+
+/// Mean brightness difference between two patches, from 0.0 (identical) to
+/// 1.0. Patches of different sizes count as completely different.
+pub fn difference(a: &Patch, b: &Patch) -> f64 {
+    if a.width != b.width || a.height != b.height || a.rgba.is_empty() {
+        return 1.0;
+    }
+    let total: f32 = a
+        .luma()
+        .iter()
+        .zip(b.luma())
+        .map(|(x, y)| (x - y).abs())
+        .sum();
+    total as f64 / (a.rgba.len() / 4) as f64 / 255.0
+}
+
+/// Best match for `reference` inside `area`: its top-left offset in `area`
+/// and the difference there
+pub fn locate(reference: &Patch, area: &Patch) -> Option<((u32, u32), f64)> {
+    best_matches(reference, area, 1).into_iter().next()
+}
+
+/// Up to `limit` best matches for `reference` inside `area`, best first.
+/// Matches overlapping a better one by more than half the reference size are skipped.
+pub fn best_matches(reference: &Patch, area: &Patch, limit: usize) -> Vec<((u32, u32), f64)> {
+    if reference.width > area.width || reference.height > area.height || reference.rgba.is_empty() {
+        return Vec::new();
+    }
+    let needle = reference.luma();
+    let haystack = area.luma();
+    let pixels = needle.len() as f64;
+
+    let mut scores = Vec::new();
+    for top in 0..=area.height - reference.height {
+        for left in 0..=area.width - reference.width {
+            let mut total = 0.0f32;
+            for row in 0..reference.height {
+                let needle_row =
+                    &needle[(row * reference.width) as usize..][..reference.width as usize];
+                let start = ((top + row) * area.width + left) as usize;
+                let area_row = &haystack[start..start + reference.width as usize];
+                total += needle_row
+                    .iter()
+                    .zip(area_row)
+                    .map(|(a, b)| (a - b).abs())
+                    .sum::<f32>();
+            }
+            scores.push(((left, top), total as f64 / pixels / 255.0));
+        }
+    }
+    // Stable, so equal scores keep scan order
+    scores.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    let (min_dx, min_dy) = (reference.width / 2, reference.height / 2);
+    let mut matches: Vec<((u32, u32), f64)> = Vec::new();
+    for ((left, top), score) in scores {
+        if matches.len() == limit {
+            break;
+        }
+        let overlaps = matches
+            .iter()
+            .any(|((l, t), _)| l.abs_diff(left) <= min_dx && t.abs_diff(top) <= min_dy);
+        if !overlaps {
+            matches.push(((left, top), score));
+        }
+    }
+    matches
+}
+
+/// Scan `radius` pixels around `position` for `reference` and suggest a match
+/// tolerance: halfway between the best match and the runner-up, so the
+/// target is accepted and look-alikes nearby are not
+pub fn calibrate(
+    grab: ScreenGrabber,
+    reference: &Patch,
+    position: (i32, i32),
+    radius: u32,
+) -> Result<ImageCalibration, String> {
+    let size = reference.width.max(reference.height);
+    let area = region_around(position.0, position.1, size + 2 * radius);
+    let matches: Vec<ImageMatch> = best_matches(reference, &grab(area)?, CALIBRATION_MATCHES)
+        .into_iter()
+        .map(|((left, top), score)| ImageMatch {
+            x: area.x + (left + reference.width / 2) as i32,
+            y: area.y + (top + reference.height / 2) as i32,
+            score,
+        })
+        .collect();
+    let recorded_score = difference(
+        reference,
+        &grab(region_around(position.0, position.1, size))?,
+    );
+
+    let suggested_tolerance = match matches.as_slice() {
+        [] => None,
+        [best] => Some((best.score * 2.0).max(DEFAULT_MATCH_TOLERANCE)),
+        [best, runner_up, ..] => Some((best.score + runner_up.score) / 2.0),
+    };
+
+    Ok(ImageCalibration {
+        matches,
+        recorded_score,
+        suggested_tolerance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A gradient patch with a bright square at (x, y)
+    fn patch_with_square(width: u32, height: u32, x: u32, y: u32) -> Patch {
+        let mut rgba = Vec::new();
+        for row in 0..height {
+            for col in 0..width {
+                let inside = (x..x + 4).contains(&col) && (y..y + 4).contains(&row);
+                let value = if inside {
+                    255
+                } else {
+                    ((row + col) % 64) as u8
+                };
+                rgba.extend([value, value, value, 255]);
+            }
+        }
+        Patch {
+            width,
+            height,
+            rgba,
+        }
+    }
+
+    #[test]
+    fn reads_the_pixel_under_a_point() {
+        fn orange(region: ScreenRegion) -> Result<Patch, String> {
+            Ok(Patch {
+                width: region.width,
+                height: region.height,
+                rgba: [255, 128, 0, 255].repeat((region.width * region.height) as usize),
+            })
+        }
+
+        let color = pixel_color(orange, 10, 20).unwrap();
+        assert_eq!((color.r, color.g, color.b), (255, 128, 0));
+        assert_eq!(color.hex, "#ff8000");
+    }
+
+    #[test]
+    fn png_round_trip_keeps_pixels() {
+        let patch = patch_with_square(8, 6, 2, 1);
+        assert_eq!(decode_png(&encode_png(&patch).unwrap()).unwrap(), patch);
+    }
+
+    #[test]
+    fn locates_a_moved_target() {
+        let area = patch_with_square(32, 32, 20, 9);
+        let reference = Patch {
+            width: 8,
+            height: 8,
+            rgba: (0..8)
+                .flat_map(|row| {
+                    let start = (((7 + row) * 32 + 18) * 4) as usize;
+                    area.rgba[start..start + 32].to_vec()
+                })
+                .collect(),
+        };
+
+        let ((left, top), score) = locate(&reference, &area).unwrap();
+        assert_eq!((left, top), (18, 7));
+        assert_eq!(score, 0.0);
+        assert_eq!(difference(&reference, &reference), 0.0);
+    }
+
+    #[test]
+    fn calibration_ranks_matches_and_suggests_a_tolerance() {
+        // Two squares: the recorded one at (20, 20) and a look-alike at (60, 20)
+        fn two_squares(region: ScreenRegion) -> Result<Patch, String> {
+            let mut rgba = Vec::new();
+            for row in region.y..region.y + region.height as i32 {
+                for col in region.x..region.x + region.width as i32 {
+                    let value = if (18..22).contains(&row) && (18..22).contains(&col) {
+                        255
+                    } else if (18..22).contains(&row) && (58..62).contains(&col) {
+                        220
+                    } else {
+                        0
+                    };
+                    rgba.extend([value, value, value, 255]);
+                }
+            }
+            Ok(Patch {
+                width: region.width,
+                height: region.height,
+                rgba,
+            })
+        }
+
+        let reference = two_squares(region_around(20, 20, 8)).unwrap();
+        let calibration = calibrate(two_squares, &reference, (20, 20), 48).unwrap();
+
+        assert_eq!(calibration.recorded_score, 0.0);
+        assert_eq!(
+            (calibration.matches[0].x, calibration.matches[0].y),
+            (20, 20)
+        );
+        assert_eq!(
+            (calibration.matches[1].x, calibration.matches[1].y),
+            (60, 20)
+        );
+        let tolerance = calibration.suggested_tolerance.unwrap();
+        assert!(tolerance > 0.0 && tolerance < calibration.matches[1].score);
+    }
+}
@@ -0,0 +1,336 @@
+// Script text module
+//
+// Converts macro events to a readable line-based script and back, so power
+// users can tweak a recording in a text editor:
+//
+//   Move to 120,400
+//   Click Left
+//   Type 'hello'
+//   Wait 1.2s
+//   Key Enter
+//
+// Quick taps are merged into Click, Key and Type lines, so the time a key or
+// button was held and short pauses while typing are not kept exactly.
+
+use serde_json::{json, Value};
+
+use super::types::MacroEvent;
+
+/// Gaps up to this long inside a tap or typed run are dropped on export
+const TAP_GAP_MS: u64 = 250;
+/// Spacing between characters of a parsed Type line
+const TYPE_INTERVAL_MS: u64 = 30;
+
+/// Render events as script text, one action per line
+pub fn to_script(events: &[MacroEvent]) -> String {
+    let mut lines = Vec::new();
+    let mut typed = String::new();
+    let mut last_time = events.first().map(|e| e.timestamp).unwrap_or(0);
+    let mut i = 0;
+
+    while i < events.len() {
+        let event = &events[i];
+        let gap = event.timestamp.saturating_sub(last_time);
+
+        // Taps are a down immediately followed by the matching up
+        let tap = events
+            .get(i + 1)
+            .filter(|next| is_release_of(event, next))
+            .filter(|next| next.timestamp.saturating_sub(event.timestamp) <= TAP_GAP_MS);
+
+        if let (Some(ch), Some(_)) = (typed_char(event), tap) {
+            if typed.is_empty() || gap > TAP_GAP_MS {
+                flush_typed(&mut lines, &mut typed);
+                push_wait(&mut lines, gap);
+            }
+            typed.push(ch);
+        } else {
+            flush_typed(&mut lines, &mut typed);
+            push_wait(&mut lines, gap);
+            lines.push(match tap {
+                Some(_) => tap_line(event),
+                None => event_line(event),
+            });
+        }
+
+        let consumed = if tap.is_some() { 2 } else { 1 };
+        last_time = events[i + consumed - 1].timestamp;
+        i += consumed;
+    }
+    flush_typed(&mut lines, &mut typed);
+
+    lines.join("\n")
+}
+
+/// Parse script text back into events, with timestamps starting at zero
+pub fn parse_script(text: &str) -> Result<Vec<MacroEvent>, String> {
+    let mut events = Vec::new();
+    let mut time = 0u64;
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        parse_line(line, &mut time, &mut events)
+            .map_err(|e| format!("Line {}: {}", index + 1, e))?;
+    }
+
+    Ok(events)
+}
+
+fn is_release_of(down: &MacroEvent, up: &MacroEvent) -> bool {
+    let (field, release) = match down.event_type.as_str() {
+        "KeyDown" => ("key", "KeyUp"),
+        "MouseDown" => ("button", "MouseUp"),
+        _ => return false,
+    };
+    up.event_type == release && up.data.get(field) == down.data.get(field)
+}
+
+/// Character typed by a plain key press, if it is one
+fn typed_char(event: &MacroEvent) -> Option<char> {
+    if event.event_type != "KeyDown" {
+        return None;
+    }
+    let key = event.data.get("key")?.as_str()?;
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) if !ch.is_control() => Some(ch),
+        _ => None,
+    }
+}
+
+fn flush_typed(lines: &mut Vec<String>, typed: &mut String) {
+    if typed.is_empty() {
+        return;
+    }
+    let escaped = typed.replace('\\', "\\\\").replace('\'', "\\'");
+    lines.push(format!("Type '{}'", escaped));
+    typed.clear();
+}
+
+fn push_wait(lines: &mut Vec<String>, gap_ms: u64) {
+    if gap_ms > 0 {
+        lines.push(format!("Wait {}s", gap_ms as f64 / 1000.0));
+    }
+}
+
+fn str_field<'a>(event: &'a MacroEvent, field: &str) -> &'a str {
+    event.data.get(field).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+fn position_suffix(event: &MacroEvent) -> String {
+    match (
+        event.data.get("x").and_then(|v| v.as_i64()),
+        event.data.get("y").and_then(|v| v.as_i64()),
+    ) {
+        (Some(x), Some(y)) => format!(" at {},{}", x, y),
+        _ => String::new(),
+    }
+}
+
+fn tap_line(event: &MacroEvent) -> String {
+    match event.event_type.as_str() {
+        "MouseDown" => format!(
+            "Click {}{}",
+            str_field(event, "button"),
+            position_suffix(event)
+        ),
+        _ => format!("Key {}", str_field(event, "key")),
+    }
+}
+
+fn event_line(event: &MacroEvent) -> String {
+    let data = &event.data;
+    let int = |field: &str| data.get(field).and_then(|v| v.as_i64()).unwrap_or(0);
+
+    match event.event_type.as_str() {
+        "MouseMove" => format!("Move to {},{}", int("x"), int("y")),
+        "MouseMoveRelative" => format!("Move by {},{}", int("dx"), int("dy")),
+        "MouseDown" => format!(
+            "Mouse down {}{}",
+            str_field(event, "button"),
+            position_suffix(event)
+        ),
+        "MouseUp" => format!(
+            "Mouse up {}{}",
+            str_field(event, "button"),
+            position_suffix(event)
+        ),
+        "KeyDown" => format!("Key down {}", str_field(event, "key")),
+        "KeyUp" => format!("Key up {}", str_field(event, "key")),
+        "MouseWheel" if int("delta_x") == 0 => format!("Scroll {}", int("delta_y")),
+        "MouseWheel" => format!("Scroll {},{}", int("delta_y"), int("delta_x")),
+        // Anything else, e.g. plugin events, keeps its raw data
+        other => format!("Event {} {}", other, data),
+    }
+}
+
+fn parse_line(line: &str, time: &mut u64, events: &mut Vec<MacroEvent>) -> Result<(), String> {
+    if let Some(rest) = line.strip_prefix("Wait ") {
+        let seconds: f64 = rest
+            .trim()
+            .trim_end_matches('s')
+            .parse()
+            .map_err(|_| format!("Invalid wait '{}'", rest))?;
+        if seconds < 0.0 {
+            return Err("Wait can't be negative".to_string());
+        }
+        *time += (seconds * 1000.0).round() as u64;
+    } else if let Some(rest) = line.strip_prefix("Move to ") {
+        let (x, y) = parse_pair(rest)?;
+        push(events, *time, "MouseMove", json!({ "x": x, "y": y }));
+    } else if let Some(rest) = line.strip_prefix("Move by ") {
+        let (dx, dy) = parse_pair(rest)?;
+        push(
+            events,
+            *time,
+            "MouseMoveRelative",
+            json!({ "dx": dx, "dy": dy }),
+        );
+    } else if let Some(rest) = line.strip_prefix("Click ") {
+        let data = button_data(rest)?;
+        push(events, *time, "MouseDown", data.clone());
+        push(events, *time, "MouseUp", data);
+    } else if let Some(rest) = line.strip_prefix("Mouse down ") {
+        push(events, *time, "MouseDown", button_data(rest)?);
+    } else if let Some(rest) = line.strip_prefix("Mouse up ") {
+        push(events, *time, "MouseUp", button_data(rest)?);
+    } else if let Some(rest) = line.strip_prefix("Key down ") {
+        push(events, *time, "KeyDown", json!({ "key": rest.trim() }));
+    } else if let Some(rest) = line.strip_prefix("Key up ") {
+        push(events, *time, "KeyUp", json!({ "key": rest.trim() }));
+    } else if let Some(rest) = line.strip_prefix("Key ") {
+        push(events, *time, "KeyDown", json!({ "key": rest.trim() }));
+        push(events, *time, "KeyUp", json!({ "key": rest.trim() }));
+    } else if let Some(rest) = line.strip_prefix("Scroll ") {
+        let (delta_y, delta_x) = match rest.split_once(',') {
+            Some(_) => parse_pair(rest)?,
+            None => (parse_int(rest)?, 0),
+        };
+        push(
+            events,
+            *time,
+            "MouseWheel",
+            json!({ "delta_x": delta_x, "delta_y": delta_y }),
+        );
+    } else if let Some(rest) = line.strip_prefix("Type ") {
+        for (i, ch) in parse_quoted(rest)?.chars().enumerate() {
+            if i > 0 {
+                *time += TYPE_INTERVAL_MS;
+            }
+            push(events, *time, "KeyDown", json!({ "key": ch.to_string() }));
+            push(events, *time, "KeyUp", json!({ "key": ch.to_string() }));
+        }
+    } else if let Some(rest) = line.strip_prefix("Event ") {
+        let (event_type, data) = rest.split_once(' ').unwrap_or((rest, "{}"));
+        let data: Value =
+            serde_json::from_str(data).map_err(|e| format!("Invalid event data: {}", e))?;
+        push(events, *time, event_type, data);
+    } else {
+        return Err(format!("Unknown action '{}'", line));
+    }
+
+    Ok(())
+}
+
+fn push(events: &mut Vec<MacroEvent>, timestamp: u64, event_type: &str, data: Value) {
+    events.push(MacroEvent {
+        event_type: event_type.to_string(),
+        timestamp,
+        data,
+    });
+}
+
+fn parse_int(text: &str) -> Result<i64, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("Expected a number, got '{}'", text.trim()))
+}
+
+fn parse_pair(text: &str) -> Result<(i64, i64), String> {
+    let (a, b) = text
+        .split_once(',')
+        .ok_or_else(|| format!("Expected 'x,y', got '{}'", text.trim()))?;
+    Ok((parse_int(a)?, parse_int(b)?))
+}
+
+/// "Left" or "Left at 120,400"
+fn button_data(text: &str) -> Result<Value, String> {
+    match text.split_once(" at ") {
+        Some((button, position)) => {
+            let (x, y) = parse_pair(position)?;
+            Ok(json!({ "button": button.trim(), "x": x, "y": y }))
+        }
+        None => Ok(json!({ "button": text.trim() })),
+    }
+}
+
+fn parse_quoted(text: &str) -> Result<String, String> {
+    let inner = text
+        .trim()
+        .strip_prefix('\'')
+        .and_then(|t| t.strip_suffix('\''))
+        .ok_or_else(|| "Type text must be in single quotes".to_string())?;
+
+    let mut result = String::new();
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            result.push(chars.next().ok_or("Trailing backslash in Type text")?);
+        } else {
+            result.push(ch);
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, timestamp: u64, data: Value) -> MacroEvent {
+        MacroEvent {
+            event_type: event_type.to_string(),
+            timestamp,
+            data,
+        }
+    }
+
+    #[test]
+    fn exports_readable_lines() {
+        let events = vec![
+            event("MouseMove", 1000, json!({ "x": 120, "y": 400 })),
+            event("MouseDown", 1100, json!({ "button": "Left" })),
+            event("MouseUp", 1180, json!({ "button": "Left" })),
+            event("KeyDown", 2380, json!({ "key": "h" })),
+            event("KeyUp", 2400, json!({ "key": "h" })),
+            event("KeyDown", 2500, json!({ "key": "'" })),
+            event("KeyUp", 2520, json!({ "key": "'" })),
+            event("KeyDown", 2600, json!({ "key": "Enter" })),
+            event("KeyUp", 2650, json!({ "key": "Enter" })),
+        ];
+
+        assert_eq!(
+            to_script(&events),
+            "Move to 120,400\nWait 0.1s\nClick Left\nWait 1.2s\nType 'h\\''\nWait 0.08s\nKey Enter"
+        );
+    }
+
+    #[test]
+    fn parsed_script_exports_unchanged() {
+        let script = "Move to 10,20\nWait 0.5s\nClick Right at 10,20\nType 'ab'\nWait 1s\nKey down Shift\nScroll -3\nKey up Shift\nEvent Echo {\"n\":1}";
+        let events = parse_script(script).unwrap();
+
+        assert_eq!(events[2].timestamp, 500);
+        assert_eq!(events[2].data["x"], 10);
+        assert_eq!(to_script(&events), script);
+    }
+
+    #[test]
+    fn reports_the_failing_line() {
+        let err = parse_script("Move to 1,2\n# note\nJump 3").unwrap_err();
+        assert!(err.starts_with("Line 3:"), "{}", err);
+    }
+}
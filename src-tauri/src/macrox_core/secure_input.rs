@@ -0,0 +1,33 @@
+// Secure input module
+//
+// While a password field (or an app like a terminal with "Secure Keyboard
+// Entry") has secure input turned on, macOS stops delivering key events to
+// every event tap, so the recorder silently misses keystrokes. Other
+// platforms have no such mode.
+
+/// Whether key capture is currently blocked; swapped for a fake in tests
+pub type SecureInputProbe = fn() -> bool;
+
+/// Whether some application has secure keyboard input turned on
+pub fn is_enabled() -> bool {
+    platform::is_enabled()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        fn IsSecureEventInputEnabled() -> u8;
+    }
+
+    pub fn is_enabled() -> bool {
+        unsafe { IsSecureEventInputEnabled() != 0 }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+mod platform {
+    pub fn is_enabled() -> bool {
+        false
+    }
+}
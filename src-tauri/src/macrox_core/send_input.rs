@@ -0,0 +1,211 @@
+// SendInput backend module (Windows only)
+//
+// Injects input with SendInput directly, sending keys as hardware scan codes
+// instead of virtual keys. Games using DirectInput or raw input often only
+// react to scan codes, which enigo doesn't send for most keys.
+
+use enigo::{Axis, Button, Coordinate, Direction, Key};
+use windows::Win32::Foundation::POINT;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    MapVirtualKeyW, SendInput, VkKeyScanW, INPUT, INPUT_0, INPUT_KEYBOARD, INPUT_MOUSE, KEYBDINPUT,
+    KEYBD_EVENT_FLAGS, KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE,
+    KEYEVENTF_UNICODE, MAPVK_VK_TO_VSC_EX, MOUSEEVENTF_ABSOLUTE, MOUSEEVENTF_HWHEEL,
+    MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP, MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP,
+    MOUSEEVENTF_MOVE, MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP, MOUSEEVENTF_VIRTUALDESK,
+    MOUSEEVENTF_WHEEL, MOUSEEVENTF_XDOWN, MOUSEEVENTF_XUP, MOUSEINPUT, MOUSE_EVENT_FLAGS,
+    VIRTUAL_KEY,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetCursorPos, GetSystemMetrics, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
+    SM_YVIRTUALSCREEN,
+};
+
+use super::input::InputSink;
+
+const WHEEL_DELTA: i32 = 120;
+const XBUTTON1: u32 = 0x0001;
+const XBUTTON2: u32 = 0x0002;
+
+/// Injects input with SendInput and scan codes
+pub struct SendInputSink;
+
+impl SendInputSink {
+    fn send(inputs: &[INPUT]) -> Result<(), String> {
+        let sent = unsafe { SendInput(inputs, std::mem::size_of::<INPUT>() as i32) };
+        if sent as usize != inputs.len() {
+            // Blocked by UIPI when the foreground window runs elevated
+            return Err(format!(
+                "SendInput injected {} of {} events",
+                sent,
+                inputs.len()
+            ));
+        }
+        Ok(())
+    }
+
+    fn mouse(dx: i32, dy: i32, data: u32, flags: MOUSE_EVENT_FLAGS) -> INPUT {
+        INPUT {
+            r#type: INPUT_MOUSE,
+            Anonymous: INPUT_0 {
+                mi: MOUSEINPUT {
+                    dx,
+                    dy,
+                    mouseData: data,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+
+    fn keyboard(vk: u16, scan: u16, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: VIRTUAL_KEY(vk),
+                    wScan: scan,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+}
+
+impl InputSink for SendInputSink {
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> Result<(), String> {
+        let input = match coordinate {
+            Coordinate::Rel => Self::mouse(x, y, 0, MOUSEEVENTF_MOVE),
+            Coordinate::Abs => {
+                // Absolute moves use 0..65535 across the whole virtual desktop
+                let (left, top, width, height) = unsafe {
+                    (
+                        GetSystemMetrics(SM_XVIRTUALSCREEN),
+                        GetSystemMetrics(SM_YVIRTUALSCREEN),
+                        GetSystemMetrics(SM_CXVIRTUALSCREEN).max(2),
+                        GetSystemMetrics(SM_CYVIRTUALSCREEN).max(2),
+                    )
+                };
+                let nx = ((x - left) as i64 * 65535 / (width - 1) as i64) as i32;
+                let ny = ((y - top) as i64 * 65535 / (height - 1) as i64) as i32;
+                Self::mouse(
+                    nx,
+                    ny,
+                    0,
+                    MOUSEEVENTF_MOVE | MOUSEEVENTF_ABSOLUTE | MOUSEEVENTF_VIRTUALDESK,
+                )
+            }
+        };
+        Self::send(&[input])
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+        let (down, up, data) = match button {
+            Button::Right => (MOUSEEVENTF_RIGHTDOWN, MOUSEEVENTF_RIGHTUP, 0),
+            Button::Middle => (MOUSEEVENTF_MIDDLEDOWN, MOUSEEVENTF_MIDDLEUP, 0),
+            Button::Back => (MOUSEEVENTF_XDOWN, MOUSEEVENTF_XUP, XBUTTON1),
+            Button::Forward => (MOUSEEVENTF_XDOWN, MOUSEEVENTF_XUP, XBUTTON2),
+            Button::ScrollUp => return self.scroll(-1, Axis::Vertical),
+            Button::ScrollDown => return self.scroll(1, Axis::Vertical),
+            Button::ScrollLeft => return self.scroll(-1, Axis::Horizontal),
+            Button::ScrollRight => return self.scroll(1, Axis::Horizontal),
+            Button::Left => (MOUSEEVENTF_LEFTDOWN, MOUSEEVENTF_LEFTUP, 0),
+        };
+
+        let mut inputs = Vec::new();
+        if matches!(direction, Direction::Press | Direction::Click) {
+            inputs.push(Self::mouse(0, 0, data, down));
+        }
+        if matches!(direction, Direction::Release | Direction::Click) {
+            inputs.push(Self::mouse(0, 0, data, up));
+        }
+        Self::send(&inputs)
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        let press = matches!(direction, Direction::Press | Direction::Click);
+        let release = matches!(direction, Direction::Release | Direction::Click);
+
+        // Characters on an unshifted key of the current layout go out as that
+        // key's scan code, so games see WASD; anything else is typed as Unicode
+        let vk = match key {
+            Key::Unicode(ch) => match plain_key_for(ch) {
+                Some(vk) => vk,
+                None => return Self::send(&unicode_inputs(ch, press, release)),
+            },
+            other => VIRTUAL_KEY::try_from(other)
+                .map_err(|e| format!("Key {:?} has no virtual key: {}", other, e))?,
+        };
+        let scan = unsafe { MapVirtualKeyW(vk.0 as u32, MAPVK_VK_TO_VSC_EX) };
+        if scan == 0 {
+            return Err(format!("Key {:?} has no scan code", key));
+        }
+        // Extended keys (arrows, right Ctrl, ...) report an 0xE0 prefix
+        let mut flags = KEYEVENTF_SCANCODE;
+        if scan & 0xFF00 == 0xE000 {
+            flags |= KEYEVENTF_EXTENDEDKEY;
+        }
+
+        let mut inputs = Vec::new();
+        if press {
+            inputs.push(Self::keyboard(0, scan as u16 & 0xFF, flags));
+        }
+        if release {
+            inputs.push(Self::keyboard(
+                0,
+                scan as u16 & 0xFF,
+                flags | KEYEVENTF_KEYUP,
+            ));
+        }
+        Self::send(&inputs)
+    }
+
+    fn scroll(&mut self, amount: i32, axis: Axis) -> Result<(), String> {
+        // enigo scrolls down for positive amounts, the wheel scrolls up
+        let input = match axis {
+            Axis::Vertical => Self::mouse(0, 0, (-amount * WHEEL_DELTA) as u32, MOUSEEVENTF_WHEEL),
+            Axis::Horizontal => {
+                Self::mouse(0, 0, (amount * WHEEL_DELTA) as u32, MOUSEEVENTF_HWHEEL)
+            }
+        };
+        Self::send(&[input])
+    }
+
+    fn cursor_position(&self) -> Result<(i32, i32), String> {
+        let mut point = POINT::default();
+        unsafe { GetCursorPos(&mut point) }.map_err(|e| format!("GetCursorPos failed: {}", e))?;
+        Ok((point.x, point.y))
+    }
+}
+
+/// The virtual key that types `ch` without modifiers on the current layout
+fn plain_key_for(ch: char) -> Option<VIRTUAL_KEY> {
+    let mut units = [0u16; 2];
+    let [unit] = ch.encode_utf16(&mut units) else {
+        return None;
+    };
+    let result = unsafe { VkKeyScanW(*unit) };
+    let (vk, shift_state) = (result as u16 & 0xFF, (result as u16 >> 8) & 0xFF);
+    (result != -1 && shift_state == 0).then_some(VIRTUAL_KEY(vk))
+}
+
+fn unicode_inputs(ch: char, press: bool, release: bool) -> Vec<INPUT> {
+    let mut units = [0u16; 2];
+    let mut inputs = Vec::new();
+    for unit in ch.encode_utf16(&mut units) {
+        if press {
+            inputs.push(SendInputSink::keyboard(0, *unit, KEYEVENTF_UNICODE));
+        }
+        if release {
+            inputs.push(SendInputSink::keyboard(
+                0,
+                *unit,
+                KEYEVENTF_UNICODE | KEYEVENTF_KEYUP,
+            ));
+        }
+    }
+    inputs
+}
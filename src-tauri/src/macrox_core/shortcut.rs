@@ -0,0 +1,180 @@
+// Shortcut syntax module
+//
+// Hotkeys are typed by hand in the settings and in imported files, so
+// bindings like "ctrl+shift+f7", "CmdOrCtrl+R" or "Win+F2" are accepted and
+// rewritten in the global shortcut plugin's syntax: modifiers first in a fixed
+// order, then one key, joined with "+".
+
+/// Modifiers in the order they are written, with the names accepted for each
+const MODIFIERS: [(&str, &[&str]); 5] = [
+    (
+        "CommandOrControl",
+        &[
+            "commandorcontrol",
+            "commandorctrl",
+            "cmdorctrl",
+            "cmdorcontrol",
+        ],
+    ),
+    ("Control", &["control", "ctrl", "ctl"]),
+    ("Alt", &["alt", "option", "opt"]),
+    ("Shift", &["shift"]),
+    // The Windows key and the Mac command key are the same modifier
+    (
+        "Super",
+        &["super", "meta", "win", "windows", "cmd", "command"],
+    ),
+];
+
+/// Keys that aren't a letter, digit or function key, with their aliases
+const NAMED_KEYS: [(&str, &[&str]); 28] = [
+    ("Space", &["space", "spacebar"]),
+    ("Enter", &["enter", "return"]),
+    ("Escape", &["escape", "esc"]),
+    ("Tab", &["tab"]),
+    ("Backspace", &["backspace"]),
+    ("Delete", &["delete", "del"]),
+    ("Insert", &["insert", "ins"]),
+    ("Home", &["home"]),
+    ("End", &["end"]),
+    ("PageUp", &["pageup", "pgup"]),
+    ("PageDown", &["pagedown", "pgdn"]),
+    ("ArrowUp", &["arrowup", "up"]),
+    ("ArrowDown", &["arrowdown", "down"]),
+    ("ArrowLeft", &["arrowleft", "left"]),
+    ("ArrowRight", &["arrowright", "right"]),
+    ("PrintScreen", &["printscreen", "prtsc"]),
+    ("Pause", &["pause"]),
+    ("Minus", &["minus", "-"]),
+    ("Equal", &["equal", "="]),
+    ("Comma", &["comma", ","]),
+    ("Period", &["period", "."]),
+    ("Slash", &["slash", "/"]),
+    ("Backslash", &["backslash", "\\"]),
+    ("Semicolon", &["semicolon", ";"]),
+    ("Quote", &["quote", "'"]),
+    ("Backquote", &["backquote", "`"]),
+    ("BracketLeft", &["bracketleft", "["]),
+    ("BracketRight", &["bracketright", "]"]),
+];
+
+/// `binding` in canonical form, e.g. "ctrl + shift + f7" gives "Control+Shift+F7"
+pub fn normalize(binding: &str) -> Result<String, String> {
+    if binding.trim().is_empty() {
+        return Err("The hotkey is empty".to_string());
+    }
+
+    let mut modifiers = [false; MODIFIERS.len()];
+    let mut key: Option<String> = None;
+    for part in binding.split('+').map(str::trim) {
+        if part.is_empty() {
+            return Err(format!("'{}' has an empty part between '+' signs", binding));
+        }
+        let lower = part.to_ascii_lowercase();
+        if let Some(i) = MODIFIERS
+            .iter()
+            .position(|(_, names)| names.contains(&lower.as_str()))
+        {
+            if modifiers[i] {
+                return Err(format!(
+                    "'{}' has {} more than once",
+                    binding, MODIFIERS[i].0
+                ));
+            }
+            modifiers[i] = true;
+            continue;
+        }
+
+        let Some(name) = key_name(&lower) else {
+            return Err(format!(
+                "'{}' in '{}' is not a known key or modifier",
+                part, binding
+            ));
+        };
+        if let Some(first) = &key {
+            return Err(format!(
+                "'{}' has two keys, '{}' and '{}'; use one key with modifiers",
+                binding, first, name
+            ));
+        }
+        key = Some(name);
+    }
+
+    let Some(key) = key else {
+        return Err(format!("'{}' has only modifiers, add a key", binding));
+    };
+    if modifiers[0] && (modifiers[1] || modifiers[4]) {
+        return Err(format!(
+            "'{}' has CommandOrControl together with the modifier it stands for",
+            binding
+        ));
+    }
+
+    let mut parts: Vec<String> = MODIFIERS
+        .iter()
+        .zip(modifiers)
+        .filter(|(_, used)| *used)
+        .map(|((name, _), _)| name.to_string())
+        .collect();
+    parts.push(key);
+    Ok(parts.join("+"))
+}
+
+/// Canonical name of a lowercased key
+fn key_name(lower: &str) -> Option<String> {
+    let mut chars = lower.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphanumeric() {
+            return Some(c.to_ascii_uppercase().to_string());
+        }
+    }
+    if let Some(number) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        if (1..=24).contains(&number) {
+            return Some(format!("F{}", number));
+        }
+    }
+    // "KeyA" and "Digit1" are what the browser reports for a pressed key
+    if let Some(rest) = lower
+        .strip_prefix("key")
+        .or_else(|| lower.strip_prefix("digit"))
+    {
+        if rest.len() == 1 {
+            return key_name(rest);
+        }
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(_, names)| names.contains(&lower))
+        .map(|(name, _)| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_loose_spelling_and_aliases() {
+        assert_eq!(normalize("ctrl+shift+f7").unwrap(), "Control+Shift+F7");
+        assert_eq!(normalize("Shift + Ctrl + F7").unwrap(), "Control+Shift+F7");
+        assert_eq!(normalize("CmdOrCtrl+R").unwrap(), "CommandOrControl+R");
+        assert_eq!(normalize("Win+F2").unwrap(), "Super+F2");
+        assert_eq!(normalize("option+esc").unwrap(), "Alt+Escape");
+        assert_eq!(normalize("f10").unwrap(), "F10");
+        assert_eq!(normalize("Alt+KeyA").unwrap(), "Alt+A");
+    }
+
+    #[test]
+    fn explains_what_is_wrong() {
+        assert!(normalize("").unwrap_err().contains("empty"));
+        assert!(normalize("Ctrl+Shift")
+            .unwrap_err()
+            .contains("only modifiers"));
+        assert!(normalize("Ctrl+A+B").unwrap_err().contains("two keys"));
+        assert!(normalize("Ctrl+Ctrl+A")
+            .unwrap_err()
+            .contains("more than once"));
+        assert!(normalize("Hyper+A").unwrap_err().contains("'Hyper'"));
+        assert!(normalize("F25").is_err());
+        assert!(normalize("Ctrl++A").is_err());
+    }
+}
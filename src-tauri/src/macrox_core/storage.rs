@@ -0,0 +1,281 @@
+// Macro library persistence module
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::error;
+
+use super::types::Macro;
+
+/// When each macro last ran, by id. Kept beside the library rather than in
+/// each macro so a run doesn't copy the macro or mark its events dirty.
+pub type LastRuns = HashMap<String, DateTime<Utc>>;
+
+/// Quiet period before queued changes are written to disk
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(250);
+
+enum PersistMessage {
+    Save(Vec<Arc<Macro>>),
+    Flush(mpsc::Sender<()>),
+}
+
+/// Writes the macro library on a background thread.
+///
+/// Saves are debounced so bursts of edits produce a single write, and every
+/// write goes to a temp file that is renamed over the target so a crash never
+/// leaves a truncated file behind. Only macros that changed since the last
+/// write are serialized again. Run times are written from `last_runs` as they
+/// are at the time of the write.
+#[derive(Clone)]
+pub struct MacroPersister {
+    tx: mpsc::Sender<PersistMessage>,
+    last_written: Arc<Mutex<Option<Vec<u8>>>>,
+}
+
+impl MacroPersister {
+    pub fn spawn(path: PathBuf, last_runs: Arc<Mutex<LastRuns>>) -> Self {
+        let (tx, rx) = mpsc::channel::<PersistMessage>();
+        let last_written = Arc::new(Mutex::new(None));
+
+        let written = Arc::clone(&last_written);
+        thread::spawn(move || {
+            let mut pending: Option<Vec<Arc<Macro>>> = None;
+            let mut encoded = EncodedLibrary::default();
+
+            loop {
+                let message = if pending.is_some() {
+                    match rx.recv_timeout(SAVE_DEBOUNCE) {
+                        Ok(message) => Some(message),
+                        Err(mpsc::RecvTimeoutError::Timeout) => None,
+                        Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                    }
+                } else {
+                    match rx.recv() {
+                        Ok(message) => Some(message),
+                        Err(_) => break,
+                    }
+                };
+
+                match message {
+                    Some(PersistMessage::Save(macros)) => pending = Some(macros),
+                    Some(PersistMessage::Flush(done)) => {
+                        if let Some(macros) = pending.take() {
+                            write_macros(&path, &macros, &last_runs, &mut encoded, &written);
+                        }
+                        let _ = done.send(());
+                    }
+                    None => {
+                        if let Some(macros) = pending.take() {
+                            write_macros(&path, &macros, &last_runs, &mut encoded, &written);
+                        }
+                    }
+                }
+            }
+
+            // Channel closed, don't drop the last change
+            if let Some(macros) = pending.take() {
+                write_macros(&path, &macros, &last_runs, &mut encoded, &written);
+            }
+        });
+
+        Self { tx, last_written }
+    }
+
+    /// Queue a snapshot of the library to be written. The snapshot shares the
+    /// macros, so taking it doesn't copy any events.
+    pub fn save(&self, macros: Vec<Arc<Macro>>) {
+        let _ = self.tx.send(PersistMessage::Save(macros));
+    }
+
+    /// Block until any queued snapshot has been written
+    pub fn flush(&self) {
+        let (done_tx, done_rx) = mpsc::channel();
+        if self.tx.send(PersistMessage::Flush(done_tx)).is_ok() {
+            let _ = done_rx.recv_timeout(Duration::from_secs(5));
+        }
+    }
+
+    /// Whether the given file contents are exactly what we last wrote
+    pub fn is_own_write(&self, contents: &[u8]) -> bool {
+        self.last_written.lock().as_deref() == Some(contents)
+    }
+}
+
+/// Each macro's JSON as last written, so unchanged macros aren't serialized again.
+///
+/// Edits go through `Arc::make_mut`, which copies a macro while this cache
+/// still shares it, so a macro is dirty when the library holds a different
+/// `Arc` than the one its JSON came from.
+#[derive(Default)]
+struct EncodedLibrary {
+    entries: HashMap<String, EncodedMacro>,
+}
+
+struct EncodedMacro {
+    source: Arc<Macro>,
+    json: Vec<u8>,
+}
+
+impl EncodedLibrary {
+    fn is_dirty(&self, macro_data: &Arc<Macro>) -> bool {
+        self.entries
+            .get(&macro_data.id)
+            .map_or(true, |entry| !Arc::ptr_eq(&entry.source, macro_data))
+    }
+
+    /// The library file contents, serializing only the dirty macros
+    fn encode(
+        &mut self,
+        macros: &[Arc<Macro>],
+        last_runs: &LastRuns,
+    ) -> serde_json::Result<Vec<u8>> {
+        for macro_data in macros {
+            if self.is_dirty(macro_data) {
+                let json = serde_json::to_vec_pretty(&**macro_data)?;
+                self.entries.insert(
+                    macro_data.id.clone(),
+                    EncodedMacro {
+                        source: Arc::clone(macro_data),
+                        json,
+                    },
+                );
+            }
+        }
+        // Forget deleted macros so the cache doesn't keep their events alive
+        let ids: HashSet<&str> = macros.iter().map(|m| m.id.as_str()).collect();
+        self.entries.retain(|id, _| ids.contains(id.as_str()));
+
+        // Same layout the store plugin uses, so load_macros_from_store keeps working
+        let mut bytes = b"{\n  \"macros\": [".to_vec();
+        for (i, macro_data) in macros.iter().enumerate() {
+            let separator: &[u8] = if i == 0 { b"\n" } else { b",\n" };
+            bytes.extend_from_slice(separator);
+            bytes.extend_from_slice(&self.entries[&macro_data.id].json);
+        }
+        bytes.extend_from_slice(b"\n  ],\n  \"lastRuns\": ");
+
+        // Small, so written in full; sorted so unchanged times give the same bytes
+        let runs: BTreeMap<&str, &DateTime<Utc>> = last_runs
+            .iter()
+            .filter(|(id, _)| ids.contains(id.as_str()))
+            .map(|(id, at)| (id.as_str(), at))
+            .collect();
+        bytes.extend_from_slice(&serde_json::to_vec(&runs)?);
+        bytes.extend_from_slice(b"\n}");
+        Ok(bytes)
+    }
+}
+
+fn write_macros(
+    path: &Path,
+    macros: &[Arc<Macro>],
+    last_runs: &Mutex<LastRuns>,
+    encoded: &mut EncodedLibrary,
+    last_written: &Mutex<Option<Vec<u8>>>,
+) {
+    let bytes = match encoded.encode(macros, &last_runs.lock()) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!("Failed to serialize macros: {}", e);
+            return;
+        }
+    };
+
+    // Record before writing so the file watcher can recognise the change
+    *last_written.lock() = Some(bytes.clone());
+
+    if let Err(e) = write_atomic(path, &bytes) {
+        error!("Failed to write {}: {}", path.display(), e);
+    }
+}
+
+/// Run times from a library file's contents. Files written before they were
+/// kept apart still have a `lastRunAt` on each macro.
+pub fn read_last_runs(library: &serde_json::Value) -> LastRuns {
+    let mut last_runs: LastRuns = library
+        .get("lastRuns")
+        .and_then(|runs| serde_json::from_value(runs.clone()).ok())
+        .unwrap_or_default();
+    let macros = library.get("macros").and_then(|m| m.as_array());
+    for stored in macros.into_iter().flatten() {
+        let id = stored.get("id").and_then(|id| id.as_str());
+        let at = stored
+            .get("lastRunAt")
+            .and_then(|at| serde_json::from_value(at.clone()).ok());
+        if let (Some(id), Some(at)) = (id, at) {
+            last_runs.entry(id.to_string()).or_insert(at);
+        }
+    }
+    last_runs
+}
+
+/// Write to a sibling temp file, fsync it, then rename it over `path`
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macrox_core::types::MacroEvent;
+
+    fn stored_macro(id: &str) -> Macro {
+        let key_down = MacroEvent {
+            event_type: "KeyDown".to_string(),
+            timestamp: 0,
+            data: serde_json::json!({ "key": "a" }),
+        };
+        Macro::for_test(id, vec![key_down])
+    }
+
+    #[test]
+    fn only_edited_macros_are_serialized_again() {
+        let mut macros = vec![Arc::new(stored_macro("a")), Arc::new(stored_macro("b"))];
+        let mut encoded = EncodedLibrary::default();
+        let mut last_runs = LastRuns::new();
+        encoded.encode(&macros, &last_runs).unwrap();
+
+        Arc::make_mut(&mut macros[1]).name = "renamed".to_string();
+        assert!(!encoded.is_dirty(&macros[0]));
+        assert!(encoded.is_dirty(&macros[1]));
+
+        // A run only changes the run times
+        let ran_at = Utc::now();
+        last_runs.insert("a".to_string(), ran_at);
+        last_runs.insert("b".to_string(), ran_at);
+        encoded.encode(&macros, &last_runs).unwrap();
+        assert!(!encoded.is_dirty(&macros[1]));
+
+        macros.remove(0);
+        let bytes = encoded.encode(&macros, &last_runs).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let written: Vec<Macro> = serde_json::from_value(value["macros"].clone()).unwrap();
+        assert_eq!(written.len(), 1);
+        assert_eq!(written[0].name, "renamed");
+        assert_eq!(encoded.entries.len(), 1);
+        assert_eq!(
+            read_last_runs(&value),
+            LastRuns::from([("b".to_string(), ran_at)])
+        );
+    }
+}
@@ -0,0 +1,197 @@
+// Storyboard module
+//
+// Draws a macro's mouse path and clicks, one frame per click, over the
+// reference screenshots recorded around each click. The frames document what
+// an automated procedure does and can be saved as images or an animated GIF.
+
+use xcap::image::codecs::gif::{GifEncoder, Repeat};
+use xcap::image::imageops::{self, FilterType};
+use xcap::image::{Delay, Frame, Rgba, RgbaImage};
+
+use super::screen::{self, Patch, PATCH_SIZE};
+use super::types::MacroEvent;
+
+/// Frames are scaled down so neither side is longer than this
+const MAX_SIDE: u32 = 1280;
+/// Space around the outermost points, enough for a click screenshot
+const MARGIN: i64 = PATCH_SIZE as i64;
+/// How long each frame shows in the GIF
+const FRAME_DELAY_MS: u32 = 800;
+
+const BACKGROUND: Rgba<u8> = Rgba([240, 240, 240, 255]);
+const PATH_COLOR: Rgba<u8> = Rgba([70, 130, 220, 255]);
+const PAST_CLICK_COLOR: Rgba<u8> = Rgba([120, 120, 120, 255]);
+const CLICK_COLOR: Rgba<u8> = Rgba([220, 40, 40, 255]);
+
+struct Click {
+    at: (i64, i64),
+    screenshot: Option<Patch>,
+    /// Points of the mouse path up to this click
+    path_len: usize,
+}
+
+/// One frame per click with a known position, showing the path up to it
+pub fn render(events: &[MacroEvent]) -> Result<Vec<RgbaImage>, String> {
+    let mut path: Vec<(i64, i64)> = Vec::new();
+    let mut clicks = Vec::new();
+    for event in events {
+        if let Some(point) = position(event) {
+            path.push(point);
+        }
+        if event.event_type != "MouseDown" {
+            continue;
+        }
+        let Some(&at) = path.last() else {
+            continue;
+        };
+        let screenshot = event
+            .data
+            .get("screenshot")
+            .and_then(|v| v.as_str())
+            .map(screen::decode_png)
+            .transpose()?;
+        clicks.push(Click {
+            at,
+            screenshot,
+            path_len: path.len(),
+        });
+    }
+    if clicks.is_empty() {
+        return Err("The macro has no clicks with a known position to draw".to_string());
+    }
+
+    let min_x = path.iter().map(|p| p.0).min().unwrap_or(0) - MARGIN;
+    let min_y = path.iter().map(|p| p.1).min().unwrap_or(0) - MARGIN;
+    let max_x = path.iter().map(|p| p.0).max().unwrap_or(0) + MARGIN;
+    let max_y = path.iter().map(|p| p.1).max().unwrap_or(0) + MARGIN;
+    let scale = (MAX_SIDE as f64 / (max_x - min_x).max(max_y - min_y) as f64).min(1.0);
+    let to_canvas = |(x, y): (i64, i64)| {
+        (
+            ((x - min_x) as f64 * scale) as i64,
+            ((y - min_y) as f64 * scale) as i64,
+        )
+    };
+    let width = ((max_x - min_x) as f64 * scale).ceil() as u32;
+    let height = ((max_y - min_y) as f64 * scale).ceil() as u32;
+
+    let mut frames = Vec::with_capacity(clicks.len());
+    for (i, click) in clicks.iter().enumerate() {
+        let mut frame = RgbaImage::from_pixel(width, height, BACKGROUND);
+        for shown in &clicks[..=i] {
+            if let Some(patch) = &shown.screenshot {
+                let half = (patch.width / 2) as i64;
+                let corner = to_canvas((shown.at.0 - half, shown.at.1 - half));
+                paste(&mut frame, patch, corner, scale);
+            }
+        }
+        for segment in path[..click.path_len].windows(2) {
+            draw_line(
+                &mut frame,
+                to_canvas(segment[0]),
+                to_canvas(segment[1]),
+                PATH_COLOR,
+            );
+        }
+        for earlier in &clicks[..i] {
+            draw_dot(&mut frame, to_canvas(earlier.at), 4, PAST_CLICK_COLOR);
+        }
+        draw_dot(&mut frame, to_canvas(click.at), 6, CLICK_COLOR);
+        frames.push(frame);
+    }
+    Ok(frames)
+}
+
+/// An animated GIF of the frames that loops forever
+pub fn encode_gif(frames: Vec<RgbaImage>) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = GifEncoder::new(&mut bytes);
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|e| e.to_string())?;
+        let delay = Delay::from_numer_denom_ms(FRAME_DELAY_MS, 1);
+        encoder
+            .encode_frames(
+                frames
+                    .into_iter()
+                    .map(|f| Frame::from_parts(f, 0, 0, delay)),
+            )
+            .map_err(|e| format!("Failed to encode GIF: {}", e))?;
+    }
+    Ok(bytes)
+}
+
+fn position(event: &MacroEvent) -> Option<(i64, i64)> {
+    Some((
+        event.data.get("x").and_then(|v| v.as_i64())?,
+        event.data.get("y").and_then(|v| v.as_i64())?,
+    ))
+}
+
+fn paste(frame: &mut RgbaImage, patch: &Patch, (x, y): (i64, i64), scale: f64) {
+    let Some(image) = RgbaImage::from_raw(patch.width, patch.height, patch.rgba.clone()) else {
+        return;
+    };
+    let width = ((patch.width as f64 * scale).round() as u32).max(1);
+    let height = ((patch.height as f64 * scale).round() as u32).max(1);
+    let image = imageops::resize(&image, width, height, FilterType::Triangle);
+    imageops::overlay(frame, &image, x, y);
+}
+
+fn put(frame: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>) {
+    if x >= 0 && y >= 0 && (x as u32) < frame.width() && (y as u32) < frame.height() {
+        frame.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+fn draw_line(frame: &mut RgbaImage, from: (i64, i64), to: (i64, i64), color: Rgba<u8>) {
+    let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).max(1);
+    for step in 0..=steps {
+        let x = from.0 + (to.0 - from.0) * step / steps;
+        let y = from.1 + (to.1 - from.1) * step / steps;
+        put(frame, x, y, color);
+    }
+}
+
+fn draw_dot(frame: &mut RgbaImage, (cx, cy): (i64, i64), radius: i64, color: Rgba<u8>) {
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            if dx * dx + dy * dy <= radius * radius {
+                put(frame, cx + dx, cy + dy, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, data: serde_json::Value) -> MacroEvent {
+        MacroEvent {
+            event_type: event_type.to_string(),
+            timestamp: 0,
+            data,
+        }
+    }
+
+    #[test]
+    fn draws_one_frame_per_click() {
+        let events = vec![
+            event("MouseMove", serde_json::json!({ "x": 100, "y": 100 })),
+            event("MouseDown", serde_json::json!({ "button": "Left" })),
+            event("MouseUp", serde_json::json!({ "button": "Left" })),
+            event("MouseMove", serde_json::json!({ "x": 300, "y": 200 })),
+            event("MouseDown", serde_json::json!({ "button": "Left" })),
+        ];
+
+        let frames = render(&events).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].dimensions(), (200 + 2 * 48, 100 + 2 * 48));
+        // The current click is red, the earlier one grey
+        assert_eq!(*frames[1].get_pixel(48 + 200, 48 + 100), CLICK_COLOR);
+        assert_eq!(*frames[1].get_pixel(48, 48), PAST_CLICK_COLOR);
+
+        assert!(render(&events[..1]).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+// Playback timing module
+//
+// While a macro plays, the player notes for every event how long the wait
+// before it was meant to take, how long it really took, and how long the
+// event itself took to inject. The report sums that up and lists the events
+// that fell behind the most, for runs that feel slower than the recording.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::types::MacroEvent;
+
+/// Events listed one by one in a report
+pub const SLOWEST_EVENTS: usize = 10;
+
+/// Timing of one event, averaged over the repetitions it was played in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventTiming {
+    #[serde(rename = "eventIndex")]
+    pub event_index: usize,
+    #[serde(rename = "eventType")]
+    pub event_type: String,
+    #[serde(rename = "intendedDelayMs")]
+    pub intended_delay_ms: f64,
+    #[serde(rename = "actualDelayMs")]
+    pub actual_delay_ms: f64,
+    /// Includes events that wait for something themselves, e.g. an image
+    #[serde(rename = "injectionMs")]
+    pub injection_ms: f64,
+    pub runs: u32,
+}
+
+impl EventTiming {
+    /// How far this event fell behind its schedule, per run
+    pub fn lateness_ms(&self) -> f64 {
+        (self.actual_delay_ms - self.intended_delay_ms).max(0.0) + self.injection_ms
+    }
+}
+
+/// Intended against actual time for a whole run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TimingReport {
+    /// The waits between events as scheduled
+    #[serde(rename = "intendedMs")]
+    pub intended_ms: f64,
+    /// The waits and injections as they really took
+    #[serde(rename = "actualMs")]
+    pub actual_ms: f64,
+    /// Time waits ran past their schedule
+    #[serde(rename = "sleepOvershootMs")]
+    pub sleep_overshoot_ms: f64,
+    #[serde(rename = "injectionMs")]
+    pub injection_ms: f64,
+    #[serde(rename = "eventsPlayed")]
+    pub events_played: u64,
+    /// The events that fell behind the most, worst first
+    pub slowest: Vec<EventTiming>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct EventTotals {
+    event_type: String,
+    intended: Duration,
+    actual: Duration,
+    injection: Duration,
+    runs: u32,
+}
+
+/// Collects timings while a macro plays
+#[derive(Debug, Clone, Default)]
+pub struct TimingRecorder {
+    events: Vec<EventTotals>,
+}
+
+impl TimingRecorder {
+    pub fn new(events: &[MacroEvent]) -> Self {
+        Self {
+            events: events
+                .iter()
+                .map(|e| EventTotals {
+                    event_type: e.event_type.clone(),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    /// Note one play of event `index`
+    pub fn record(
+        &mut self,
+        index: usize,
+        intended: Duration,
+        actual: Duration,
+        injection: Duration,
+    ) {
+        if let Some(totals) = self.events.get_mut(index) {
+            totals.intended += intended;
+            totals.actual += actual;
+            totals.injection += injection;
+            totals.runs += 1;
+        }
+    }
+
+    pub fn report(&self) -> TimingReport {
+        let ms = |d: Duration| d.as_micros() as f64 / 1000.0;
+        let mut report = TimingReport::default();
+        let mut timings = Vec::new();
+        for (i, totals) in self.events.iter().enumerate() {
+            if totals.runs == 0 {
+                continue;
+            }
+            report.intended_ms += ms(totals.intended);
+            report.actual_ms += ms(totals.actual + totals.injection);
+            report.sleep_overshoot_ms += ms(totals.actual.saturating_sub(totals.intended));
+            report.injection_ms += ms(totals.injection);
+            report.events_played += totals.runs as u64;
+
+            let runs = totals.runs as f64;
+            timings.push(EventTiming {
+                event_index: i,
+                event_type: totals.event_type.clone(),
+                intended_delay_ms: ms(totals.intended) / runs,
+                actual_delay_ms: ms(totals.actual) / runs,
+                injection_ms: ms(totals.injection) / runs,
+                runs: totals.runs,
+            });
+        }
+
+        timings.sort_by(|a, b| b.lateness_ms().total_cmp(&a.lateness_ms()));
+        timings.truncate(SLOWEST_EVENTS);
+        report.slowest = timings;
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_event(event_type: &str) -> MacroEvent {
+        MacroEvent {
+            event_type: event_type.to_string(),
+            timestamp: 0,
+            data: serde_json::json!({ "key": "a" }),
+        }
+    }
+
+    #[test]
+    fn reports_overshoot_and_the_slowest_events() {
+        let ms = Duration::from_millis;
+        let mut recorder = TimingRecorder::new(&[key_event("KeyDown"), key_event("KeyUp")]);
+        for _ in 0..2 {
+            recorder.record(0, ms(10), ms(10), ms(1));
+            recorder.record(1, ms(20), ms(35), ms(1));
+        }
+
+        let report = recorder.report();
+        assert_eq!(report.events_played, 4);
+        assert_eq!(report.intended_ms, 60.0);
+        assert_eq!(report.actual_ms, 94.0);
+        assert_eq!(report.sleep_overshoot_ms, 30.0);
+        assert_eq!(report.slowest[0].event_index, 1);
+        assert_eq!(report.slowest[0].actual_delay_ms, 35.0);
+        assert_eq!(report.slowest[1].event_type, "KeyDown");
+    }
+}
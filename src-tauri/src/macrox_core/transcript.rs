@@ -0,0 +1,174 @@
+// Keystroke transcript module
+//
+// Rebuilds the text a macro would type from its key events so it can be
+// reviewed before playing. Assumes a US keyboard layout for shifted symbols.
+
+use super::types::MacroEvent;
+
+#[derive(Default)]
+struct Modifiers {
+    shift: bool,
+    control: bool,
+    alt: bool,
+    meta: bool,
+    caps_lock: bool,
+}
+
+/// Reconstruct the text typed by a macro's key events.
+///
+/// Backspace removes the previous character, and shortcuts held with
+/// Control/Alt/Meta are shown in brackets, e.g. `[Control+v]`.
+pub fn transcribe(events: &[MacroEvent]) -> String {
+    let mut text = String::new();
+    let mut mods = Modifiers::default();
+
+    for event in events {
+        let Some(key) = event.data.get("key").and_then(|k| k.as_str()) else {
+            continue;
+        };
+        let pressed = match event.event_type.as_str() {
+            "KeyDown" => true,
+            "KeyUp" => false,
+            _ => continue,
+        };
+
+        match key {
+            "Shift" | "ShiftLeft" | "ShiftRight" => mods.shift = pressed,
+            "Control" | "ControlLeft" | "ControlRight" => mods.control = pressed,
+            "Alt" | "AltGr" => mods.alt = pressed,
+            "Meta" | "MetaLeft" | "MetaRight" => mods.meta = pressed,
+            "CapsLock" if pressed => mods.caps_lock = !mods.caps_lock,
+            _ if !pressed => {}
+            _ if mods.control || mods.alt || mods.meta => {
+                text.push_str(&format!("[{}]", chord(&mods, key)));
+            }
+            "Backspace" => {
+                text.pop();
+            }
+            "Enter" => text.push('\n'),
+            "Tab" => text.push('\t'),
+            "Space" => text.push(' '),
+            _ => match typed_char(key, &mods) {
+                Some(ch) => text.push(ch),
+                None => text.push_str(&format!("[{}]", key)),
+            },
+        }
+    }
+
+    text
+}
+
+fn chord(mods: &Modifiers, key: &str) -> String {
+    let mut parts = Vec::new();
+    if mods.control {
+        parts.push("Control");
+    }
+    if mods.alt {
+        parts.push("Alt");
+    }
+    if mods.meta {
+        parts.push("Meta");
+    }
+    if mods.shift {
+        parts.push("Shift");
+    }
+    parts.push(key);
+    parts.join("+")
+}
+
+/// Character produced by a key, or None for keys that don't type anything
+fn typed_char(key: &str, mods: &Modifiers) -> Option<char> {
+    let mut chars = key.chars();
+    if let (Some(ch), None) = (chars.next(), chars.next()) {
+        if ch.is_alphabetic() {
+            let upper = mods.shift != mods.caps_lock;
+            return Some(if upper {
+                ch.to_uppercase().next().unwrap_or(ch)
+            } else {
+                ch
+            });
+        }
+        if mods.shift {
+            return Some(shifted(ch));
+        }
+        return Some(ch);
+    }
+
+    // Punctuation keys are recorded by their rdev names
+    let (plain, with_shift) = match key {
+        "Minus" => ('-', '_'),
+        "Equal" => ('=', '+'),
+        "LeftBracket" => ('[', '{'),
+        "RightBracket" => (']', '}'),
+        "BackSlash" | "IntlBackslash" => ('\\', '|'),
+        "SemiColon" => (';', ':'),
+        "Quote" => ('\'', '"'),
+        "BackQuote" => ('`', '~'),
+        "Comma" => (',', '<'),
+        "Dot" => ('.', '>'),
+        "Slash" => ('/', '?'),
+        _ => return None,
+    };
+    Some(if mods.shift { with_shift } else { plain })
+}
+
+fn shifted(ch: char) -> char {
+    match ch {
+        '1' => '!',
+        '2' => '@',
+        '3' => '#',
+        '4' => '$',
+        '5' => '%',
+        '6' => '^',
+        '7' => '&',
+        '8' => '*',
+        '9' => '(',
+        '0' => ')',
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(steps: &[(&str, &str)]) -> Vec<MacroEvent> {
+        steps
+            .iter()
+            .map(|(event_type, key)| MacroEvent {
+                event_type: event_type.to_string(),
+                timestamp: 0,
+                data: serde_json::json!({ "key": key }),
+            })
+            .collect()
+    }
+
+    fn tap(key: &str) -> [(&str, &str); 2] {
+        [("KeyDown", key), ("KeyUp", key)]
+    }
+
+    #[test]
+    fn applies_shift_and_backspace() {
+        let mut steps = vec![("KeyDown", "Shift")];
+        steps.extend(tap("h"));
+        steps.extend(tap("1"));
+        steps.push(("KeyUp", "Shift"));
+        steps.extend(tap("i"));
+        steps.extend(tap("x"));
+        steps.extend(tap("Backspace"));
+        steps.extend(tap("Space"));
+        steps.extend(tap("Dot"));
+
+        assert_eq!(transcribe(&keys(&steps)), "H!i .");
+    }
+
+    #[test]
+    fn shortcuts_are_bracketed() {
+        let mut steps = vec![("KeyDown", "ControlLeft")];
+        steps.extend(tap("v"));
+        steps.push(("KeyUp", "ControlLeft"));
+        steps.extend(tap("F5"));
+
+        assert_eq!(transcribe(&keys(&steps)), "[Control+v][F5]");
+    }
+}
@@ -0,0 +1,103 @@
+// Trigger cleanup module
+//
+// Per-macro hotkeys, leader key bindings and scenes name macros by id and
+// live in the settings, so deleting a macro or editing the macros file by hand
+// can leave them pointing at nothing. Reconciling drops those entries and
+// reports what it removed.
+
+use serde::Serialize;
+use std::collections::HashSet;
+
+use super::types::{AppSettings, MacroHotkey};
+
+/// Triggers removed because their macro no longer exists
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TriggerCleanup {
+    #[serde(rename = "macroHotkeys")]
+    pub macro_hotkeys: Vec<MacroHotkey>,
+    /// Leader second keys whose macro is gone
+    #[serde(rename = "leaderKeys")]
+    pub leader_keys: Vec<String>,
+    /// Scene memberships dropped, across all scenes
+    #[serde(rename = "sceneEntries")]
+    pub scene_entries: usize,
+    /// Macro ids that still had a global shortcut registered
+    #[serde(rename = "registeredShortcuts")]
+    pub registered_shortcuts: Vec<String>,
+}
+
+impl TriggerCleanup {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Remove triggers of macros not in `macro_ids` from `settings`
+pub fn remove_orphans(settings: &mut AppSettings, macro_ids: &HashSet<&str>) -> TriggerCleanup {
+    let mut cleanup = TriggerCleanup::default();
+
+    let (kept, orphaned) = std::mem::take(&mut settings.macro_hotkeys)
+        .into_iter()
+        .partition(|h| macro_ids.contains(h.macro_id.as_str()));
+    settings.macro_hotkeys = kept;
+    cleanup.macro_hotkeys = orphaned;
+
+    settings.leader.bindings.retain(|key, id| {
+        let exists = macro_ids.contains(id.as_str());
+        if !exists {
+            cleanup.leader_keys.push(key.clone());
+        }
+        exists
+    });
+
+    for scene in &mut settings.scenes {
+        let before = scene.macro_ids.len();
+        scene.macro_ids.retain(|id| macro_ids.contains(id.as_str()));
+        cleanup.scene_entries += before - scene.macro_ids.len();
+    }
+
+    cleanup
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::macrox_core::types::Scene;
+
+    fn hotkey(macro_id: &str, hotkey: &str) -> MacroHotkey {
+        MacroHotkey {
+            macro_id: macro_id.to_string(),
+            hotkey: hotkey.to_string(),
+        }
+    }
+
+    #[test]
+    fn drops_only_triggers_of_missing_macros() {
+        let mut settings = AppSettings {
+            macro_hotkeys: vec![hotkey("kept", "F5"), hotkey("deleted", "F6")],
+            ..AppSettings::default()
+        };
+        settings
+            .leader
+            .bindings
+            .insert("a".to_string(), "deleted".to_string());
+        settings
+            .leader
+            .bindings
+            .insert("b".to_string(), "kept".to_string());
+        settings.scenes.push(Scene {
+            id: "s".to_string(),
+            name: "Scene".to_string(),
+            macro_ids: vec!["kept".to_string(), "deleted".to_string()],
+        });
+
+        let cleanup = remove_orphans(&mut settings, &HashSet::from(["kept"]));
+        assert_eq!(cleanup.macro_hotkeys, vec![hotkey("deleted", "F6")]);
+        assert_eq!(cleanup.leader_keys, vec!["a"]);
+        assert_eq!(cleanup.scene_entries, 1);
+        assert_eq!(settings.macro_hotkeys, vec![hotkey("kept", "F5")]);
+        assert_eq!(settings.scenes[0].macro_ids, vec!["kept"]);
+
+        assert!(remove_orphans(&mut settings, &HashSet::from(["kept"])).is_empty());
+    }
+}
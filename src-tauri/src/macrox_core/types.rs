@@ -0,0 +1,1243 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::metadata::RecordingMetadata;
+use super::onboarding::OnboardingState;
+use super::timing::TimingReport;
+
+/// Mouse button types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+/// Individual macro event with flexible data storage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroEvent {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub timestamp: u64,
+    pub data: Value,
+}
+
+/// Playback settings for macro execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybackSettings {
+    pub speed: f64,
+    #[serde(rename = "repeatMode")]
+    pub repeat_mode: String,
+    #[serde(rename = "repeatCount")]
+    pub repeat_count: u32,
+    /// Replay mouse positions relative to the cursor, anchored on the first click
+    #[serde(rename = "playAtCursor")]
+    #[serde(default)]
+    pub play_at_cursor: bool,
+    /// Pause between repetitions
+    #[serde(rename = "repeatDelayMs")]
+    #[serde(default = "default_repeat_delay_ms")]
+    pub repeat_delay_ms: u64,
+    /// How long to keep repeating in "duration" mode
+    #[serde(rename = "repeatDurationMinutes")]
+    #[serde(default)]
+    pub repeat_duration_minutes: f64,
+    /// What to do when the user touches the mouse or keyboard mid-playback
+    #[serde(rename = "humanOverride")]
+    #[serde(default)]
+    pub human_override: HumanOverride,
+    /// Experimental, Windows only: send input to the window with this title instead of globally
+    #[serde(rename = "targetWindow")]
+    #[serde(default)]
+    pub target_window: Option<String>,
+    /// Stretches mouse movement while a button is held, e.g. 2.0 drags at half speed
+    #[serde(rename = "dragSpeedFactor")]
+    #[serde(default = "default_drag_speed_factor")]
+    pub drag_speed_factor: f64,
+    /// Compare clicks that carry a screenshot against the screen before clicking
+    #[serde(rename = "clickVerification")]
+    #[serde(default)]
+    pub click_verification: ClickVerification,
+    /// Click recorded UI elements wherever they are now instead of at their
+    /// recorded coordinates, when the element can still be found
+    #[serde(rename = "clickByElement")]
+    #[serde(default)]
+    pub click_by_element: bool,
+    /// Number typed in place of `{counter}` in TypeText events, stepped each repetition
+    #[serde(default)]
+    pub counter: Option<CounterSettings>,
+    /// Ask before destructive key combinations; None follows the app setting
+    #[serde(rename = "confirmDestructive")]
+    #[serde(default)]
+    pub confirm_destructive: Option<bool>,
+    /// Play the whole macro again when a run fails, e.g. a window wasn't found yet
+    #[serde(rename = "retryOnFailure")]
+    #[serde(default)]
+    pub retry_on_failure: bool,
+    #[serde(rename = "maxRetries")]
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for each one after it
+    #[serde(rename = "backoffMs")]
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+    /// Devices the macro sends input to; scoped macros can play alongside each other
+    #[serde(rename = "inputScope")]
+    #[serde(default)]
+    pub input_scope: InputScope,
+}
+
+/// Same defaults the UI gives a fresh recording
+impl Default for PlaybackSettings {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            repeat_mode: "once".to_string(),
+            repeat_count: 1,
+            play_at_cursor: false,
+            repeat_delay_ms: default_repeat_delay_ms(),
+            repeat_duration_minutes: 0.0,
+            human_override: HumanOverride::Off,
+            target_window: None,
+            drag_speed_factor: default_drag_speed_factor(),
+            click_verification: ClickVerification::Off,
+            click_by_element: false,
+            counter: None,
+            confirm_destructive: None,
+            retry_on_failure: false,
+            max_retries: default_max_retries(),
+            backoff_ms: default_backoff_ms(),
+            input_scope: InputScope::All,
+        }
+    }
+}
+
+/// Per-repetition counter, e.g. start 1 step 1 types 1, 2, 3 into successive rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterSettings {
+    pub start: i64,
+    #[serde(default = "default_counter_step")]
+    pub step: i64,
+    /// Zero-pad to this many digits, 0 for none
+    #[serde(rename = "padDigits")]
+    #[serde(default)]
+    pub pad_digits: usize,
+}
+
+impl CounterSettings {
+    /// The counter's text on the given (zero-based) repetition
+    pub fn value_at(&self, iteration: u32) -> String {
+        let value = self.start + self.step * iteration as i64;
+        format!("{:0width$}", value, width = self.pad_digits)
+    }
+}
+
+fn default_counter_step() -> i64 {
+    1
+}
+
+/// Which of the keyboard and mouse a macro plays on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputScope {
+    #[default]
+    All,
+    Keyboard,
+    Mouse,
+}
+
+/// Reaction to real user input during playback
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HumanOverride {
+    #[default]
+    Off,
+    Pause,
+    Stop,
+}
+
+/// What to do with recorded click screenshots during playback
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClickVerification {
+    #[default]
+    Off,
+    /// Abort when the target doesn't look like it did while recording
+    Verify,
+    /// Search near the recorded position and click where the target moved to
+    Relocate,
+}
+
+fn default_repeat_delay_ms() -> u64 {
+    500
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_backoff_ms() -> u64 {
+    1000
+}
+
+fn default_drag_speed_factor() -> f64 {
+    1.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn legacy_recording_version() -> u32 {
+    1
+}
+
+/// `RecordingSettings` layout written by this version
+pub const RECORDING_SETTINGS_VERSION: u32 = 2;
+
+/// Recording settings - what to capture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingSettings {
+    /// 1 for settings saved before the per-type toggles, when wheel followed clicks
+    #[serde(default = "legacy_recording_version")]
+    pub version: u32,
+    #[serde(rename = "recordMouseMovement")]
+    pub record_mouse_movement: bool,
+    #[serde(rename = "recordMouseClicks")]
+    pub record_mouse_clicks: bool,
+    #[serde(rename = "recordKeyboard")]
+    pub record_keyboard: bool,
+    #[serde(rename = "recordMouseWheel")]
+    #[serde(default = "default_true")]
+    pub record_mouse_wheel: bool,
+    /// Keep keys pressed while Control, Alt or Meta is held; off records plain typing only
+    #[serde(rename = "recordKeyCombos")]
+    #[serde(default = "default_true")]
+    pub record_key_combos: bool,
+    /// Add a WindowFocus event whenever the focused application changes
+    #[serde(rename = "recordWindowFocus")]
+    #[serde(default)]
+    pub record_window_focus: bool,
+    /// Fold the input touchpad gestures turn into back into Pinch and Swipe events
+    #[serde(rename = "recordGestures")]
+    #[serde(default)]
+    pub record_gestures: bool,
+    /// Give a drag's MouseUp the release position so it lands there without movement
+    #[serde(rename = "detectDrags")]
+    #[serde(default)]
+    pub detect_drags: bool,
+    /// Keep at most one mouse move per this many milliseconds, 0 keeps them all
+    #[serde(rename = "mouseSampleMs")]
+    #[serde(default)]
+    pub mouse_sample_ms: u64,
+    /// Drop mouse moves closer than this many pixels to the last one kept
+    #[serde(rename = "mouseMinDistance")]
+    #[serde(default)]
+    pub mouse_min_distance: u32,
+    /// Ignore mouse moves within this many pixels of the last one recorded, to
+    /// filter out sensor jitter while the mouse is held still. Applied with
+    /// `mouse_min_distance`, whichever is larger.
+    #[serde(rename = "mouseDeadZone")]
+    #[serde(default)]
+    pub mouse_dead_zone: u32,
+    /// Keep ShiftLeft/ShiftRight etc. distinct instead of collapsing them
+    #[serde(rename = "distinguishModifierSides")]
+    #[serde(default)]
+    pub distinguish_modifier_sides: bool,
+    /// Record mouse movement as deltas instead of absolute positions (for games using raw input)
+    #[serde(rename = "relativeMouseMovement")]
+    #[serde(default)]
+    pub relative_mouse_movement: bool,
+    /// Skip mouse movement entirely and stamp clicks with their position instead
+    #[serde(rename = "recordClickPositions")]
+    #[serde(default)]
+    pub record_click_positions: bool,
+    /// Only capture mouse events inside this region; playback refuses to click outside it
+    #[serde(default)]
+    pub region: Option<ScreenRegion>,
+    /// Store a small screenshot around each click for the editor and click verification
+    #[serde(rename = "recordClickScreenshots")]
+    #[serde(default)]
+    pub record_click_screenshots: bool,
+    /// Tag key, button and wheel events with the device that produced them (Windows)
+    #[serde(rename = "recordDevices")]
+    #[serde(default)]
+    pub record_devices: bool,
+    /// Only keep events of this device's kind that came from this device
+    #[serde(rename = "deviceFilter")]
+    #[serde(default)]
+    pub device_filter: Option<InputDevice>,
+    /// Resolve each click to the UI element under it (Windows UI Automation)
+    #[serde(rename = "recordClickElements")]
+    #[serde(default)]
+    pub record_click_elements: bool,
+    /// Recordings with more events are saved as a chain of sub-macros of at
+    /// most this many events, cut where the input paused. 0 never splits.
+    #[serde(rename = "splitAfterEvents")]
+    #[serde(default)]
+    pub split_after_events: u32,
+}
+
+/// Mouse movement, clicks and keys, without any of the extras
+impl Default for RecordingSettings {
+    fn default() -> Self {
+        Self {
+            version: RECORDING_SETTINGS_VERSION,
+            record_mouse_movement: true,
+            record_mouse_clicks: true,
+            record_keyboard: true,
+            record_mouse_wheel: true,
+            record_key_combos: true,
+            record_window_focus: false,
+            record_gestures: false,
+            detect_drags: false,
+            mouse_sample_ms: 0,
+            mouse_min_distance: 0,
+            mouse_dead_zone: 0,
+            distinguish_modifier_sides: false,
+            relative_mouse_movement: false,
+            record_click_positions: false,
+            region: None,
+            record_click_screenshots: false,
+            record_devices: false,
+            device_filter: None,
+            record_click_elements: false,
+            split_after_events: 0,
+        }
+    }
+}
+
+impl RecordingSettings {
+    pub fn records_wheel(&self) -> bool {
+        if self.version < 2 {
+            self.record_mouse_clicks
+        } else {
+            self.record_mouse_wheel
+        }
+    }
+}
+
+/// Kind of physical input device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    Keyboard,
+    Mouse,
+}
+
+/// A connected keyboard or mouse, identified by its platform device path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputDevice {
+    pub id: String,
+    pub kind: DeviceKind,
+}
+
+/// Screen rectangle a macro is allowed to act in
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScreenRegion {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ScreenRegion {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && y >= self.y
+            && (x as i64) < self.x as i64 + self.width as i64
+            && (y as i64) < self.y as i64 + self.height as i64
+    }
+
+    pub fn scaled(&self, scale: f64) -> ScreenRegion {
+        ScreenRegion {
+            x: (self.x as f64 * scale).round() as i32,
+            y: (self.y as f64 * scale).round() as i32,
+            width: (self.width as f64 * scale).round() as u32,
+            height: (self.height as f64 * scale).round() as u32,
+        }
+    }
+}
+
+/// Display scaling captured alongside a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayInfo {
+    #[serde(rename = "scaleFactor")]
+    pub scale_factor: f64,
+    pub dpi: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// An RGB screen color, e.g. for a "color under cursor" readout
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PixelColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// "#rrggbb"
+    pub hex: String,
+}
+
+/// One place a click screenshot was found on screen
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageMatch {
+    /// Centre of the match in screen coordinates
+    pub x: i32,
+    pub y: i32,
+    /// Difference from the screenshot, 0.0 for identical
+    pub score: f64,
+}
+
+/// Result of scanning the screen for a click screenshot, for tuning its matchTolerance
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageCalibration {
+    /// Best matches first
+    pub matches: Vec<ImageMatch>,
+    /// Difference at the click's recorded position
+    #[serde(rename = "recordedScore")]
+    pub recorded_score: f64,
+    #[serde(rename = "suggestedTolerance")]
+    pub suggested_tolerance: Option<f64>,
+}
+
+/// A UI element a click landed on, as reported by the accessibility tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UiElement {
+    pub name: String,
+    /// Control type, e.g. "button" or "edit"
+    pub role: String,
+    #[serde(rename = "automationId")]
+    #[serde(default)]
+    pub automation_id: String,
+}
+
+/// Complete macro with metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Macro {
+    pub id: String,
+    pub name: String,
+    /// Markdown, shown with the macro when it's shared
+    pub description: String,
+    pub events: Vec<MacroEvent>,
+    #[serde(rename = "recordingSettings")]
+    pub recording_settings: RecordingSettings,
+    #[serde(rename = "playbackSettings")]
+    pub playback_settings: PlaybackSettings,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(rename = "displayInfo")]
+    #[serde(default)]
+    pub display_info: Option<DisplayInfo>,
+    /// Archived automatically once this passes
+    #[serde(rename = "expiresAt")]
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Left out of the library, hotkeys and summaries until restored
+    #[serde(default)]
+    pub archived: bool,
+    /// Who made the macro, kept through export and import
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Where a shared macro was downloaded from
+    #[serde(rename = "sourceUrl")]
+    #[serde(default)]
+    pub source_url: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+}
+
+#[cfg(test)]
+impl Macro {
+    /// A macro named after its id, with default settings and fixed timestamps
+    pub fn for_test(id: &str, events: Vec<MacroEvent>) -> Self {
+        let created_at = "2024-01-01T00:00:00Z".parse().unwrap();
+        Self {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            events,
+            recording_settings: RecordingSettings::default(),
+            playback_settings: PlaybackSettings::default(),
+            created_at,
+            updated_at: created_at,
+            display_info: None,
+            expires_at: None,
+            archived: false,
+            author: None,
+            source_url: None,
+            license: None,
+        }
+    }
+}
+
+/// Descriptive fields of a macro, edited without resending its events
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MacroMetadata {
+    pub description: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(rename = "sourceUrl")]
+    #[serde(default)]
+    pub source_url: Option<String>,
+    #[serde(default)]
+    pub license: Option<String>,
+}
+
+/// Events `start..end` of a macro, by index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Outcome of `save_macro`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SaveMacroResult {
+    /// Another macro with the same steps, so the UI can offer to replace it
+    #[serde(rename = "duplicateOf")]
+    pub duplicate_of: Option<MacroRef>,
+    /// Sub-macros a long recording was split into, played in turn by the saved macro
+    #[serde(default)]
+    pub parts: Vec<MacroRef>,
+}
+
+/// Names a stored macro
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MacroRef {
+    pub id: String,
+    pub name: String,
+}
+
+/// Captured events plus capture statistics, returned when a recording stops
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingSession {
+    pub events: Vec<MacroEvent>,
+    #[serde(rename = "startedAt")]
+    pub started_at: DateTime<Utc>,
+    /// Wall-clock time between starting and stopping the recording
+    #[serde(rename = "durationMs")]
+    pub duration_ms: u64,
+    /// Captured events by type
+    pub counts: BTreeMap<String, usize>,
+    /// Input seen but not kept: hotkeys, input outside the region, filtered devices
+    #[serde(rename = "droppedEvents")]
+    pub dropped_events: usize,
+    /// Warnings raised while recording, in order
+    pub warnings: Vec<RecordingWarning>,
+    pub metadata: RecordingMetadata,
+}
+
+/// Why the recorder left input out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DropReason {
+    OutsideRegion,
+    DeviceFilter,
+    /// The recording reached its event limit
+    BufferFull,
+}
+
+/// Something the recorder skipped or couldn't capture
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RecordingWarning {
+    /// A MacroX hotkey was pressed and left out of the recording
+    HotkeyIgnored {
+        key: String,
+    },
+    /// Raised once per reason; `droppedEvents` in the session has the total
+    EventDropped {
+        reason: DropReason,
+    },
+    /// Part of the input can't be captured, e.g. keys under secure input
+    PermissionDegraded {
+        capability: String,
+        message: String,
+    },
+    BufferNearLimit {
+        events: usize,
+        limit: usize,
+    },
+}
+
+impl RecordingWarning {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::HotkeyIgnored { .. } => "hotkeyIgnored",
+            Self::EventDropped { .. } => "eventDropped",
+            Self::PermissionDegraded { .. } => "permissionDegraded",
+            Self::BufferNearLimit { .. } => "bufferNearLimit",
+        }
+    }
+}
+
+impl std::fmt::Display for RecordingWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::HotkeyIgnored { key } => write!(f, "Hotkey '{}' detected and ignored", key),
+            Self::EventDropped { reason } => match reason {
+                DropReason::OutsideRegion => write!(f, "Mouse input outside the region is dropped"),
+                DropReason::DeviceFilter => write!(f, "Input from other devices was dropped"),
+                DropReason::BufferFull => {
+                    write!(f, "The recording is full, further input is dropped")
+                }
+            },
+            Self::PermissionDegraded { message, .. } => write!(f, "{}", message),
+            Self::BufferNearLimit { events, limit } => write!(
+                f,
+                "The recording holds {} of at most {} events",
+                events, limit
+            ),
+        }
+    }
+}
+
+/// The warnings of the last recording, counted by kind
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecordingWarningSummary {
+    pub total: usize,
+    /// Number of warnings of each kind, e.g. "hotkeyIgnored"
+    pub counts: BTreeMap<String, usize>,
+    #[serde(rename = "droppedEvents")]
+    pub dropped_events: usize,
+    pub warnings: Vec<RecordingWarning>,
+}
+
+/// Expected runtime of a macro with given playback settings
+#[derive(Debug, Clone, Serialize)]
+pub struct DurationEstimate {
+    /// One pass through the events at the chosen speed
+    #[serde(rename = "singleRunMs")]
+    pub single_run_ms: u64,
+    /// None when repeating until stopped or for a fixed time
+    pub iterations: Option<u32>,
+    /// None when repeating until stopped
+    #[serde(rename = "totalMs")]
+    pub total_ms: Option<u64>,
+}
+
+/// Category of a lint warning
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LintKind {
+    UnbalancedInput,
+    OffScreenClick,
+    LongWait,
+    Burst,
+    HotkeyKey,
+    MissingMacro,
+}
+
+/// A likely problem found by lint_macro
+#[derive(Debug, Clone, Serialize)]
+pub struct LintWarning {
+    pub kind: LintKind,
+    /// Event the warning points at, if any
+    #[serde(rename = "eventIndex")]
+    pub event_index: Option<usize>,
+    pub message: String,
+}
+
+/// How a playback run ended
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunOutcome {
+    Completed,
+    Stopped,
+    Failed,
+}
+
+/// Values read by a run's events, keyed by each event's "outputVar"
+pub type RunOutputs = BTreeMap<String, String>;
+
+/// One finished playback run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    #[serde(rename = "macroId")]
+    pub macro_id: String,
+    #[serde(rename = "macroName")]
+    pub macro_name: String,
+    #[serde(rename = "startedAt")]
+    pub started_at: DateTime<Utc>,
+    #[serde(rename = "durationMs")]
+    pub duration_ms: u64,
+    pub outcome: RunOutcome,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub outputs: RunOutputs,
+    /// Intended against actual event timing; None if the player never started
+    #[serde(default)]
+    pub timing: Option<TimingReport>,
+}
+
+/// Current state of macro playback
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum PlaybackStatus {
+    #[default]
+    Idle,
+    Playing {
+        #[serde(rename = "macroId")]
+        macro_id: String,
+        #[serde(rename = "eventIndex")]
+        event_index: usize,
+        iteration: u32,
+        #[serde(rename = "elapsedMs")]
+        elapsed_ms: u64,
+        /// Expected time left, None when repeating until stopped
+        #[serde(rename = "etaMs")]
+        #[serde(default)]
+        eta_ms: Option<u64>,
+    },
+    Paused {
+        #[serde(rename = "macroId")]
+        macro_id: String,
+        #[serde(rename = "eventIndex")]
+        event_index: usize,
+        iteration: u32,
+        #[serde(rename = "elapsedMs")]
+        elapsed_ms: u64,
+        /// Expected time left, None when repeating until stopped
+        #[serde(rename = "etaMs")]
+        #[serde(default)]
+        eta_ms: Option<u64>,
+    },
+}
+
+impl PlaybackStatus {
+    /// State and macro without progress, e.g. "playing:<id>", to detect real changes
+    pub fn summary(&self) -> String {
+        match self {
+            PlaybackStatus::Idle => "idle".to_string(),
+            PlaybackStatus::Playing { macro_id, .. } => format!("playing:{}", macro_id),
+            PlaybackStatus::Paused { macro_id, .. } => format!("paused:{}", macro_id),
+        }
+    }
+}
+
+/// App-wide hotkey configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotkeySettings {
+    #[serde(rename = "recordStart")]
+    pub record_start: String,
+    #[serde(rename = "recordStop")]
+    pub record_stop: String,
+    #[serde(rename = "playbackStart")]
+    pub playback_start: String,
+    #[serde(rename = "playbackStop")]
+    pub playback_stop: String,
+    /// Pressed while recording, drops a Marker event instead of being recorded.
+    /// Single keys only, it is matched by the recorder rather than registered.
+    #[serde(default = "default_mark_hotkey")]
+    pub mark: String,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            record_start: "F9".to_string(),
+            record_stop: "F10".to_string(),
+            playback_start: "F11".to_string(),
+            playback_stop: "F12".to_string(),
+            mark: default_mark_hotkey(),
+        }
+    }
+}
+
+fn default_max_infinite_iterations() -> u32 {
+    10_000
+}
+
+fn default_mouse_acceleration_factor() -> f64 {
+    1.0
+}
+
+fn default_mark_hotkey() -> String {
+    "F8".to_string()
+}
+
+/// Outcome of one health check probe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    /// Works, but something will likely go wrong for some macros
+    Warning,
+    Failed,
+}
+
+/// One probe of `run_health_check`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    /// Stable id, e.g. "inputHook"
+    pub id: String,
+    pub label: String,
+    pub status: HealthStatus,
+    pub message: String,
+}
+
+/// Everything MacroX needs to record and play, checked at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub checks: Vec<HealthCheck>,
+    /// No check failed
+    pub healthy: bool,
+}
+
+/// Engine the player injects input with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputBackend {
+    #[default]
+    Enigo,
+    /// SendInput with scan codes (Windows only)
+    SendInput,
+    /// The xdotool command (Linux/X11 only)
+    Xdotool,
+    /// The xdg-desktop-portal RemoteDesktop interface (Linux/Wayland only)
+    Portal,
+}
+
+/// How `export_macro` writes a macro file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One pretty-printed JSON document
+    #[default]
+    Json,
+    /// The macro on the first line, then one event per line
+    Ndjson,
+    MessagePack,
+    /// Compact MacroX binary form for large recordings
+    Binary,
+}
+
+/// What the desktop session allows on Linux under Wayland
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WaylandCapabilities {
+    #[serde(rename = "isWayland")]
+    pub is_wayland: bool,
+    /// Playback through the RemoteDesktop portal is possible
+    #[serde(rename = "remoteDesktop")]
+    pub remote_desktop: bool,
+    /// The InputCapture portal exists; MacroX can't record through it yet
+    #[serde(rename = "inputCapture")]
+    pub input_capture: bool,
+}
+
+/// General application settings. Every field falls back to its default, so
+/// stores written by older versions (or with fields added since) still load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    #[serde(rename = "alwaysOnTop")]
+    pub always_on_top: bool,
+    pub theme: Theme,
+    /// Open minimized, e.g. when started with the system
+    #[serde(rename = "startMinimized")]
+    pub start_minimized: bool,
+    /// Ask before deleting a macro
+    #[serde(rename = "confirmDelete")]
+    pub confirm_delete: bool,
+    pub telemetry: TelemetrySettings,
+    #[serde(rename = "lastSelectedMacroId")]
+    #[serde(default)]
+    pub last_selected_macro_id: Option<String>,
+    #[serde(default)]
+    pub mqtt: MqttSettings,
+    #[serde(rename = "controlServer")]
+    #[serde(default)]
+    pub control_server: ControlServerSettings,
+    #[serde(default)]
+    pub emitter: EmitterSettings,
+    #[serde(default)]
+    pub logging: LoggingSettings,
+    /// Macros allowed to play privileged events
+    #[serde(rename = "privilegeGrants")]
+    #[serde(default)]
+    pub privilege_grants: Vec<PrivilegeGrant>,
+    #[serde(default)]
+    pub leader: LeaderSettings,
+    #[serde(default)]
+    pub launcher: LauncherSettings,
+    #[serde(default)]
+    pub browser: BrowserSettings,
+    #[serde(rename = "inputBackend")]
+    #[serde(default)]
+    pub input_backend: InputBackend,
+    /// Ask before playing destructive key combinations, unless a macro says otherwise
+    #[serde(rename = "confirmDestructive")]
+    #[serde(default)]
+    pub confirm_destructive: bool,
+    /// Global hotkeys that each play one stored macro
+    #[serde(rename = "macroHotkeys")]
+    #[serde(default)]
+    pub macro_hotkeys: Vec<MacroHotkey>,
+    /// Where the main window was last placed, restored at startup
+    #[serde(rename = "windowGeometry")]
+    #[serde(default)]
+    pub window_geometry: Option<WindowGeometry>,
+    /// First-run steps done so far
+    #[serde(default)]
+    pub onboarding: OnboardingState,
+    /// Archive macros neither played nor edited for this many days
+    #[serde(rename = "archiveUnusedDays")]
+    #[serde(default)]
+    pub archive_unused_days: Option<u32>,
+    /// "infinite" runs ask before going past this many repetitions, 0 for no limit
+    #[serde(rename = "maxInfiniteIterations")]
+    #[serde(default = "default_max_infinite_iterations")]
+    pub max_infinite_iterations: u32,
+    #[serde(default)]
+    pub scenes: Vec<Scene>,
+    /// Scene whose macro hotkeys are registered; None registers all of them
+    #[serde(rename = "activeScene")]
+    #[serde(default)]
+    pub active_scene: Option<String>,
+    #[serde(default)]
+    pub retro: RetroSettings,
+    /// Encoding `export_macro` uses when none is given
+    #[serde(rename = "exportFormat")]
+    #[serde(default)]
+    pub export_format: ExportFormat,
+    /// Relative mouse moves are scaled by this, set by `calibrate_mouse`
+    #[serde(rename = "mouseAccelerationFactor")]
+    #[serde(default = "default_mouse_acceleration_factor")]
+    pub mouse_acceleration_factor: f64,
+    #[serde(default)]
+    pub updates: UpdateSettings,
+    /// Play into the practice window instead of the desktop
+    #[serde(rename = "practiceMode")]
+    #[serde(default)]
+    pub practice_mode: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            always_on_top: false,
+            theme: Theme::default(),
+            start_minimized: false,
+            confirm_delete: true,
+            telemetry: TelemetrySettings::default(),
+            last_selected_macro_id: None,
+            mqtt: MqttSettings::default(),
+            control_server: ControlServerSettings::default(),
+            emitter: EmitterSettings::default(),
+            logging: LoggingSettings::default(),
+            privilege_grants: Vec::new(),
+            leader: LeaderSettings::default(),
+            launcher: LauncherSettings::default(),
+            browser: BrowserSettings::default(),
+            input_backend: InputBackend::default(),
+            confirm_destructive: false,
+            macro_hotkeys: Vec::new(),
+            window_geometry: None,
+            onboarding: OnboardingState::default(),
+            archive_unused_days: None,
+            max_infinite_iterations: default_max_infinite_iterations(),
+            scenes: Vec::new(),
+            active_scene: None,
+            retro: RetroSettings::default(),
+            export_format: ExportFormat::default(),
+            mouse_acceleration_factor: default_mouse_acceleration_factor(),
+            updates: UpdateSettings::default(),
+            practice_mode: false,
+        }
+    }
+}
+
+/// UI color scheme
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// What the user agreed to share; nothing is sent unless turned on
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TelemetrySettings {
+    #[serde(rename = "crashReports")]
+    #[serde(default)]
+    pub crash_reports: bool,
+    #[serde(rename = "usageStats")]
+    #[serde(default)]
+    pub usage_stats: bool,
+}
+
+/// Main window position and size in physical pixels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Name of the monitor the window was on, when the OS reports one
+    #[serde(default)]
+    pub monitor: Option<String>,
+}
+
+/// A global hotkey that plays one stored macro
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroHotkey {
+    #[serde(rename = "macroId")]
+    pub macro_id: String,
+    pub hotkey: String,
+}
+
+/// A named group of macros whose hotkeys are switched on together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "macroIds")]
+    pub macro_ids: Vec<String>,
+}
+
+/// Keep the last few seconds of input in memory, saved as a macro by a hotkey
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetroSettings {
+    pub enabled: bool,
+    pub seconds: u32,
+    pub hotkey: String,
+}
+
+impl Default for RetroSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            seconds: 30,
+            hotkey: "CommandOrControl+Shift+F9".to_string(),
+        }
+    }
+}
+
+/// When to look for a newer MacroX release
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UpdateSettings {
+    #[serde(rename = "checkOnStartup")]
+    #[serde(default)]
+    pub check_on_startup: bool,
+    #[serde(rename = "includePrereleases")]
+    #[serde(default)]
+    pub include_prereleases: bool,
+}
+
+/// Global hotkey for the quick launcher palette
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LauncherSettings {
+    pub enabled: bool,
+    pub hotkey: String,
+}
+
+impl Default for LauncherSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hotkey: "CommandOrControl+Shift+Space".to_string(),
+        }
+    }
+}
+
+/// Leader key: the hotkey arms a window in which one more key plays a macro
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderSettings {
+    pub enabled: bool,
+    pub hotkey: String,
+    /// How long to wait for the second key
+    #[serde(rename = "timeoutMs")]
+    pub timeout_ms: u64,
+    /// Second key, named as in recorded events (e.g. "a", "F5"), to macro id
+    #[serde(default)]
+    pub bindings: BTreeMap<String, String>,
+}
+
+impl Default for LeaderSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hotkey: "Alt+Space".to_string(),
+            timeout_ms: 1500,
+            bindings: BTreeMap::new(),
+        }
+    }
+}
+
+/// Permission for one macro to play privileged events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrivilegeGrant {
+    #[serde(rename = "macroId")]
+    pub macro_id: String,
+    /// Privileged event types the macro used when permission was given
+    #[serde(rename = "eventTypes")]
+    pub event_types: Vec<String>,
+}
+
+/// Log file verbosity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+/// What ends up in the rotating log files
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoggingSettings {
+    #[serde(default)]
+    pub level: LogLevel,
+}
+
+/// How live updates are throttled on their way to the frontend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmitterSettings {
+    /// Live updates are collected and sent once per window
+    #[serde(rename = "batchWindowMs")]
+    pub batch_window_ms: u64,
+    /// Mouse moves streamed to the UI per second, 0 for no limit. Recordings keep every move.
+    #[serde(rename = "maxMouseMovesPerSec")]
+    pub max_mouse_moves_per_sec: u32,
+}
+
+impl Default for EmitterSettings {
+    fn default() -> Self {
+        Self {
+            batch_window_ms: 50,
+            max_mouse_moves_per_sec: 30,
+        }
+    }
+}
+
+/// Chrome DevTools Protocol connection for recording and playing browser clicks
+/// as DOM selectors. Chrome must be started with `--remote-debugging-port`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrowserSettings {
+    pub enabled: bool,
+    /// Chrome's remote debugging port on 127.0.0.1
+    pub port: u16,
+}
+
+impl Default for BrowserSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 9222,
+        }
+    }
+}
+
+/// Local WebSocket control endpoint, e.g. for a Stream Deck
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlServerSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Requests without one of these are refused. A full access token is
+    /// created when the server is enabled without any.
+    /// Changed with mint/revoke_control_token only.
+    #[serde(default)]
+    pub tokens: Vec<ControlToken>,
+}
+
+impl Default for ControlServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 7878,
+            tokens: Vec::new(),
+        }
+    }
+}
+
+/// What a control server token may do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TokenScope {
+    /// Status, the macro list and run history
+    ReadOnly,
+    /// Reading, stopping playback and playing these macros, by id
+    Trigger { macros: Vec<String> },
+    /// Everything, including recording
+    Full,
+}
+
+/// Shown in place of tokens and passwords when settings are logged or exported
+pub const REDACTED: &str = "<redacted>";
+
+/// Access token for the control server
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlToken {
+    pub id: String,
+    /// Label to tell tokens apart, e.g. "Stream Deck"
+    pub name: String,
+    /// Sent as `Authorization: Bearer <token>` or a `?token=` query parameter
+    pub token: String,
+    pub scope: TokenScope,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+}
+
+impl std::fmt::Debug for ControlToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ControlToken")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("token", &REDACTED)
+            .field("scope", &self.scope)
+            .field("created_at", &self.created_at)
+            .finish()
+    }
+}
+
+/// MQTT broker connection for home automation triggers
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct MqttSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Topics are `<prefix>/play`, `<prefix>/stop`, `<prefix>/status` and `<prefix>/playback`
+    #[serde(rename = "topicPrefix")]
+    pub topic_prefix: String,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 1883,
+            username: None,
+            password: None,
+            topic_prefix: "macrox".to_string(),
+        }
+    }
+}
+
+impl std::fmt::Debug for MqttSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MqttSettings")
+            .field("enabled", &self.enabled)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username.as_ref().map(|_| REDACTED))
+            .field("password", &self.password.as_ref().map(|_| REDACTED))
+            .field("topic_prefix", &self.topic_prefix)
+            .finish()
+    }
+}
@@ -0,0 +1,44 @@
+// Foreground window lookup module
+
+use super::types::ScreenRegion;
+
+/// Finds the bounds of an application's window
+pub type WindowLocator = fn(&str) -> Option<ScreenRegion>;
+
+/// The focused application and where its window was
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActiveWindow {
+    pub app: String,
+    pub bounds: ScreenRegion,
+}
+
+/// The focused window's application and bounds, if the platform reports them
+pub fn active_window() -> Option<ActiveWindow> {
+    let window = active_win_pos_rs::get_active_window().ok()?;
+    if window.app_name.is_empty() {
+        return None;
+    }
+    Some(ActiveWindow {
+        app: window.app_name,
+        bounds: ScreenRegion {
+            x: window.position.x.round() as i32,
+            y: window.position.y.round() as i32,
+            width: window.position.width.max(0.0).round() as u32,
+            height: window.position.height.max(0.0).round() as u32,
+        },
+    })
+}
+
+/// Bounds of the first visible window belonging to `app`
+pub fn find_app_window(app: &str) -> Option<ScreenRegion> {
+    xcap::Window::all()
+        .ok()?
+        .into_iter()
+        .find(|window| !window.is_minimized() && window.app_name() == app)
+        .map(|window| ScreenRegion {
+            x: window.x(),
+            y: window.y(),
+            width: window.width(),
+            height: window.height(),
+        })
+}
@@ -0,0 +1,178 @@
+// Window-targeted input module (Windows only, experimental)
+//
+// Posts mouse and keyboard messages straight to one window instead of
+// injecting global input, so a macro can drive a background window while the
+// user keeps using the real mouse. Applications that read raw input or poll
+// the keyboard state will not see these events.
+
+use enigo::{Axis, Button, Coordinate, Direction, Key};
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM, POINT, WPARAM};
+use windows::Win32::Graphics::Gdi::ScreenToClient;
+use windows::Win32::UI::Input::KeyboardAndMouse::{MapVirtualKeyW, MAPVK_VK_TO_VSC, VIRTUAL_KEY};
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetCursorPos, GetWindowTextW, IsWindow, IsWindowVisible, PostMessageW, WM_CHAR,
+    WM_KEYDOWN, WM_KEYUP, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MBUTTONDOWN, WM_MBUTTONUP,
+    WM_MOUSEHWHEEL, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_RBUTTONDOWN, WM_RBUTTONUP,
+};
+
+use super::elevation;
+use super::input::InputSink;
+
+const MK_LBUTTON: usize = 0x0001;
+const MK_RBUTTON: usize = 0x0002;
+const MK_MBUTTON: usize = 0x0010;
+const WHEEL_DELTA: i32 = 120;
+
+/// Sends input to a single window with PostMessage
+pub struct WindowSink {
+    hwnd: isize,
+    /// Last cursor position in screen coordinates
+    cursor: (i32, i32),
+    /// MK_* flags for the buttons currently held
+    buttons: usize,
+}
+
+impl WindowSink {
+    /// Target the first visible top-level window whose title contains `title`
+    pub fn find(title: &str) -> Result<Self, String> {
+        let hwnd = find_window(title)
+            .ok_or_else(|| format!("No window with a title containing '{}'", title))?;
+        // Messages posted to an elevated window are dropped just like injected input
+        if elevation::is_window_blocked(hwnd) {
+            return Err(elevation::blocked_message(&format!("'{}'", title)));
+        }
+
+        let mut point = POINT::default();
+        let _ = unsafe { GetCursorPos(&mut point) };
+
+        Ok(Self {
+            hwnd,
+            cursor: (point.x, point.y),
+            buttons: 0,
+        })
+    }
+
+    fn post(&self, msg: u32, wparam: usize, lparam: isize) -> Result<(), String> {
+        let hwnd = HWND(self.hwnd);
+        if !unsafe { IsWindow(hwnd) }.as_bool() {
+            return Err("Target window was closed".to_string());
+        }
+        unsafe { PostMessageW(hwnd, msg, WPARAM(wparam), LPARAM(lparam)) }
+            .map_err(|e| format!("PostMessage failed: {}", e))
+    }
+
+    /// Cursor position relative to the target window's client area
+    fn client_lparam(&self) -> isize {
+        let mut point = POINT {
+            x: self.cursor.0,
+            y: self.cursor.1,
+        };
+        unsafe {
+            let _ = ScreenToClient(HWND(self.hwnd), &mut point);
+        }
+        make_lparam(point.x, point.y)
+    }
+}
+
+impl InputSink for WindowSink {
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> Result<(), String> {
+        self.cursor = match coordinate {
+            Coordinate::Abs => (x, y),
+            Coordinate::Rel => (self.cursor.0 + x, self.cursor.1 + y),
+        };
+        self.post(WM_MOUSEMOVE, self.buttons, self.client_lparam())
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+        let (down, up, flag) = match button {
+            Button::Right => (WM_RBUTTONDOWN, WM_RBUTTONUP, MK_RBUTTON),
+            Button::Middle => (WM_MBUTTONDOWN, WM_MBUTTONUP, MK_MBUTTON),
+            _ => (WM_LBUTTONDOWN, WM_LBUTTONUP, MK_LBUTTON),
+        };
+
+        if matches!(direction, Direction::Press | Direction::Click) {
+            self.buttons |= flag;
+            self.post(down, self.buttons, self.client_lparam())?;
+        }
+        if matches!(direction, Direction::Release | Direction::Click) {
+            self.buttons &= !flag;
+            self.post(up, self.buttons, self.client_lparam())?;
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        // Characters are delivered as WM_CHAR so they don't depend on the keyboard layout
+        if let Key::Unicode(ch) = key {
+            if matches!(direction, Direction::Press | Direction::Click) {
+                let mut units = [0u16; 2];
+                for unit in ch.encode_utf16(&mut units) {
+                    self.post(WM_CHAR, *unit as usize, 1)?;
+                }
+            }
+            return Ok(());
+        }
+
+        let vk = VIRTUAL_KEY::try_from(key)
+            .map_err(|e| format!("Key {:?} has no virtual key: {}", key, e))?;
+        let scan = unsafe { MapVirtualKeyW(vk.0 as u32, MAPVK_VK_TO_VSC) } as isize;
+
+        if matches!(direction, Direction::Press | Direction::Click) {
+            self.post(WM_KEYDOWN, vk.0 as usize, 1 | (scan << 16))?;
+        }
+        if matches!(direction, Direction::Release | Direction::Click) {
+            self.post(WM_KEYUP, vk.0 as usize, 1 | (scan << 16) | (3 << 30))?;
+        }
+        Ok(())
+    }
+
+    fn scroll(&mut self, amount: i32, axis: Axis) -> Result<(), String> {
+        // enigo scrolls down for positive amounts, the wheel message scrolls up
+        let (msg, delta) = match axis {
+            Axis::Vertical => (WM_MOUSEWHEEL, -amount * WHEEL_DELTA),
+            Axis::Horizontal => (WM_MOUSEHWHEEL, amount * WHEEL_DELTA),
+        };
+        let wparam = ((delta as u16 as usize) << 16) | self.buttons;
+        // Wheel messages carry screen coordinates
+        self.post(msg, wparam, make_lparam(self.cursor.0, self.cursor.1))
+    }
+
+    fn cursor_position(&self) -> Result<(i32, i32), String> {
+        Ok(self.cursor)
+    }
+}
+
+fn make_lparam(x: i32, y: i32) -> isize {
+    ((y as u16 as u32) << 16 | (x as u16 as u32)) as i32 as isize
+}
+
+fn find_window(title: &str) -> Option<isize> {
+    struct Search {
+        needle: String,
+        found: Option<isize>,
+    }
+
+    unsafe extern "system" fn visit(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let search = &mut *(lparam.0 as *mut Search);
+        if !IsWindowVisible(hwnd).as_bool() {
+            return BOOL(1);
+        }
+
+        let mut buffer = [0u16; 512];
+        let len = GetWindowTextW(hwnd, &mut buffer).max(0) as usize;
+        let text = String::from_utf16_lossy(&buffer[..len]).to_lowercase();
+        if !text.is_empty() && text.contains(&search.needle) {
+            search.found = Some(hwnd.0);
+            return BOOL(0);
+        }
+        BOOL(1)
+    }
+
+    let mut search = Search {
+        needle: title.to_lowercase(),
+        found: None,
+    };
+    // EnumWindows reports an error when the callback stops early, which is expected here
+    let _ = unsafe { EnumWindows(Some(visit), LPARAM(&mut search as *mut Search as isize)) };
+    search.found
+}
@@ -0,0 +1,169 @@
+// xdotool backend module (Linux only)
+//
+// Injects input by running the xdotool command, which goes through the XTEST
+// extension. Slower than enigo since every event starts a process, but some
+// X11 applications and games only accept its events. Needs xdotool on PATH.
+
+use enigo::{Axis, Button, Coordinate, Direction, Key};
+use std::io::ErrorKind;
+use std::process::Command;
+
+use super::input::InputSink;
+
+/// Injects input through the xdotool command
+pub struct XdotoolSink;
+
+impl XdotoolSink {
+    fn run(args: &[String]) -> Result<String, String> {
+        let output = Command::new("xdotool").args(args).output().map_err(|e| {
+            if e.kind() == ErrorKind::NotFound {
+                "xdotool is not installed".to_string()
+            } else {
+                format!("Failed to run xdotool: {}", e)
+            }
+        })?;
+        if !output.status.success() {
+            return Err(format!(
+                "xdotool {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl InputSink for XdotoolSink {
+    fn move_mouse(&mut self, x: i32, y: i32, coordinate: Coordinate) -> Result<(), String> {
+        let command = match coordinate {
+            Coordinate::Abs => "mousemove",
+            Coordinate::Rel => "mousemove_relative",
+        };
+        // "--" keeps negative offsets from being read as options
+        Self::run(&[
+            command.to_string(),
+            "--".to_string(),
+            x.to_string(),
+            y.to_string(),
+        ])
+        .map(|_| ())
+    }
+
+    fn button(&mut self, button: Button, direction: Direction) -> Result<(), String> {
+        let number = match button {
+            Button::Left => 1,
+            Button::Middle => 2,
+            Button::Right => 3,
+            Button::ScrollUp => 4,
+            Button::ScrollDown => 5,
+            Button::ScrollLeft => 6,
+            Button::ScrollRight => 7,
+            Button::Back => 8,
+            Button::Forward => 9,
+        };
+        Self::run(&[
+            direction_command(direction, "mouse", "click"),
+            number.to_string(),
+        ])
+        .map(|_| ())
+    }
+
+    fn key(&mut self, key: Key, direction: Direction) -> Result<(), String> {
+        let name = keysym_name(key).ok_or_else(|| format!("Key {:?} has no xdotool name", key))?;
+        Self::run(&[direction_command(direction, "key", "key"), name]).map(|_| ())
+    }
+
+    fn scroll(&mut self, amount: i32, axis: Axis) -> Result<(), String> {
+        // Wheel "buttons": 4/5 scroll up/down, 6/7 left/right
+        let button = match (axis, amount < 0) {
+            (Axis::Vertical, true) => "4",
+            (Axis::Vertical, false) => "5",
+            (Axis::Horizontal, true) => "6",
+            (Axis::Horizontal, false) => "7",
+        };
+        Self::run(&[
+            "click".to_string(),
+            "--repeat".to_string(),
+            amount.unsigned_abs().to_string(),
+            button.to_string(),
+        ])
+        .map(|_| ())
+    }
+
+    fn cursor_position(&self) -> Result<(i32, i32), String> {
+        let output = Self::run(&["getmouselocation".to_string(), "--shell".to_string()])?;
+        let value = |name: &str| {
+            output
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix('='))
+                .and_then(|v| v.trim().parse().ok())
+        };
+        value("X")
+            .zip(value("Y"))
+            .ok_or_else(|| "Unexpected output from xdotool getmouselocation".to_string())
+    }
+}
+
+/// "keydown"/"keyup"/"key" style command for a direction
+fn direction_command(direction: Direction, prefix: &str, click: &str) -> String {
+    match direction {
+        Direction::Press => format!("{}down", prefix),
+        Direction::Release => format!("{}up", prefix),
+        Direction::Click => click.to_string(),
+    }
+}
+
+/// X keysym name xdotool understands for a key
+fn keysym_name(key: Key) -> Option<String> {
+    let name = match key {
+        Key::Unicode(ch) if ch.is_ascii_alphanumeric() => return Some(ch.to_string()),
+        // X accepts any character as a "U<hex>" keysym
+        Key::Unicode(ch) => return Some(format!("U{:04X}", ch as u32)),
+        // Numpad keys are passed as raw keysyms
+        Key::Other(keysym) => return Some(format!("0x{:x}", keysym)),
+        Key::Return => "Return",
+        Key::Tab => "Tab",
+        Key::Space => "space",
+        Key::Backspace => "BackSpace",
+        Key::Escape => "Escape",
+        Key::Delete => "Delete",
+        Key::Home => "Home",
+        Key::End => "End",
+        Key::PageUp => "Prior",
+        Key::PageDown => "Next",
+        Key::LeftArrow => "Left",
+        Key::RightArrow => "Right",
+        Key::UpArrow => "Up",
+        Key::DownArrow => "Down",
+        Key::Shift | Key::LShift => "Shift_L",
+        Key::RShift => "Shift_R",
+        Key::Control | Key::LControl => "Control_L",
+        Key::RControl => "Control_R",
+        Key::Alt => "Alt_L",
+        Key::Meta => "Super_L",
+        Key::CapsLock => "Caps_Lock",
+        other => {
+            // F1..F35 share their names with X
+            let name = format!("{:?}", other);
+            let is_function_key = name
+                .strip_prefix('F')
+                .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+            return is_function_key.then_some(name);
+        }
+    };
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_keys_to_x_keysyms() {
+        assert_eq!(keysym_name(Key::Unicode('w')).as_deref(), Some("w"));
+        assert_eq!(keysym_name(Key::Unicode('!')).as_deref(), Some("U0021"));
+        assert_eq!(keysym_name(Key::PageDown).as_deref(), Some("Next"));
+        assert_eq!(keysym_name(Key::F5).as_deref(), Some("F5"));
+        assert_eq!(keysym_name(Key::Break), None);
+    }
+}
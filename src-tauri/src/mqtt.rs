@@ -0,0 +1,170 @@
+// MQTT bridge module
+//
+// Lets home automation (e.g. Home Assistant) drive MacroX over MQTT:
+//   <prefix>/play      payload: macro id or name, plays it
+//   <prefix>/stop      stops the running macro
+//   <prefix>/status    "online" / "offline" (retained, last will)
+//   <prefix>/playback  current playback state as JSON
+
+use parking_lot::Mutex;
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::Manager;
+use tracing::{error, info, warn};
+
+use crate::app::{play_stored_macro_in_background, AppState};
+use crate::macrox_core::types::{MqttSettings, PlaybackStatus};
+
+/// Wait before polling again after a connection error
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Connection state, kept in Tauri's managed state
+#[derive(Default)]
+pub struct MqttBridge {
+    connection: Mutex<Option<BridgeConnection>>,
+    /// Bumped on every reconfigure so stale connection threads exit
+    generation: Arc<AtomicU64>,
+}
+
+struct BridgeConnection {
+    client: Client,
+    prefix: String,
+    last_state: Option<String>,
+}
+
+/// Connect, reconnect or disconnect to match the given settings
+pub fn apply_settings(app: &tauri::AppHandle, settings: &MqttSettings) {
+    let Some(bridge) = app.try_state::<MqttBridge>() else {
+        return;
+    };
+
+    let generation = bridge.generation.fetch_add(1, Ordering::SeqCst) + 1;
+    if let Some(old) = bridge.connection.lock().take() {
+        publish_offline(&old);
+        let _ = old.client.disconnect();
+    }
+
+    if !settings.enabled || settings.host.is_empty() {
+        return;
+    }
+
+    let prefix = settings.topic_prefix.trim_end_matches('/').to_string();
+    let mut options = MqttOptions::new("macrox", settings.host.clone(), settings.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    options.set_last_will(LastWill::new(
+        format!("{}/status", prefix),
+        "offline",
+        QoS::AtLeastOnce,
+        true,
+    ));
+    if let Some(username) = &settings.username {
+        options.set_credentials(
+            username.clone(),
+            settings.password.clone().unwrap_or_default(),
+        );
+    }
+
+    let (client, mut connection) = Client::new(options, 10);
+    *bridge.connection.lock() = Some(BridgeConnection {
+        client: client.clone(),
+        prefix: prefix.clone(),
+        last_state: None,
+    });
+    info!(
+        "Connecting to MQTT broker {}:{}",
+        settings.host, settings.port
+    );
+
+    let app = app.clone();
+    let current_generation = Arc::clone(&bridge.generation);
+    thread::spawn(move || {
+        for notification in connection.iter() {
+            if current_generation.load(Ordering::SeqCst) != generation {
+                break;
+            }
+
+            match notification {
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    // Subscriptions don't survive a clean-session reconnect
+                    for topic in ["play", "stop"] {
+                        if let Err(e) =
+                            client.subscribe(format!("{}/{}", prefix, topic), QoS::AtLeastOnce)
+                        {
+                            error!("MQTT subscribe failed: {}", e);
+                        }
+                    }
+                    let _ = client.publish(
+                        format!("{}/status", prefix),
+                        QoS::AtLeastOnce,
+                        true,
+                        "online",
+                    );
+                    info!("MQTT connected");
+                }
+                Ok(Event::Incoming(Packet::Publish(publish))) => {
+                    let payload = String::from_utf8_lossy(&publish.payload).trim().to_string();
+                    handle_message(&app, &prefix, &publish.topic, &payload);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("MQTT connection error: {}", e);
+                    thread::sleep(RECONNECT_DELAY);
+                }
+            }
+        }
+    });
+}
+
+fn handle_message(app: &tauri::AppHandle, prefix: &str, topic: &str, payload: &str) {
+    match topic.strip_prefix(prefix).and_then(|t| t.strip_prefix('/')) {
+        Some("play") if !payload.is_empty() => {
+            info!("MQTT requested playback of '{}'", payload);
+            play_stored_macro_in_background(app, payload);
+        }
+        Some("stop") => {
+            info!("MQTT requested playback stop");
+            let state = app.state::<AppState>();
+            state.playback.stop();
+            state.pool.stop_all();
+        }
+        _ => {}
+    }
+}
+
+/// Publish playback state changes; progress within the same state is skipped
+pub fn publish_status(app: &tauri::AppHandle, status: &PlaybackStatus) {
+    let Some(bridge) = app.try_state::<MqttBridge>() else {
+        return;
+    };
+    let mut connection = bridge.connection.lock();
+    let Some(connection) = connection.as_mut() else {
+        return;
+    };
+
+    let state = status.summary();
+    if connection.last_state.as_deref() == Some(state.as_str()) {
+        return;
+    }
+    connection.last_state = Some(state);
+
+    if let Ok(payload) = serde_json::to_vec(status) {
+        let _ = connection.client.try_publish(
+            format!("{}/playback", connection.prefix),
+            QoS::AtMostOnce,
+            true,
+            payload,
+        );
+    }
+}
+
+fn publish_offline(connection: &BridgeConnection) {
+    let _ = connection.client.try_publish(
+        format!("{}/status", connection.prefix),
+        QoS::AtLeastOnce,
+        true,
+        "offline",
+    );
+}
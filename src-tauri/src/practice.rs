@@ -0,0 +1,57 @@
+// Practice screen module
+//
+// With practice mode on, macros play into a MacroX window instead of the
+// desktop. The window draws a marker wherever a click or scroll would have
+// landed and prints the typed keys, so new users can try macros and settings
+// safely.
+
+use std::sync::Arc;
+use tauri::{Manager, WebviewUrl, WebviewWindowBuilder};
+use tracing::warn;
+
+use crate::display;
+use crate::events::{self, BusEventType};
+use crate::macrox_core::practice::{PracticeAction, PracticeSink};
+
+/// The frontend renders the practice screen instead of the main UI in this window
+pub const PRACTICE_LABEL: &str = "practice";
+
+/// Show the practice window, opening it if needed
+pub fn open(app: &tauri::AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(PRACTICE_LABEL) {
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+
+    WebviewWindowBuilder::new(app, PRACTICE_LABEL, WebviewUrl::App("index.html".into()))
+        .title("MacroX Practice Screen")
+        .inner_size(960.0, 600.0)
+        .center()
+        .build()
+        .map_err(|e| format!("Failed to open practice screen: {}", e))?;
+    Ok(())
+}
+
+/// Sink that sends playback to the practice window, starting in the middle
+/// of the primary screen
+pub fn sink(app: &tauri::AppHandle) -> PracticeSink {
+    if let Err(e) = open(app) {
+        warn!("{}", e);
+    }
+    let start = display::monitor_bounds(app)
+        .first()
+        .map(|screen| {
+            (
+                screen.x + screen.width as i32 / 2,
+                screen.y + screen.height as i32 / 2,
+            )
+        })
+        .unwrap_or_default();
+
+    let handle = app.clone();
+    PracticeSink::new(
+        start,
+        Arc::new(move |action: &PracticeAction| {
+            events::emit_batched(&handle, BusEventType::PracticeActions, action);
+        }),
+    )
+}
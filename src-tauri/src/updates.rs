@@ -0,0 +1,52 @@
+// Update check module
+//
+// Asks the GitHub releases API whether a newer MacroX was published and
+// returns its release notes and release page for the UI to show. Installing
+// is left to the user until releases are signed for in-app updates.
+
+use reqwest::header::{ACCEPT, USER_AGENT};
+use tracing::{info, warn};
+
+use crate::app::load_app_settings_from_store;
+use crate::events::{self, BusEventType};
+use crate::macrox_core::release::{self, GithubRelease, UpdateInfo};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/royce-mathew/MacroX/releases";
+
+/// Compare the latest published release with the running version
+pub async fn check(app: &tauri::AppHandle) -> Result<UpdateInfo, String> {
+    let settings = load_app_settings_from_store(app).updates;
+    let releases: Vec<GithubRelease> = reqwest::Client::new()
+        .get(RELEASES_URL)
+        // GitHub rejects API requests without a user agent
+        .header(USER_AGENT, concat!("MacroX/", env!("CARGO_PKG_VERSION")))
+        .header(ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to fetch releases: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected releases response: {}", e))?;
+
+    let current = app.package_info().version.to_string();
+    release::latest_update(&releases, &current, settings.include_prereleases)
+}
+
+/// Check in the background and tell the UI when an update is out
+pub fn check_on_startup(app: &tauri::AppHandle) {
+    if !load_app_settings_from_store(app).updates.check_on_startup {
+        return;
+    }
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match check(&handle).await {
+            Ok(info) if info.update_available => {
+                info!("MacroX {} is available", info.latest_version);
+                events::emit(&handle, BusEventType::UpdateAvailable, &info);
+            }
+            Ok(_) => info!("MacroX is up to date"),
+            Err(e) => warn!("Update check failed: {}", e),
+        }
+    });
+}
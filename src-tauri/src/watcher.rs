@@ -0,0 +1,147 @@
+// Macro store file watcher module
+
+use chrono::{DateTime, Utc};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::events::{self, BusEventType};
+use crate::macrox_core::storage::MacroPersister;
+use crate::macrox_core::types::Macro;
+
+/// How long the file must stay quiet before it is re-read
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Watch the macros store file and merge external edits into memory.
+///
+/// The returned watcher must be kept alive for as long as changes should be picked up.
+pub fn watch_macros_file(
+    app: &tauri::AppHandle,
+    path: PathBuf,
+    macros: Arc<Mutex<Vec<Arc<Macro>>>>,
+    persister: MacroPersister,
+) -> Result<RecommendedWatcher, String> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| "Macros file has no parent directory".to_string())?
+        .to_path_buf();
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+
+    let file_name = path.file_name().map(|n| n.to_os_string());
+    let (tx, rx) = mpsc::channel::<()>();
+
+    // Watch the directory rather than the file so editors that replace the
+    // file (write temp + rename) are still detected
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                return;
+            }
+            if event
+                .paths
+                .iter()
+                .any(|p| p.file_name().map(|n| n.to_os_string()) == file_name)
+            {
+                let _ = tx.send(());
+            }
+        }
+    })
+    .map_err(|e| format!("Failed to create file watcher: {:?}", e))?;
+
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {:?}", dir.display(), e))?;
+
+    let app = app.clone();
+    thread::spawn(move || {
+        while rx.recv().is_ok() {
+            // Wait for the burst of write events to settle
+            while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+            if let Err(e) = reload_external_changes(&app, &path, &macros, &persister) {
+                error!("Failed to reload macros file: {}", e);
+            }
+        }
+    });
+
+    Ok(watcher)
+}
+
+fn reload_external_changes(
+    app: &tauri::AppHandle,
+    path: &Path,
+    macros: &Arc<Mutex<Vec<Arc<Macro>>>>,
+    persister: &MacroPersister,
+) -> Result<(), String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    if persister.is_own_write(contents.as_bytes()) {
+        return Ok(());
+    }
+
+    let value: serde_json::Value = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+    let external: Vec<Arc<Macro>> = match value.get("macros") {
+        Some(list) => serde_json::from_value(list.clone()).map_err(|e| e.to_string())?,
+        None => Vec::new(),
+    };
+
+    let modified_at: DateTime<Utc> = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(DateTime::from)
+        .unwrap_or_else(|_| Utc::now());
+
+    let mut current = macros.lock();
+    let merged = merge_external_macros(&current, external.clone(), modified_at);
+
+    // Nothing to do if the contents already match
+    if serde_json::to_value(&merged).ok() == serde_json::to_value(&*current).ok() {
+        return Ok(());
+    }
+
+    *current = merged.clone();
+
+    // Unsaved in-memory edits survived the merge, write them back
+    if serde_json::to_value(&merged).ok() != serde_json::to_value(&external).ok() {
+        persister.save(merged.clone());
+    }
+    drop(current);
+
+    info!(
+        "Macros file changed externally, reloaded {} macros",
+        merged.len()
+    );
+    events::emit(app, BusEventType::MacrosChanged, merged);
+
+    Ok(())
+}
+
+/// Merge macros read from disk with the in-memory list.
+///
+/// Disk entries win unless the in-memory copy was updated more recently.
+/// In-memory macros missing from disk are kept only if they changed after the
+/// file was written, otherwise they are treated as deleted externally.
+fn merge_external_macros(
+    current: &[Arc<Macro>],
+    external: Vec<Arc<Macro>>,
+    file_modified_at: DateTime<Utc>,
+) -> Vec<Arc<Macro>> {
+    let mut merged: Vec<Arc<Macro>> = external
+        .into_iter()
+        .map(|ext| match current.iter().find(|m| m.id == ext.id) {
+            Some(mem) if mem.updated_at > ext.updated_at => mem.clone(),
+            _ => ext,
+        })
+        .collect();
+
+    for mem in current {
+        if !merged.iter().any(|m| m.id == mem.id) && mem.updated_at > file_modified_at {
+            merged.push(mem.clone());
+        }
+    }
+
+    merged
+}
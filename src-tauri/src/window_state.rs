@@ -0,0 +1,102 @@
+// Window geometry module
+//
+// Remembers where the main window was and how big it was, saved shortly after
+// it is moved or resized, and puts it back there on the next launch. A saved
+// position is only reused while its monitor is still connected and the top
+// of the window would land on screen, so a window can't come back stranded on
+// an unplugged display.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{Manager, PhysicalPosition, PhysicalSize, WebviewWindow, WindowEvent};
+use tracing::{error, info};
+
+use crate::app::{load_app_settings_from_store, save_app_settings};
+use crate::display;
+use crate::macrox_core::types::WindowGeometry;
+
+/// Moves and resizes arrive in bursts while dragging; save once they settle
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// How far into the window a point must be on screen to grab it by the title bar
+const GRAB_MARGIN: i32 = 40;
+
+static SAVE_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+/// Restore the saved geometry, then keep it up to date as the window changes
+pub fn restore_and_track(window: &WebviewWindow, saved: Option<&WindowGeometry>) {
+    if let Some(geometry) = saved {
+        restore(window, geometry);
+    }
+
+    let tracked = window.clone();
+    window.on_window_event(move |event| {
+        if matches!(event, WindowEvent::Moved(_) | WindowEvent::Resized(_)) {
+            schedule_save(&tracked);
+        }
+    });
+}
+
+fn restore(window: &WebviewWindow, geometry: &WindowGeometry) {
+    if geometry.width > 0 && geometry.height > 0 {
+        let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+    }
+
+    let monitors = window.available_monitors().unwrap_or_default();
+    let monitor_connected = match &geometry.monitor {
+        Some(name) => monitors.iter().any(|m| m.name() == Some(name)),
+        None => true,
+    };
+    let grab_x = geometry.x + GRAB_MARGIN.min(geometry.width as i32 / 2);
+    let grab_y = geometry.y + GRAB_MARGIN.min(geometry.height as i32 / 2);
+    let on_screen = display::monitor_bounds(window.app_handle())
+        .iter()
+        .any(|bounds| bounds.contains(grab_x, grab_y));
+
+    if monitor_connected && on_screen {
+        let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+    } else {
+        info!("Saved window position is off screen, centering instead");
+        let _ = window.center();
+    }
+}
+
+fn schedule_save(window: &WebviewWindow) {
+    if SAVE_SCHEDULED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let window = window.clone();
+    thread::spawn(move || {
+        thread::sleep(SAVE_DELAY);
+        SAVE_SCHEDULED.store(false, Ordering::SeqCst);
+        if let Err(e) = save(&window) {
+            error!("Failed to save window geometry: {}", e);
+        }
+    });
+}
+
+fn save(window: &WebviewWindow) -> Result<(), String> {
+    // Keep the last normal geometry to restore into, not the minimized or maximized one
+    if window.is_minimized().unwrap_or(false) || window.is_maximized().unwrap_or(false) {
+        return Ok(());
+    }
+    let position = window.outer_position().map_err(|e| e.to_string())?;
+    let size = window.inner_size().map_err(|e| e.to_string())?;
+    let monitor = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .and_then(|m| m.name().cloned());
+
+    let app = window.app_handle();
+    let mut app_settings = load_app_settings_from_store(app);
+    app_settings.window_geometry = Some(WindowGeometry {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+        monitor,
+    });
+    save_app_settings(app, &app_settings)
+}
@@ -1,7 +1,16 @@
 import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { attachConsole, info, error as logError } from "@tauri-apps/plugin-log";
-import { listen } from "@tauri-apps/api/event";
+import {
+  attachConsole,
+  info,
+  warn,
+  error as logError,
+} from "@tauri-apps/plugin-log";
+import {
+  describeRecordingWarning,
+  listenBus,
+  type RunOutputsPayload,
+} from "./lib/events";
 import {
   Macro,
   MacroEvent,
@@ -9,6 +18,10 @@ import {
   RecordingSettings,
   PlaybackSettings,
   AppSettings,
+  DisplayInfo,
+  RecordingSession,
+  RecordingWarning,
+  SaveMacroResult,
 } from "./types/macro";
 import { ViewType, MainLayout } from "./components/layout/main-layout";
 import { RecordingPanel } from "./components/recording/recording-panel";
@@ -26,6 +39,7 @@ function App() {
   const [isPlaying, setIsPlaying] = useState(false);
   const [recordedEvents, setRecordedEvents] = useState<MacroEvent[]>([]);
   const [isAlwaysOnTop, setIsAlwaysOnTop] = useState(false);
+  const confirmDeleteRef = useRef(true);
 
   // Window Manager Hook
   const { isMiniMode, toggleMiniMode } = useWindowManager();
@@ -36,9 +50,12 @@ function App() {
 
   const [recordingSettings, setRecordingSettings] = useState<RecordingSettings>(
     {
+      version: 2,
       recordMouseMovement: true,
       recordMouseClicks: true,
       recordKeyboard: true,
+      recordMouseWheel: true,
+      recordKeyCombos: true,
     }
   );
 
@@ -47,6 +64,7 @@ function App() {
     recordStop: "F10",
     playbackStart: "F11",
     playbackStop: "F12",
+    mark: "F8",
   });
 
   // Refs for state access in event listeners
@@ -104,14 +122,24 @@ function App() {
     }).length;
   };
 
-  const handleStartRecording = async () => {
+  const handleStartRecording = async (allowDuringPlayback = false) => {
     try {
-      await invoke("start_recording", { settings: recordingSettings });
+      await invoke("start_recording", {
+        settings: recordingSettings,
+        allowDuringPlayback,
+      });
       setIsRecording(true);
       setRecordedEvents([]);
       info("Recording started");
       handleNotify("Recording started", "success");
     } catch (error) {
+      if (
+        !allowDuringPlayback &&
+        String(error).includes("while a macro is playing") &&
+        window.confirm(`${error}\n\nRecord anyway?`)
+      ) {
+        return handleStartRecording(true);
+      }
       logError(`Failed to start recording: ${error}`);
       handleNotify(`Failed to start recording: ${error}`, "error");
     }
@@ -119,23 +147,30 @@ function App() {
 
   const handleStopRecording = async () => {
     try {
-      const events = await invoke<MacroEvent[]>("stop_recording");
+      const { events, metadata, droppedEvents, warnings } =
+        await invoke<RecordingSession>("stop_recording");
       setIsRecording(false);
       setRecordedEvents(events);
 
       const actionCount = getActionEventCount(events);
       info(
-        `Recording stopped. Captured ${events.length} total events (${actionCount} actions)`
+        `Recording stopped. Captured ${events.length} total events (${actionCount} actions), dropped ${droppedEvents}`
       );
+      warnings.forEach((w) => warn(describeRecordingWarning(w)));
       handleNotify(
-        `Recording stopped. Captured ${events.length} events`,
-        "success"
+        warnings.length > 0
+          ? `Recording stopped. Captured ${events.length} events with ${warnings.length} warning(s)`
+          : `Recording stopped. Captured ${events.length} events`,
+        warnings.length > 0 ? "warning" : "success"
       );
 
       if (events.length > 0) {
+        const displayInfo = await invoke<DisplayInfo | null>(
+          "get_display_info"
+        );
         const newMacro: Macro = {
           id: Date.now().toString(),
-          name: `Macro ${macros.length + 1}`,
+          name: metadata.suggestedName,
           description: "",
           events,
           recordingSettings,
@@ -146,9 +181,30 @@ function App() {
           },
           createdAt: new Date(),
           updatedAt: new Date(),
+          displayInfo: displayInfo ?? undefined,
         };
         setMacros([...macros, newMacro]);
-        await invoke("save_macro", { macroData: newMacro });
+        const { duplicateOf, parts } = await invoke<SaveMacroResult>(
+          "save_macro",
+          { macroData: newMacro }
+        );
+        if (parts.length > 0) {
+          handleNotify(
+            `Long recording saved as ${parts.length} chained parts`,
+            "info"
+          );
+        }
+
+        // Same steps as a stored macro: offer to keep just the new one
+        if (
+          duplicateOf &&
+          window.confirm(
+            `This recording has the same steps as "${duplicateOf.name}". Replace "${duplicateOf.name}" with it?`
+          )
+        ) {
+          await invoke("delete_macro", { macroId: duplicateOf.id });
+          setMacros((current) => current.filter((m) => m.id !== duplicateOf.id));
+        }
 
         // Auto-select the newly created macro
         handleMacroSelect(newMacro.id);
@@ -193,12 +249,24 @@ function App() {
   };
 
   const handleStopPlayback = () => {
+    invoke("stop_playback").catch((e) => logError(String(e)));
     setIsPlaying(false);
     info("Playback stopped");
   };
 
   const handlePlayMacro = async (macro: Macro) => {
     try {
+      const missing = await invoke<string[]>("get_missing_privilege_grants", {
+        id: macro.id,
+      });
+      if (missing.length > 0) {
+        const allowed = window.confirm(
+          `"${macro.name}" can run commands, use the clipboard or type hidden text (${missing.join(", ")}). Allow it to play?`
+        );
+        if (!allowed) return;
+        await invoke("grant_privileged_events", { id: macro.id });
+      }
+
       setIsPlaying(true);
       info(`Playing macro: ${macro.name}`);
       handleNotify(`Playing macro: ${macro.name}`, "info");
@@ -208,6 +276,16 @@ function App() {
     } catch (error) {
       logError(`Failed to play macro: ${error}`);
       handleNotify(`Failed to play macro: ${error}`, "error");
+      if (String(error).includes("runs as administrator")) {
+        const relaunch = window.confirm(
+          `${error}\n\nRestart MacroX as administrator now?`
+        );
+        if (relaunch) {
+          await invoke("relaunch_elevated").catch((e) =>
+            handleNotify(`Failed to restart: ${e}`, "error")
+          );
+        }
+      }
     } finally {
       setIsPlaying(false);
     }
@@ -218,6 +296,10 @@ function App() {
   };
 
   const handleDeleteMacro = async (macroId: string) => {
+    if (confirmDeleteRef.current) {
+      const name = macros.find((m) => m.id === macroId)?.name ?? "this macro";
+      if (!window.confirm(`Delete ${name}?`)) return;
+    }
     try {
       setMacros(macros.filter((m) => m.id !== macroId));
       await invoke("delete_macro", { macroId });
@@ -242,7 +324,8 @@ function App() {
   const handleExportMacro = async (macro: Macro) => {
     try {
       info(`Exporting macro: ${JSON.stringify(macro)}`);
-      await invoke("export_macro", { macroData: macro });
+      const path = await invoke<string>("export_macro", { macroData: macro });
+      handleNotify(`Macro exported to ${path}`, "success");
     } catch (error) {
       logError(`Failed to export macro: ${error}`);
     }
@@ -302,6 +385,7 @@ function App() {
       try {
         const settings = await invoke<AppSettings>("get_app_settings");
         setIsAlwaysOnTop(settings.alwaysOnTop);
+        confirmDeleteRef.current = settings.confirmDelete ?? true;
         if (settings.lastSelectedMacroId) {
           setSelectedPlaybackMacroId(settings.lastSelectedMacroId);
         }
@@ -320,6 +404,7 @@ function App() {
           recordStop: hotkeySettings.recordStop,
           playbackStart: hotkeySettings.playbackStart,
           playbackStop: hotkeySettings.playbackStop,
+          mark: hotkeySettings.mark ?? "F8",
         });
         info(`Hotkeys updated: ${JSON.stringify(hotkeySettings)}`);
       } catch (error) {
@@ -380,7 +465,7 @@ function App() {
     let unlistenFunctions: (() => void)[] = [];
 
     const setupListeners = async () => {
-      const u1 = await listen("hotkey:record-start", () => {
+      const u1 = await listenBus("hotkey-record-start", () => {
         if (!isMounted) return;
         // Disable hotkeys in settings to prevent accidental triggering while configuring
         if (currentViewRef.current === "settings") return;
@@ -415,38 +500,117 @@ function App() {
       }
       unlistenFunctions.push(u1);
 
-      // Listen for recording warnings (hotkeys pressed)
-      const uWarning = await listen<string>("recording-warning", (event) => {
-        if (!isMounted) return;
-        if (isMiniModeRef.current) {
-          setNotificationMsg(event.payload || "Hotkey detected");
-          setTimeout(() => setNotificationMsg(""), 3000);
-        } else {
-          toast.warning(event.payload || "Hotkey detected and ignored");
+      // Listen for recording warnings (hotkeys pressed, input dropped, ...)
+      const uWarning = await listenBus<RecordingWarning>(
+        "recording-warning",
+        (event) => {
+          if (!isMounted) return;
+          const message = describeRecordingWarning(event.payload);
+          if (isMiniModeRef.current) {
+            setNotificationMsg(message);
+            setTimeout(() => setNotificationMsg(""), 3000);
+          } else {
+            toast.warning(message);
+          }
         }
-      });
+      );
       if (!isMounted) {
         uWarning();
         return;
       }
       unlistenFunctions.push(uWarning);
 
-      const u2 = await listen("hotkey:record-stop", () => {
+      const uSettings = await listenBus<AppSettings>(
+        "settings-changed",
+        (event) => {
+          if (!isMounted) return;
+          setIsAlwaysOnTop(event.payload.alwaysOnTop);
+          confirmDeleteRef.current = event.payload.confirmDelete ?? true;
+        },
+      );
+      if (!isMounted) {
+        uSettings();
+        return;
+      }
+      unlistenFunctions.push(uSettings);
+
+      // Macros file was edited outside the app and reloaded by the backend
+      const uMacros = await listenBus<Macro[]>("macros-changed", (event) => {
+        if (!isMounted) return;
+        setMacros(event.payload);
+        info(`Macros reloaded from disk (${event.payload.length})`);
+      });
+      if (!isMounted) {
+        uMacros();
+        return;
+      }
+      unlistenFunctions.push(uMacros);
+
+      // Playback is paused on a destructive key combination, or at the
+      // infinite repeat limit, until answered
+      const uConfirm = await listenBus<{
+        id: number;
+        kind: "destructive" | "repeatLimit";
+        action: string;
+      }>(
+        "confirmation-requested",
+        (event) => {
+          if (!isMounted) return;
+          const approved = window.confirm(
+            event.payload.kind === "repeatLimit"
+              ? `The macro has repeated many times. Run ${event.payload.action}?`
+              : `The macro is about to press ${event.payload.action}. Continue?`,
+          );
+          invoke("answer_confirmation", {
+            id: event.payload.id,
+            approved,
+          }).catch((e) => logError(String(e)));
+        },
+      );
+      if (!isMounted) {
+        uConfirm();
+        return;
+      }
+      unlistenFunctions.push(uConfirm);
+
+      // Values read during a run, e.g. an order number taken from a page
+      const uOutputs = await listenBus<RunOutputsPayload>(
+        "run-outputs",
+        (event) => {
+          if (!isMounted) return;
+          const entries = Object.entries(event.payload.outputs);
+          if (entries.length === 0) return;
+          handleNotify(
+            entries.map(([name, value]) => `${name}: ${value}`).join("\n"),
+            "info",
+          );
+        },
+      );
+      if (!isMounted) {
+        uOutputs();
+        return;
+      }
+      unlistenFunctions.push(uOutputs);
+
+      const u2 = await listenBus("hotkey-record-stop", () => {
         if (!isMounted) return;
         if (currentViewRef.current === "settings") return;
 
         if (isRecordingRef.current) {
-          invoke<MacroEvent[]>("stop_recording")
-            .then((events) => {
+          invoke<RecordingSession>("stop_recording")
+            .then(async ({ events, metadata }) => {
               setIsRecording(false);
               setRecordedEvents(events);
               info(`Recording stopped. Captured ${events.length} events`);
 
               if (events.length > 0) {
+                const displayInfo = await invoke<DisplayInfo | null>(
+                  "get_display_info"
+                ).catch(() => null);
                 setMacros((prevMacros) => {
                   const newMacro: Macro = {
                     id: Date.now().toString(),
-                    name: `Macro ${prevMacros.length + 1}`,
+                    name: metadata.suggestedName,
                     description: "",
                     events,
                     recordingSettings: recordingSettingsRef.current,
@@ -457,6 +621,7 @@ function App() {
                     },
                     createdAt: new Date(),
                     updatedAt: new Date(),
+                    displayInfo: displayInfo ?? undefined,
                   };
                   // Persist the new macro
                   invoke("save_macro", { macroData: newMacro }).catch((e) =>
@@ -493,7 +658,7 @@ function App() {
       }
       unlistenFunctions.push(u2);
 
-      const u3 = await listen("hotkey:playback-start", () => {
+      const u3 = await listenBus("hotkey-playback-start", () => {
         if (!isMounted) return;
         if (currentViewRef.current === "settings") return;
 
@@ -539,11 +704,12 @@ function App() {
       }
       unlistenFunctions.push(u3);
 
-      const u4 = await listen("hotkey:playback-stop", () => {
+      const u4 = await listenBus("hotkey-playback-stop", () => {
         if (!isMounted) return;
         if (currentViewRef.current === "settings") return;
 
         if (isPlayingRef.current) {
+          invoke("stop_playback").catch((e) => logError(String(e)));
           setIsPlaying(false);
         }
       });
@@ -574,7 +740,7 @@ function App() {
           <MiniRecordingPanel
             isRecording={isRecording}
             notificationMsg={notificationMsg}
-            onStartRecording={handleStartRecording}
+            onStartRecording={() => handleStartRecording()}
             onStopRecording={handleStopRecording}
             // Recording Props
             recordingSettings={recordingSettings}
@@ -594,7 +760,7 @@ function App() {
                 <RecordingPanel
                   isRecording={isRecording}
                   recordingSettings={recordingSettings}
-                  onStartRecording={handleStartRecording}
+                  onStartRecording={() => handleStartRecording()}
                   onStopRecording={handleStopRecording}
                   onSettingsChange={setRecordingSettings}
                 />
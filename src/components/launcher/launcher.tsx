@@ -0,0 +1,89 @@
+import React, { useEffect, useMemo, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { error as logError } from "@tauri-apps/plugin-log";
+import { Input } from "@/components/ui/input";
+import { cn } from "@/lib/utils";
+import { Macro } from "../../types/macro";
+
+/**
+ * Spotlight-style palette shown in the "launcher" window
+ */
+export const Launcher: React.FC = () => {
+  const [macros, setMacros] = useState<Macro[]>([]);
+  const [query, setQuery] = useState("");
+  const [selected, setSelected] = useState(0);
+
+  useEffect(() => {
+    invoke<Macro[]>("load_all_macros")
+      .then(setMacros)
+      .catch((e) => logError(String(e)));
+  }, []);
+
+  const matches = useMemo(() => {
+    const needle = query.trim().toLowerCase();
+    return macros.filter(
+      (m) =>
+        needle === "" ||
+        m.name.toLowerCase().includes(needle) ||
+        m.description.toLowerCase().includes(needle)
+    );
+  }, [macros, query]);
+
+  useEffect(() => setSelected(0), [query]);
+
+  const launch = (macro: Macro | undefined) => {
+    if (!macro) return;
+    invoke("launch_macro", { id: macro.id }).catch((e) =>
+      logError(String(e))
+    );
+  };
+
+  const handleKeyDown = (e: React.KeyboardEvent<HTMLInputElement>) => {
+    if (e.key === "ArrowDown") {
+      e.preventDefault();
+      setSelected((i) => Math.min(i + 1, matches.length - 1));
+    } else if (e.key === "ArrowUp") {
+      e.preventDefault();
+      setSelected((i) => Math.max(i - 1, 0));
+    } else if (e.key === "Enter") {
+      launch(matches[selected]);
+    } else if (e.key === "Escape") {
+      invoke("toggle_launcher").catch((e) => logError(String(e)));
+    }
+  };
+
+  return (
+    <div className="flex h-screen flex-col gap-2 bg-background p-3">
+      <Input
+        autoFocus
+        placeholder="Play a macro..."
+        value={query}
+        onChange={(e) => setQuery(e.target.value)}
+        onKeyDown={handleKeyDown}
+      />
+      <ul className="flex-1 overflow-y-auto">
+        {matches.map((macro, i) => (
+          <li
+            key={macro.id}
+            className={cn(
+              "cursor-pointer rounded-md px-3 py-2 text-sm",
+              i === selected && "bg-accent text-accent-foreground"
+            )}
+            onMouseEnter={() => setSelected(i)}
+            onClick={() => launch(macro)}
+          >
+            <div className="font-medium">{macro.name}</div>
+            <div className="text-xs text-muted-foreground">
+              {macro.events.length} events
+            </div>
+          </li>
+        ))}
+        {matches.length === 0 && (
+          <li className="px-3 py-2 text-sm text-muted-foreground">
+            No matching macros
+          </li>
+        )}
+      </ul>
+    </div>
+  );
+};
@@ -96,6 +96,14 @@ export const MacroList: React.FC<MacroListProps> = ({
                         </span>
                       </>
                     )}
+                    {macro.playbackSettings.repeatMode === "duration" && (
+                      <>
+                        <span>•</span>
+                        <span className="text-primary font-medium">
+                          {macro.playbackSettings.repeatDurationMinutes ?? 1} min
+                        </span>
+                      </>
+                    )}
                     {macro.playbackSettings.repeatMode === "count" && (
                       <>
                         <span>•</span>
@@ -0,0 +1,93 @@
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { error as logError } from "@tauri-apps/plugin-log";
+import { listenBus } from "@/lib/events";
+import { DisplayInfo, PracticeAction } from "../../types/macro";
+
+/** Markers kept on screen before the oldest fade out */
+const MAX_MARKERS = 50;
+
+type Marker = Extract<PracticeAction, { kind: "click" | "scroll" }> & {
+  id: number;
+};
+
+/**
+ * Shown in the "practice" window: where a practice run would have clicked,
+ * scrolled and typed, drawn on a scaled-down copy of the screen
+ */
+export const PracticeScreen: React.FC = () => {
+  const [display, setDisplay] = useState<DisplayInfo | null>(null);
+  const [cursor, setCursor] = useState<{ x: number; y: number } | null>(null);
+  const [markers, setMarkers] = useState<Marker[]>([]);
+  const [typed, setTyped] = useState("");
+
+  useEffect(() => {
+    invoke<DisplayInfo | null>("get_display_info")
+      .then(setDisplay)
+      .catch((e) => logError(String(e)));
+
+    let nextId = 0;
+    const unlisten = listenBus<PracticeAction[]>("practice-actions", (event) => {
+      for (const action of event.payload) {
+        if (action.kind === "move") {
+          setCursor({ x: action.x, y: action.y });
+        } else if (action.kind === "key") {
+          if (action.text) setTyped((t) => (t + action.text).slice(-500));
+        } else if (action.kind === "scroll" || action.pressed) {
+          const marker = { ...action, id: nextId++ };
+          setCursor({ x: action.x, y: action.y });
+          setMarkers((m) => [...m, marker].slice(-MAX_MARKERS));
+        }
+      }
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  // Screen coordinates as a percentage of the practice area
+  const place = (x: number, y: number) => ({
+    left: `${(x / (display?.width || window.screen.width)) * 100}%`,
+    top: `${(y / (display?.height || window.screen.height)) * 100}%`,
+  });
+
+  return (
+    <div className="flex h-screen flex-col gap-2 bg-background p-3">
+      <div className="flex items-center justify-between text-sm">
+        <span className="font-medium">Practice screen</span>
+        <button
+          className="text-muted-foreground hover:text-foreground"
+          onClick={() => {
+            setMarkers([]);
+            setTyped("");
+          }}
+        >
+          Clear
+        </button>
+      </div>
+      <div className="relative flex-1 overflow-hidden rounded-md border bg-muted/30">
+        {markers.map((m) => (
+          <div
+            key={m.id}
+            className="absolute -translate-x-1/2 -translate-y-1/2 rounded-full border-2 border-primary px-1 text-[10px]"
+            style={place(m.x, m.y)}
+            title={m.kind === "click" ? `${m.button} click` : `Scroll ${m.amount}`}
+          >
+            {m.kind === "click" ? m.button[0] : "↕"}
+          </div>
+        ))}
+        {cursor && (
+          <div
+            className="absolute h-2 w-2 -translate-x-1/2 -translate-y-1/2 rounded-full bg-destructive"
+            style={place(cursor.x, cursor.y)}
+          />
+        )}
+      </div>
+      <div className="h-16 overflow-y-auto whitespace-pre-wrap rounded-md border p-2 font-mono text-xs">
+        {typed || (
+          <span className="text-muted-foreground">Typed text shows here</span>
+        )}
+      </div>
+    </div>
+  );
+};
@@ -4,6 +4,7 @@ import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Label } from "@/components/ui/label";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
+import { Checkbox } from "@/components/ui/checkbox";
 import {
   Select,
   SelectContent,
@@ -12,7 +13,15 @@ import {
   SelectValue,
 } from "@/components/ui/select";
 import { Separator } from "@/components/ui/separator"; // Added separator
-import { PlaybackSettings, MacroEvent, Macro } from "../../types/macro";
+import {
+  PlaybackSettings,
+  MacroEvent,
+  Macro,
+  RepeatMode,
+  ClickVerification,
+  HumanOverride,
+  InputScope,
+} from "../../types/macro";
 
 interface PlaybackPanelProps {
   isPlaying: boolean;
@@ -67,10 +76,14 @@ export const PlaybackPanel: React.FC<PlaybackPanelProps> = ({
 
     if (playbackSettings.repeatMode === "count") {
       const count = playbackSettings.repeatCount || 1;
-      const delay = 500;
+      const delay = playbackSettings.repeatDelayMs ?? 500;
       return adjustedSingleDuration * count + (count - 1) * delay;
     }
 
+    if (playbackSettings.repeatMode === "duration") {
+      return (playbackSettings.repeatDurationMinutes || 1) * 60_000;
+    }
+
     return adjustedSingleDuration;
   }, [selectedMacroId, recordedEvents, macros, playbackSettings]);
 
@@ -225,7 +238,7 @@ export const PlaybackPanel: React.FC<PlaybackPanelProps> = ({
                 onValueChange={(value) =>
                   setPlaybackSettings({
                     ...playbackSettings,
-                    repeatMode: value as "once" | "count" | "infinite",
+                    repeatMode: value as RepeatMode,
                   })
                 }
               >
@@ -236,6 +249,8 @@ export const PlaybackPanel: React.FC<PlaybackPanelProps> = ({
                   <SelectItem value="once">Once</SelectItem>
                   <SelectItem value="count">Count</SelectItem>
                   <SelectItem value="infinite">Infinite</SelectItem>
+                  <SelectItem value="duration">For Duration</SelectItem>
+                  <SelectItem value="held">While Hotkey Held</SelectItem>
                 </SelectContent>
               </Select>
             </div>
@@ -262,6 +277,331 @@ export const PlaybackPanel: React.FC<PlaybackPanelProps> = ({
               />
             </div>
           )}
+
+          {/* Repeat Duration - Only show if mode is 'duration' */}
+          {playbackSettings.repeatMode === "duration" && (
+            <div className="space-y-2 animate-in fade-in slide-in-from-top-1 duration-200">
+              <Label className="text-xs text-muted-foreground">
+                Repeat For (minutes)
+              </Label>
+              <Input
+                type="number"
+                min="1"
+                value={playbackSettings.repeatDurationMinutes ?? 1}
+                onChange={(e) =>
+                  setPlaybackSettings({
+                    ...playbackSettings,
+                    repeatDurationMinutes: parseFloat(e.target.value) || 1,
+                  })
+                }
+                className="h-9"
+              />
+            </div>
+          )}
+
+          {/* Delay Between Repetitions */}
+          {playbackSettings.repeatMode !== "once" && (
+            <div className="space-y-2">
+              <Label className="text-xs text-muted-foreground">
+                Delay Between Repeats (ms)
+              </Label>
+              <Input
+                type="number"
+                min="0"
+                value={playbackSettings.repeatDelayMs ?? 500}
+                onChange={(e) =>
+                  setPlaybackSettings({
+                    ...playbackSettings,
+                    repeatDelayMs: Math.max(0, parseInt(e.target.value) || 0),
+                  })
+                }
+                className="h-9"
+              />
+            </div>
+          )}
+
+          {/* Counter */}
+          {playbackSettings.repeatMode !== "once" && (
+            <div className="space-y-2">
+              <Label className="text-xs text-muted-foreground">
+                Counter Start / Step (typed as {"{counter}"})
+              </Label>
+              <div className="flex gap-2">
+                <Input
+                  type="number"
+                  placeholder="Off"
+                  value={playbackSettings.counter?.start ?? ""}
+                  onChange={(e) =>
+                    setPlaybackSettings({
+                      ...playbackSettings,
+                      counter:
+                        e.target.value === ""
+                          ? undefined
+                          : {
+                              ...playbackSettings.counter,
+                              start: parseInt(e.target.value) || 0,
+                            },
+                    })
+                  }
+                  className="h-9"
+                />
+                <Input
+                  type="number"
+                  disabled={!playbackSettings.counter}
+                  value={playbackSettings.counter?.step ?? 1}
+                  onChange={(e) =>
+                    playbackSettings.counter &&
+                    setPlaybackSettings({
+                      ...playbackSettings,
+                      counter: {
+                        ...playbackSettings.counter,
+                        step: parseInt(e.target.value) || 1,
+                      },
+                    })
+                  }
+                  className="h-9"
+                />
+              </div>
+            </div>
+          )}
+
+          {/* Drag Speed */}
+          <div className="space-y-2">
+            <Label className="text-xs text-muted-foreground">
+              Drag Slowdown (x)
+            </Label>
+            <Input
+              type="number"
+              min="1"
+              step="0.5"
+              value={playbackSettings.dragSpeedFactor ?? 1}
+              onChange={(e) =>
+                setPlaybackSettings({
+                  ...playbackSettings,
+                  dragSpeedFactor: Math.max(1, parseFloat(e.target.value) || 1),
+                })
+              }
+              className="h-9"
+            />
+          </div>
+
+          {/* Human Override */}
+          <div className="space-y-2">
+            <Label className="text-xs text-muted-foreground">
+              On User Input
+            </Label>
+            <Select
+              value={playbackSettings.humanOverride ?? "off"}
+              onValueChange={(value) =>
+                setPlaybackSettings({
+                  ...playbackSettings,
+                  humanOverride: value as HumanOverride,
+                })
+              }
+            >
+              <SelectTrigger className="h-9">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="off">Keep Playing</SelectItem>
+                <SelectItem value="pause">Pause</SelectItem>
+                <SelectItem value="stop">Stop</SelectItem>
+              </SelectContent>
+            </Select>
+          </div>
+
+          {/* Input Scope */}
+          <div className="space-y-2">
+            <Label className="text-xs text-muted-foreground">
+              Plays On (scoped macros can run together)
+            </Label>
+            <Select
+              value={playbackSettings.inputScope ?? "all"}
+              onValueChange={(value) =>
+                setPlaybackSettings({
+                  ...playbackSettings,
+                  inputScope: value as InputScope,
+                })
+              }
+            >
+              <SelectTrigger className="h-9">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="all">Keyboard and Mouse</SelectItem>
+                <SelectItem value="keyboard">Keyboard Only</SelectItem>
+                <SelectItem value="mouse">Mouse Only</SelectItem>
+              </SelectContent>
+            </Select>
+          </div>
+
+          {/* Click Verification */}
+          <div className="space-y-2">
+            <Label className="text-xs text-muted-foreground">
+              Click Screenshots
+            </Label>
+            <Select
+              value={playbackSettings.clickVerification ?? "off"}
+              onValueChange={(value) =>
+                setPlaybackSettings({
+                  ...playbackSettings,
+                  clickVerification: value as ClickVerification,
+                })
+              }
+            >
+              <SelectTrigger className="h-9">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="off">Ignore</SelectItem>
+                <SelectItem value="verify">Verify Target</SelectItem>
+                <SelectItem value="relocate">Find Moved Target</SelectItem>
+              </SelectContent>
+            </Select>
+          </div>
+
+          {/* Target Window */}
+          <div className="space-y-2">
+            <Label className="text-xs text-muted-foreground">
+              Target Window (Windows, experimental)
+            </Label>
+            <Input
+              placeholder="Global input"
+              value={playbackSettings.targetWindow ?? ""}
+              onChange={(e) =>
+                setPlaybackSettings({
+                  ...playbackSettings,
+                  targetWindow: e.target.value || undefined,
+                })
+              }
+              className="h-9"
+            />
+          </div>
+
+          {/* Play At Cursor */}
+          <div className="flex items-center space-x-3">
+            <Checkbox
+              id="play-at-cursor"
+              checked={playbackSettings.playAtCursor ?? false}
+              onCheckedChange={(checked) =>
+                setPlaybackSettings({
+                  ...playbackSettings,
+                  playAtCursor: checked as boolean,
+                })
+              }
+            />
+            <div className="grid gap-1.5 leading-none">
+              <Label htmlFor="play-at-cursor" className="text-sm font-medium">
+                Play at cursor
+              </Label>
+              <p className="text-xs text-muted-foreground">
+                Offset mouse positions so the first click lands under the cursor
+              </p>
+            </div>
+          </div>
+
+          {/* Confirm Destructive */}
+          <div className="flex items-center space-x-3">
+            <Checkbox
+              id="confirm-destructive"
+              checked={playbackSettings.confirmDestructive ?? false}
+              onCheckedChange={(checked) =>
+                setPlaybackSettings({
+                  ...playbackSettings,
+                  confirmDestructive: checked as boolean,
+                })
+              }
+            />
+            <div className="grid gap-1.5 leading-none">
+              <Label
+                htmlFor="confirm-destructive"
+                className="text-sm font-medium"
+              >
+                Confirm destructive keys
+              </Label>
+              <p className="text-xs text-muted-foreground">
+                Ask before Shift+Delete, select all + Delete, Cmd+Q or Alt+F4
+              </p>
+            </div>
+          </div>
+
+          {/* Retry On Failure */}
+          <div className="flex items-center space-x-3">
+            <Checkbox
+              id="retry-on-failure"
+              checked={playbackSettings.retryOnFailure ?? false}
+              onCheckedChange={(checked) =>
+                setPlaybackSettings({
+                  ...playbackSettings,
+                  retryOnFailure: checked as boolean,
+                })
+              }
+            />
+            <div className="grid gap-1.5 leading-none">
+              <Label htmlFor="retry-on-failure" className="text-sm font-medium">
+                Retry on failure
+              </Label>
+              <p className="text-xs text-muted-foreground">
+                Play the macro again if a run fails, waiting twice as long each time
+              </p>
+            </div>
+          </div>
+          {playbackSettings.retryOnFailure && (
+            <div className="space-y-2">
+              <Label className="text-xs text-muted-foreground">
+                Max Retries / First Backoff (ms)
+              </Label>
+              <div className="flex gap-2">
+                <Input
+                  type="number"
+                  min="1"
+                  value={playbackSettings.maxRetries ?? 3}
+                  onChange={(e) =>
+                    setPlaybackSettings({
+                      ...playbackSettings,
+                      maxRetries: parseInt(e.target.value) || 1,
+                    })
+                  }
+                  className="h-9"
+                />
+                <Input
+                  type="number"
+                  min="0"
+                  step="500"
+                  value={playbackSettings.backoffMs ?? 1000}
+                  onChange={(e) =>
+                    setPlaybackSettings({
+                      ...playbackSettings,
+                      backoffMs: parseInt(e.target.value) || 0,
+                    })
+                  }
+                  className="h-9"
+                />
+              </div>
+            </div>
+          )}
+
+          {/* Click By Element */}
+          <div className="flex items-center space-x-3">
+            <Checkbox
+              id="click-by-element"
+              checked={playbackSettings.clickByElement ?? false}
+              onCheckedChange={(checked) =>
+                setPlaybackSettings({
+                  ...playbackSettings,
+                  clickByElement: checked as boolean,
+                })
+              }
+            />
+            <div className="grid gap-1.5 leading-none">
+              <Label htmlFor="click-by-element" className="text-sm font-medium">
+                Click by element
+              </Label>
+              <p className="text-xs text-muted-foreground">
+                Click recorded buttons and fields wherever they are now (Windows)
+              </p>
+            </div>
+          </div>
         </div>
       </CardContent>
     </Card>
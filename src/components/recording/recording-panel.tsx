@@ -131,6 +131,29 @@ export const RecordingPanel: React.FC<RecordingPanelProps> = ({
               </div>
             </div>
 
+            <div className="flex items-center space-x-3">
+              <Checkbox
+                id="mouse-wheel"
+                checked={recordingSettings.recordMouseWheel ?? true}
+                onCheckedChange={(checked) =>
+                  onSettingsChange({
+                    ...recordingSettings,
+                    version: 2,
+                    recordMouseWheel: checked as boolean,
+                  })
+                }
+                disabled={isRecording}
+              />
+              <div className="grid gap-1.5 leading-none">
+                <Label htmlFor="mouse-wheel" className="text-sm font-medium">
+                  Mouse Wheel
+                </Label>
+                <p className="text-sm text-muted-foreground">
+                  Record scrolling
+                </p>
+              </div>
+            </div>
+
             <div className="flex items-center space-x-3">
               <Checkbox
                 id="keyboard"
@@ -152,6 +175,29 @@ export const RecordingPanel: React.FC<RecordingPanelProps> = ({
                 </p>
               </div>
             </div>
+
+            <div className="flex items-center space-x-3">
+              <Checkbox
+                id="key-combos"
+                checked={recordingSettings.recordKeyCombos ?? true}
+                onCheckedChange={(checked) =>
+                  onSettingsChange({
+                    ...recordingSettings,
+                    version: 2,
+                    recordKeyCombos: checked as boolean,
+                  })
+                }
+                disabled={isRecording}
+              />
+              <div className="grid gap-1.5 leading-none">
+                <Label htmlFor="key-combos" className="text-sm font-medium">
+                  Shortcuts
+                </Label>
+                <p className="text-sm text-muted-foreground">
+                  Record keys pressed while Ctrl, Alt or Meta is held
+                </p>
+              </div>
+            </div>
           </CardContent>
         </Card>
       </div>
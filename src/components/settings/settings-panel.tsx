@@ -1,11 +1,26 @@
-import React from "react";
+import React, { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { toast } from "sonner";
+import { Button } from "@/components/ui/button";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Label } from "@/components/ui/label";
 import { Checkbox } from "@/components/ui/checkbox";
 import { Separator } from "@/components/ui/separator";
 import { HotkeyInput } from "../ui/hotkey-input";
 import { ModeToggle } from "../mode-toggle";
-import { HotkeySettings } from "../../types/macro";
+import {
+  AppSettings,
+  HealthReport,
+  HotkeySettings,
+  OnboardingStatus,
+  OnboardingStep,
+} from "../../types/macro";
+
+const ONBOARDING_LABELS: Record<OnboardingStep, string> = {
+  permissions: "Grant input permissions",
+  firstRecording: "Record your first macro",
+  hotkeys: "Customize your hotkeys",
+};
 
 interface SettingsPanelProps {
   hotkeySettings: HotkeySettings;
@@ -20,6 +35,65 @@ export const SettingsPanel: React.FC<SettingsPanelProps> = ({
   isAlwaysOnTop,
   onToggleAlwaysOnTop,
 }) => {
+  const [healthReport, setHealthReport] = useState<HealthReport | null>(null);
+  const [isCheckingHealth, setIsCheckingHealth] = useState(false);
+  const [startMinimized, setStartMinimized] = useState(false);
+  const [confirmDelete, setConfirmDelete] = useState(true);
+  const [onboarding, setOnboarding] = useState<OnboardingStatus | null>(null);
+
+  useEffect(() => {
+    invoke<OnboardingStatus>("get_onboarding_state")
+      .then(setOnboarding)
+      .catch((error) => toast.error(`Failed to load onboarding: ${error}`));
+  }, []);
+
+  const handleCompleteStep = async (step: OnboardingStep) => {
+    try {
+      setOnboarding(
+        await invoke<OnboardingStatus>("complete_onboarding_step", { step }),
+      );
+    } catch (error) {
+      toast.error(`${error}`);
+    }
+  };
+
+  useEffect(() => {
+    invoke<AppSettings>("get_app_settings")
+      .then((settings) => {
+        setStartMinimized(settings.startMinimized ?? false);
+        setConfirmDelete(settings.confirmDelete ?? true);
+      })
+      .catch((error) => toast.error(`Failed to load settings: ${error}`));
+  }, []);
+
+  const updateSetting = async (changes: Partial<AppSettings>) => {
+    try {
+      await invoke("update_app_settings", { settings: changes });
+    } catch (error) {
+      toast.error(`Failed to save settings: ${error}`);
+    }
+  };
+
+  const handleRunHealthCheck = async () => {
+    setIsCheckingHealth(true);
+    try {
+      setHealthReport(await invoke<HealthReport>("run_health_check"));
+    } catch (error) {
+      toast.error(`Health check failed: ${error}`);
+    } finally {
+      setIsCheckingHealth(false);
+    }
+  };
+
+  const handleExportDiagnostics = async () => {
+    try {
+      const path = await invoke<string>("export_diagnostics");
+      toast.success(`Diagnostics saved to ${path}`);
+    } catch (error) {
+      toast.error(`Failed to export diagnostics: ${error}`);
+    }
+  };
+
   return (
     <div className="space-y-6">
       <div>
@@ -29,6 +103,40 @@ export const SettingsPanel: React.FC<SettingsPanelProps> = ({
         </p>
       </div>
 
+      {onboarding && !onboarding.finished && (
+        <Card>
+          <CardHeader>
+            <CardTitle>Getting Started</CardTitle>
+          </CardHeader>
+          <CardContent className="space-y-2 text-sm">
+            {(Object.keys(ONBOARDING_LABELS) as OnboardingStep[]).map(
+              (step) => (
+                <div key={step} className="flex items-center justify-between">
+                  <span
+                    className={
+                      onboarding.completed.includes(step)
+                        ? "text-muted-foreground line-through"
+                        : ""
+                    }
+                  >
+                    {ONBOARDING_LABELS[step]}
+                  </span>
+                  {step === onboarding.nextStep && (
+                    <Button
+                      variant="outline"
+                      size="sm"
+                      onClick={() => handleCompleteStep(step)}
+                    >
+                      Done
+                    </Button>
+                  )}
+                </div>
+              ),
+            )}
+          </CardContent>
+        </Card>
+      )}
+
       <Card>
         <CardHeader>
           <CardTitle>Appearance</CardTitle>
@@ -56,6 +164,38 @@ export const SettingsPanel: React.FC<SettingsPanelProps> = ({
               onCheckedChange={onToggleAlwaysOnTop}
             />
           </div>
+          <Separator className="my-4" />
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>Start Minimized</Label>
+              <p className="text-sm text-muted-foreground">
+                Open MacroX minimized, e.g. when it starts with the system
+              </p>
+            </div>
+            <Checkbox
+              checked={startMinimized}
+              onCheckedChange={(checked) => {
+                setStartMinimized(checked as boolean);
+                updateSetting({ startMinimized: checked as boolean });
+              }}
+            />
+          </div>
+          <Separator className="my-4" />
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>Confirm Delete</Label>
+              <p className="text-sm text-muted-foreground">
+                Ask before deleting a macro
+              </p>
+            </div>
+            <Checkbox
+              checked={confirmDelete}
+              onCheckedChange={(checked) => {
+                setConfirmDelete(checked as boolean);
+                updateSetting({ confirmDelete: checked as boolean });
+              }}
+            />
+          </div>
         </CardContent>
       </Card>
 
@@ -119,6 +259,73 @@ export const SettingsPanel: React.FC<SettingsPanelProps> = ({
             }
             placeholder="Click and press keys..."
           />
+
+          <HotkeyInput
+            label="Drop Marker While Recording (single key)"
+            value={hotkeySettings.mark ?? "F8"}
+            onChange={(value) =>
+              onHotkeyChange({
+                ...hotkeySettings,
+                mark: value,
+              })
+            }
+            placeholder="Click and press a key..."
+          />
+        </CardContent>
+      </Card>
+
+      <Card>
+        <CardHeader>
+          <CardTitle>Troubleshooting</CardTitle>
+        </CardHeader>
+        <CardContent>
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>Health Check</Label>
+              <p className="text-sm text-muted-foreground">
+                Check hotkeys, input capture, storage and permissions
+              </p>
+            </div>
+            <Button
+              variant="outline"
+              onClick={handleRunHealthCheck}
+              disabled={isCheckingHealth}
+            >
+              {isCheckingHealth ? "Checking..." : "Run"}
+            </Button>
+          </div>
+          {healthReport && (
+            <ul className="mt-4 space-y-2 text-sm">
+              {healthReport.checks.map((check) => (
+                <li key={check.id} className="flex justify-between gap-4">
+                  <span>{check.label}</span>
+                  <span
+                    className={
+                      check.status === "ok"
+                        ? "text-muted-foreground"
+                        : check.status === "warning"
+                          ? "text-yellow-600"
+                          : "text-destructive"
+                    }
+                  >
+                    {check.message}
+                  </span>
+                </li>
+              ))}
+            </ul>
+          )}
+          <Separator className="my-4" />
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>Diagnostics</Label>
+              <p className="text-sm text-muted-foreground">
+                Save logs and anonymized settings to attach to a bug report
+              </p>
+            </div>
+            <Button variant="outline" onClick={handleExportDiagnostics}>
+              Export
+            </Button>
+          </div>
         </CardContent>
       </Card>
 
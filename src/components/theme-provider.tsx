@@ -5,15 +5,13 @@ import {
   useState,
   ReactNode,
 } from "react";
-import { Store } from "@tauri-apps/plugin-store";
+import { invoke } from "@tauri-apps/api/core";
 import { error } from "@tauri-apps/plugin-log";
-
-type Theme = "dark" | "light" | "system";
+import { AppSettings, Theme } from "../types/macro";
 
 type ThemeProviderProps = {
   children: ReactNode;
   defaultTheme?: Theme;
-  storageKey?: string;
 };
 
 type ThemeProviderState = {
@@ -31,30 +29,19 @@ const ThemeProviderContext = createContext<ThemeProviderState>(initialState);
 export function ThemeProvider({
   children,
   defaultTheme = "system",
-  storageKey = "theme",
 }: ThemeProviderProps) {
   const [theme, setTheme] = useState<Theme>(defaultTheme);
-  const [store, setStore] = useState<Store | null>(null);
 
-  // Initialize store
+  // Load saved theme
   useEffect(() => {
-    const initStore = async () => {
-      const newStore = await Store.load(".settings.dat");
-      setStore(newStore);
-
-      // Load saved theme
-      try {
-        const savedTheme = await newStore.get<Theme>(storageKey);
-        if (savedTheme) {
-          setTheme(savedTheme);
+    invoke<AppSettings>("get_app_settings")
+      .then((settings) => {
+        if (settings.theme) {
+          setTheme(settings.theme);
         }
-      } catch (err) {
-        error(`Failed to load theme: ${err}`);
-      }
-    };
-
-    initStore();
-  }, [storageKey]);
+      })
+      .catch((err) => error(`Failed to load theme: ${err}`));
+  }, []);
 
   // Apply theme to document
   useEffect(() => {
@@ -87,13 +74,10 @@ export function ThemeProvider({
   const value = {
     theme,
     setTheme: async (newTheme: Theme) => {
-      if (store) {
-        try {
-          await store.set(storageKey, newTheme);
-          await store.save();
-        } catch (err) {
-          error(`Failed to save theme: ${err}`);
-        }
+      try {
+        await invoke("update_app_settings", { settings: { theme: newTheme } });
+      } catch (err) {
+        error(`Failed to save theme: ${err}`);
       }
       setTheme(newTheme);
     },
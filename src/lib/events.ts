@@ -0,0 +1,74 @@
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { RecordingWarning } from "../types/macro";
+
+/**
+ * Kinds of events pushed by the backend event bus
+ */
+export type BusEventType =
+  | "hotkey-record-start"
+  | "hotkey-record-stop"
+  | "hotkey-playback-start"
+  | "hotkey-playback-stop"
+  | "recording-warning" // payload: RecordingWarning
+  | "recording-blocked" // payload: { blocked: boolean; reason: "secureInput" }
+  | "macros-changed"
+  | "settings-changed" // payload: the full AppSettings
+  | "playback-progress"
+  | "confirmation-requested" // payload: { id: number; macroId: string; kind: "destructive" | "repeatLimit"; action: string }
+  | "recorded-events" // payload: array of events captured in the last batch window
+  | "run-outputs" // payload: RunOutputsPayload
+  | "timing-report" // payload: { macroId: string; report: TimingReport }
+  | "update-available" // payload: UpdateInfo, sent after the startup check
+  | "practice-actions"; // payload: PracticeAction[] a practice run would have sent
+
+/**
+ * Values read by a finished run's events, keyed by their "outputVar"
+ */
+export interface RunOutputsPayload {
+  macroId: string;
+  outputs: Record<string, string>;
+}
+
+/**
+ * A single backend event with its sequence id
+ */
+export interface BusEvent<T = unknown> {
+  seq: number;
+  type: BusEventType;
+  payload: T;
+  timestamp: string;
+}
+
+const EVENT_CHANNEL = "macrox:event";
+
+/**
+ * Listen for one type of bus event
+ */
+export function listenBus<T = unknown>(
+  type: BusEventType,
+  handler: (event: BusEvent<T>) => void
+): Promise<UnlistenFn> {
+  return listen<BusEvent<T>>(EVENT_CHANNEL, (event) => {
+    if (event.payload.type === type) handler(event.payload);
+  });
+}
+
+/**
+ * One-line text for a recording warning, for toasts and logs
+ */
+export function describeRecordingWarning(warning: RecordingWarning): string {
+  switch (warning.kind) {
+    case "hotkeyIgnored":
+      return `Hotkey '${warning.key}' detected and ignored`;
+    case "eventDropped":
+      return {
+        outsideRegion: "Mouse input outside the region is dropped",
+        deviceFilter: "Input from other devices was dropped",
+        bufferFull: "The recording is full, further input is dropped",
+      }[warning.reason];
+    case "permissionDegraded":
+      return warning.message;
+    case "bufferNearLimit":
+      return `The recording holds ${warning.events} of at most ${warning.limit} events`;
+  }
+}
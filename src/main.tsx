@@ -1,13 +1,25 @@
 import { StrictMode } from "react";
 import { createRoot } from "react-dom/client";
+import { getCurrentWindow } from "@tauri-apps/api/window";
 import App from "./App";
+import { Launcher } from "./components/launcher/launcher";
+import { PracticeScreen } from "./components/practice/practice-screen";
 import { ThemeProvider } from "./components/theme-provider";
 import "./index.css";
 
+// The quick launcher and practice screen are windows loading the same page
+const label = getCurrentWindow().label;
+
 createRoot(document.getElementById("root")!).render(
   <StrictMode>
-    <ThemeProvider defaultTheme="system" storageKey="macrox-ui-theme">
-      <App />
+    <ThemeProvider defaultTheme="system">
+      {label === "launcher" ? (
+        <Launcher />
+      ) : label === "practice" ? (
+        <PracticeScreen />
+      ) : (
+        <App />
+      )}
     </ThemeProvider>
   </StrictMode>
 );
@@ -28,12 +28,24 @@ export interface MacroEvent {
 
   // Keyboard event data
   key?: string;
+
+  // KeyTurbo: press `key` every intervalMs for durationMs
+  intervalMs?: number;
+  durationMs?: number;
+
+  // RunMacro: play this stored macro's events in place
+  macroId?: string;
 }
 
 /**
  * Playback repeat modes
  */
-export type RepeatMode = "once" | "count" | "infinite";
+export type RepeatMode = "once" | "count" | "infinite" | "duration" | "held";
+
+/**
+ * What happens when the user touches the mouse or keyboard during playback
+ */
+export type HumanOverride = "off" | "pause" | "stop";
 
 /**
  * Playback settings for macro execution
@@ -44,15 +56,98 @@ export interface PlaybackSettings {
   repeatCount?: number; // Only used when repeatMode is 'count'
   interval?: number; // Delay between repetitions in milliseconds
   scheduledTime?: Date; // When to automatically execute
+  playAtCursor?: boolean; // Replay mouse positions relative to the cursor at start
+  repeatDelayMs?: number; // Pause between repetitions, defaults to 500
+  repeatDurationMinutes?: number; // Only used when repeatMode is 'duration'
+  humanOverride?: HumanOverride; // React to real mouse/keyboard input during playback
+  targetWindow?: string; // Windows only, experimental: post input to this window title
+  dragSpeedFactor?: number; // Stretches movement while a button is held, defaults to 1
+  clickVerification?: ClickVerification; // Check clicks against their recorded screenshots
+  clickByElement?: boolean; // Click recorded UI elements where they are now
+  counter?: CounterSettings; // Typed in place of {counter} in TypeText events
+  confirmDestructive?: boolean; // Ask before e.g. Shift+Delete; unset follows the app setting
+  retryOnFailure?: boolean; // Play the whole macro again when a run fails
+  maxRetries?: number; // Defaults to 3
+  backoffMs?: number; // Wait before the first retry, doubled each time; defaults to 1000
+  inputScope?: InputScope; // Scoped macros can play alongside each other
+}
+
+/**
+ * Number that steps each repetition, e.g. start 1 step 1 types 1, 2, 3
+ */
+export interface CounterSettings {
+  start: number;
+  step?: number; // Defaults to 1
+  padDigits?: number; // Zero-pad to this many digits
 }
 
+/**
+ * What playback does with recorded click screenshots
+ */
+export type ClickVerification = "off" | "verify" | "relocate";
+
 /**
  * Recording settings - what to capture
  */
 export interface RecordingSettings {
+  version?: number; // 2 since the per-type toggles; older settings tie the wheel to clicks
   recordMouseMovement: boolean;
   recordMouseClicks: boolean;
   recordKeyboard: boolean;
+  recordMouseWheel?: boolean; // Defaults to true
+  recordKeyCombos?: boolean; // Keys pressed with Control/Alt/Meta held; defaults to true
+  recordWindowFocus?: boolean; // Add a WindowFocus event when the focused app changes
+  recordGestures?: boolean; // Turn Ctrl+wheel and desktop-switch shortcuts into Pinch/Swipe events
+  detectDrags?: boolean; // Store where dragged buttons were released
+  mouseSampleMs?: number; // At most one mouse move per this many ms, 0 keeps all
+  mouseMinDistance?: number; // Drop mouse moves closer than this many pixels
+  mouseDeadZone?: number; // Ignore jitter: moves within this many pixels of the last recorded one
+  distinguishModifierSides?: boolean; // Keep ShiftLeft/ShiftRight etc. distinct
+  relativeMouseMovement?: boolean; // Record mouse deltas instead of absolute positions
+  recordClickPositions?: boolean; // Skip mouse paths, record clicks with their positions
+  region?: ScreenRegion; // Capture mouse input only here; playback never clicks outside it
+  recordClickScreenshots?: boolean; // Store a small screenshot (base64 PNG) with each click
+  recordDevices?: boolean; // Tag key/button/wheel events with their device (Windows)
+  deviceFilter?: InputDevice; // Only keep this device's events of its kind
+  recordClickElements?: boolean; // Store the UI element under each click (Windows)
+  splitAfterEvents?: number; // Save longer recordings as a chain of sub-macros this size, 0 never splits
+}
+
+/**
+ * A UI element a click landed on, stored as the click's "element" field
+ */
+export interface UiElement {
+  name: string;
+  role: string; // Control type, e.g. "button"
+  automationId: string;
+}
+
+/**
+ * A keyboard or mouse, from list_input_devices
+ */
+export interface InputDevice {
+  id: string;
+  kind: "keyboard" | "mouse";
+}
+
+/**
+ * Screen rectangle a macro is allowed to act in
+ */
+export interface ScreenRegion {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+/**
+ * Display scaling captured alongside a recording
+ */
+export interface DisplayInfo {
+  scaleFactor: number;
+  dpi: number;
+  width: number;
+  height: number;
 }
 
 /**
@@ -61,12 +156,93 @@ export interface RecordingSettings {
 export interface Macro {
   id: string;
   name: string;
-  description?: string;
+  description?: string; // Markdown
   events: MacroEvent[];
   recordingSettings: RecordingSettings;
   playbackSettings: PlaybackSettings;
   createdAt: Date;
   updatedAt: Date;
+  displayInfo?: DisplayInfo; // Scaling at recording time, used to correct coordinates
+  expiresAt?: Date; // Archived automatically once this passes
+  archived?: boolean; // Listed with list_archived_macros, brought back with restore_macro
+  author?: string;
+  sourceUrl?: string;
+  license?: string;
+}
+
+/**
+ * Sharing fields edited with update_macro_metadata, blank ones are cleared
+ */
+export interface MacroMetadata {
+  description: string;
+  author?: string;
+  sourceUrl?: string;
+  license?: string;
+}
+
+/**
+ * Screen color returned by get_pixel_color
+ */
+export interface PixelColor {
+  r: number;
+  g: number;
+  b: number;
+  hex: string; // "#rrggbb"
+}
+
+/**
+ * Result of calibrate_image_target: where a click screenshot matches on screen.
+ * Scores run from 0 (identical) to 1; clicks accept a match up to their
+ * "matchTolerance" (default 0.08).
+ */
+export interface ImageCalibration {
+  matches: { x: number; y: number; score: number }[]; // Best first
+  recordedScore: number; // Score at the click's recorded position
+  suggestedTolerance?: number;
+}
+
+/**
+ * Suggested name for a fresh recording
+ */
+export interface RecordingMetadata {
+  suggestedName: string;
+  durationMs: number;
+  dominantApplication?: string | null;
+}
+
+/**
+ * Returned by stop_recording: captured events plus capture statistics
+ */
+export interface RecordingSession {
+  events: MacroEvent[];
+  startedAt: string;
+  durationMs: number;
+  counts: Record<string, number>;
+  droppedEvents: number;
+  warnings: RecordingWarning[];
+  metadata: RecordingMetadata;
+}
+
+/**
+ * Something the recorder skipped or couldn't capture, tagged by kind
+ */
+export type RecordingWarning =
+  | { kind: "hotkeyIgnored"; key: string }
+  | {
+      kind: "eventDropped"; // Sent once per reason; the session has the total
+      reason: "outsideRegion" | "deviceFilter" | "bufferFull";
+    }
+  | { kind: "permissionDegraded"; capability: string; message: string }
+  | { kind: "bufferNearLimit"; events: number; limit: number };
+
+/**
+ * Returned by get_recording_warnings
+ */
+export interface RecordingWarningSummary {
+  total: number;
+  counts: Record<string, number>; // By kind
+  droppedEvents: number;
+  warnings: RecordingWarning[];
 }
 
 /**
@@ -77,6 +253,7 @@ export interface HotkeySettings {
   recordStop: string;
   playbackStart: string;
   playbackStop: string;
+  mark?: string; // Drops a Marker event while recording; defaults to F8
 }
 
 /**
@@ -93,5 +270,427 @@ export interface AppState {
  */
 export interface AppSettings {
   alwaysOnTop: boolean;
+  theme?: Theme;
+  startMinimized?: boolean;
+  confirmDelete?: boolean; // Defaults to true
+  telemetry?: TelemetrySettings;
   lastSelectedMacroId?: string;
+  mqtt?: MqttSettings; // Changed with update_mqtt_settings
+  controlServer?: ControlServerSettings; // Changed with update_control_server_settings
+  emitter?: EmitterSettings; // Changed with update_emitter_settings
+  logging?: LoggingSettings; // Changed with update_logging_settings
+  privilegeGrants?: PrivilegeGrant[]; // Changed with grant/revoke_privileged_events
+  leader?: LeaderSettings; // Changed with update_leader_settings
+  launcher?: LauncherSettings; // Changed with update_launcher_settings
+  browser?: BrowserSettings; // Changed with update_browser_settings
+  inputBackend?: InputBackend; // Changed with update_input_backend
+  confirmDestructive?: boolean; // Changed with update_destructive_confirm
+  macroHotkeys?: MacroHotkey[]; // Changed with set_macro_hotkey
+  windowGeometry?: WindowGeometry; // Saved by the backend when the window moves
+  onboarding?: { completed: OnboardingStep[] }; // Changed with complete_onboarding_step
+  archiveUnusedDays?: number; // Archive macros unused for this many days
+  maxInfiniteIterations?: number; // Infinite runs ask before going past this; 0 for no limit, defaults to 10000
+  scenes?: Scene[]; // Changed with save_scene / delete_scene
+  activeScene?: string | null; // Changed with activate_scene
+  retro?: RetroSettings; // Changed with update_retro_settings
+  exportFormat?: ExportFormat; // Used by export_macro when no format is given
+  mouseAccelerationFactor?: number; // Relative moves are scaled by this; set by calibrate_mouse, defaults to 1
+  updates?: UpdateSettings;
+  practiceMode?: boolean; // Play into the practice window instead of the desktop
+}
+
+/**
+ * Input a practice run would have sent, in screen pixels
+ */
+export type PracticeAction =
+  | { kind: "move"; x: number; y: number }
+  | { kind: "click"; x: number; y: number; button: string; pressed: boolean }
+  | { kind: "key"; key: string; text: string | null; pressed: boolean }
+  | { kind: "scroll"; x: number; y: number; amount: number; horizontal: boolean };
+
+/**
+ * When to look for a newer release
+ */
+export interface UpdateSettings {
+  checkOnStartup: boolean; // Sends "update-available" when a newer release is out
+  includePrereleases: boolean;
+}
+
+/**
+ * Result of check_for_updates
+ */
+export interface UpdateInfo {
+  currentVersion: string;
+  latestVersion: string;
+  updateAvailable: boolean;
+  notes: string; // Markdown release notes
+  url: string; // Release page
+  publishedAt?: string;
+}
+
+/**
+ * Result of calibrate_mouse: requested vs actual relative moves in pixels
+ */
+export interface MouseCalibration {
+  factor: number;
+  samples: { requested: number; actual: number }[];
+}
+
+/**
+ * One difference between the events played and recorded by verify_keymap
+ */
+export type KeymapChange =
+  | { kind: "added"; indexB: number; event: MacroEvent }
+  | { kind: "removed"; indexA: number; event: MacroEvent }
+  | {
+      kind: "changed";
+      indexA: number;
+      indexB: number;
+      before: MacroEvent;
+      after: MacroEvent;
+    };
+
+/**
+ * Result of verify_keymap: the key map self-test played while recording
+ */
+export interface KeymapReport {
+  passed: boolean;
+  sentEvents: number;
+  recordedEvents: number;
+  changes: KeymapChange[];
+}
+
+/**
+ * Encodings a macro can be exported in; import detects them automatically
+ */
+export type ExportFormat = "json" | "ndjson" | "messagepack" | "binary";
+
+/**
+ * First-run steps, in the order they are shown
+ */
+export type OnboardingStep = "permissions" | "firstRecording" | "hotkeys";
+
+/**
+ * Result of get_onboarding_state / complete_onboarding_step
+ */
+export interface OnboardingStatus {
+  completed: OnboardingStep[];
+  nextStep: OnboardingStep | null;
+  finished: boolean;
+}
+
+export type Theme = "system" | "light" | "dark";
+
+/**
+ * Which of the keyboard and mouse a macro plays on
+ */
+export type InputScope = "all" | "keyboard" | "mouse";
+
+/**
+ * A macro playing right now, from get_running_macros
+ */
+export interface RunningMacro {
+  macroId: string;
+  macroName: string;
+  channels: ("keyboard" | "mouse" | { window: string })[];
+}
+
+/**
+ * What the user agreed to share; everything is off by default
+ */
+export interface TelemetrySettings {
+  crashReports?: boolean;
+  usageStats?: boolean;
+}
+
+/**
+ * Main window position and size in physical pixels
+ */
+export interface WindowGeometry {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  monitor?: string;
+}
+
+/**
+ * Global hotkey that plays one stored macro
+ */
+export interface MacroHotkey {
+  macroId: string;
+  hotkey: string;
+}
+
+/**
+ * Triggers reconcile_triggers removed because their macro no longer exists
+ */
+export interface TriggerCleanup {
+  macroHotkeys: MacroHotkey[];
+  leaderKeys: string[];
+  sceneEntries: number;
+  registeredShortcuts: string[]; // Macro ids whose shortcut was still registered
+}
+
+/**
+ * A named group of macros; while active, only its macros' hotkeys are bound
+ */
+export interface Scene {
+  id: string;
+  name: string;
+  macroIds: string[];
+}
+
+/**
+ * Events start..end of a macro, by index (end excluded)
+ */
+export interface EventRange {
+  start: number;
+  end: number;
+}
+
+/**
+ * Returned by save_macro
+ */
+export interface SaveMacroResult {
+  duplicateOf: MacroRef | null; // Stored macro with the same steps, timing ignored
+  parts: MacroRef[]; // Sub-macros a long recording was split into, played in turn via RunMacro
+}
+
+export interface MacroRef {
+  id: string;
+  name: string;
+}
+
+/**
+ * Result of one run_health_check probe
+ */
+export interface HealthCheck {
+  id: string; // e.g. "shortcuts", "inputHook", "permissions"
+  label: string;
+  status: "ok" | "warning" | "failed";
+  message: string;
+}
+
+export interface HealthReport {
+  checks: HealthCheck[];
+  healthy: boolean; // No check failed
+}
+
+/**
+ * Engine the player injects input with. "sendinput" is Windows only,
+ * "xdotool" is Linux/X11 only and needs xdotool installed, "portal" is
+ * Linux/Wayland only and asks the user to allow remote control once per run.
+ */
+export type InputBackend = "enigo" | "sendinput" | "xdotool" | "portal";
+
+/**
+ * Wayland session support, from get_wayland_capabilities
+ */
+export interface WaylandCapabilities {
+  isWayland: boolean;
+  remoteDesktop: boolean; // Playback works with the "portal" backend
+  inputCapture: boolean; // Recording on Wayland isn't supported yet
+}
+
+/**
+ * One feature and whether it works on this system
+ */
+export interface Capability {
+  supported: boolean;
+  note?: string | null; // Why it isn't available, to show next to the feature
+}
+
+/**
+ * What this OS and the selected input backend support, from get_platform_capabilities
+ */
+export interface PlatformCapabilities {
+  os: string; // "windows", "macos" or "linux"
+  backend: InputBackend;
+  availableBackends: InputBackend[];
+  recording: Capability;
+  playback: Capability;
+  globalHotkeys: Capability;
+  rawScancodes: Capability;
+  horizontalScroll: Capability;
+  windowTargeting: Capability;
+  gamepad: Capability;
+  wayland: WaylandCapabilities;
+}
+
+/**
+ * Chrome DevTools Protocol connection for BrowserClick/BrowserType/BrowserNavigate
+ * events. Chrome must run with --remote-debugging-port.
+ */
+export interface BrowserSettings {
+  enabled: boolean;
+  port: number;
+}
+
+/**
+ * Keep the last few seconds of input in memory, saved as a macro by a hotkey
+ */
+export interface RetroSettings {
+  enabled: boolean;
+  seconds: number;
+  hotkey: string;
+}
+
+/**
+ * Global hotkey for the quick launcher palette
+ */
+export interface LauncherSettings {
+  enabled: boolean;
+  hotkey: string;
+}
+
+/**
+ * Leader key: the hotkey arms a short window where one more key plays a macro
+ */
+export interface LeaderSettings {
+  enabled: boolean;
+  hotkey: string;
+  timeoutMs: number;
+  bindings: Record<string, string>; // Key name (e.g. "a", "F5") to macro id
+}
+
+/**
+ * Permission for a macro to run commands, use the clipboard or type masked input
+ */
+export interface PrivilegeGrant {
+  macroId: string;
+  eventTypes: string[];
+}
+
+export type LogLevel = "error" | "warn" | "info" | "debug" | "trace";
+
+/**
+ * Verbosity of the rotating log files
+ */
+export interface LoggingSettings {
+  level: LogLevel;
+}
+
+/**
+ * Batching and throttling of live updates from the backend
+ */
+export interface EmitterSettings {
+  batchWindowMs: number;
+  maxMouseMovesPerSec: number; // 0 for no limit
+}
+
+/**
+ * MQTT broker connection for home automation triggers
+ */
+export interface MqttSettings {
+  enabled: boolean;
+  host: string;
+  port: number;
+  username?: string;
+  password?: string;
+  topicPrefix: string; // <prefix>/play, /stop, /status, /playback
+}
+
+/**
+ * Local WebSocket control endpoint, e.g. for a Stream Deck
+ */
+export interface ControlServerSettings {
+  enabled: boolean;
+  port: number;
+  tokens?: ControlToken[]; // Changed with mint/revoke_control_token; every request needs one
+}
+
+/**
+ * What a control server token may do; trigger tokens play only the listed macro ids
+ */
+export type TokenScope =
+  | { kind: "readOnly" }
+  | { kind: "trigger"; macros: string[] }
+  | { kind: "full" };
+
+/**
+ * Control server token, sent as `Authorization: Bearer <token>` or `?token=<token>`
+ */
+export interface ControlToken {
+  id: string;
+  name: string;
+  token: string;
+  scope: TokenScope;
+  createdAt: string;
+}
+
+/**
+ * Current playback state reported by the backend
+ */
+export type PlaybackStatus =
+  | { state: "idle" }
+  | {
+      state: "playing" | "paused";
+      macroId: string;
+      eventIndex: number;
+      iteration: number;
+      elapsedMs: number;
+      etaMs: number | null; // null when repeating until stopped
+    };
+
+/**
+ * Expected runtime of a macro with given playback settings
+ */
+export interface DurationEstimate {
+  singleRunMs: number;
+  iterations: number | null;
+  totalMs: number | null;
+}
+
+/**
+ * One finished playback run, from get_run_history
+ */
+export interface RunRecord {
+  macroId: string;
+  macroName: string;
+  startedAt: string;
+  durationMs: number;
+  outcome: "completed" | "stopped" | "failed";
+  error?: string | null;
+  outputs?: Record<string, string>; // Values read by the run's events
+  timing?: TimingReport | null;
+}
+
+/**
+ * Intended against actual playback timing of one event, averaged per run
+ */
+export interface EventTiming {
+  eventIndex: number;
+  eventType: string;
+  intendedDelayMs: number;
+  actualDelayMs: number;
+  injectionMs: number;
+  runs: number;
+}
+
+/**
+ * Intended against actual timing of a whole run, with the events that fell behind most
+ */
+export interface TimingReport {
+  intendedMs: number;
+  actualMs: number;
+  sleepOvershootMs: number;
+  injectionMs: number;
+  eventsPlayed: number;
+  slowest: EventTiming[];
+}
+
+/**
+ * A likely problem found by lint_macro
+ */
+export interface LintWarning {
+  kind:
+    | "unbalancedInput"
+    | "offScreenClick"
+    | "longWait"
+    | "burst"
+    | "hotkeyKey"
+    | "missingMacro";
+  eventIndex: number | null;
+  message: string;
 }